@@ -0,0 +1,108 @@
+//! Benchmarks for the stages an article goes through between the feed and the screen, run with
+//! `cargo bench`. Inputs are the `fixtures/convert` corpus, each repeated to a long article.
+
+use std::fs;
+use std::hint::black_box;
+use std::path::Path;
+
+use anyhow::Result;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use futures::future::BoxFuture;
+use rss::{Item, ItemBuilder};
+use rss_reader::db::{self, ImageDownloader};
+use rss_reader::tui;
+
+/// Copies of each fixture body joined into one article.
+const REPEAT: usize = 20;
+
+/// Answers every image with a local path without touching the network or disk.
+struct MockDownloader;
+
+impl ImageDownloader for MockDownloader {
+    fn download<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Option<String>>> {
+        Box::pin(async move { Ok(Some(format!("/images/{:x}.png", url.len()))) })
+    }
+}
+
+/// Each fixture's name and an item whose content is its HTML repeated `REPEAT` times.
+fn articles() -> Vec<(String, Item)> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/convert");
+    let mut articles: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "html"))
+        .map(|path| {
+            let html = fs::read_to_string(&path).unwrap().repeat(REPEAT);
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            (name, ItemBuilder::default().content(html).build())
+        })
+        .collect();
+    articles.sort_by(|a, b| a.0.cmp(&b.0));
+    articles
+}
+
+fn extract_markdown(c: &mut Criterion) {
+    let mut group = c.benchmark_group("extract_markdown");
+    for (name, item) in articles() {
+        group.bench_with_input(BenchmarkId::from_parameter(name), &item, |b, item| {
+            b.iter(|| db::extract_markdown(black_box(item)))
+        });
+    }
+    group.finish();
+}
+
+fn localize_images(c: &mut Criterion) {
+    let mut group = c.benchmark_group("localize_images");
+    for (name, item) in articles() {
+        let markdown = db::extract_markdown(&item);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            &markdown,
+            |b, markdown| {
+                b.iter(|| {
+                    futures::executor::block_on(db::localize_images_with(
+                        black_box(markdown),
+                        &MockDownloader,
+                    ))
+                    .unwrap()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn markdown_to_lines(c: &mut Criterion) {
+    let mut group = c.benchmark_group("markdown_to_lines");
+    for (name, item) in articles() {
+        let markdown = db::extract_markdown(&item);
+        for width in [40, 120] {
+            group.bench_with_input(BenchmarkId::new(&name, width), &markdown, |b, markdown| {
+                b.iter(|| tui::markdown_to_lines(black_box(markdown), width))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn render_markdown_html(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render_markdown_html");
+    for (name, item) in articles() {
+        let markdown = db::extract_markdown(&item);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            &markdown,
+            |b, markdown| b.iter(|| db::render_markdown_html(black_box(markdown))),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    extract_markdown,
+    localize_images,
+    markdown_to_lines,
+    render_markdown_html
+);
+criterion_main!(benches);
@@ -0,0 +1,108 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use rss::Channel;
+
+use crate::config::Config;
+use crate::db;
+use crate::feed;
+use crate::fetcher::HttpFetcher;
+
+pub struct AuditOptions {
+    /// Feeds whose newest item is older than this many months are flagged as stale.
+    pub stale_months: u32,
+    /// Ask what to do with each flagged feed. Ignored when stdin is not a terminal.
+    pub interactive: bool,
+}
+
+enum Finding {
+    Error(String),
+    Stale(DateTime<Utc>),
+    Empty,
+}
+
+/// Fetches every subscription, reports broken and stale feeds, and optionally removes or pauses
+/// them. Returns whether the config was changed.
+pub async fn audit_feeds(config: &mut Config, options: &AuditOptions) -> Result<bool> {
+    let cutoff = Duration::try_days(30 * i64::from(options.stale_months))
+        .and_then(|age| Utc::now().checked_sub_signed(age))
+        .context(format!(
+            "{} months is too long ago to flag feeds as stale",
+            options.stale_months
+        ))?;
+    let mut flagged = Vec::new();
+
+    for (item, feed) in config.subscriptions_mut() {
+        if item.paused {
+            say!("PAUSED {}", item.name);
+            continue;
+        }
+        let finding = match feed::fetch_configured_feed(&HttpFetcher, &feed).await {
+            Err(err) => Some(Finding::Error(format!("{:#}", err))),
+            Ok(channel) => match newest_item_date(&channel) {
+                Some(newest) if newest < cutoff => Some(Finding::Stale(newest)),
+                Some(_) => None,
+                None if channel.items().is_empty() => Some(Finding::Empty),
+                None => None,
+            },
+        };
+        match &finding {
+            None => say!("OK     {}", item.name),
+            Some(Finding::Error(err)) => say!("ERROR  {}: {}", item.name, err),
+            Some(Finding::Stale(newest)) => say!(
+                "STALE  {}: newest item from {}",
+                item.name,
+                newest.format("%Y-%m-%d")
+            ),
+            Some(Finding::Empty) => say!("EMPTY  {}: no items", item.name),
+        }
+        if finding.is_some() {
+            flagged.push((item.name.clone(), item.url.clone()));
+        }
+    }
+
+    say!("{} feed(s) flagged", flagged.len());
+    if flagged.is_empty() || !options.interactive || !io::stdin().is_terminal() {
+        return Ok(false);
+    }
+
+    let mut changed = false;
+    for (name, url) in flagged {
+        match prompt(&format!("{}: [k]eep, [r]emove, [p]ause? ", name))?.as_str() {
+            "r" => {
+                config.remove_feed(&url);
+                changed = true;
+            }
+            "p" => {
+                config.set_paused(&url, true);
+                changed = true;
+            }
+            _ => {}
+        }
+    }
+    Ok(changed)
+}
+
+fn newest_item_date(channel: &Channel) -> Option<DateTime<Utc>> {
+    channel
+        .items()
+        .iter()
+        .flat_map(|item| [item.pub_date().map(str::to_string), db::item_updated(item)])
+        .flatten()
+        .filter_map(|date| {
+            DateTime::parse_from_rfc2822(&date)
+                .or_else(|_| DateTime::parse_from_rfc3339(&date))
+                .ok()
+        })
+        .map(|date| date.with_timezone(&Utc))
+        .max()
+}
+
+fn prompt(question: &str) -> Result<String> {
+    print!("{}", question);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(answer.trim().to_lowercase())
+}
@@ -0,0 +1,128 @@
+use std::collections::HashSet;
+
+use anyhow::{anyhow, Result};
+use rss::Channel;
+use url::Url;
+
+use crate::config::Feed;
+use crate::db::{self, ArticleQuery, Database, StoreOptions};
+use crate::feed;
+use crate::fetcher::HttpFetcher;
+
+/// `atom:link` relations pointing at older entries, most specific first: RFC 5005 archived
+/// feeds use `prev-archive`, paged feeds `next` (some use `prev` or `previous` instead).
+const OLDER_PAGE_RELS: &[&str] = &["prev-archive", "next", "previous", "prev"];
+
+/// Extension prefixes under which RSS channels carry `atom:link` elements.
+const ATOM_PREFIXES: &[&str] = &["atom", "a10"];
+
+/// Walks the feed's older pages, following archive links and falling back to WordPress'
+/// `?paged=N`, and stores every page's items. Stops after `max_pages` pages or when a page
+/// brings nothing new. Returns the number of articles added to the archive.
+pub async fn backfill(feed: &Feed, db: &Database, max_pages: usize) -> Result<usize> {
+    if feed.selectors.is_some() || feed.newsletter.is_some() {
+        return Err(anyhow!("{} has no feed pages to backfill", feed.name));
+    }
+    if feed.max_items.is_some() {
+        say!(
+            "Note: {} sets max_items, so the next refresh prunes older articles again",
+            feed.name
+        );
+    }
+
+    let stored_before = stored_count(feed, db)?;
+    let first_url = feed::build_feed_url(feed)?;
+    let mut page_url = first_url.clone();
+    let mut visited = HashSet::new();
+    let mut seen_items = HashSet::new();
+    let mut wordpress_page = None;
+
+    for page in 1..=max_pages {
+        visited.insert(page_url.clone());
+        let channel = match feed::fetch_channel(&HttpFetcher, &page_url).await {
+            Ok(channel) => channel,
+            Err(err) if page > 1 => {
+                say!("Page {} ({}): {:#}", page, page_url, err);
+                break;
+            }
+            Err(err) => return Err(err),
+        };
+        let ids: Vec<String> = channel
+            .items()
+            .iter()
+            .map(|item| db::item_id(&feed.name, &feed.url, item))
+            .collect();
+        let unseen = ids.iter().filter(|id| !seen_items.contains(*id)).count();
+        seen_items.extend(ids);
+        say!(
+            "Page {} ({}): {} items, {} not on earlier pages",
+            page,
+            page_url,
+            channel.items().len(),
+            unseen
+        );
+        if unseen == 0 {
+            break;
+        }
+
+        let options = StoreOptions {
+            max_items: None,
+            ..feed.store_options()
+        };
+        db.store_channel(&feed.name, &feed.url, &channel, options)
+            .await?;
+
+        let next = match older_page_link(&channel, &page_url) {
+            Some(link) if wordpress_page.is_none() => Some(link),
+            _ => {
+                let number = wordpress_page.map_or(2, |number| number + 1);
+                wordpress_page = Some(number);
+                wordpress_page_url(&first_url, number)
+            }
+        };
+        match next.filter(|url| !visited.contains(url)) {
+            Some(url) => page_url = url,
+            None => break,
+        }
+    }
+
+    Ok(stored_count(feed, db)?.saturating_sub(stored_before))
+}
+
+fn stored_count(feed: &Feed, db: &Database) -> Result<usize> {
+    Ok(db.query_articles(&ArticleQuery::feed(&feed.name))?.len())
+}
+
+/// The channel's `atom:link` to its next older page, resolved against `page_url`.
+fn older_page_link(channel: &Channel, page_url: &str) -> Option<String> {
+    let links: Vec<_> = ATOM_PREFIXES
+        .iter()
+        .filter_map(|prefix| channel.extensions().get(*prefix)?.get("link"))
+        .flatten()
+        .collect();
+    OLDER_PAGE_RELS.iter().find_map(|rel| {
+        let link = links
+            .iter()
+            .find(|link| link.attrs().get("rel").map(String::as_str) == Some(*rel))?;
+        let href = link.attrs().get("href")?;
+        Url::parse(page_url)
+            .and_then(|base| base.join(href))
+            .ok()
+            .map(String::from)
+    })
+}
+
+/// `url` with WordPress' `paged` query parameter set to `number`.
+fn wordpress_page_url(url: &str, number: usize) -> Option<String> {
+    let mut url = Url::parse(url).ok()?;
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| key != "paged")
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs)
+        .append_pair("paged", &number.to_string());
+    Some(url.into())
+}
@@ -0,0 +1,333 @@
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+
+use crate::config::BackupConfig;
+use crate::db::Database;
+use crate::writer::StoreWriter;
+
+/// Name of the config file inside a backup.
+const CONFIG_ENTRY: &str = "feeds.toml";
+/// Directory the article store is kept under inside a backup.
+const STORE_ENTRY: &str = "articles";
+/// Directory articles stored outside the store, in a feed's own `store_dir`, are kept under.
+const ELSEWHERE_ENTRY: &str = "elsewhere";
+/// Lists where each file under `ELSEWHERE_ENTRY` is restored to, one `entry,path` row each.
+const ELSEWHERE_MANIFEST: &str = "elsewhere.csv";
+/// zstd's default level: fast, and text compresses well at it.
+const COMPRESSION_LEVEL: i32 = 3;
+
+const FILE_PREFIX: &str = "rss_reader-";
+const FILE_EXTENSION: &str = ".tar.zst";
+
+/// Name for a backup taken now, e.g. `rss_reader-20250101-120000.tar.zst`. Names sort by time.
+pub fn default_file_name() -> String {
+    format!(
+        "{}{}{}",
+        FILE_PREFIX,
+        Utc::now().format("%Y%m%d-%H%M%S"),
+        FILE_EXTENSION
+    )
+}
+
+/// Writes the config and the article store (index, read state, logs, articles and images, but
+/// not the HTTP cache) to `output` as a zstd-compressed tar archive, along with the articles of
+/// feeds with their own `store_dir`, and returns its size in bytes. State writes are held off
+/// while the store is copied; article writes are too when this runs as a store writer job. The
+/// archive is written under a temporary name and renamed once complete, so `output` never holds
+/// a partial backup.
+pub fn create(database: &Database, config_path: &Path, output: &Path) -> Result<u64> {
+    let store_dir = database.store_dir();
+    let output_dir = output
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    if let (Ok(store), Ok(output_dir)) = (store_dir.canonicalize(), output_dir.canonicalize()) {
+        if output_dir.starts_with(store) {
+            bail!(
+                "Can't write a backup inside the store it copies ({})",
+                store_dir.display()
+            );
+        }
+    }
+
+    let partial = with_suffix(output, ".partial");
+    if let Err(err) = write_archive(database, config_path, &partial) {
+        let _ = fs::remove_file(&partial);
+        return Err(err);
+    }
+    fs::rename(&partial, output).context(format!("Failed to write {}", output.display()))?;
+    Ok(fs::metadata(output)?.len())
+}
+
+fn write_archive(database: &Database, config_path: &Path, path: &Path) -> Result<()> {
+    let file = File::create(path).context(format!("Failed to create {}", path.display()))?;
+    let mut archive = tar::Builder::new(zstd::Encoder::new(file, COMPRESSION_LEVEL)?);
+    archive.follow_symlinks(false);
+    {
+        let _paused = database.pause_state_writes();
+        if config_path.exists() {
+            archive
+                .append_path_with_name(config_path, CONFIG_ENTRY)
+                .context(format!("Failed to back up {}", config_path.display()))?;
+        }
+        append_store(database, &mut archive).context(format!(
+            "Failed to back up {}",
+            database.store_dir().display()
+        ))?;
+        append_elsewhere(database, &mut archive)?;
+    }
+    let file = archive
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .context("Failed to finish the backup")?;
+    file.sync_all().context("Failed to finish the backup")
+}
+
+/// Adds the store directory, except for its HTTP cache, which can be fetched again.
+fn append_store<W: std::io::Write>(
+    database: &Database,
+    archive: &mut tar::Builder<W>,
+) -> Result<()> {
+    let store_dir = database.store_dir();
+    archive.append_dir(STORE_ENTRY, store_dir)?;
+    for entry in fs::read_dir(store_dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path == database.http_cache_dir() {
+            continue;
+        }
+        let name = Path::new(STORE_ENTRY).join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            archive.append_dir_all(&name, &path)?;
+        } else {
+            archive.append_path_with_name(&path, &name)?;
+        }
+    }
+    Ok(())
+}
+
+/// Adds the articles stored outside the store directory, and a manifest of where they belong.
+fn append_elsewhere<W: std::io::Write>(
+    database: &Database,
+    archive: &mut tar::Builder<W>,
+) -> Result<()> {
+    let mut manifest = csv::Writer::from_writer(Vec::new());
+    let elsewhere = database
+        .list_articles()?
+        .into_iter()
+        .filter(|record| !record.path.starts_with(database.store_dir()) && record.path.is_file());
+    for (index, record) in elsewhere.enumerate() {
+        let entry = format!("{}/{}", ELSEWHERE_ENTRY, index);
+        archive
+            .append_path_with_name(&record.path, &entry)
+            .context(format!("Failed to back up {}", record.path.display()))?;
+        manifest.write_record([entry.as_str(), &record.path.to_string_lossy()])?;
+    }
+    let manifest = manifest.into_inner()?;
+    if manifest.is_empty() {
+        return Ok(());
+    }
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    archive
+        .append_data(&mut header, ELSEWHERE_MANIFEST, manifest.as_slice())
+        .context("Failed to back up the articles stored outside the store")
+}
+
+/// Replaces the config and the article store with the ones in a backup made by `create`, and
+/// writes the articles of feeds with their own `store_dir` back where they were. The archive is
+/// unpacked next to the store and swapped in only once complete, so a damaged backup leaves the
+/// current data as it was. Nothing else may be using the store meanwhile.
+pub fn restore(archive: &Path, config_path: &Path, store_dir: &Path) -> Result<()> {
+    let staging = with_suffix(store_dir, ".restoring");
+    if staging.exists() {
+        fs::remove_dir_all(&staging).context(format!("Failed to remove {}", staging.display()))?;
+    }
+    let unpacked = File::open(archive)
+        .context(format!("Failed to open {}", archive.display()))
+        .and_then(|file| Ok(zstd::Decoder::new(file)?))
+        .and_then(|decoder| {
+            tar::Archive::new(decoder)
+                .unpack(&staging)
+                .context(format!("Failed to unpack {}", archive.display()))
+        });
+    let restored_store = staging.join(STORE_ENTRY);
+    if let Err(err) = unpacked.and_then(|()| {
+        if restored_store.join("index.csv").is_file() {
+            Ok(())
+        } else {
+            bail!("{} is not an rss_reader backup", archive.display())
+        }
+    }) {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(err);
+    }
+
+    let replaced = with_suffix(store_dir, ".replaced");
+    if replaced.exists() {
+        fs::remove_dir_all(&replaced)
+            .context(format!("Failed to remove {}", replaced.display()))?;
+    }
+    if store_dir.exists() {
+        fs::rename(store_dir, &replaced)
+            .context(format!("Failed to move {} aside", store_dir.display()))?;
+    }
+    if let Err(err) = fs::rename(&restored_store, store_dir) {
+        let _ = fs::rename(&replaced, store_dir);
+        return Err(err).context(format!("Failed to restore {}", store_dir.display()));
+    }
+    let restored_config = staging.join(CONFIG_ENTRY);
+    if restored_config.is_file() {
+        fs::copy(&restored_config, config_path)
+            .context(format!("Failed to restore {}", config_path.display()))?;
+    }
+    restore_elsewhere(&staging)?;
+    let _ = fs::remove_dir_all(&replaced);
+    let _ = fs::remove_dir_all(&staging);
+    Ok(())
+}
+
+/// Copies the articles listed in an unpacked backup's manifest back to their paths.
+fn restore_elsewhere(staging: &Path) -> Result<()> {
+    let manifest = staging.join(ELSEWHERE_MANIFEST);
+    if !manifest.is_file() {
+        return Ok(());
+    }
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_path(&manifest)?;
+    for row in reader.records() {
+        let row = row.context("Invalid backup manifest")?;
+        let (Some(entry), Some(path)) = (row.get(0), row.get(1)) else {
+            bail!("Invalid backup manifest row: {:?}", row);
+        };
+        let path = Path::new(path);
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent).context(format!("Failed to create {}", parent.display()))?;
+        }
+        fs::copy(staging.join(entry), path)
+            .context(format!("Failed to restore {}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Backups in `dir` taken by `schedule`, oldest first.
+fn scheduled_backups(dir: &Path) -> Vec<PathBuf> {
+    let mut backups: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(FILE_PREFIX) && name.ends_with(FILE_EXTENSION))
+        })
+        .collect();
+    backups.sort();
+    backups
+}
+
+/// Takes a backup into `settings.dir` every `interval_hours` while the server runs, through the
+/// store writer so no article is half-written, and deletes all but the newest `keep`. The first
+/// one is due an interval after the newest backup already in the directory.
+pub async fn schedule(settings: BackupConfig, config_path: PathBuf, store: StoreWriter) {
+    let interval = Duration::from_secs(settings.interval_hours.max(1) * 60 * 60);
+    let age = scheduled_backups(&settings.dir)
+        .last()
+        .and_then(|newest| fs::metadata(newest).ok()?.modified().ok())
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+    let mut due = age.map_or(Duration::ZERO, |age| interval.saturating_sub(age));
+    loop {
+        tokio::time::sleep(due).await;
+        due = interval;
+        if let Err(err) = fs::create_dir_all(&settings.dir) {
+            tracing::warn!("Failed to create {}: {}", settings.dir.display(), err);
+            continue;
+        }
+        let output = settings.dir.join(default_file_name());
+        match store.backup(&config_path, &output).await {
+            Ok(bytes) => tracing::info!("Backed up to {} ({} bytes)", output.display(), bytes),
+            Err(err) => {
+                tracing::warn!("Backup failed: {:#}", err);
+                continue;
+            }
+        }
+        let backups = scheduled_backups(&settings.dir);
+        if settings.keep > 0 && backups.len() > settings.keep {
+            for old in &backups[..backups.len() - settings.keep] {
+                if let Err(err) = fs::remove_file(old) {
+                    tracing::warn!("Failed to delete old backup {}: {}", old.display(), err);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use rss::{Channel, Item};
+
+    use super::{create, restore};
+    use crate::db::{Database, StoreOptions};
+    use crate::test_util::TempDir;
+
+    #[tokio::test]
+    async fn backs_up_and_restores_articles_in_a_feed_store_dir() {
+        let dir = TempDir::new("backup");
+        let store_dir = dir.join("articles");
+        let feed_dir = dir.join("synced");
+        let config_path = dir.join("feeds.toml");
+        let archive = dir.join("backup.tar.zst");
+        fs::create_dir_all(&feed_dir).unwrap();
+        fs::write(&config_path, "").unwrap();
+
+        let database = Database::initialize(&store_dir, None).await.unwrap();
+        let mut item = Item::default();
+        item.set_title("Elsewhere".to_string());
+        item.set_link("https://x/1".to_string());
+        item.set_description("body".to_string());
+        let mut channel = Channel::default();
+        channel.set_items(vec![item]);
+        let options = StoreOptions {
+            store_dir: Some(feed_dir.clone()),
+            ..StoreOptions::default()
+        };
+        database
+            .store_channel("Work", "https://x/feed", &channel, options)
+            .await
+            .unwrap();
+        let record = database.list_articles().unwrap().remove(0);
+        assert!(record.path.starts_with(&feed_dir));
+        let markdown = fs::read(&record.path).unwrap();
+
+        create(&database, &config_path, &archive).unwrap();
+        drop(database);
+        fs::remove_dir_all(&feed_dir).unwrap();
+        restore(&archive, &config_path, &store_dir).unwrap();
+
+        assert_eq!(fs::read(&record.path).unwrap(), markdown);
+        let restored = Database::initialize(&store_dir, None).await.unwrap();
+        let records = restored.list_articles().unwrap();
+        assert_eq!(records.len(), 1);
+        assert!(restored.read_article_markdown(&records[0]).is_some());
+    }
+}
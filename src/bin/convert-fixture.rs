@@ -0,0 +1,44 @@
+//! Writes the expected markdown for HTML conversion fixtures, for reviewing and committing
+//! alongside them after an intended change to the conversion pipeline.
+//!
+//! `cargo run --bin convert-fixture -- fixtures/convert/wordpress.html` writes
+//! `fixtures/convert/wordpress.md`.
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use rss::ItemBuilder;
+use rss_reader::db;
+
+#[derive(Parser)]
+#[command(name = "convert-fixture")]
+#[command(about = "Convert item HTML fixtures to the markdown stored for them")]
+struct Cli {
+    /// HTML files holding an item's content; each gets a `.md` file next to it
+    #[arg(required = true)]
+    fixtures: Vec<PathBuf>,
+    /// Print the markdown instead of writing it
+    #[arg(long)]
+    stdout: bool,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    for fixture in &cli.fixtures {
+        let html = fs::read_to_string(fixture)
+            .context(format!("Failed to read fixture: {:?}", fixture))?;
+        let item = ItemBuilder::default().content(html).build();
+        let markdown = db::extract_markdown(&item);
+        if cli.stdout {
+            print!("{}", markdown);
+            continue;
+        }
+        let expected = fixture.with_extension("md");
+        fs::write(&expected, markdown)
+            .context(format!("Failed to write expected markdown: {:?}", expected))?;
+        println!("{}", expected.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+use regex::Regex;
+use rss::{Channel, Item};
+use url::Url;
+
+/// The config's `blocklist` file and the rules last read from it.
+static BLOCKLIST: RwLock<Option<Blocklist>> = RwLock::new(None);
+
+struct Blocklist {
+    path: PathBuf,
+    /// Modification time of the file when the rules were read; they are re-read once it changes.
+    modified: Option<SystemTime>,
+    rules: Vec<Rule>,
+    /// Whether a failure to read the file was logged, so it's logged once rather than per fetch.
+    failed: bool,
+}
+
+enum Rule {
+    /// Items linking to this host or its subdomains.
+    Domain(String),
+    /// Items whose link matches, `*` standing for any text.
+    Link(Regex),
+    /// Items whose author contains this, lowercased.
+    Author(String),
+}
+
+impl Rule {
+    /// Parses a line of the file: `domain: HOST`, `link: PATTERN` or `author: NAME`, a bare
+    /// host being a domain rule. Blank lines and `#` comments are skipped.
+    fn parse(line: &str) -> Option<Rule> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (kind, value) = match line.split_once(':') {
+            Some((kind, value)) if ["domain", "link", "author"].contains(&kind.trim()) => {
+                (kind.trim(), value.trim())
+            }
+            _ => ("domain", line),
+        };
+        if value.is_empty() {
+            return None;
+        }
+        match kind {
+            "link" => {
+                let pattern = regex::escape(value).replace(r"\*", ".*");
+                Regex::new(&format!("(?i)^{}$", pattern))
+                    .ok()
+                    .map(Rule::Link)
+            }
+            "author" => Some(Rule::Author(value.to_lowercase())),
+            _ => Some(Rule::Domain(
+                value.trim_start_matches("www.").to_lowercase(),
+            )),
+        }
+    }
+
+    fn matches(&self, item: &Item) -> bool {
+        match self {
+            Rule::Domain(domain) => item
+                .link()
+                .and_then(|link| Url::parse(link).ok())
+                .and_then(|url| url.host_str().map(str::to_lowercase))
+                .is_some_and(|host| {
+                    host == *domain
+                        || host
+                            .strip_suffix(domain.as_str())
+                            .is_some_and(|prefix| prefix.ends_with('.'))
+                }),
+            Rule::Link(pattern) => item.link().is_some_and(|link| pattern.is_match(link)),
+            Rule::Author(name) => item
+                .author()
+                .is_some_and(|author| author.to_lowercase().contains(name)),
+        }
+    }
+}
+
+/// Sets the blocklist file whose matching items `filter_channel` drops, or none.
+pub fn set_path(path: Option<&Path>) {
+    let mut blocklist = BLOCKLIST.write().unwrap_or_else(|err| err.into_inner());
+    *blocklist = path.map(|path| Blocklist {
+        path: path.to_path_buf(),
+        modified: None,
+        rules: Vec::new(),
+        failed: false,
+    });
+}
+
+/// Drops the channel's items that a rule of the blocklist file matches, and returns how many.
+/// The file is read again whenever it has changed since, so edits apply to the next fetch
+/// without a restart; while it can't be read, the rules read last stay in force.
+pub fn filter_channel(channel: &mut Channel) -> usize {
+    let mut guard = BLOCKLIST.write().unwrap_or_else(|err| err.into_inner());
+    let Some(blocklist) = guard.as_mut() else {
+        return 0;
+    };
+    reload(blocklist);
+    if blocklist.rules.is_empty() {
+        return 0;
+    }
+    let before = channel.items().len();
+    let items: Vec<Item> = channel
+        .items()
+        .iter()
+        .filter(|item| !blocklist.rules.iter().any(|rule| rule.matches(item)))
+        .cloned()
+        .collect();
+    channel.set_items(items);
+    before - channel.items().len()
+}
+
+fn reload(blocklist: &mut Blocklist) {
+    let result = fs::metadata(&blocklist.path)
+        .and_then(|meta| meta.modified())
+        .and_then(|modified| {
+            if blocklist.modified != Some(modified) {
+                let content = fs::read_to_string(&blocklist.path)?;
+                blocklist.rules = content.lines().filter_map(Rule::parse).collect();
+                blocklist.modified = Some(modified);
+            }
+            Ok(())
+        });
+    match result {
+        Ok(()) => blocklist.failed = false,
+        Err(err) if !blocklist.failed => {
+            tracing::warn!(
+                "Failed to read blocklist {}: {}",
+                blocklist.path.display(),
+                err
+            );
+            blocklist.failed = true;
+        }
+        Err(_) => {}
+    }
+}
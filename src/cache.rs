@@ -0,0 +1,53 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroUsize;
+
+use lru::LruCache;
+
+pub const DEFAULT_RENDER_CACHE_CAPACITY: usize = 128;
+
+/// LRU cache of rendered article output keyed by item ID.
+///
+/// Each entry remembers a fingerprint of the markdown it was rendered from, so an
+/// entry is re-rendered as soon as the stored markdown changes.
+pub struct RenderCache<T> {
+    entries: LruCache<String, (u64, T)>,
+}
+
+impl<T: Clone> RenderCache<T> {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            entries: LruCache::new(capacity),
+        }
+    }
+
+    pub fn get_or_render<F>(&mut self, key: &str, markdown: &str, render: F) -> T
+    where
+        F: FnOnce(&str) -> T,
+    {
+        let fingerprint = fingerprint(markdown);
+        if let Some((cached_fingerprint, rendered)) = self.entries.get(key) {
+            if *cached_fingerprint == fingerprint {
+                return rendered.clone();
+            }
+        }
+
+        let rendered = render(markdown);
+        self.entries
+            .put(key.to_string(), (fingerprint, rendered.clone()));
+        rendered
+    }
+}
+
+impl<T: Clone> Default for RenderCache<T> {
+    fn default() -> Self {
+        Self::new(DEFAULT_RENDER_CACHE_CAPACITY)
+    }
+}
+
+fn fingerprint(markdown: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    markdown.hash(&mut hasher);
+    hasher.finish()
+}
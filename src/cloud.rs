@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use rss::Cloud;
+use url::Url;
+
+use crate::http_cache;
+
+/// Path, under the server's public URL, that clouds send change notifications to.
+pub const NOTIFY_PATH: &str = "/cloud/notify";
+
+/// Clouds drop subscriptions that aren't renewed within 25 hours.
+const RENEW_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Largest answer read from a cloud; it is a single `<notifyResult>` element.
+const MAX_RESPONSE_BYTES: u64 = 64 * 1024;
+
+/// Feeds the server asked a cloud to notify it about, by feed URL, with the time of the request.
+#[derive(Default)]
+pub struct Subscriptions {
+    requested: HashMap<String, Instant>,
+}
+
+impl Subscriptions {
+    /// Whether the feed's cloud was asked to notify the server recently enough that the
+    /// subscription still holds, so the feed needn't be polled.
+    pub fn is_active(&self, feed_url: &str) -> bool {
+        self.requested
+            .get(feed_url)
+            .is_some_and(|requested| requested.elapsed() < RENEW_AFTER)
+    }
+
+    /// Records a subscription request unless a current one exists; returns whether to send it.
+    pub fn start(&mut self, feed_url: &str) -> bool {
+        if self.is_active(feed_url) {
+            return false;
+        }
+        self.requested.insert(feed_url.to_string(), Instant::now());
+        true
+    }
+
+    pub fn cancel(&mut self, feed_url: &str) {
+        self.requested.remove(feed_url);
+    }
+
+    pub fn contains(&self, feed_url: &str) -> bool {
+        self.requested.contains_key(feed_url)
+    }
+}
+
+/// Whether the server can subscribe to `cloud`. Only the REST-style `http-post` protocol is
+/// supported, not XML-RPC or SOAP.
+pub fn is_supported(cloud: &Cloud) -> bool {
+    cloud.protocol().eq_ignore_ascii_case("http-post")
+}
+
+/// Asks the channel's cloud to POST to `callback` whenever `feed_url` changes. The cloud checks
+/// the callback first by requesting it with a `challenge` to echo back.
+pub async fn subscribe(cloud: &Cloud, feed_url: &str, callback: &Url) -> Result<()> {
+    let endpoint = format!("http://{}:{}{}", cloud.domain(), cloud.port(), cloud.path());
+    let port = callback
+        .port_or_known_default()
+        .context("Public URL has no port")?
+        .to_string();
+    let form = [
+        ("notifyProcedure", ""),
+        ("port", port.as_str()),
+        ("path", callback.path()),
+        ("protocol", "http-post"),
+        ("domain", callback.host_str().unwrap_or_default()),
+        ("url1", feed_url),
+    ];
+    let response = http_cache::post_untrusted(&endpoint, &form, MAX_RESPONSE_BYTES)
+        .await
+        .context(format!("Failed to reach the cloud at {}", endpoint))?;
+    let body = String::from_utf8_lossy(&response.body);
+    let result = Regex::new(r"<notifyResult\s[^>]*>")
+        .unwrap()
+        .find(&body)
+        .map(|tag| {
+            (
+                attribute(tag.as_str(), "success"),
+                attribute(tag.as_str(), "msg"),
+            )
+        });
+    match result {
+        Some((Some(success), _)) if success.eq_ignore_ascii_case("true") => Ok(()),
+        Some((_, msg)) => anyhow::bail!(
+            "The cloud at {} refused: {}",
+            endpoint,
+            msg.unwrap_or_default()
+        ),
+        None if response.status.is_success() => Ok(()),
+        None => anyhow::bail!("The cloud at {} answered {}", endpoint, response.status),
+    }
+}
+
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    Regex::new(&format!(r#"\s{}\s*=\s*["']([^"']*)["']"#, name))
+        .unwrap()
+        .captures(tag)
+        .map(|caps| caps[1].to_string())
+}
@@ -1,9 +1,14 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use rss::Channel;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use url::Url;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+use crate::db::StoreOptions;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Config {
     #[serde(default)]
     pub rsshub: RssHubConfig,
@@ -11,6 +16,209 @@ pub struct Config {
     pub rss: Vec<FeedItem>,
     #[serde(default)]
     pub rsshub_feeds: Vec<FeedItem>,
+    /// HTML pages turned into feeds via CSS selectors.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scraper_feeds: Vec<FeedItem>,
+    /// IMAP mailboxes whose newsletters are read as virtual feeds.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub newsletters: Vec<NewsletterConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugins: Option<PluginsConfig>,
+    /// Items listed when a feed is opened in the TUI or web UI; more are loaded on request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item_limit: Option<usize>,
+    /// Language of the TUI and web UI (`en` or `zh`); defaults to the one in `LANG`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// strftime format of absolute item dates, e.g. `%Y-%m-%d` or `%b %e, %I:%M %p`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_format: Option<String>,
+    /// Command the TUI opens articles with instead of its article view, e.g. `glow -p`, `bat`
+    /// or `$PAGER`. The article's markdown file is passed as the last argument.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub viewer: Option<String>,
+    /// Rules for ranking stored articles by interest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scoring: Option<ScoringConfig>,
+    /// CSS applied to the article container of the web UI. Declarations style the container
+    /// itself and nested rules its content, e.g. `max-width: 40rem; p { line-height: 1.8 }`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub article_css: Option<String>,
+    /// Where the TUI copies links to; defaults to OSC 52 over SSH and the system clipboard
+    /// otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clipboard: Option<ClipboardMode>,
+    /// Whether the TUI makes URLs clickable with OSC 8 hyperlinks; defaults to on in terminals
+    /// known to support them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hyperlinks: Option<bool>,
+    /// Size in MiB the article store (markdown, images and logs) is kept under; past it the
+    /// oldest unstarred articles of any feed are pruned after each store.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_store_mb: Option<u64>,
+    /// Directory of the article store (index, read state, logs, articles and images) in place of
+    /// `data/articles`, so each config can keep its own archive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub store_dir: Option<PathBuf>,
+    /// Hosts treated as trackers on top of the built-in list: their images, scripts and iframes
+    /// are removed from articles. Subdomains match too.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tracker_hosts: Vec<String>,
+    /// File of domains, link patterns and authors whose items are dropped as feeds are
+    /// fetched, before they're listed or stored. Edits apply from the next fetch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocklist: Option<PathBuf>,
+    /// Backups taken automatically while the server runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup: Option<BackupConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BackupConfig {
+    /// Directory the backups are written to.
+    #[serde(default = "default_backup_dir")]
+    pub dir: PathBuf,
+    #[serde(default = "default_backup_interval_hours")]
+    pub interval_hours: u64,
+    /// Newest backups kept in `dir`; older ones are deleted. 0 keeps them all.
+    #[serde(default = "default_backup_keep")]
+    pub keep: usize,
+}
+
+fn default_backup_dir() -> PathBuf {
+    PathBuf::from("data/backups")
+}
+
+fn default_backup_interval_hours() -> u64 {
+    24
+}
+
+fn default_backup_keep() -> usize {
+    7
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipboardMode {
+    /// Through `pbcopy`, `wl-copy`, `xclip`, `xsel` or `clip.exe`, whichever is installed.
+    System,
+    /// An OSC 52 escape sequence asking the terminal to set its clipboard, which reaches the
+    /// local clipboard through SSH.
+    Osc52,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScoringConfig {
+    /// Sort `/feeds/all.xml` and the archive listing by score unless the request says otherwise.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub sort_by_score: bool,
+    /// Points for a feed whose stored articles have all been opened, scaled by the share that
+    /// was.
+    #[serde(default = "default_read_weight")]
+    pub read_weight: f64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<KeywordRule>,
+}
+
+fn default_read_weight() -> f64 {
+    1.0
+}
+
+/// Adds `weight` (which may be negative) to articles whose title contains `pattern`, ignoring
+/// case.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct KeywordRule {
+    pub pattern: String,
+    pub weight: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewsletterConfig {
+    pub name: String,
+    pub host: String,
+    #[serde(default = "default_imap_port")]
+    pub port: u16,
+    /// May be a secret reference; see [`resolve_secret`].
+    pub username: String,
+    /// May be a secret reference; see [`resolve_secret`].
+    pub password: String,
+    #[serde(default = "default_mailbox")]
+    pub mailbox: String,
+    /// Only messages from one of these senders are included; empty matches all.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub from: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    /// Most recent matching messages turned into items.
+    #[serde(default = "default_max_messages")]
+    pub max_messages: usize,
+    /// Seconds between background polls in server mode.
+    #[serde(default = "default_poll_interval")]
+    pub poll_interval: u64,
+}
+
+/// Credential values starting with `env:` name an environment variable holding the secret, and
+/// values starting with `file:` a file holding it (trailing line breaks are ignored). Anything
+/// else is the secret itself. References are resolved when the credential is used, so saving the
+/// config never writes the secret into it.
+pub fn resolve_secret(value: &str) -> Result<String> {
+    if let Some(name) = value.strip_prefix("env:") {
+        return std::env::var(name)
+            .context(format!("Secret environment variable {} is not set", name));
+    }
+    if let Some(path) = value.strip_prefix("file:") {
+        let secret =
+            fs::read_to_string(path).context(format!("Failed to read secret file: {:?}", path))?;
+        return Ok(secret.trim_end_matches(['\r', '\n']).to_string());
+    }
+    Ok(value.to_string())
+}
+
+fn default_imap_port() -> u16 {
+    993
+}
+
+fn default_mailbox() -> String {
+    "INBOX".to_string()
+}
+
+fn default_max_messages() -> usize {
+    50
+}
+
+fn default_poll_interval() -> u64 {
+    30 * 60
+}
+
+impl NewsletterConfig {
+    fn to_feed(&self) -> Feed {
+        Feed {
+            name: self.name.clone(),
+            url: format!("imap://{}@{}/{}", self.username, self.host, self.mailbox),
+            is_rsshub: false,
+            rsshub_host: None,
+            max_age: Some(self.poll_interval),
+            timeout: None,
+            max_size_mb: None,
+            max_items: None,
+            monthly_budget_mb: None,
+            selectors: None,
+            category: None,
+            weight: None,
+            article_css: None,
+            language: None,
+            full_content: false,
+            newsletter: Some(self.clone()),
+            max_store_mb: None,
+            store_dir: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PluginsConfig {
+    /// Directory of `.rhai` plugin scripts.
+    pub dir: PathBuf,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -30,6 +238,157 @@ impl Default for RssHubConfig {
 pub struct FeedItem {
     pub name: String,
     pub url: String,
+    /// Seconds a fetched channel stays fresh in the server cache.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age: Option<u64>,
+    /// Seconds before a fetch of this feed is abandoned.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Largest feed download accepted, in MiB; bigger responses fail the fetch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size_mb: Option<u64>,
+    /// Newest articles kept in the archive for this feed; starred articles are always kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_items: Option<usize>,
+    /// Monthly download budget in MiB (feed plus images); once spent, images are left remote.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monthly_budget_mb: Option<u64>,
+    /// Only used by `scraper_feeds`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selectors: Option<ScraperSelectors>,
+    /// The name was taken from the channel title and follows it on `feeds sync-titles`.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub auto_name: bool,
+    /// Channel description, recorded from the feed itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Channel `<link>`, usually the site's home page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub site_link: Option<String>,
+    /// Paused feeds stay in the config but are not listed or fetched.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub paused: bool,
+    /// Heading the feed is grouped under in the TUI feed list; nested categories are separated
+    /// by `/`, as in `Tech/Rust`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// Points added to the interest score of every article from this feed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<f64>,
+    /// CSS for this feed's articles in the web UI, applied after the global `article_css`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub article_css: Option<String>,
+    /// Language of the feed's articles (e.g. `zh-CN`), for feeds whose `<language>` is missing
+    /// or wrong.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Offer each article's full text, extracted from its web page, in the web UI's reader
+    /// mode; for feeds that only carry summaries.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub full_content: bool,
+    /// Directory this feed's article markdown is written under instead of the article store,
+    /// e.g. a synced folder. The index, read state and images stay in the store.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub store_dir: Option<PathBuf>,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// CSS selectors that turn an HTML page into feed items. `title`, `link`, `date` and
+/// `description` are evaluated inside each element matched by `item`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScraperSelectors {
+    pub item: String,
+    pub title: String,
+    pub link: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl FeedItem {
+    pub fn new(name: &str, url: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            url: url.to_string(),
+            max_age: None,
+            timeout: None,
+            max_size_mb: None,
+            max_items: None,
+            monthly_budget_mb: None,
+            selectors: None,
+            auto_name: false,
+            description: None,
+            site_link: None,
+            paused: false,
+            category: None,
+            weight: None,
+            article_css: None,
+            language: None,
+            full_content: false,
+            store_dir: None,
+        }
+    }
+
+    /// A subscription named after the channel's title, falling back to the URL.
+    pub fn from_channel(url: &str, channel: &Channel) -> Self {
+        let mut item = Self::new(url, url);
+        item.auto_name = true;
+        item.update_from_channel(channel, false);
+        item
+    }
+
+    /// Records the channel's description and site link. The name follows the channel title if it
+    /// was auto-filled, or always when `rename` is set. Returns whether anything changed.
+    pub fn update_from_channel(&mut self, channel: &Channel, rename: bool) -> bool {
+        let before = (
+            self.name.clone(),
+            self.description.clone(),
+            self.site_link.clone(),
+        );
+        let title = channel.title().trim();
+        if (self.auto_name || rename) && !title.is_empty() {
+            self.name = title.to_string();
+        }
+        self.description = Some(channel.description().trim())
+            .filter(|description| !description.is_empty())
+            .map(str::to_string);
+        self.site_link = Some(channel.link().trim())
+            .filter(|link| !link.is_empty())
+            .map(str::to_string);
+        before
+            != (
+                self.name.clone(),
+                self.description.clone(),
+                self.site_link.clone(),
+            )
+    }
+
+    pub fn to_feed(&self, rsshub_host: Option<&str>) -> Feed {
+        Feed {
+            name: self.name.clone(),
+            url: self.url.clone(),
+            is_rsshub: rsshub_host.is_some(),
+            rsshub_host: rsshub_host.map(str::to_string),
+            max_age: self.max_age,
+            timeout: self.timeout,
+            max_size_mb: self.max_size_mb,
+            max_items: self.max_items,
+            monthly_budget_mb: self.monthly_budget_mb,
+            selectors: self.selectors.clone(),
+            category: self.category.clone(),
+            weight: self.weight,
+            article_css: self.article_css.clone(),
+            language: self.language.clone(),
+            full_content: self.full_content,
+            newsletter: None,
+            max_store_mb: None,
+            store_dir: self.store_dir.clone(),
+        }
+    }
 }
 
 // Unified struct for internal use
@@ -39,6 +398,92 @@ pub struct Feed {
     pub url: String,
     pub is_rsshub: bool,
     pub rsshub_host: Option<String>,
+    pub max_age: Option<u64>,
+    pub timeout: Option<u64>,
+    pub max_size_mb: Option<u64>,
+    pub max_items: Option<usize>,
+    pub monthly_budget_mb: Option<u64>,
+    pub selectors: Option<ScraperSelectors>,
+    pub category: Option<String>,
+    pub weight: Option<f64>,
+    pub article_css: Option<String>,
+    pub language: Option<String>,
+    pub full_content: bool,
+    pub newsletter: Option<NewsletterConfig>,
+    /// The config's global `max_store_mb`.
+    pub max_store_mb: Option<u64>,
+    pub store_dir: Option<PathBuf>,
+}
+
+/// Separates the levels of a nested category, as in `Tech/Rust`.
+pub const CATEGORY_SEPARATOR: char = '/';
+
+/// Whether `category` is `ancestor` itself or nested anywhere below it.
+pub fn in_category(category: &str, ancestor: &str) -> bool {
+    category
+        .strip_prefix(ancestor)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(CATEGORY_SEPARATOR))
+}
+
+/// Cache max age used for feeds that don't set `max_age`.
+pub const DEFAULT_FEED_MAX_AGE_SECS: u64 = 15 * 60;
+
+/// Fetch timeout used for feeds that don't set `timeout`.
+pub const DEFAULT_FETCH_TIMEOUT_SECS: u64 = 30;
+
+/// Download size limit used for feeds that don't set `max_size_mb`.
+pub const DEFAULT_MAX_FEED_SIZE_MB: u64 = 20;
+
+impl Feed {
+    /// The feed's URL to show: without a user name or password, such as the IMAP user name in a
+    /// newsletter's `imap://` URL. `url` itself keeps them, since stored article IDs include it.
+    pub fn display_url(&self) -> String {
+        match Url::parse(&self.url) {
+            Ok(mut url) if !url.username().is_empty() || url.password().is_some() => {
+                let _ = url.set_username("");
+                let _ = url.set_password(None);
+                url.to_string()
+            }
+            _ => self.url.clone(),
+        }
+    }
+
+    pub fn cache_max_age(&self) -> Duration {
+        Duration::from_secs(self.max_age.unwrap_or(DEFAULT_FEED_MAX_AGE_SECS))
+    }
+
+    pub fn fetch_timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout.unwrap_or(DEFAULT_FETCH_TIMEOUT_SECS))
+    }
+
+    pub fn max_feed_bytes(&self) -> u64 {
+        self.max_size_mb.unwrap_or(DEFAULT_MAX_FEED_SIZE_MB) * 1024 * 1024
+    }
+
+    pub fn monthly_budget_bytes(&self) -> Option<u64> {
+        self.monthly_budget_mb.map(|mb| mb * 1024 * 1024)
+    }
+
+    pub fn max_store_bytes(&self) -> Option<u64> {
+        self.max_store_mb.map(|mb| mb * 1024 * 1024)
+    }
+
+    /// The configured `language` as a BCP 47 tag, if it is one.
+    pub fn language_override(&self) -> Option<String> {
+        self.language.as_deref().and_then(crate::feed::language_tag)
+    }
+
+    /// Storage settings for a channel just fetched from this feed, taking the bytes downloaded
+    /// since the last store into its bandwidth account.
+    pub fn store_options(&self) -> StoreOptions {
+        StoreOptions {
+            max_items: self.max_items,
+            monthly_budget_bytes: self.monthly_budget_bytes(),
+            feed_bytes: crate::feed::take_downloaded_bytes(self),
+            max_store_bytes: self.max_store_bytes(),
+            store_dir: self.store_dir.clone(),
+        }
+    }
 }
 
 impl Config {
@@ -46,6 +491,17 @@ impl Config {
         let content =
             fs::read_to_string(path).context(format!("Failed to read config file: {:?}", path))?;
         let config: Config = toml::from_str(&content).context("Failed to parse config file")?;
+        if let Some(item) = config
+            .scraper_feeds
+            .iter()
+            .find(|item| item.selectors.is_none())
+        {
+            bail!(
+                "Scraper feed {:?} ({}) has no [scraper_feeds.selectors]",
+                item.name,
+                item.url
+            );
+        }
         Ok(config)
     }
 
@@ -55,40 +511,210 @@ impl Config {
         Ok(())
     }
 
+    pub fn add_feed_item(&mut self, item: FeedItem) {
+        self.rss.push(item);
+    }
+
+    /// The existing subscription whose URL normalizes to the same feed as `url`.
+    pub fn find_duplicate(&self, url: &str) -> Option<Feed> {
+        let normalized = normalize_feed_url(url);
+        self.feeds(true).into_iter().find(|feed| {
+            let existing = if feed.is_rsshub {
+                crate::feed::build_feed_url(feed).unwrap_or_else(|_| feed.url.clone())
+            } else {
+                feed.url.clone()
+            };
+            normalize_feed_url(&existing) == normalized
+        })
+    }
+
+    /// Subscriptions paired with the feed they resolve to, so entries can be updated from
+    /// fetched channels.
+    pub fn subscriptions_mut(&mut self) -> Vec<(&mut FeedItem, Feed)> {
+        let host = self.rsshub.host.clone();
+        let rss = self.rss.iter_mut().map(|item| {
+            let feed = item.to_feed(None);
+            (item, feed)
+        });
+        let rsshub = self.rsshub_feeds.iter_mut().map(|item| {
+            let feed = item.to_feed(Some(&host));
+            (item, feed)
+        });
+        let scraper = self.scraper_feeds.iter_mut().map(|item| {
+            let feed = item.to_feed(None);
+            (item, feed)
+        });
+        rss.chain(rsshub).chain(scraper).collect()
+    }
+
+    /// The global `article_css` followed by the feed's own, or `None` when neither is set.
+    pub fn article_css(&self, feed: Option<&Feed>) -> Option<String> {
+        let snippets: Vec<&str> = [
+            self.article_css.as_deref(),
+            feed.and_then(|feed| feed.article_css.as_deref()),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|css| !css.trim().is_empty())
+        .collect();
+        (!snippets.is_empty()).then(|| snippets.join("\n"))
+    }
+
     pub fn get_all_feeds(&self) -> Vec<Feed> {
+        self.feeds(false)
+    }
+
+    fn feeds(&self, include_paused: bool) -> Vec<Feed> {
         let mut feeds = Vec::new();
+        let active = |item: &&FeedItem| include_paused || !item.paused;
+
+        for item in self.rss.iter().filter(active) {
+            feeds.push(item.to_feed(None));
+        }
+
+        for item in self.rsshub_feeds.iter().filter(active) {
+            feeds.push(item.to_feed(Some(&self.rsshub.host)));
+        }
 
-        for item in &self.rss {
-            feeds.push(Feed {
-                name: item.name.clone(),
-                url: item.url.clone(),
-                is_rsshub: false,
-                rsshub_host: None,
-            });
+        for item in self.scraper_feeds.iter().filter(active) {
+            feeds.push(item.to_feed(None));
         }
 
-        for item in &self.rsshub_feeds {
-            feeds.push(Feed {
-                name: item.name.clone(),
-                url: item.url.clone(),
-                is_rsshub: true,
-                rsshub_host: Some(self.rsshub.host.clone()),
-            });
+        for newsletter in &self.newsletters {
+            feeds.push(newsletter.to_feed());
         }
 
+        for feed in &mut feeds {
+            feed.max_store_mb = self.max_store_mb;
+        }
         feeds
     }
+
+    /// Swaps the subscription with this URL (or RSSHub route) with the listed feed above or below
+    /// it in the same section and category. Returns whether it moved.
+    pub fn move_feed(&mut self, url: &str, up: bool) -> bool {
+        let sections = [
+            &mut self.rss,
+            &mut self.rsshub_feeds,
+            &mut self.scraper_feeds,
+        ];
+        for items in sections {
+            let Some(index) = items.iter().position(|item| item.url == url) else {
+                continue;
+            };
+            let category = items[index].category.clone();
+            let listed = |item: &FeedItem| !item.paused && item.category == category;
+            let neighbour = if up {
+                items[..index].iter().rposition(listed)
+            } else {
+                items[index + 1..]
+                    .iter()
+                    .position(listed)
+                    .map(|offset| index + 1 + offset)
+            };
+            return match neighbour {
+                Some(neighbour) => {
+                    items.swap(index, neighbour);
+                    true
+                }
+                None => false,
+            };
+        }
+        false
+    }
+
+    /// Pauses or resumes every subscription with this URL (or RSSHub route).
+    pub fn set_paused(&mut self, url: &str, paused: bool) {
+        for (item, _) in self.subscriptions_mut() {
+            if item.url == url {
+                item.paused = paused;
+            }
+        }
+    }
+
+    /// Files the subscription with this URL (or RSSHub route) under `category`.
+    pub fn set_category(&mut self, url: &str, category: Option<String>) {
+        for item in self
+            .rss
+            .iter_mut()
+            .chain(&mut self.rsshub_feeds)
+            .chain(&mut self.scraper_feeds)
+            .filter(|item| item.url == url)
+        {
+            item.category = category.clone();
+        }
+    }
+
+    /// Removes every subscription with this URL (or RSSHub route).
+    pub fn remove_feed(&mut self, url: &str) {
+        self.rss.retain(|item| item.url != url);
+        self.rsshub_feeds.retain(|item| item.url != url);
+        self.scraper_feeds.retain(|item| item.url != url);
+    }
+}
+
+/// Canonical form of a feed or article URL for duplicate detection: ignores the http/https scheme, a
+/// leading `www.`, host case, trailing slashes, fragments and `utm_*` tracking parameters.
+pub fn normalize_feed_url(url: &str) -> String {
+    let Ok(parsed) = Url::parse(url.trim()) else {
+        return url.trim().trim_end_matches('/').to_string();
+    };
+    let host = parsed.host_str().unwrap_or_default().to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+    let port = parsed
+        .port()
+        .map(|port| format!(":{}", port))
+        .unwrap_or_default();
+    let path = parsed.path().trim_end_matches('/');
+    let mut query: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(key, _)| !key.starts_with("utm_"))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    query.sort();
+    let query = query
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let scheme = match parsed.scheme() {
+        "http" | "https" => "http",
+        other => other,
+    };
+    if query.is_empty() {
+        format!("{}://{}{}{}", scheme, host, port, path)
+    } else {
+        format!("{}://{}{}{}?{}", scheme, host, port, path, query)
+    }
+}
+
+/// Loads the plugins declared in the config, if any.
+pub fn install_plugins(config: &Config) -> Result<()> {
+    if let Some(plugins) = &config.plugins {
+        crate::plugins::install(&plugins.dir)?;
+    }
+    Ok(())
 }
 
 pub fn load_or_create_config(path: &Path) -> Result<Config> {
     if !path.exists() {
-        println!(
+        say!(
             "Config file not found at {:?}. Creating default config.",
             path
         );
         create_default_config(path)?;
     }
-    Config::load(path)
+    let config = Config::load(path)?;
+    if let Some(language) = &config.language {
+        crate::i18n::set_language(language);
+    }
+    if let Some(format) = &config.date_format {
+        crate::dates::set_format(format)?;
+    }
+    crate::hygiene::set_tracker_hosts(&config.tracker_hosts);
+    crate::blocklist::set_path(config.blocklist.as_deref());
+    Ok(config)
 }
 
 pub fn create_default_config(path: &Path) -> Result<()> {
@@ -96,14 +722,12 @@ pub fn create_default_config(path: &Path) -> Result<()> {
         rsshub: RssHubConfig {
             host: "https://rsshub.app".to_string(),
         },
-        rss: vec![FeedItem {
-            name: "Hacker News".to_string(),
-            url: "https://news.ycombinator.com/rss".to_string(),
-        }],
-        rsshub_feeds: vec![FeedItem {
-            name: "GitHub Trending".to_string(),
-            url: "/github/trending/daily".to_string(),
-        }],
+        rss: vec![FeedItem::new(
+            "Hacker News",
+            "https://news.ycombinator.com/rss",
+        )],
+        rsshub_feeds: vec![FeedItem::new("GitHub Trending", "/github/trending/daily")],
+        ..Config::default()
     };
     config.save(path)?;
     Ok(())
@@ -0,0 +1,122 @@
+use std::fs;
+use std::io::{ErrorKind, Read};
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+
+/// Environment variable holding the passphrase that article storage is encrypted with.
+pub const PASSPHRASE_ENV: &str = "RSS_READER_PASSPHRASE";
+
+/// Prefix of every encrypted file, followed by the nonce and the ciphertext.
+const MAGIC: &[u8] = b"RSSENC1\0";
+const NONCE_LEN: usize = 24;
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+
+/// At-rest encryption of stored articles with XChaCha20-Poly1305.
+pub struct Cipher {
+    cipher: XChaCha20Poly1305,
+}
+
+impl Cipher {
+    /// Loads a 32-byte key (raw or hex encoded) from `path`, generating one if the file doesn't
+    /// exist yet.
+    pub fn from_key_file(path: &Path) -> Result<Self> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                let key = XChaCha20Poly1305::generate_key(&mut OsRng);
+                write_private(path, hex::encode(key).as_bytes())
+                    .context(format!("Failed to create key file {:?}", path))?;
+                say!("Generated a new encryption key in {:?}", path);
+                key.to_vec()
+            }
+            Err(err) => return Err(err).context(format!("Failed to read key file {:?}", path)),
+        };
+        let key = match hex::decode(String::from_utf8_lossy(&bytes).trim()) {
+            Ok(key) if key.len() == KEY_LEN => key,
+            _ if bytes.len() == KEY_LEN => bytes,
+            _ => return Err(anyhow!("Key file {:?} must hold a 32-byte key", path)),
+        };
+        Ok(Self {
+            cipher: XChaCha20Poly1305::new(Key::from_slice(&key)),
+        })
+    }
+
+    /// Derives the key from a passphrase with Argon2id. The salt is kept in `salt_path` and
+    /// created on first use.
+    pub fn from_passphrase(passphrase: &str, salt_path: &Path) -> Result<Self> {
+        let salt = match fs::read(salt_path) {
+            Ok(salt) => salt,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                let mut salt = vec![0; SALT_LEN];
+                OsRng.fill_bytes(&mut salt);
+                write_private(salt_path, &salt).context("Failed to write key salt")?;
+                salt
+            }
+            Err(err) => return Err(err).context("Failed to read key salt"),
+        };
+        let mut key = [0; KEY_LEN];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|err| anyhow!("Failed to derive key from passphrase: {}", err))?;
+        Ok(Self {
+            cipher: XChaCha20Poly1305::new(Key::from_slice(&key)),
+        })
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| anyhow!("Encryption failed"))?;
+        Ok([MAGIC, nonce.as_slice(), &ciphertext].concat())
+    }
+
+    /// Decrypts `data`, passing through files written before encryption was turned on.
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        if !is_encrypted(data) {
+            return Ok(data.to_vec());
+        }
+        let body = &data[MAGIC.len()..];
+        if body.len() < NONCE_LEN {
+            return Err(anyhow!("Encrypted file is truncated"));
+        }
+        let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow!("Decryption failed: wrong key or corrupted file"))
+    }
+}
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Whether the file at `path` starts like an encrypted file, reading only its first bytes.
+pub fn is_encrypted_file(path: &Path) -> bool {
+    let mut head = [0; MAGIC.len()];
+    fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut head))
+        .map(|()| is_encrypted(&head))
+        .unwrap_or(false)
+}
+
+/// Writes a file only its owner can read.
+pub fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, contents)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use std::sync::{LazyLock, Mutex};
+
+use anyhow::Result;
+use chrono::format::StrftimeItems;
+use chrono::{DateTime, Local};
+
+/// strftime format of absolute dates unless the config sets `date_format`.
+pub const DEFAULT_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+static FORMAT: LazyLock<Mutex<String>> = LazyLock::new(|| Mutex::new(DEFAULT_FORMAT.to_string()));
+
+/// Days after which relative dates give way to the calendar date.
+const RELATIVE_DAYS: i64 = 7;
+
+/// Parses an RSS (RFC 2822) or Atom and archive (RFC 3339) date into the local timezone.
+pub fn parse(raw: &str) -> Option<DateTime<Local>> {
+    let raw = raw.trim();
+    DateTime::parse_from_rfc2822(raw)
+        .or_else(|_| DateTime::parse_from_rfc3339(raw))
+        .ok()
+        .map(|date| date.with_timezone(&Local))
+}
+
+/// Sets the strftime format of absolute dates, from the config's `date_format`.
+pub fn set_format(format: &str) -> Result<()> {
+    if StrftimeItems::new(format).parse().is_err() {
+        anyhow::bail!("Invalid date_format {:?}", format);
+    }
+    *FORMAT.lock().unwrap_or_else(|err| err.into_inner()) = format.to_string();
+    Ok(())
+}
+
+/// Local date and time in the configured format, by default `2024-03-05 14:30`.
+pub fn absolute(date: &DateTime<Local>) -> String {
+    let format = FORMAT.lock().unwrap_or_else(|err| err.into_inner());
+    date.format(&format).to_string()
+}
+
+/// How long before `now` the date was: `just now`, `5m ago`, `3h ago`, `yesterday`, `4 days
+/// ago`, and the local calendar date once it is a week old or in the future.
+pub fn relative(date: &DateTime<Local>, now: &DateTime<Local>) -> String {
+    let elapsed = *now - *date;
+    let days = (now.date_naive() - date.date_naive()).num_days();
+    if elapsed.num_minutes() < -1 || days >= RELATIVE_DAYS {
+        return date.format("%Y-%m-%d").to_string();
+    }
+    if elapsed.num_minutes() < 1 {
+        String::from(tr!("just now"))
+    } else if elapsed.num_hours() < 1 {
+        tr!("{}m ago", elapsed.num_minutes())
+    } else if days == 0 {
+        tr!("{}h ago", elapsed.num_hours())
+    } else if days == 1 {
+        String::from(tr!("yesterday"))
+    } else {
+        tr!("{} days ago", days)
+    }
+}
+
+/// A feed's date for display, relative to now or absolute. Strings that aren't dates are shown
+/// as they are.
+pub fn display(raw: &str, relative: bool) -> String {
+    match parse(raw) {
+        Some(date) if relative => self::relative(&date, &Local::now()),
+        Some(date) => absolute(&date),
+        None => raw.to_string(),
+    }
+}
+
+/// Absolute date followed by the relative one, e.g. `2024-03-05 14:30 (3h ago)`.
+pub fn display_full(raw: &str) -> String {
+    match parse(raw) {
+        Some(date) => format!("{} ({})", absolute(&date), relative(&date, &Local::now())),
+        None => raw.to_string(),
+    }
+}
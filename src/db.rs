@@ -1,163 +1,2245 @@
 use std::collections::{HashMap, HashSet};
-use std::fs::{self, OpenOptions};
-use std::io::ErrorKind;
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, MutexGuard};
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use comrak::{markdown_to_html, ComrakOptions};
+use futures::future::BoxFuture;
 use html2md::parse_html;
 use regex::Regex;
 use reqwest::header::CONTENT_TYPE;
+use rss::extension::dublincore::DublinCoreExtension;
 use rss::Channel;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use tokio::sync::Semaphore;
 use url::Url;
 
+use crate::config::normalize_feed_url;
+use crate::crypto::{self, Cipher};
+use crate::feed::{self, UpdateHints};
+use crate::figures;
+use crate::footnotes;
+use crate::http_cache;
+use crate::hygiene;
+use crate::math;
+use crate::media;
+use crate::plugins;
+use crate::related;
+
 pub fn default_store_dir() -> PathBuf {
     Path::new("data/articles").to_path_buf()
 }
 
-fn default_image_dir(store_dir: &Path) -> PathBuf {
-    store_dir.join("images")
+fn default_image_dir(store_dir: &Path) -> PathBuf {
+    store_dir.join("images")
+}
+
+/// A row of index.csv describing one stored article.
+#[derive(Debug, Clone)]
+pub struct ArticleRecord {
+    pub id: String,
+    pub time: String,
+    pub title: String,
+    pub feed_name: String,
+    pub path: PathBuf,
+    pub link: Option<String>,
+    /// When the feed last reported the item as updated, if later than `time`.
+    pub updated: Option<String>,
+}
+
+impl ArticleRecord {
+    /// The later of the published and updated times, used to order articles.
+    pub fn last_modified(&self) -> &str {
+        self.updated.as_deref().unwrap_or(&self.time)
+    }
+}
+
+/// Filters for [`Database::query_articles`]; unset fields match every article.
+#[derive(Debug, Clone, Default)]
+pub struct ArticleQuery {
+    pub feed_name: Option<String>,
+    /// Only articles published or updated at or after this time (RFC 3339 or `YYYY-MM-DD`).
+    pub since: Option<String>,
+    /// Only articles published or updated before this time (RFC 3339 or `YYYY-MM-DD`).
+    pub until: Option<String>,
+    /// Case-insensitive substring of the title.
+    pub text: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl ArticleQuery {
+    pub fn feed(feed_name: &str) -> Self {
+        Self {
+            feed_name: Some(feed_name.to_string()),
+            ..Self::default()
+        }
+    }
+
+    fn matches(&self, record: &ArticleRecord) -> bool {
+        let time = parse_query_time(record.last_modified());
+        let after =
+            |bound: &str| matches!((time, parse_query_time(bound)), (Some(t), Some(b)) if t >= b);
+        let before =
+            |bound: &str| matches!((time, parse_query_time(bound)), (Some(t), Some(b)) if t < b);
+        self.feed_name
+            .as_deref()
+            .is_none_or(|feed_name| record.feed_name == feed_name)
+            && self.since.as_deref().is_none_or(after)
+            && self.until.as_deref().is_none_or(before)
+            && self
+                .text
+                .as_deref()
+                .is_none_or(|text| record.title.to_lowercase().contains(&text.to_lowercase()))
+    }
+}
+
+/// Parses an RFC 3339 time or a bare `YYYY-MM-DD` date (midnight UTC).
+pub fn parse_query_time(raw: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(raw)
+        .map(|time| time.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            chrono::NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+                .ok()?
+                .and_hms_opt(0, 0, 0)
+                .map(|time| time.and_utc())
+        })
+}
+
+/// Read/starred flags of one article; `updated_at` drives sync conflict resolution.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ItemState {
+    pub id: String,
+    pub read: bool,
+    pub starred: bool,
+    pub updated_at: String,
+}
+
+/// One opening of an article in the TUI or web UI. Only the ID is logged; titles come from the
+/// index, so nothing readable is kept outside it when articles are encrypted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HistoryEntry {
+    opened_at: String,
+    id: String,
+}
+
+/// How far into an article the reader had scrolled, as a fraction of its length, so the TUI and
+/// the web UI can resume at the same spot whatever the screen size.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ReadingPosition {
+    id: String,
+    position: f64,
+    updated_at: String,
+}
+
+/// Positions this close to the end mean the article was read to the end, and aren't kept.
+const FINISHED_POSITION: f64 = 0.98;
+
+/// Another stored article with the same link as the one being read: the same story syndicated by
+/// another feed, or stored before under another ID.
+#[derive(Debug, Clone)]
+pub struct PreviousCopy {
+    pub record: ArticleRecord,
+    /// When it was last opened (RFC 3339), if it was read.
+    pub read_at: Option<String>,
+}
+
+/// Mutes a feed, or hides the items whose title contains `pattern`, until `until` (RFC 3339).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snooze {
+    /// The feed it applies to; every feed when unset.
+    pub feed_name: Option<String>,
+    /// Case-insensitive title substring; the whole feed is muted when unset.
+    pub pattern: Option<String>,
+    pub until: String,
+}
+
+impl Snooze {
+    fn is_active(&self, now: DateTime<Utc>) -> bool {
+        DateTime::parse_from_rfc3339(&self.until).is_ok_and(|until| until > now)
+    }
+
+    fn applies_to(&self, feed_name: &str) -> bool {
+        self.feed_name
+            .as_deref()
+            .is_none_or(|name| name == feed_name)
+    }
+
+    /// Whether the whole feed is muted.
+    pub fn mutes(&self, feed_name: &str) -> bool {
+        self.pattern.is_none() && self.applies_to(feed_name)
+    }
+
+    /// Whether the feed's item titled `title` matches the pattern.
+    pub fn hides(&self, feed_name: &str, title: &str) -> bool {
+        self.pattern.as_deref().is_some_and(|pattern| {
+            self.applies_to(feed_name) && title.to_lowercase().contains(&pattern.to_lowercase())
+        })
+    }
+}
+
+/// Whether an item is muted with its feed or hidden by a pattern.
+pub fn is_snoozed(snoozes: &[Snooze], feed_name: &str, title: &str) -> bool {
+    snoozes
+        .iter()
+        .any(|snooze| snooze.mutes(feed_name) || snooze.hides(feed_name, title))
+}
+
+/// Refresh runs kept in runs.csv; older ones are dropped as new ones start.
+const MAX_RUNS: usize = 100;
+
+/// A row of runs.csv. Rows are appended as a refresh run goes: one when it starts, one per feed
+/// as it is fetched, failed or skipped, and one when it ends, so a run cut short still shows how
+/// far it got.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RunRecord {
+    /// Start time of the run (RFC 3339), identifying it.
+    run: String,
+    time: String,
+    /// `start`, `end`, or the feed's `RunOutcome`.
+    event: String,
+    #[serde(default)]
+    feed_name: Option<String>,
+    #[serde(default)]
+    items: usize,
+    #[serde(default)]
+    added: usize,
+    #[serde(default)]
+    message: Option<String>,
+}
+
+/// A `refresh` run over the configured feeds, from runs.csv.
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct Run {
+    /// RFC 3339; identifies the run.
+    pub started_at: String,
+    /// Unset while the run is going on, or when it was cut short.
+    pub finished_at: Option<String>,
+    /// In the order they were refreshed.
+    pub feeds: Vec<RunFeed>,
+}
+
+impl Run {
+    pub fn added(&self) -> usize {
+        self.feeds.iter().map(|feed| feed.added).sum()
+    }
+
+    pub fn count(&self, outcome: RunOutcome) -> usize {
+        self.feeds
+            .iter()
+            .filter(|feed| feed.outcome == outcome)
+            .count()
+    }
+}
+
+/// What a refresh run did with one feed.
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct RunFeed {
+    pub feed_name: String,
+    pub outcome: RunOutcome,
+    /// Items in the fetched feed.
+    pub items: usize,
+    /// Items that were new to the archive.
+    pub added: usize,
+    /// The error, or why the feed was skipped.
+    pub message: Option<String>,
+    pub time: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RunOutcome {
+    Fetched,
+    Failed,
+    /// Not due yet by the feed's `<ttl>`, `<skipHours>` or `<skipDays>`.
+    Skipped,
+}
+
+impl RunOutcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            RunOutcome::Fetched => "fetched",
+            RunOutcome::Failed => "failed",
+            RunOutcome::Skipped => "skipped",
+        }
+    }
+
+    fn parse(event: &str) -> Option<Self> {
+        [RunOutcome::Fetched, RunOutcome::Failed, RunOutcome::Skipped]
+            .into_iter()
+            .find(|outcome| outcome.as_str() == event)
+    }
+}
+
+/// Time of the last successful fetch of a feed, kept so offline views can say how old they are,
+/// and the channel's `<ttl>`, `<skipHours>`, `<skipDays>` and `<language>` at the time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FetchRecord {
+    feed_name: String,
+    fetched_at: String,
+    #[serde(default)]
+    ttl: Option<u64>,
+    /// Space-separated hours.
+    #[serde(default)]
+    skip_hours: String,
+    /// Space-separated day names.
+    #[serde(default)]
+    skip_days: String,
+    /// BCP 47 tag, see `feed::language_tag`.
+    #[serde(default)]
+    language: Option<String>,
+}
+
+impl FetchRecord {
+    fn update_hints(&self) -> UpdateHints {
+        UpdateHints {
+            ttl_minutes: self.ttl,
+            ..UpdateHints::parse(
+                None,
+                self.skip_hours.split_whitespace(),
+                self.skip_days.split_whitespace(),
+            )
+        }
+    }
+}
+
+/// Longest feed or title slug in article paths.
+const MAX_SLUG_CHARS: usize = 60;
+
+/// Hex digits of the article ID kept in its file name to tell same-titled articles apart.
+const SHORT_ID_CHARS: usize = 12;
+
+/// Extension prefixes under which feeds carry Atom's `<updated>` element in RSS items.
+const ATOM_PREFIXES: [&str; 2] = ["atom", "a10"];
+
+/// Bytes downloaded for one feed in one calendar month (`YYYY-MM`).
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct BandwidthRecord {
+    pub feed_name: String,
+    pub month: String,
+    /// Feed documents (XML or scraped pages).
+    pub feed_bytes: u64,
+    /// Images localized into the store.
+    pub image_bytes: u64,
+}
+
+impl BandwidthRecord {
+    pub fn total(&self) -> u64 {
+        self.feed_bytes + self.image_bytes
+    }
+}
+
+/// Bytes the articles of one feed take in the store.
+#[derive(Debug, Clone, Default)]
+pub struct FeedUsage {
+    pub feed_name: String,
+    pub articles: usize,
+    pub markdown_bytes: u64,
+    /// Images shown only by this feed's articles.
+    pub image_bytes: u64,
+}
+
+impl FeedUsage {
+    pub fn total(&self) -> u64 {
+        self.markdown_bytes + self.image_bytes
+    }
+}
+
+/// How the size of the article store breaks down.
+#[derive(Debug, Clone, Default)]
+pub struct DiskUsage {
+    /// Largest first.
+    pub feeds: Vec<FeedUsage>,
+    /// Images shown by articles of more than one feed.
+    pub shared_image_bytes: u64,
+    /// Images no stored article shows any more.
+    pub unreferenced_image_bytes: u64,
+    /// The index, read state, history and logs.
+    pub metadata_bytes: u64,
+    /// Cached feed and page responses.
+    pub http_cache_bytes: u64,
+}
+
+impl DiskUsage {
+    pub fn markdown_bytes(&self) -> u64 {
+        self.feeds.iter().map(|feed| feed.markdown_bytes).sum()
+    }
+
+    pub fn image_bytes(&self) -> u64 {
+        self.feeds.iter().map(|feed| feed.image_bytes).sum::<u64>()
+            + self.shared_image_bytes
+            + self.unreferenced_image_bytes
+    }
+
+    pub fn total(&self) -> u64 {
+        self.markdown_bytes() + self.image_bytes() + self.metadata_bytes + self.http_cache_bytes
+    }
+}
+
+/// Per-feed settings for [`Database::store_channel`].
+#[derive(Debug, Clone, Default)]
+pub struct StoreOptions {
+    /// Newest articles kept for the feed; older unstarred ones are pruned.
+    pub max_items: Option<usize>,
+    /// Once the feed has downloaded this much in the current month, images are no longer
+    /// localized.
+    pub monthly_budget_bytes: Option<u64>,
+    /// Bytes downloaded fetching the channel, added to the feed's bandwidth account.
+    pub feed_bytes: u64,
+    /// Size the whole store is kept under; past it the oldest unstarred articles of any feed
+    /// are pruned.
+    pub max_store_bytes: Option<u64>,
+    /// Directory the feed's new articles are written under instead of the store directory.
+    pub store_dir: Option<PathBuf>,
+}
+
+/// The calendar month bandwidth is currently accounted to, as `YYYY-MM`.
+pub fn current_month() -> String {
+    Utc::now().format("%Y-%m").to_string()
+}
+
+/// Items rebuilt from the archive carry their stored ID in the guid, behind this prefix.
+const ARCHIVE_GUID_PREFIX: &str = "archive:";
+
+#[derive(Clone)]
+pub struct Database {
+    store_dir: PathBuf,
+    index_path: PathBuf,
+    state_path: PathBuf,
+    fetch_log_path: PathBuf,
+    bandwidth_path: PathBuf,
+    history_path: PathBuf,
+    snooze_path: PathBuf,
+    position_path: PathBuf,
+    runs_path: PathBuf,
+    /// Serializes read-modify-writes of state.csv and the other logs, across threads and
+    /// processes.
+    state_lock: StoreLock,
+    /// Serializes index.csv changes and the article writes that go with them. Taken before
+    /// `state_lock` when both are needed.
+    index_lock: StoreLock,
+    /// index.csv kept open for appending, so storing articles doesn't reopen it for every batch.
+    /// Dropped whenever the index is rewritten, as that replaces the file.
+    index_file: Arc<Mutex<Option<File>>>,
+    image_dir: PathBuf,
+    /// Encrypts article markdown and index.csv when set.
+    cipher: Option<Arc<Cipher>>,
+}
+
+impl Database {
+    pub async fn initialize(store_dir: &Path, cipher: Option<Cipher>) -> Result<Self> {
+        fs::create_dir_all(store_dir).context("Failed to create article store directory")?;
+        let image_dir = default_image_dir(store_dir);
+        fs::create_dir_all(&image_dir).context("Failed to create image store directory")?;
+        let index_path = store_dir.join("index.csv");
+        let state_path = store_dir.join("state.csv");
+        let fetch_log_path = store_dir.join("fetches.csv");
+        let bandwidth_path = store_dir.join("bandwidth.csv");
+        let history_path = store_dir.join("history.csv");
+        let snooze_path = store_dir.join("snoozes.csv");
+        let position_path = store_dir.join("positions.csv");
+        let runs_path = store_dir.join("runs.csv");
+
+        let db = Self {
+            store_dir: store_dir.to_path_buf(),
+            index_path,
+            state_path,
+            fetch_log_path,
+            bandwidth_path,
+            history_path,
+            snooze_path,
+            position_path,
+            runs_path,
+            state_lock: StoreLock::new(store_dir.join("state.lock")),
+            index_lock: StoreLock::new(store_dir.join("index.lock")),
+            index_file: Arc::default(),
+            image_dir,
+            cipher: cipher.map(Arc::new),
+        };
+
+        let index_guard = db.index_lock.lock();
+        let needs_header = match fs::metadata(&db.index_path) {
+            Ok(meta) => meta.len() == 0,
+            Err(err) if err.kind() == ErrorKind::NotFound => true,
+            Err(err) => return Err(err.into()),
+        };
+        if needs_header {
+            db.append_index_rows(&[[
+                "time",
+                "article_name",
+                "rss_subscription_name",
+                "path",
+                "link",
+                "updated",
+                "id",
+            ]
+            .map(str::to_string)])
+                .context("Failed to write index.csv header")?;
+        }
+        drop(index_guard);
+
+        Ok(db)
+    }
+
+    /// Directory holding the index, read state, logs, articles and images.
+    pub fn store_dir(&self) -> &Path {
+        &self.store_dir
+    }
+
+    /// The HTTP cache's directory inside the store, counted in its size and quota.
+    pub fn http_cache_dir(&self) -> PathBuf {
+        self.store_dir.join(http_cache::DIR_NAME)
+    }
+
+    /// Holds off article, index, read state, history, reading position, snooze, run and fetch
+    /// log writes until the guard is dropped, in this process and others sharing the store, for
+    /// copying the store while it is in use.
+    pub fn pause_state_writes(&self) -> PausedWrites<'_> {
+        let index = self.index_lock.lock();
+        PausedWrites {
+            _index: index,
+            _state: self.state_lock.lock(),
+        }
+    }
+
+    /// Stores the channel's items and returns the ones that weren't in the archive yet. With
+    /// `max_items`, only the newest items are stored and older unstarred articles of the feed are
+    /// pruned from the archive.
+    pub async fn store_channel(
+        &self,
+        feed_name: &str,
+        feed_url: &str,
+        channel: &Channel,
+        options: StoreOptions,
+    ) -> Result<Vec<rss::Item>> {
+        let month = current_month();
+        let used = self
+            .bandwidth(&month)?
+            .iter()
+            .find(|record| record.feed_name == feed_name)
+            .map_or(0, BandwidthRecord::total);
+        let localize = options
+            .monthly_budget_bytes
+            .is_none_or(|budget| used + options.feed_bytes < budget);
+
+        let root = options.store_dir.as_deref().unwrap_or(&self.store_dir);
+        let mut paths = self.article_paths()?;
+        let mut new_items = Vec::new();
+        let mut pending = Vec::new();
+        for item in newest_items(channel, options.max_items) {
+            let is_new = !paths.contains_key(&item_id(feed_name, feed_url, item));
+            if let Some(article) =
+                self.pending_article(root, feed_name, feed_url, item, &mut paths)?
+            {
+                pending.push(article);
+            }
+            if is_new {
+                new_items.push(item.clone());
+            }
+        }
+
+        // Images of all the articles are fetched at once; `download_image` caps the connections.
+        let image_bytes = AtomicU64::new(0);
+        let contents = futures::future::try_join_all(pending.iter().map(|article| async {
+            if localize {
+                self.localize_images(&article.markdown, &image_bytes).await
+            } else {
+                Ok(article.markdown.clone())
+            }
+        }))
+        .await?;
+        let image_bytes = image_bytes.into_inner();
+
+        // Another process may have stored some of the articles while the images downloaded.
+        let _index = self.index_lock.lock();
+        let stored = self.article_paths()?;
+        new_items.retain(|item| !stored.contains_key(&item_id(feed_name, feed_url, item)));
+        let mut index_rows = Vec::new();
+        let mut index_updates = HashMap::new();
+        for (article, content) in pending.into_iter().zip(contents) {
+            if matches!(&article.index, PendingIndex::New(row) if stored.contains_key(&row[6])) {
+                continue;
+            }
+            self.write_article(&article.path, &content)?;
+            match article.index {
+                PendingIndex::New(row) => {
+                    let modified = if row[5].is_empty() { &row[0] } else { &row[5] };
+                    related::index_article(&self.store_dir, &row[6], modified, &row[1], &content);
+                    index_rows.push(row);
+                }
+                PendingIndex::Updated {
+                    id,
+                    title,
+                    updated_at,
+                } => {
+                    related::index_article(&self.store_dir, &id, &updated_at, &title, &content);
+                    index_updates.insert(id, updated_at);
+                }
+            }
+        }
+        self.set_index_updated(&index_updates)?;
+        self.append_index_rows(&index_rows)?;
+        plugins::notify_new_items(feed_name, &new_items);
+        let language = channel.language().and_then(feed::language_tag);
+        self.record_fetch(feed_name, &UpdateHints::from_channel(channel), language)?;
+        self.record_bandwidth(feed_name, &month, options.feed_bytes, image_bytes)?;
+        if let Some(max_items) = options.max_items {
+            self.prune_feed(feed_name, max_items)?;
+        }
+        if let Some(max_store_bytes) = options.max_store_bytes {
+            self.enforce_quota(max_store_bytes)?;
+        }
+
+        Ok(new_items)
+    }
+
+    /// The article file to write for the item, if it is new or the feed reports it was edited
+    /// since it was stored. New articles go under `root` and are added to `paths`; edited ones
+    /// stay where they are.
+    fn pending_article(
+        &self,
+        root: &Path,
+        feed_name: &str,
+        feed_url: &str,
+        item: &rss::Item,
+        paths: &mut HashMap<String, PathBuf>,
+    ) -> Result<Option<PendingArticle>> {
+        let title = item.title().unwrap_or("No Title");
+        let link = item.link().unwrap_or("");
+        let published_at = parse_pub_date(item.pub_date());
+        let time_for_hash = published_at.clone().unwrap_or_default();
+        let time_for_csv = published_at.unwrap_or_else(|| Utc::now().to_rfc3339());
+        let updated_at = item_updated(item);
+        let id = item_hash(feed_name, feed_url, title, link, &time_for_hash);
+
+        if let Some(file_path) = paths.get(&id) {
+            let Some(updated_at) = updated_at else {
+                return Ok(None);
+            };
+            // The item keeps its ID when it is edited, so refresh the stored copy only when the
+            // feed reports a newer update than the one already stored.
+            let stored = self.find_article(&id)?;
+            let is_edit = stored
+                .as_ref()
+                .map(|record| is_newer(&updated_at, record.last_modified()))
+                .unwrap_or(false);
+            if !is_edit {
+                return Ok(None);
+            }
+            return Ok(Some(PendingArticle {
+                path: file_path.clone(),
+                markdown: extract_markdown(item),
+                index: PendingIndex::Updated {
+                    id,
+                    title: title.to_string(),
+                    updated_at,
+                },
+            }));
+        }
+
+        let file_path = self.article_file_path(root, feed_name, &time_for_csv, title, &id);
+        paths.insert(id.clone(), file_path.clone());
+        let row = [
+            time_for_csv.clone(),
+            title.to_string(),
+            feed_name.to_string(),
+            file_path.to_string_lossy().to_string(),
+            link.to_string(),
+            updated_at
+                .filter(|updated| is_newer(updated, &time_for_csv))
+                .unwrap_or_default(),
+            id,
+        ];
+        Ok(Some(PendingArticle {
+            path: file_path,
+            markdown: extract_markdown(item),
+            index: PendingIndex::New(row),
+        }))
+    }
+
+    pub fn read_item_markdown(
+        &self,
+        feed_name: &str,
+        feed_url: &str,
+        item: &rss::Item,
+    ) -> Option<String> {
+        let record = self
+            .find_article(&item_id(feed_name, feed_url, item))
+            .ok()??;
+        self.read_article_markdown(&record)
+    }
+
+    /// Where a new article is stored: `<feed>/<yyyy>/<mm>/<title>-<short id>.md` under `root`, the
+    /// store directory or the feed's own, so the archive can be browsed with ordinary tools.
+    fn article_file_path(
+        &self,
+        root: &Path,
+        feed_name: &str,
+        time: &str,
+        title: &str,
+        id: &str,
+    ) -> PathBuf {
+        let date = DateTime::parse_from_rfc3339(time)
+            .map(|date| date.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now());
+        let mut feed_dir = slugify(feed_name, MAX_SLUG_CHARS, "feed");
+        if root.join(&feed_dir) == self.image_dir {
+            feed_dir.push_str("-feed");
+        }
+        root.join(feed_dir)
+            .join(date.format("%Y").to_string())
+            .join(date.format("%m").to_string())
+            .join(format!(
+                "{}-{}.md",
+                slugify(title, MAX_SLUG_CHARS, "untitled"),
+                &id[..SHORT_ID_CHARS]
+            ))
+    }
+
+    fn write_article(&self, path: &Path, markdown: &str) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).context("Failed to create article directory")?;
+        }
+        let bytes = match &self.cipher {
+            Some(cipher) => cipher.encrypt(markdown.as_bytes())?,
+            None => markdown.as_bytes().to_vec(),
+        };
+        write_atomic(path, &bytes).context("Failed to write markdown file")
+    }
+
+    fn read_article_file(&self, path: &Path) -> Option<String> {
+        let bytes = self.decrypt(fs::read(path).ok()?).ok()?;
+        String::from_utf8(bytes).ok()
+    }
+
+    fn read_index(&self) -> Result<Vec<u8>> {
+        let bytes = fs::read(&self.index_path).context("Failed to open index.csv")?;
+        self.decrypt(bytes).context("Failed to decrypt index.csv")
+    }
+
+    fn write_index(&self, bytes: &[u8]) -> Result<()> {
+        let bytes = match &self.cipher {
+            Some(cipher) => cipher.encrypt(bytes)?,
+            None => bytes.to_vec(),
+        };
+        let mut index_file = self
+            .index_file
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        *index_file = None;
+        write_atomic(&self.index_path, &bytes).context("Failed to write index.csv")
+    }
+
+    fn decrypt(&self, bytes: Vec<u8>) -> Result<Vec<u8>> {
+        match &self.cipher {
+            Some(cipher) => cipher.decrypt(&bytes),
+            None if crypto::is_encrypted(&bytes) => Err(anyhow::anyhow!(
+                "Article store is encrypted; pass --key-file or set {}",
+                crypto::PASSPHRASE_ENV
+            )),
+            None => Ok(bytes),
+        }
+    }
+
+    /// Removes an article file and whichever of its feed/year/month directories are left empty.
+    /// Outside the store directory, the directory holding the feed's is left even if empty.
+    fn remove_article_file(&self, path: &Path) {
+        let _ = fs::remove_file(path);
+        let root = if path.starts_with(&self.store_dir) {
+            &self.store_dir
+        } else {
+            match path.ancestors().nth(4) {
+                Some(root) => root,
+                None => return,
+            }
+        };
+        let mut dir = path.parent();
+        while let Some(current) = dir.filter(|dir| dir.starts_with(root)) {
+            if current == root || fs::remove_dir(current).is_err() {
+                break;
+            }
+            dir = current.parent();
+        }
+    }
+
+    /// Files of all stored articles, keyed by article ID.
+    fn article_paths(&self) -> Result<HashMap<String, PathBuf>> {
+        Ok(self
+            .list_articles()?
+            .into_iter()
+            .map(|record| (record.id, record.path))
+            .collect())
+    }
+}
+
+impl Database {
+    /// Reads every article row from index.csv, oldest first. Rows that can't be parsed, or that
+    /// don't point at an article file, are skipped with a warning.
+    pub fn list_articles(&self) -> Result<Vec<ArticleRecord>> {
+        let index = self.read_index()?;
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(true)
+            .flexible(true)
+            .from_reader(index.as_slice());
+
+        let mut records = Vec::new();
+        for row in reader.records() {
+            match row.map(|row| record_from_row(&row)) {
+                Ok(Some(record)) => records.push(record),
+                Ok(None) => tracing::warn!("Skipping index.csv row without an article ID"),
+                Err(err) => tracing::warn!("Skipping unreadable index.csv row: {}", err),
+            }
+        }
+
+        Ok(records)
+    }
+
+    /// Articles matching `query`, newest first by publication or update time.
+    pub fn query_articles(&self, query: &ArticleQuery) -> Result<Vec<ArticleRecord>> {
+        let mut records: Vec<_> = self
+            .list_articles()?
+            .into_iter()
+            .filter(|record| query.matches(record))
+            .collect();
+        records.sort_by(|a, b| b.last_modified().cmp(a.last_modified()));
+        if let Some(limit) = query.limit {
+            records.truncate(limit);
+        }
+        Ok(records)
+    }
+
+    /// Rebuilds a feed's item list from stored articles, newest first, for when the feed itself
+    /// can't be fetched.
+    pub fn archived_channel(&self, feed_name: &str) -> Result<Channel> {
+        let records = self.query_articles(&ArticleQuery::feed(feed_name))?;
+
+        let items = records
+            .into_iter()
+            .map(|record| {
+                let mut item = rss::Item::default();
+                item.set_title(record.title);
+                item.set_link(record.link);
+                item.set_pub_date(record.time);
+                item.set_dublin_core_ext(record.updated.map(|updated| DublinCoreExtension {
+                    dates: vec![updated],
+                    ..Default::default()
+                }));
+                item.set_guid(rss::Guid {
+                    value: format!("{}{}", ARCHIVE_GUID_PREFIX, record.id),
+                    permalink: false,
+                });
+                item
+            })
+            .collect::<Vec<_>>();
+
+        let mut channel = Channel::default();
+        channel.set_title(feed_name);
+        channel.set_language(self.feed_language(feed_name));
+        channel.set_items(items);
+        Ok(channel)
+    }
+
+    /// Appends the feed's archived articles that the fetched `channel` no longer lists, newest
+    /// first, so the item list keeps the feed's history. Articles are matched by ID or link.
+    /// Returns the number appended.
+    pub fn merge_archived(
+        &self,
+        feed_name: &str,
+        feed_url: &str,
+        channel: &mut Channel,
+    ) -> Result<usize> {
+        let ids: HashSet<String> = channel
+            .items()
+            .iter()
+            .map(|item| item_id(feed_name, feed_url, item))
+            .collect();
+        let links: HashSet<String> = channel
+            .items()
+            .iter()
+            .filter_map(|item| item.link())
+            .filter(|link| !link.is_empty())
+            .map(str::to_string)
+            .collect();
+        let history: Vec<rss::Item> = self
+            .archived_channel(feed_name)?
+            .into_items()
+            .into_iter()
+            .filter(|item| !ids.contains(&item_id(feed_name, feed_url, item)))
+            .filter(|item| item.link().is_none_or(|link| !links.contains(link)))
+            .collect();
+        let appended = history.len();
+        let mut items = channel.items().to_vec();
+        items.extend(history);
+        channel.set_items(items);
+        Ok(appended)
+    }
+
+    /// When `feed_name` was last fetched successfully (RFC 3339).
+    pub fn last_fetch(&self, feed_name: &str) -> Option<String> {
+        self.fetch_log()
+            .ok()?
+            .into_iter()
+            .find(|record| record.feed_name == feed_name)
+            .map(|record| record.fetched_at)
+    }
+
+    /// The `<language>` of `feed_name`'s channel when it was last fetched.
+    pub fn feed_language(&self, feed_name: &str) -> Option<String> {
+        self.fetch_log()
+            .ok()?
+            .into_iter()
+            .find(|record| record.feed_name == feed_name)
+            .and_then(|record| record.language)
+    }
+
+    fn fetch_log(&self) -> Result<Vec<FetchRecord>> {
+        let content = match fs::read(&self.fetch_log_path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err).context("Failed to read fetches.csv"),
+        };
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(true)
+            .from_reader(content.as_slice());
+        reader
+            .deserialize()
+            .map(|row| row.context("Failed to read fetches.csv row"))
+            .collect()
+    }
+
+    /// The update hints each feed declared when it was last fetched, with the time of that fetch.
+    pub fn update_hints(&self) -> Result<HashMap<String, (DateTime<Utc>, UpdateHints)>> {
+        Ok(self
+            .fetch_log()?
+            .into_iter()
+            .filter_map(|record| {
+                let fetched_at = DateTime::parse_from_rfc3339(&record.fetched_at).ok()?;
+                let hints = record.update_hints();
+                Some((record.feed_name, (fetched_at.with_timezone(&Utc), hints)))
+            })
+            .collect())
+    }
+
+    fn record_fetch(
+        &self,
+        feed_name: &str,
+        hints: &UpdateHints,
+        language: Option<String>,
+    ) -> Result<()> {
+        let _guard = self.state_lock.lock();
+        let mut log = self.fetch_log()?;
+        log.retain(|record| record.feed_name != feed_name);
+        log.push(FetchRecord {
+            feed_name: feed_name.to_string(),
+            fetched_at: Utc::now().to_rfc3339(),
+            ttl: hints.ttl_minutes,
+            skip_hours: hints
+                .skip_hours
+                .iter()
+                .map(u32::to_string)
+                .collect::<Vec<_>>()
+                .join(" "),
+            skip_days: hints
+                .skip_days
+                .iter()
+                .map(chrono::Weekday::to_string)
+                .collect::<Vec<_>>()
+                .join(" "),
+            language,
+        });
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(true)
+            .from_writer(Vec::new());
+        for record in &log {
+            writer
+                .serialize(record)
+                .context("Failed to write fetches.csv row")?;
+        }
+        let bytes = writer
+            .into_inner()
+            .context("Failed to serialize fetches.csv")?;
+        write_atomic(&self.fetch_log_path, &bytes).context("Failed to write fetches.csv")
+    }
+
+    /// Bandwidth used by each feed in `month` (`YYYY-MM`).
+    pub fn bandwidth(&self, month: &str) -> Result<Vec<BandwidthRecord>> {
+        Ok(self
+            .bandwidth_log()?
+            .into_iter()
+            .filter(|record| record.month == month)
+            .collect())
+    }
+
+    fn bandwidth_log(&self) -> Result<Vec<BandwidthRecord>> {
+        let content = match fs::read(&self.bandwidth_path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err).context("Failed to read bandwidth.csv"),
+        };
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(true)
+            .from_reader(content.as_slice());
+        reader
+            .deserialize()
+            .map(|row| row.context("Failed to read bandwidth.csv row"))
+            .collect()
+    }
+
+    fn record_bandwidth(
+        &self,
+        feed_name: &str,
+        month: &str,
+        feed_bytes: u64,
+        image_bytes: u64,
+    ) -> Result<()> {
+        if feed_bytes == 0 && image_bytes == 0 {
+            return Ok(());
+        }
+        let _guard = self.state_lock.lock();
+        let mut log = self.bandwidth_log()?;
+        match log
+            .iter_mut()
+            .find(|record| record.feed_name == feed_name && record.month == month)
+        {
+            Some(record) => {
+                record.feed_bytes += feed_bytes;
+                record.image_bytes += image_bytes;
+            }
+            None => log.push(BandwidthRecord {
+                feed_name: feed_name.to_string(),
+                month: month.to_string(),
+                feed_bytes,
+                image_bytes,
+            }),
+        }
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(true)
+            .from_writer(Vec::new());
+        for record in &log {
+            writer
+                .serialize(record)
+                .context("Failed to write bandwidth.csv row")?;
+        }
+        let bytes = writer
+            .into_inner()
+            .context("Failed to serialize bandwidth.csv")?;
+        write_atomic(&self.bandwidth_path, &bytes).context("Failed to write bandwidth.csv")
+    }
+
+    /// Looks up a stored article by its item ID (the markdown file stem).
+    pub fn find_article(&self, id: &str) -> Result<Option<ArticleRecord>> {
+        Ok(self
+            .list_articles()?
+            .into_iter()
+            .find(|record| record.id == id))
+    }
+
+    /// Per-item read/starred flags keyed by item ID.
+    pub fn item_states(&self) -> Result<HashMap<String, ItemState>> {
+        let content = match fs::read(&self.state_path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(err) => return Err(err).context("Failed to read state.csv"),
+        };
+
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(true)
+            .from_reader(content.as_slice());
+        let mut states = HashMap::new();
+        for row in reader.deserialize() {
+            let state: ItemState = row.context("Failed to read state.csv row")?;
+            states.insert(state.id.clone(), state);
+        }
+        Ok(states)
+    }
+
+    fn write_item_states(&self, states: &HashMap<String, ItemState>) -> Result<()> {
+        let mut states: Vec<_> = states.values().collect();
+        states.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(true)
+            .from_writer(Vec::new());
+        for state in states {
+            writer
+                .serialize(state)
+                .context("Failed to write state.csv row")?;
+        }
+        let bytes = writer
+            .into_inner()
+            .context("Failed to serialize state.csv")?;
+        write_atomic(&self.state_path, &bytes).context("Failed to write state.csv")
+    }
+
+    fn update_item_state<F>(&self, id: &str, update: F) -> Result<()>
+    where
+        F: FnOnce(&mut ItemState),
+    {
+        let _guard = self.state_lock.lock();
+        let mut states = self.item_states()?;
+        let state = states.entry(id.to_string()).or_insert_with(|| ItemState {
+            id: id.to_string(),
+            ..ItemState::default()
+        });
+        let before = state.clone();
+        update(state);
+        if *state == before {
+            return Ok(());
+        }
+        state.updated_at = Utc::now().to_rfc3339();
+        self.write_item_states(&states)
+    }
+
+    pub fn starred_ids(&self) -> Result<HashSet<String>> {
+        Ok(self
+            .item_states()?
+            .into_values()
+            .filter(|state| state.starred)
+            .map(|state| state.id)
+            .collect())
+    }
+
+    pub fn set_starred(&self, id: &str, starred: bool) -> Result<()> {
+        self.update_item_state(id, |state| state.starred = starred)
+    }
+
+    pub fn mark_read(&self, id: &str) -> Result<()> {
+        self.update_item_state(id, |state| state.read = true)
+    }
+
+    /// Logs that the article was opened now, for the reading history.
+    pub fn record_open(&self, id: &str) -> Result<()> {
+        let _guard = self.state_lock.lock();
+        let needs_header = fs::metadata(&self.history_path).map_or(true, |meta| meta.len() == 0);
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(needs_header)
+            .from_writer(Vec::new());
+        writer
+            .serialize(HistoryEntry {
+                opened_at: Utc::now().to_rfc3339(),
+                id: id.to_string(),
+            })
+            .context("Failed to write history.csv row")?;
+        let bytes = writer
+            .into_inner()
+            .context("Failed to serialize history.csv row")?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.history_path)
+            .context("Failed to open history.csv for append")?;
+        file.write_all(&bytes)
+            .context("Failed to append history.csv row")
+    }
+
+    /// Opened articles, most recently opened first and each listed once, with the time it was
+    /// last opened (RFC 3339). Articles pruned from the archive are left out.
+    pub fn reading_history(&self) -> Result<Vec<(String, ArticleRecord)>> {
+        let content = match fs::read(&self.history_path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err).context("Failed to read history.csv"),
+        };
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(true)
+            .from_reader(content.as_slice());
+        let entries = reader
+            .deserialize()
+            .map(|row| row.context("Failed to read history.csv row"))
+            .collect::<Result<Vec<HistoryEntry>>>()?;
+
+        let mut articles: HashMap<String, ArticleRecord> = self
+            .list_articles()?
+            .into_iter()
+            .map(|record| (record.id.clone(), record))
+            .collect();
+        Ok(entries
+            .into_iter()
+            .rev()
+            .filter_map(|entry| Some((entry.opened_at, articles.remove(&entry.id)?)))
+            .collect())
+    }
+
+    /// Where reading the article was left off, as a fraction of its length, if it was left
+    /// unfinished.
+    pub fn reading_position(&self, id: &str) -> Result<Option<f64>> {
+        Ok(self
+            .reading_positions()?
+            .into_iter()
+            .find(|row| row.id == id)
+            .map(|row| row.position))
+    }
+
+    fn reading_positions(&self) -> Result<Vec<ReadingPosition>> {
+        let content = match fs::read(&self.position_path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err).context("Failed to read positions.csv"),
+        };
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(true)
+            .from_reader(content.as_slice());
+        reader
+            .deserialize()
+            .map(|row| row.context("Failed to read positions.csv row"))
+            .collect()
+    }
+
+    /// Remembers how far into the article the reader scrolled (0 is the top, 1 the end). The top
+    /// and the very end forget the position, so a finished article opens at the top again.
+    pub fn set_reading_position(&self, id: &str, position: f64) -> Result<()> {
+        let _guard = self.state_lock.lock();
+        let mut positions = self.reading_positions()?;
+        let before = positions.len();
+        positions.retain(|row| row.id != id);
+        if position.is_finite() && position > 0.0 && position < FINISHED_POSITION {
+            positions.push(ReadingPosition {
+                id: id.to_string(),
+                position,
+                updated_at: Utc::now().to_rfc3339(),
+            });
+        } else if positions.len() == before {
+            return Ok(());
+        }
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(true)
+            .from_writer(Vec::new());
+        for row in &positions {
+            writer
+                .serialize(row)
+                .context("Failed to write positions.csv row")?;
+        }
+        let bytes = writer
+            .into_inner()
+            .context("Failed to serialize positions.csv")?;
+        write_atomic(&self.position_path, &bytes).context("Failed to write positions.csv")
+    }
+
+    /// A stored article other than `id` whose link is `link`, ignoring http/https, `www.`,
+    /// trailing slashes and `utm_*` parameters. The most recently read one is preferred, then
+    /// the earliest stored.
+    pub fn previous_copy(&self, id: &str, link: &str) -> Result<Option<PreviousCopy>> {
+        let link = normalize_feed_url(link);
+        let mut copies: Vec<ArticleRecord> = self
+            .list_articles()?
+            .into_iter()
+            .filter(|record| {
+                record.id != id
+                    && record
+                        .link
+                        .as_deref()
+                        .is_some_and(|other| normalize_feed_url(other) == link)
+            })
+            .collect();
+        if copies.is_empty() {
+            return Ok(None);
+        }
+
+        if let Some((opened_at, record)) = self
+            .reading_history()?
+            .into_iter()
+            .find(|(_, record)| copies.iter().any(|copy| copy.id == record.id))
+        {
+            return Ok(Some(PreviousCopy {
+                record,
+                read_at: Some(opened_at),
+            }));
+        }
+        let states = self.item_states()?;
+        copies.sort_by(|a, b| a.time.cmp(&b.time));
+        let read = copies
+            .iter()
+            .position(|copy| states.get(&copy.id).is_some_and(|state| state.read));
+        let copy = copies.swap_remove(read.unwrap_or(0));
+        let read_at = read.map(|_| states[&copy.id].updated_at.clone());
+        Ok(Some(PreviousCopy {
+            record: copy,
+            read_at,
+        }))
+    }
+
+    /// Journals the start of a refresh run and returns its ID, for `record_run_feed` and
+    /// `finish_run`. Runs beyond the newest `MAX_RUNS` are dropped.
+    pub fn start_run(&self) -> Result<String> {
+        let _guard = self.state_lock.lock();
+        let records = self.run_records()?;
+        let mut runs: Vec<&str> = records.iter().map(|record| record.run.as_str()).collect();
+        runs.sort_unstable();
+        runs.dedup();
+        if runs.len() >= MAX_RUNS {
+            let oldest_kept = runs[runs.len() + 1 - MAX_RUNS];
+            let kept: Vec<&RunRecord> = records
+                .iter()
+                .filter(|record| record.run.as_str() >= oldest_kept)
+                .collect();
+            let mut writer = csv::WriterBuilder::new()
+                .has_headers(true)
+                .from_writer(Vec::new());
+            for record in kept {
+                writer
+                    .serialize(record)
+                    .context("Failed to write runs.csv row")?;
+            }
+            let bytes = writer
+                .into_inner()
+                .context("Failed to serialize runs.csv")?;
+            write_atomic(&self.runs_path, &bytes).context("Failed to write runs.csv")?;
+        }
+
+        let run = Utc::now().to_rfc3339();
+        self.append_run_record(RunRecord {
+            run: run.clone(),
+            time: run.clone(),
+            event: "start".to_string(),
+            feed_name: None,
+            items: 0,
+            added: 0,
+            message: None,
+        })?;
+        Ok(run)
+    }
+
+    /// Journals what the run did with a feed.
+    pub fn record_run_feed(
+        &self,
+        run: &str,
+        feed_name: &str,
+        outcome: RunOutcome,
+        items: usize,
+        added: usize,
+        message: Option<String>,
+    ) -> Result<()> {
+        let _guard = self.state_lock.lock();
+        self.append_run_record(RunRecord {
+            run: run.to_string(),
+            time: Utc::now().to_rfc3339(),
+            event: outcome.as_str().to_string(),
+            feed_name: Some(feed_name.to_string()),
+            items,
+            added,
+            message,
+        })
+    }
+
+    /// Journals the end of the run.
+    pub fn finish_run(&self, run: &str) -> Result<()> {
+        let _guard = self.state_lock.lock();
+        self.append_run_record(RunRecord {
+            run: run.to_string(),
+            time: Utc::now().to_rfc3339(),
+            event: "end".to_string(),
+            feed_name: None,
+            items: 0,
+            added: 0,
+            message: None,
+        })
+    }
+
+    fn append_run_record(&self, record: RunRecord) -> Result<()> {
+        let needs_header = fs::metadata(&self.runs_path).map_or(true, |meta| meta.len() == 0);
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(needs_header)
+            .from_writer(Vec::new());
+        writer
+            .serialize(record)
+            .context("Failed to write runs.csv row")?;
+        let bytes = writer
+            .into_inner()
+            .context("Failed to serialize runs.csv row")?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.runs_path)
+            .context("Failed to open runs.csv for append")?;
+        file.write_all(&bytes)
+            .context("Failed to append runs.csv row")
+    }
+
+    fn run_records(&self) -> Result<Vec<RunRecord>> {
+        let content = match fs::read(&self.runs_path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err).context("Failed to read runs.csv"),
+        };
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(true)
+            .from_reader(content.as_slice());
+        reader
+            .deserialize()
+            .map(|row| row.context("Failed to read runs.csv row"))
+            .collect()
+    }
+
+    /// Journaled refresh runs, newest first.
+    pub fn runs(&self) -> Result<Vec<Run>> {
+        // Rows of runs going on at the same time, from separate processes, are interleaved.
+        let mut runs: Vec<Run> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for record in self.run_records()? {
+            let position = *positions.entry(record.run.clone()).or_insert_with(|| {
+                runs.push(Run {
+                    started_at: record.run.clone(),
+                    finished_at: None,
+                    feeds: Vec::new(),
+                });
+                runs.len() - 1
+            });
+            let run = &mut runs[position];
+            if record.event == "end" {
+                run.finished_at = Some(record.time);
+            } else if let (Some(outcome), Some(feed_name)) =
+                (RunOutcome::parse(&record.event), record.feed_name)
+            {
+                run.feeds.push(RunFeed {
+                    feed_name,
+                    outcome,
+                    items: record.items,
+                    added: record.added,
+                    message: record.message,
+                    time: record.time,
+                });
+            }
+        }
+        runs.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+        Ok(runs)
+    }
+
+    /// Snoozes that haven't expired yet.
+    pub fn snoozes(&self) -> Result<Vec<Snooze>> {
+        let now = Utc::now();
+        Ok(self
+            .read_snoozes()?
+            .into_iter()
+            .filter(|snooze| snooze.is_active(now))
+            .collect())
+    }
+
+    fn read_snoozes(&self) -> Result<Vec<Snooze>> {
+        let content = match fs::read(&self.snooze_path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err).context("Failed to read snoozes.csv"),
+        };
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(true)
+            .from_reader(content.as_slice());
+        reader
+            .deserialize()
+            .map(|row| row.context("Failed to read snoozes.csv row"))
+            .collect()
+    }
+
+    /// Adds a snooze, replacing one for the same feed and pattern. Expired snoozes are dropped.
+    pub fn snooze(&self, snooze: Snooze) -> Result<()> {
+        self.update_snoozes(|snoozes| {
+            snoozes.retain(|existing| {
+                existing.feed_name != snooze.feed_name || existing.pattern != snooze.pattern
+            });
+            snoozes.push(snooze);
+        })
+        .map(|_| ())
+    }
+
+    /// Lifts the snooze for this feed and pattern before it expires. Returns whether there was
+    /// one.
+    pub fn unsnooze(&self, feed_name: Option<&str>, pattern: Option<&str>) -> Result<bool> {
+        self.update_snoozes(|snoozes| {
+            let before = snoozes.len();
+            snoozes.retain(|existing| {
+                existing.feed_name.as_deref() != feed_name || existing.pattern.as_deref() != pattern
+            });
+            snoozes.len() < before
+        })
+    }
+
+    fn update_snoozes<T>(&self, update: impl FnOnce(&mut Vec<Snooze>) -> T) -> Result<T> {
+        let _guard = self.state_lock.lock();
+        let mut snoozes = self.snoozes()?;
+        let result = update(&mut snoozes);
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(true)
+            .from_writer(Vec::new());
+        for snooze in &snoozes {
+            writer
+                .serialize(snooze)
+                .context("Failed to write snoozes.csv row")?;
+        }
+        let bytes = writer
+            .into_inner()
+            .context("Failed to serialize snoozes.csv")?;
+        write_atomic(&self.snooze_path, &bytes).context("Failed to write snoozes.csv")?;
+        Ok(result)
+    }
+
+    /// Stored articles not yet read, counted per feed name. Snoozed articles aren't counted.
+    pub fn unread_counts(&self) -> Result<HashMap<String, usize>> {
+        let states = self.item_states()?;
+        let snoozes = self.snoozes()?;
+        let mut counts = HashMap::new();
+        for record in self.list_articles()? {
+            if !states.get(&record.id).is_some_and(|state| state.read)
+                && !is_snoozed(&snoozes, &record.feed_name, &record.title)
+            {
+                *counts.entry(record.feed_name).or_default() += 1;
+            }
+        }
+        Ok(counts)
+    }
+
+    /// Stored articles per feed published or updated after `since`, leaving out snoozed ones.
+    pub fn new_item_counts(&self, since: DateTime<Utc>) -> Result<HashMap<String, usize>> {
+        let snoozes = self.snoozes()?;
+        let mut counts = HashMap::new();
+        for record in self.list_articles()? {
+            let newer = parse_query_time(record.last_modified()).is_some_and(|time| time > since);
+            if newer && !is_snoozed(&snoozes, &record.feed_name, &record.title) {
+                *counts.entry(record.feed_name).or_default() += 1;
+            }
+        }
+        Ok(counts)
+    }
+
+    /// Marks every stored article of these feeds as read. Returns how many were unread.
+    pub fn mark_feeds_read(&self, feed_names: &[String]) -> Result<usize> {
+        let _guard = self.state_lock.lock();
+        let mut states = self.item_states()?;
+        let now = Utc::now().to_rfc3339();
+        let mut marked = 0;
+        for record in self.list_articles()? {
+            if !feed_names.contains(&record.feed_name) {
+                continue;
+            }
+            let state = states
+                .entry(record.id.clone())
+                .or_insert_with(|| ItemState {
+                    id: record.id,
+                    ..ItemState::default()
+                });
+            if !state.read {
+                state.read = true;
+                state.updated_at = now.clone();
+                marked += 1;
+            }
+        }
+        if marked > 0 {
+            self.write_item_states(&states)?;
+        }
+        Ok(marked)
+    }
+
+    /// Merges item states from another instance; the most recently updated side wins. Returns
+    /// the merged states and how many of the incoming ones changed the local state.
+    pub fn merge_item_states(&self, incoming: Vec<ItemState>) -> Result<(Vec<ItemState>, usize)> {
+        let _guard = self.state_lock.lock();
+        let mut states = self.item_states()?;
+        let mut changed = 0;
+        for state in incoming {
+            let newer = match states.get(&state.id) {
+                Some(existing) => is_newer(&state.updated_at, &existing.updated_at),
+                None => true,
+            };
+            if newer {
+                states.insert(state.id.clone(), state);
+                changed += 1;
+            }
+        }
+        if changed > 0 {
+            self.write_item_states(&states)?;
+        }
+        Ok((states.into_values().collect(), changed))
+    }
+
+    /// Stores an article received from another instance, unless it already exists.
+    pub fn import_article(&self, record: &ArticleRecord, markdown: &str) -> Result<bool> {
+        if !is_item_id(&record.id) {
+            return Err(anyhow::anyhow!("Invalid article ID: {}", record.id));
+        }
+        let _index = self.index_lock.lock();
+        if self.find_article(&record.id)?.is_some() {
+            return Ok(false);
+        }
+
+        let file_path = self.article_file_path(
+            &self.store_dir,
+            &record.feed_name,
+            &record.time,
+            &record.title,
+            &record.id,
+        );
+        self.write_article(&file_path, markdown)?;
+        related::index_article(
+            &self.store_dir,
+            &record.id,
+            record.last_modified(),
+            &record.title,
+            markdown,
+        );
+        self.append_index_rows(&[[
+            record.time.clone(),
+            record.title.clone(),
+            record.feed_name.clone(),
+            file_path.to_string_lossy().to_string(),
+            record.link.clone().unwrap_or_default(),
+            record.updated.clone().unwrap_or_default(),
+            record.id.clone(),
+        ]])?;
+        Ok(true)
+    }
+
+    pub fn image_path(&self, name: &str) -> Option<PathBuf> {
+        let valid = !name.is_empty()
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '.')
+            && !name.starts_with('.');
+        valid.then(|| self.image_dir.join(name))
+    }
+
+    pub fn write_image(&self, name: &str, bytes: &[u8]) -> Result<()> {
+        let path = self
+            .image_path(name)
+            .context(format!("Invalid image name: {}", name))?;
+        write_atomic(&path, bytes).context("Failed to write image file")
+    }
+
+    /// Sets the `updated` column of the articles with these IDs, rewriting the index once.
+    fn set_index_updated(&self, updates: &HashMap<String, String>) -> Result<()> {
+        if updates.is_empty() {
+            return Ok(());
+        }
+        self.rewrite_index(|row_id, fields| {
+            if let Some(updated_at) = updates.get(row_id) {
+                fields[5] = updated_at.clone();
+            }
+            true
+        })
+    }
+
+    /// Removes all but the newest `keep` articles of a feed, sparing starred ones, along with
+    /// images no remaining article refers to.
+    fn prune_feed(&self, feed_name: &str, keep: usize) -> Result<()> {
+        let records = self.query_articles(&ArticleQuery::feed(feed_name))?;
+        if records.len() <= keep {
+            return Ok(());
+        }
+        let starred = self.starred_ids()?;
+        let removed: Vec<ArticleRecord> = records
+            .into_iter()
+            .skip(keep)
+            .filter(|record| !starred.contains(&record.id))
+            .collect();
+        self.remove_articles(&removed)
+    }
+
+    /// Prunes the oldest unstarred articles, of any feed, until the store fits in `max_bytes`.
+    fn enforce_quota(&self, max_bytes: u64) -> Result<()> {
+        let mut total = self.store_size()?;
+        if total <= max_bytes {
+            return Ok(());
+        }
+        // Cached responses can be fetched again, so they go before any article.
+        let cache_dir = self.http_cache_dir();
+        let cache_bytes = http_cache::size(&cache_dir);
+        total -= http_cache::prune(&cache_dir, cache_bytes.saturating_sub(total - max_bytes));
+        if total <= max_bytes {
+            return Ok(());
+        }
+        let starred = self.starred_ids()?;
+        let mut records = self.list_articles()?;
+        records.sort_by(|a, b| a.last_modified().cmp(b.last_modified()));
+        let articles: Vec<(ArticleRecord, Vec<String>)> = records
+            .into_iter()
+            .map(|record| {
+                let images = self
+                    .read_article_markdown(&record)
+                    .map(|markdown| local_image_names(&markdown))
+                    .unwrap_or_default();
+                (record, images)
+            })
+            .collect();
+        let mut image_refs: HashMap<&str, usize> = HashMap::new();
+        for name in articles.iter().flat_map(|(_, images)| images) {
+            *image_refs.entry(name).or_default() += 1;
+        }
+
+        let mut excess = total - max_bytes;
+        let mut removed = Vec::new();
+        for (record, images) in &articles {
+            if excess == 0 {
+                break;
+            }
+            if starred.contains(&record.id) {
+                continue;
+            }
+            let mut freed = file_size(&record.path);
+            for name in images {
+                let refs = image_refs.entry(name).or_default();
+                *refs = refs.saturating_sub(1);
+                if *refs == 0 {
+                    freed += self.image_path(name).map_or(0, |path| file_size(&path));
+                }
+            }
+            excess = excess.saturating_sub(freed);
+            removed.push(record.clone());
+        }
+        if excess > 0 {
+            tracing::warn!(
+                "The article store is over its {} byte quota even without unstarred articles",
+                max_bytes
+            );
+        }
+        if !removed.is_empty() {
+            tracing::info!(
+                "The article store is over its {} byte quota; pruning the {} oldest unstarred articles",
+                max_bytes,
+                removed.len()
+            );
+        }
+        self.remove_articles(&removed)
+    }
+
+    /// Bytes the store takes: its directory plus the articles of feeds with their own
+    /// `store_dir`.
+    fn store_size(&self) -> Result<u64> {
+        let elsewhere: u64 = self
+            .list_articles()?
+            .iter()
+            .filter(|record| !record.path.starts_with(&self.store_dir))
+            .map(|record| file_size(&record.path))
+            .sum();
+        Ok(dir_size(&self.store_dir) + elsewhere)
+    }
+
+    /// Removes stored articles along with the images no remaining article refers to.
+    fn remove_articles(&self, removed: &[ArticleRecord]) -> Result<()> {
+        if removed.is_empty() {
+            return Ok(());
+        }
+        let ids: HashSet<&str> = removed.iter().map(|record| record.id.as_str()).collect();
+        let mut images = HashSet::new();
+        for record in removed {
+            if let Some(markdown) = self.read_article_markdown(record) {
+                images.extend(local_image_names(&markdown));
+            }
+            self.remove_article_file(&record.path);
+        }
+        self.rewrite_index(|row_id, _| !ids.contains(row_id))?;
+
+        for record in self.list_articles()? {
+            if images.is_empty() {
+                break;
+            }
+            if let Some(markdown) = self.read_article_markdown(&record) {
+                for name in local_image_names(&markdown) {
+                    images.remove(&name);
+                }
+            }
+        }
+        for name in images {
+            if let Some(path) = self.image_path(&name) {
+                let _ = fs::remove_file(path);
+            }
+        }
+        Ok(())
+    }
+
+    /// Bytes the store takes, by feed and by kind of file.
+    pub fn disk_usage(&self) -> Result<DiskUsage> {
+        let mut feeds: HashMap<String, FeedUsage> = HashMap::new();
+        let mut image_feeds: HashMap<String, HashSet<String>> = HashMap::new();
+        for record in self.list_articles()? {
+            let usage = feeds
+                .entry(record.feed_name.clone())
+                .or_insert_with(|| FeedUsage {
+                    feed_name: record.feed_name.clone(),
+                    ..FeedUsage::default()
+                });
+            usage.articles += 1;
+            usage.markdown_bytes += file_size(&record.path);
+            if let Some(markdown) = self.read_article_markdown(&record) {
+                for name in local_image_names(&markdown) {
+                    image_feeds
+                        .entry(name)
+                        .or_default()
+                        .insert(record.feed_name.clone());
+                }
+            }
+        }
+
+        let mut usage = DiskUsage::default();
+        let mut image_total = 0;
+        for entry in fs::read_dir(&self.image_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+        {
+            let bytes = entry.metadata().map_or(0, |meta| meta.len());
+            image_total += bytes;
+            let name = entry.file_name().to_string_lossy().to_string();
+            match image_feeds.get(&name) {
+                None => usage.unreferenced_image_bytes += bytes,
+                Some(names) if names.len() == 1 => {
+                    let feed_name = names.iter().next().unwrap();
+                    if let Some(feed) = feeds.get_mut(feed_name) {
+                        feed.image_bytes += bytes;
+                    }
+                }
+                Some(_) => usage.shared_image_bytes += bytes,
+            }
+        }
+        usage.feeds = feeds.into_values().collect();
+        usage.feeds.sort_by(|a, b| {
+            b.total()
+                .cmp(&a.total())
+                .then(a.feed_name.cmp(&b.feed_name))
+        });
+        usage.http_cache_bytes = http_cache::size(&self.http_cache_dir());
+        usage.metadata_bytes = self
+            .store_size()?
+            .saturating_sub(usage.markdown_bytes())
+            .saturating_sub(image_total)
+            .saturating_sub(usage.http_cache_bytes);
+        Ok(usage)
+    }
+
+    /// Rewrites index.csv through `edit`, which gets each article row's ID and its fields
+    /// (padded to all columns) and returns whether to keep the row.
+    fn rewrite_index<F>(&self, mut edit: F) -> Result<()>
+    where
+        F: FnMut(&str, &mut Vec<String>) -> bool,
+    {
+        let index = self.read_index()?;
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(index.as_slice());
+        let mut writer = csv::WriterBuilder::new()
+            .flexible(true)
+            .from_writer(Vec::new());
+        for (index, row) in reader.records().enumerate() {
+            let row = row.context("Failed to read index.csv row")?;
+            let mut fields: Vec<String> = row.iter().map(str::to_string).collect();
+            fields.resize(7, String::new());
+            if index == 0 {
+                fields[5] = "updated".to_string();
+                fields[6] = "id".to_string();
+            } else {
+                if fields[6].is_empty() {
+                    fields[6] = legacy_id(Path::new(&fields[3])).unwrap_or_default();
+                }
+                let id = fields[6].clone();
+                if !edit(&id, &mut fields) {
+                    continue;
+                }
+            }
+            writer
+                .write_record(&fields)
+                .context("Failed to write index.csv row")?;
+        }
+        let bytes = writer
+            .into_inner()
+            .context("Failed to serialize index.csv")?;
+        self.write_index(&bytes)
+    }
+
+    /// Appends rows to index.csv. An encrypted index can't be appended to in place, so it is
+    /// rewritten whole, which also encrypts an index left over from before encryption was on.
+    fn append_index_rows(&self, rows: &[[String; 7]]) -> Result<()> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(Vec::new());
+        for row in rows {
+            writer
+                .write_record(row)
+                .context("Failed to append index.csv row")?;
+        }
+        let bytes = writer
+            .into_inner()
+            .context("Failed to serialize index.csv rows")?;
+
+        if self.cipher.is_some() {
+            let mut index = match self.read_index() {
+                Ok(index) => index,
+                Err(_) if !self.index_path.exists() => Vec::new(),
+                Err(err) => return Err(err),
+            };
+            index.extend_from_slice(&bytes);
+            return self.write_index(&index);
+        }
+        let mut index_file = self
+            .index_file
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        // Another process may have replaced the index since it was opened.
+        let mut file = match index_file.take() {
+            Some(file) if is_same_file(&file, &self.index_path) => file,
+            _ => {
+                if crypto::is_encrypted_file(&self.index_path) {
+                    self.read_index()?;
+                }
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.index_path)
+                    .context("Failed to open index.csv for append")?
+            }
+        };
+        file.write_all(&bytes)
+            .context("Failed to append index.csv rows")?;
+        *index_file = Some(file);
+        Ok(())
+    }
+
+    /// The article's stored markdown file, unless articles are encrypted.
+    pub fn plain_article_path(&self, id: &str) -> Option<PathBuf> {
+        if self.cipher.is_some() {
+            return None;
+        }
+        Some(self.find_article(id).ok()??.path)
+    }
+
+    pub fn read_article_markdown(&self, record: &ArticleRecord) -> Option<String> {
+        self.read_article_file(&record.path)
+    }
+}
+
+/// An article file `store_channel` is about to write.
+struct PendingArticle {
+    path: PathBuf,
+    /// Converted content, before its images are localized.
+    markdown: String,
+    index: PendingIndex,
+}
+
+/// The index.csv change that goes with a [`PendingArticle`].
+enum PendingIndex {
+    /// The row of a new article, appended once the whole channel is stored.
+    New([String; 7]),
+    /// A newer update time for an edited article.
+    Updated {
+        id: String,
+        title: String,
+        updated_at: String,
+    },
+}
+
+/// Stable identifier of a stored item, shared by the markdown filename and render caches.
+pub fn item_id(feed_name: &str, feed_url: &str, item: &rss::Item) -> String {
+    if let Some(id) = item
+        .guid()
+        .and_then(|guid| guid.value().strip_prefix(ARCHIVE_GUID_PREFIX))
+    {
+        return id.to_string();
+    }
+    let title = item.title().unwrap_or("No Title");
+    let link = item.link().unwrap_or("");
+    let published_at = parse_pub_date(item.pub_date()).unwrap_or_default();
+    item_hash(feed_name, feed_url, title, link, &published_at)
+}
+
+/// Converts the item's content (or description) to markdown with trackers stripped, noting how
+/// many elements were removed in a marker line at the end.
+pub fn extract_markdown(item: &rss::Item) -> String {
+    let Some(html) = item.content().or(item.description()) else {
+        return String::new();
+    };
+    let (html, removed) = hygiene::strip_trackers(html);
+    let mut markdown = html_to_markdown(&html);
+    if removed > 0 {
+        markdown.push_str(&format!("\n\n{}\n", hygiene::removed_marker(removed)));
+    }
+    markdown
 }
 
-#[derive(Clone)]
-pub struct Database {
-    store_dir: PathBuf,
-    index_path: PathBuf,
-    image_dir: PathBuf,
+/// Converts HTML to markdown. html2md drops `<details>`, so each collapsible section is kept as
+/// a `<details><summary>…</summary>` line, its converted body, and a closing `</details>` line;
+/// block spoilers become such sections too (see `details_to_markdown`). Footnotes become
+/// markdown footnotes, their definitions at the end, equations markdown math (see `math`),
+/// figure captions italic lines under their image (see `figures`), and audio players links to
+/// their file.
+fn html_to_markdown(html: &str) -> String {
+    let html = media::convert_audio_html(html);
+    let html = resolve_image_sources(&figures::convert_html(&html));
+    let (html, equations) = math::extract_html(&html);
+    let (html, notes) = footnotes::extract_html(&html);
+    let mut markdown = details_to_markdown(&spoilers_to_details(&html));
+    for (index, note) in notes.iter().enumerate() {
+        markdown.push_str("\n\n");
+        markdown.push_str(&footnotes::definition(index + 1, &parse_html(note)));
+    }
+    math::restore(&markdown, &equations)
 }
 
-impl Database {
-    pub async fn initialize(store_dir: &Path) -> Result<Self> {
-        fs::create_dir_all(store_dir).context("Failed to create article store directory")?;
-        let image_dir = default_image_dir(store_dir);
-        fs::create_dir_all(&image_dir).context("Failed to create image store directory")?;
-        let index_path = store_dir.join("index.csv");
+static DETAILS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?is)<details\b[^>]*>").unwrap());
+static SUMMARY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)^\s*<summary\b[^>]*>(.*?)</summary\s*>").unwrap());
+/// Blocks that hide their content as a spoiler, as marked by Discourse, Reddit, forums and
+/// blog themes.
+static SPOILER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)<(div|blockquote|section|p)\b([^>]*\bclass\s*=\s*["'][^"']*spoiler[^"']*["'][^>]*)>"#)
+        .unwrap()
+});
 
-        let needs_header = match fs::metadata(&index_path) {
-            Ok(meta) => meta.len() == 0,
-            Err(err) if err.kind() == ErrorKind::NotFound => true,
-            Err(err) => return Err(err.into()),
+/// Converts HTML holding `<details>` sections, nested ones included. A section without a
+/// `<summary>` gets "Details", as browsers show, and summaries are kept as plain text since the
+/// marker line is not markdown.
+fn details_to_markdown(html: &str) -> String {
+    let mut markdown = String::new();
+    let mut rest = html;
+    while let Some(open) = DETAILS.find(rest) {
+        markdown.push_str(&parse_html(&rest[..open.start()]));
+        let (body, after) = split_element(&rest[open.end()..], "details");
+        let (summary, body) = match SUMMARY.captures(body) {
+            Some(caps) => (
+                scraper::Html::parse_fragment(&caps[1])
+                    .root_element()
+                    .text()
+                    .collect::<String>(),
+                &body[caps[0].len()..],
+            ),
+            None => (String::new(), body),
         };
+        let summary = summary.split_whitespace().collect::<Vec<_>>().join(" ");
+        markdown.push_str(&format!(
+            "\n\n<details><summary>{}</summary>\n\n{}\n\n</details>\n\n",
+            if summary.is_empty() {
+                "Details"
+            } else {
+                &summary
+            },
+            details_to_markdown(body).trim()
+        ));
+        rest = after;
+    }
+    markdown.push_str(&parse_html(rest));
+    markdown
+}
 
-        let file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&index_path)
-            .context("Failed to open index.csv")?;
-
-        let mut writer = csv::WriterBuilder::new()
-            .has_headers(false)
-            .from_writer(file);
+/// Turns spoiler blocks into `<details>` sections summarized "Spoiler", so the web UI keeps
+/// them hidden until opened and the TUI shows them under a summary line. Inline spoilers are
+/// left as plain text.
+fn spoilers_to_details(html: &str) -> String {
+    let mut converted = String::new();
+    let mut rest = html;
+    while let Some(caps) = SPOILER.captures(rest) {
+        let open = caps.get(0).unwrap();
+        converted.push_str(&rest[..open.start()]);
+        let (body, after) = split_element(&rest[open.end()..], &caps[1].to_ascii_lowercase());
+        converted.push_str(&format!(
+            "<details><summary>Spoiler</summary>{}</details>",
+            spoilers_to_details(body)
+        ));
+        rest = after;
+    }
+    converted.push_str(rest);
+    converted
+}
 
-        if needs_header {
-            writer
-                .write_record(["time", "article_name", "rss_subscription_name", "path"])
-                .context("Failed to write index.csv header")?;
-            writer.flush().context("Failed to flush index.csv header")?;
+/// Splits HTML following a `<tag>` opening tag into the element's content and what follows its
+/// matching closing tag, counting nested elements of the same name. An element left open runs to
+/// the end.
+fn split_element<'a>(html: &'a str, tag: &str) -> (&'a str, &'a str) {
+    let Ok(tags) = Regex::new(&format!(r"(?is)<(/?){}\b[^>]*>", regex::escape(tag))) else {
+        return (html, "");
+    };
+    let mut depth = 0;
+    for caps in tags.captures_iter(html) {
+        let found = caps.get(0).unwrap();
+        if caps[1].is_empty() {
+            depth += 1;
+        } else if depth == 0 {
+            return (&html[..found.start()], &html[found.end()..]);
+        } else {
+            depth -= 1;
         }
-
-        Ok(Self {
-            store_dir: store_dir.to_path_buf(),
-            index_path,
-            image_dir,
-        })
     }
+    (html, "")
+}
 
-    pub async fn store_channel(
-        &self,
-        feed_name: &str,
-        feed_url: &str,
-        channel: &Channel,
-    ) -> Result<()> {
-        for item in channel.items() {
-            self.store_item(feed_name, feed_url, item).await?;
+/// Renders stored markdown to HTML, turning `<details>` marker lines back into real elements and
+/// `[^label]` footnotes into numbered references with the notes listed at the end. The document
+/// is rendered in one piece, so references find their notes across `<details>` sections; each
+/// marker line stands in as a placeholder paragraph until the HTML is done. Math comes out as
+/// `data-math-style` code for the web UI to typeset, and headings get `section-N` ids for the
+/// table of contents to link to.
+pub fn render_markdown_html(markdown: &str) -> String {
+    let mut options = ComrakOptions::default();
+    options.extension.footnotes = true;
+    options.extension.math_code = true;
+    let mut markers = Vec::new();
+    let mut document = String::new();
+    for line in markdown.lines().filter(|line| !hygiene::is_marker(line)) {
+        let marker = details_summary(line)
+            .map(|summary| format!("<details><summary>{}</summary>\n", escape_html(summary)))
+            .or_else(|| (line.trim() == "</details>").then(|| "</details>\n".to_string()));
+        match marker {
+            Some(marker) => {
+                document.push_str(&format!("\n\n{}\n\n", details_placeholder(markers.len())));
+                markers.push(marker);
+            }
+            None => {
+                document.push_str(line);
+                document.push('\n');
+            }
         }
-
-        Ok(())
     }
+    let mut html = markdown_to_html(&document, &options);
+    for (index, marker) in markers.iter().enumerate() {
+        html = html.replacen(
+            &format!("<p>{}</p>\n", details_placeholder(index)),
+            marker,
+            1,
+        );
+    }
+    let mut sections = 0;
+    HEADING_TAG
+        .replace_all(&html, |caps: &regex::Captures<'_>| {
+            sections += 1;
+            format!(r#"<h{} id="section-{}">"#, &caps[1], sections)
+        })
+        .into_owned()
+}
 
-    pub async fn store_item(
-        &self,
-        feed_name: &str,
-        feed_url: &str,
-        item: &rss::Item,
-    ) -> Result<String> {
-        let title = item.title().unwrap_or("No Title");
-        let link = item.link().unwrap_or("");
-        let published_at = parse_pub_date(item.pub_date());
-        let time_for_hash = published_at.clone().unwrap_or_default();
-        let time_for_csv = published_at.unwrap_or_else(|| Utc::now().to_rfc3339());
-        let filename = item_filename(feed_name, feed_url, title, link, &time_for_hash);
-        let file_path = self.store_dir.join(&filename);
+/// Headings an article needs before it gets a table of contents.
+pub const MIN_TOC_HEADINGS: usize = 3;
 
-        if file_path.exists() {
-            let existing = fs::read_to_string(&file_path).unwrap_or_default();
-            return Ok(existing);
-        }
+static HEADING_TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<h([1-6])>").unwrap());
+static HEADING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?s)<h([1-6]) id="(section-[0-9]+)">(.*?)</h[1-6]>"#).unwrap());
 
-        let content_markdown = extract_markdown(item);
-        let content_markdown = self.localize_images(&content_markdown).await?;
+/// A heading of a rendered article, for its table of contents.
+pub struct Heading {
+    pub level: u8,
+    /// The heading element's `id`, `section-1` for the first heading and so on.
+    pub id: String,
+    pub text: String,
+}
 
-        fs::write(&file_path, content_markdown.as_bytes())
-            .context("Failed to write markdown file")?;
+/// The headings of HTML from `render_markdown_html`, when there are at least
+/// `MIN_TOC_HEADINGS` of them; otherwise none, as short articles need no table of contents.
+pub fn table_of_contents(html: &str) -> Vec<Heading> {
+    let headings: Vec<Heading> = HEADING
+        .captures_iter(html)
+        .map(|caps| Heading {
+            level: caps[1].parse().unwrap_or(1),
+            id: caps[2].to_string(),
+            text: scraper::Html::parse_fragment(&caps[3])
+                .root_element()
+                .text()
+                .collect::<String>()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" "),
+        })
+        .collect();
+    if headings.len() < MIN_TOC_HEADINGS {
+        return Vec::new();
+    }
+    headings
+}
 
-        let file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&self.index_path)
-            .context("Failed to open index.csv for append")?;
-        let mut writer = csv::WriterBuilder::new()
-            .has_headers(false)
-            .from_writer(file);
-        writer
-            .write_record([
-                time_for_csv,
-                title.to_string(),
-                feed_name.to_string(),
-                file_path.to_string_lossy().to_string(),
-            ])
-            .context("Failed to append index.csv row")?;
-        writer.flush().context("Failed to flush index.csv")?;
+/// Paragraph standing in for the `index`th `<details>` marker line while rendering, in the
+/// private use area so it never collides with article text.
+fn details_placeholder(index: usize) -> String {
+    format!("\u{e000}details-{}\u{e000}", index)
+}
 
-        Ok(content_markdown)
-    }
+/// Summary text of a `<details><summary>…</summary>` marker line.
+pub fn details_summary(line: &str) -> Option<&str> {
+    line.trim()
+        .strip_prefix("<details><summary>")?
+        .strip_suffix("</summary>")
+}
 
-    pub fn read_item_markdown(
-        &self,
-        feed_name: &str,
-        feed_url: &str,
-        item: &rss::Item,
-    ) -> Option<String> {
-        let title = item.title().unwrap_or("No Title");
-        let link = item.link().unwrap_or("");
-        let published_at = parse_pub_date(item.pub_date()).unwrap_or_default();
-        let filename = item_filename(feed_name, feed_url, title, link, &published_at);
-        let file_path = self.store_dir.join(&filename);
-        fs::read_to_string(&file_path).ok()
-    }
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
 }
 
-pub fn extract_markdown(item: &rss::Item) -> String {
-    if let Some(content) = item.content() {
-        html_to_markdown(content)
-    } else if let Some(description) = item.description() {
-        html_to_markdown(description)
-    } else {
-        String::new()
+/// Articles stored before index.csv had an `id` column are named after their ID.
+fn legacy_id(path: &Path) -> Option<String> {
+    path.file_stem()?.to_str().map(str::to_string)
+}
+
+fn record_from_row(row: &csv::StringRecord) -> Option<ArticleRecord> {
+    let path = PathBuf::from(row.get(3)?);
+    let id = match row.get(6).filter(|id| !id.is_empty()) {
+        Some(id) => id.to_string(),
+        None => legacy_id(&path)?,
+    };
+    if !is_item_id(&id) {
+        return None;
     }
+    let field = |index| {
+        row.get(index)
+            .filter(|value: &&str| !value.is_empty())
+            .map(str::to_string)
+    };
+    Some(ArticleRecord {
+        id,
+        time: row.get(0).unwrap_or_default().to_string(),
+        title: row.get(1).unwrap_or_default().to_string(),
+        feed_name: row.get(2).unwrap_or_default().to_string(),
+        path,
+        link: field(4),
+        updated: field(5),
+    })
 }
 
-fn html_to_markdown(html: &str) -> String {
-    parse_html(html)
+/// The channel's fetched items, limited to the newest `max_items` by publication or update time
+/// when set. Undated items rank last, in feed order. Items merged in from the archive are never
+/// stored again.
+fn newest_items(channel: &Channel, max_items: Option<usize>) -> Vec<&rss::Item> {
+    let mut items: Vec<&rss::Item> = channel
+        .items()
+        .iter()
+        .filter(|item| {
+            !item
+                .guid()
+                .is_some_and(|guid| guid.value().starts_with(ARCHIVE_GUID_PREFIX))
+        })
+        .collect();
+    let Some(max_items) = max_items else {
+        return items;
+    };
+    if items.len() > max_items {
+        items.sort_by_cached_key(|item| {
+            std::cmp::Reverse(item_updated(item).or_else(|| parse_pub_date(item.pub_date())))
+        });
+        items.truncate(max_items);
+    }
+    items
 }
 
-pub fn render_markdown_html(markdown: &str) -> String {
-    markdown_to_html(markdown, &ComrakOptions::default())
+/// When the item was last updated (RFC 3339), from Atom's `<updated>` or, for items that also
+/// carry a `pubDate`, a differing `dc:date`.
+pub fn item_updated(item: &rss::Item) -> Option<String> {
+    let atom_updated = ATOM_PREFIXES.iter().find_map(|prefix| {
+        item.extensions()
+            .get(*prefix)?
+            .get("updated")?
+            .first()?
+            .value()
+    });
+    let dc_date = item
+        .pub_date()
+        .and(item.dublin_core_ext())
+        .and_then(|dc| dc.dates().first())
+        .map(String::as_str);
+    let updated = parse_pub_date(atom_updated.or(dc_date))?;
+    (parse_pub_date(item.pub_date()).as_deref() != Some(updated.as_str())).then_some(updated)
 }
 
-fn parse_pub_date(input: Option<&str>) -> Option<String> {
+/// An RSS (RFC 2822) or Atom (RFC 3339) date as RFC 3339 in UTC.
+pub fn parse_pub_date(input: Option<&str>) -> Option<String> {
     input.and_then(|raw| {
         DateTime::parse_from_rfc2822(raw)
             .or_else(|_| DateTime::parse_from_rfc3339(raw))
@@ -166,6 +2248,130 @@ fn parse_pub_date(input: Option<&str>) -> Option<String> {
     })
 }
 
+fn is_newer(candidate: &str, current: &str) -> bool {
+    match (
+        DateTime::parse_from_rfc3339(candidate),
+        DateTime::parse_from_rfc3339(current),
+    ) {
+        (Ok(candidate), Ok(current)) => candidate > current,
+        (Ok(_), Err(_)) => true,
+        _ => false,
+    }
+}
+
+/// Item IDs are hex SHA-256 digests; anything else must never reach the filesystem.
+pub fn is_item_id(id: &str) -> bool {
+    id.len() == 64 && id.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Whether `file` is still the file at `path`, rather than one a rename has since replaced.
+#[cfg(unix)]
+fn is_same_file(file: &File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (file.metadata(), fs::metadata(path)) {
+        (Ok(open), Ok(current)) => open.dev() == current.dev() && open.ino() == current.ino(),
+        _ => false,
+    }
+}
+
+/// Without inode numbers to compare, the file is assumed replaced and reopened.
+#[cfg(not(unix))]
+fn is_same_file(_file: &File, _path: &Path) -> bool {
+    false
+}
+
+/// Suffix of the next temporary file, so concurrent writes of one file don't share it.
+static NEXT_TEMP: AtomicUsize = AtomicUsize::new(0);
+
+/// Writes via a temporary sibling file and rename so readers never see partial content. The
+/// temporary name is unique to the process and write, so concurrent writers each rename a
+/// complete file. A symlink is written through, keeping the link and renaming next to its
+/// target, which may be on another file system.
+fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let is_link = fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_symlink());
+    let target = if is_link {
+        // A dangling link is written through to where it points.
+        fs::canonicalize(path).or_else(|_| {
+            let link = fs::read_link(path)?;
+            Ok::<_, std::io::Error>(path.parent().unwrap_or(Path::new("")).join(link))
+        })?
+    } else {
+        path.to_path_buf()
+    };
+    let tmp_path = target.with_extension(format!(
+        "tmp{}-{}",
+        std::process::id(),
+        NEXT_TEMP.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::write(&tmp_path, contents)?;
+    fs::rename(&tmp_path, &target).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp_path);
+    })
+}
+
+/// An advisory lock on a file in the store, so that a TUI or server and a `refresh` run by cron
+/// don't interleave their writes. Threads of one process queue on a mutex first, since the file
+/// lock is held per process.
+#[derive(Clone)]
+struct StoreLock {
+    path: PathBuf,
+    threads: Arc<Mutex<()>>,
+    /// Whether a failure to lock the file was logged, so it's logged once.
+    failed: Arc<AtomicBool>,
+}
+
+/// Holds a `StoreLock` until dropped.
+pub struct StoreGuard<'a> {
+    // Fields drop in order: the file lock is released before other threads may take it.
+    _file: Option<File>,
+    _threads: MutexGuard<'a, ()>,
+}
+
+/// Holds both store locks until dropped; see [`Database::pause_state_writes`].
+pub struct PausedWrites<'a> {
+    _index: StoreGuard<'a>,
+    _state: StoreGuard<'a>,
+}
+
+impl StoreLock {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            threads: Arc::default(),
+            failed: Arc::default(),
+        }
+    }
+
+    /// Waits until no other thread or process holds the lock. Where files can't be locked, such
+    /// as on some network file systems, only threads of this process are kept apart.
+    fn lock(&self) -> StoreGuard<'_> {
+        let threads = self.threads.lock().unwrap_or_else(|err| err.into_inner());
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&self.path)
+            .and_then(|file| file.lock().map(|()| file));
+        let file = match file {
+            Ok(file) => Some(file),
+            Err(err) => {
+                if !self.failed.swap(true, Ordering::Relaxed) {
+                    tracing::warn!(
+                        "Failed to lock {}, so other processes may write the store at the same time: {}",
+                        self.path.display(),
+                        err
+                    );
+                }
+                None
+            }
+        };
+        StoreGuard {
+            _file: file,
+            _threads: threads,
+        }
+    }
+}
+
 fn hash_string(input: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(input.as_bytes());
@@ -174,30 +2380,16 @@ fn hash_string(input: &str) -> String {
 }
 
 impl Database {
-    async fn localize_images(&self, markdown: &str) -> Result<String> {
-        let urls = extract_image_urls(markdown);
-        if urls.is_empty() {
-            return Ok(markdown.to_string());
-        }
-
-        let mut replacements = HashMap::new();
-        for url in urls {
-            if replacements.contains_key(&url) {
-                continue;
-            }
-            if let Some(local) = self.download_image(&url).await? {
-                replacements.insert(url, local);
-            }
-        }
-
-        let mut updated = replace_html_img_tags(markdown, &replacements);
-        for (url, local) in replacements {
-            updated = updated.replace(&url, &local);
-        }
-        Ok(updated)
+    /// Downloads the article's images into the store, adding the bytes fetched to `downloaded`.
+    async fn localize_images(&self, markdown: &str, downloaded: &AtomicU64) -> Result<String> {
+        let downloader = StoreImages {
+            db: self,
+            downloaded,
+        };
+        localize_images_with(markdown, &downloader).await
     }
 
-    async fn download_image(&self, url: &str) -> Result<Option<String>> {
+    async fn download_image(&self, url: &str, downloaded: &AtomicU64) -> Result<Option<String>> {
         let parsed = match Url::parse(url) {
             Ok(parsed) => parsed,
             Err(_) => return Ok(None),
@@ -212,32 +2404,132 @@ impl Database {
             return Ok(Some(format!("/images/{}", filename)));
         }
 
-        let client = reqwest::Client::new();
-        let response = client.get(url).send().await?;
-        if !response.status().is_success() {
+        let host = host_downloads(parsed.host_str().unwrap_or_default());
+        let _host_permit = host.acquire().await?;
+        let _permit = IMAGE_DOWNLOADS.acquire().await?;
+        // Stored images are kept in `images/`, so they skip the HTTP cache.
+        let response = match http_cache::get_untrusted_uncached(url, MAX_IMAGE_BYTES).await {
+            Ok(response) => response,
+            Err(err) if http_cache::is_forbidden(&err) => {
+                tracing::warn!("Not downloading image {}: {:#}", url, err);
+                return Ok(None);
+            }
+            // The image stays remote, so one unreachable image doesn't keep the articles of the
+            // whole fetch from being stored.
+            Err(err) => {
+                tracing::warn!("Failed to download image {}: {:#}", url, err);
+                return Ok(None);
+            }
+        };
+        if !response.status.is_success() {
             return Ok(None);
         }
         let content_type = response
-            .headers()
+            .headers
             .get(CONTENT_TYPE)
             .and_then(|value| value.to_str().ok())
             .map(|value| value.to_string());
-        let bytes = response.bytes().await?;
+        if !response.from_cache {
+            downloaded.fetch_add(response.body.len() as u64, Ordering::Relaxed);
+        }
+        if response.body.len() < MIN_IMAGE_BYTES {
+            tracing::debug!(
+                "Not storing image {}: {} bytes is a tracking pixel",
+                url,
+                response.body.len()
+            );
+            return Ok(None);
+        }
 
         let filename = image_filename(url, content_type.as_deref());
         let target_path = self.image_dir.join(&filename);
         if !target_path.exists() {
-            fs::write(&target_path, &bytes).context("Failed to write image file")?;
+            write_atomic(&target_path, &response.body).context("Failed to write image file")?;
         }
 
         Ok(Some(format!("/images/{}", filename)))
     }
 }
 
+/// An `<img>` tag and its attribute text.
+static IMG_TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?is)<img\b([^>]*)>").unwrap());
+
+/// Images smaller than this are tracking pixels or spacers (a 1x1 GIF or PNG is under 100
+/// bytes) and aren't stored.
+const MIN_IMAGE_BYTES: usize = 100;
+
+/// Largest image stored; bigger ones are left as remote links.
+const MAX_IMAGE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Image downloads in flight at once, across every feed being stored.
+const MAX_IMAGE_DOWNLOADS: usize = 16;
+/// Image downloads in flight at once from one host, so a feed of many images from its own small
+/// server doesn't hit it with all of them.
+const MAX_IMAGE_DOWNLOADS_PER_HOST: usize = 4;
+
+static IMAGE_DOWNLOADS: Semaphore = Semaphore::const_new(MAX_IMAGE_DOWNLOADS);
+
+/// The limit on image downloads from `host`, shared by everything storing articles.
+fn host_downloads(host: &str) -> Arc<Semaphore> {
+    static HOSTS: LazyLock<Mutex<HashMap<String, Arc<Semaphore>>>> = LazyLock::new(Mutex::default);
+    let mut hosts = HOSTS.lock().unwrap_or_else(|err| err.into_inner());
+    // Forget hosts with no download in flight once many have been seen.
+    if hosts.len() > 256 {
+        hosts.retain(|_, permits| Arc::strong_count(permits) > 1);
+    }
+    hosts
+        .entry(host.to_string())
+        .or_insert_with(|| Arc::new(Semaphore::new(MAX_IMAGE_DOWNLOADS_PER_HOST)))
+        .clone()
+}
+
+/// Fetches an article image and returns the path it is served from locally, or `None` to keep
+/// linking to the original. Downloads of one article run concurrently.
+pub trait ImageDownloader: Send + Sync {
+    fn download<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Option<String>>>;
+}
+
+/// Downloads images into the database's image directory, counting the bytes fetched.
+struct StoreImages<'a> {
+    db: &'a Database,
+    downloaded: &'a AtomicU64,
+}
+
+impl ImageDownloader for StoreImages<'_> {
+    fn download<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Option<String>>> {
+        Box::pin(self.db.download_image(url, self.downloaded))
+    }
+}
+
+/// Points the markdown's images at the local paths `downloader` returns for their URLs, once per
+/// URL. Images it returns `None` for keep their original URL.
+pub async fn localize_images_with(
+    markdown: &str,
+    downloader: &dyn ImageDownloader,
+) -> Result<String> {
+    let urls = extract_image_urls(markdown);
+    if urls.is_empty() {
+        return Ok(markdown.to_string());
+    }
+
+    let locals =
+        futures::future::try_join_all(urls.iter().map(|url| downloader.download(url))).await?;
+    let replacements: HashMap<String, String> = urls
+        .into_iter()
+        .zip(locals)
+        .filter_map(|(url, local)| Some((url, local?)))
+        .collect();
+
+    let mut updated = replace_html_img_tags(markdown, &replacements);
+    for (url, local) in replacements {
+        updated = updated.replace(&url, &local);
+    }
+    Ok(updated)
+}
+
 fn extract_image_urls(markdown: &str) -> Vec<String> {
     let mut urls = HashSet::new();
     let md_re = Regex::new(r"!\[[^\]]*]\(([^)]+)\)").unwrap();
-    let html_re = Regex::new(r#"<img[^>]+src=["']([^"']+)["'][^>]*>"#).unwrap();
 
     for caps in md_re.captures_iter(markdown) {
         if let Some(url) = caps.get(1) {
@@ -245,45 +2537,171 @@ fn extract_image_urls(markdown: &str) -> Vec<String> {
         }
     }
 
-    for caps in html_re.captures_iter(markdown) {
-        if let Some(url) = caps.get(1) {
-            urls.insert(url.as_str().to_string());
+    for caps in IMG_TAG.captures_iter(markdown) {
+        if let Some(url) = image_source(&caps[1]) {
+            urls.insert(url);
         }
     }
 
-    urls.into_iter().collect()
+    // Data URIs are already inline; there is nothing to download.
+    urls.into_iter()
+        .filter(|url| !url.trim_start().to_ascii_lowercase().starts_with("data:"))
+        .collect()
 }
 
-fn replace_html_img_tags(markdown: &str, replacements: &HashMap<String, String>) -> String {
-    let img_tag = Regex::new(r#"<img[^>]*>"#).unwrap();
-    let src_attr = Regex::new(r#"src=["']([^"']+)["']"#).unwrap();
-    let alt_attr = Regex::new(r#"alt=["']([^"']*)["']"#).unwrap();
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map_or(0, |meta| meta.len())
+}
 
-    img_tag
+/// Bytes of all files under `dir`.
+fn dir_size(dir: &Path) -> u64 {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(kind) if kind.is_dir() => dir_size(&entry.path()),
+            _ => entry.metadata().map_or(0, |meta| meta.len()),
+        })
+        .sum()
+}
+
+/// Names of images in the local image store referenced by a stored article.
+fn local_image_names(markdown: &str) -> Vec<String> {
+    let re = Regex::new(r"/images/([A-Za-z0-9.]+)").unwrap();
+    re.captures_iter(markdown)
+        .map(|caps| caps[1].to_string())
+        .collect()
+}
+
+fn replace_html_img_tags(markdown: &str, replacements: &HashMap<String, String>) -> String {
+    IMG_TAG
         .replace_all(markdown, |caps: &regex::Captures<'_>| {
-            let tag = caps.get(0).map(|m| m.as_str()).unwrap_or("");
-            let src = src_attr
-                .captures(tag)
-                .and_then(|c| c.get(1).map(|m| m.as_str()))
-                .unwrap_or("");
-            let alt = alt_attr
-                .captures(tag)
-                .and_then(|c| c.get(1).map(|m| m.as_str()))
-                .unwrap_or("");
-            let target = replacements.get(src).map(|s| s.as_str()).unwrap_or(src);
+            let src = image_source(&caps[1]).unwrap_or_default();
+            let alt = hygiene::attr(&caps[1], "alt").unwrap_or_default();
+            let target = replacements.get(&src).unwrap_or(&src);
             format!("![{}]({})", alt, target)
         })
         .to_string()
 }
 
+/// Points each `<img>` at its real image (see `image_source`) before the HTML is converted to
+/// markdown, which only keeps `src`.
+fn resolve_image_sources(html: &str) -> String {
+    IMG_TAG
+        .replace_all(html, |caps: &regex::Captures<'_>| {
+            let attrs = &caps[1];
+            let Some(source) = image_source(attrs) else {
+                return caps[0].to_string();
+            };
+            if hygiene::attr(attrs, "src").as_deref() == Some(source.as_str()) {
+                return caps[0].to_string();
+            }
+            let mut tag = format!("<img src=\"{}\"", source.replace('"', "&quot;"));
+            for name in ["alt", "title"] {
+                if let Some(value) = hygiene::attr(attrs, name) {
+                    tag.push_str(&format!(" {}=\"{}\"", name, value.replace('"', "&quot;")));
+                }
+            }
+            tag.push('>');
+            tag
+        })
+        .into_owned()
+}
+
+/// The image an `<img>` tag shows, given its attribute text. Lazy-loading scripts leave a
+/// placeholder in `src` and the real image in `data-srcset`, `data-src` or `data-original`, so
+/// those come first, then the largest `srcset` candidate, then `src`. Placeholder data URIs are
+/// passed over while another source exists.
+fn image_source(attrs: &str) -> Option<String> {
+    let srcset =
+        |name| hygiene::attr(attrs, name).and_then(|srcset| largest_srcset_candidate(&srcset));
+    let candidates = [
+        srcset("data-srcset"),
+        hygiene::attr(attrs, "data-src"),
+        hygiene::attr(attrs, "data-original"),
+        srcset("srcset"),
+        hygiene::attr(attrs, "src"),
+    ];
+    let candidates: Vec<String> = candidates
+        .into_iter()
+        .flatten()
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+        .collect();
+    candidates
+        .iter()
+        .find(|url| !url.to_ascii_lowercase().starts_with("data:"))
+        .or(candidates.first())
+        .cloned()
+}
+
+/// The widest (or, for density descriptors, densest) image of a `srcset`, parsed as browsers do:
+/// URLs run to the next whitespace and may themselves contain commas.
+fn largest_srcset_candidate(srcset: &str) -> Option<String> {
+    let mut best: Option<(&str, bool, f64)> = None;
+    let mut rest = srcset;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if rest.is_empty() {
+            break;
+        }
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let (url, after) = rest.split_at(end);
+        let mut descriptor = "";
+        rest = after;
+        if !url.ends_with(',') {
+            let end = rest.find(',').unwrap_or(rest.len());
+            descriptor = rest[..end].trim();
+            rest = &rest[end..];
+        }
+        let url = url.trim_end_matches(',');
+        // A width descriptor outranks any density one; no descriptor means 1x.
+        let (is_width, size) = match descriptor.split_whitespace().next() {
+            Some(width) if width.ends_with('w') => (true, width[..width.len() - 1].parse().ok()),
+            Some(density) if density.ends_with('x') => {
+                (false, density[..density.len() - 1].parse().ok())
+            }
+            _ => (false, Some(1.0)),
+        };
+        let Some(size) = size else {
+            continue;
+        };
+        if url.is_empty() {
+            continue;
+        }
+        if best.is_none_or(|(_, best_width, best_size)| (is_width, size) > (best_width, best_size))
+        {
+            best = Some((url, is_width, size));
+        }
+    }
+    best.map(|(url, _, _)| url.to_string())
+}
+
 fn image_filename(url: &str, content_type: Option<&str>) -> String {
     let ext = image_extension(url, content_type).unwrap_or("img");
     format!("{}.{}", hash_string(url), ext)
 }
 
-fn item_filename(feed_name: &str, feed_url: &str, title: &str, link: &str, time: &str) -> String {
+/// Lowercase words of `text` joined by dashes, at most `max_chars` long.
+fn slugify(text: &str, max_chars: usize, fallback: &str) -> String {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let slug: String = words.join("-").chars().take(max_chars).collect();
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        fallback.to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+fn item_hash(feed_name: &str, feed_url: &str, title: &str, link: &str, time: &str) -> String {
     let hash_input = format!("{}|{}|{}|{}|{}", feed_name, feed_url, title, link, time);
-    format!("{}.md", hash_string(&hash_input))
+    hash_string(&hash_input)
 }
 
 fn image_extension(url: &str, content_type: Option<&str>) -> Option<&'static str> {
@@ -312,6 +2730,23 @@ fn owned_extension(ext: &str) -> &'static str {
     }
 }
 
+/// Content type of a stored image from the extension it was saved with. Images saved as `.img`
+/// had no recognizable extension or content type, so their first bytes decide.
+pub fn image_content_type(name: &str, bytes: &[u8]) -> &'static str {
+    match Path::new(name).extension().and_then(|ext| ext.to_str()) {
+        Some("png") => "image/png",
+        Some("jpg") => "image/jpeg",
+        Some("webp") => "image/webp",
+        Some("gif") => "image/gif",
+        Some("svg") => "image/svg+xml",
+        _ if bytes.starts_with(b"\x89PNG") => "image/png",
+        _ if bytes.starts_with(b"\xff\xd8\xff") => "image/jpeg",
+        _ if bytes.starts_with(b"GIF8") => "image/gif",
+        _ if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") => "image/webp",
+        _ => "application/octet-stream",
+    }
+}
+
 fn content_type_extension(content_type: Option<&str>) -> Option<&'static str> {
     match content_type {
         Some(ct) if ct.contains("image/png") => Some("png"),
@@ -323,3 +2758,364 @@ fn content_type_extension(content_type: Option<&str>) -> Option<&'static str> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{temp_database, TempDir};
+
+    /// Small deterministic xorshift generator, so failures reproduce without extra dependencies.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+
+        fn weird_string(&mut self, max_len: usize) -> String {
+            const POOL: &[char] = &[
+                'a', 'Z', '0', ' ', ',', ';', '"', '\'', '\n', '\r', '\t', '\\', '|', '#', '=',
+                'é', 'ß', '中', '🦀', '\u{200b}', '\u{feff}',
+            ];
+            (0..self.below(max_len + 1))
+                .map(|_| POOL[self.below(POOL.len())])
+                .collect()
+        }
+    }
+
+    fn item(title: &str, link: &str, pub_date: Option<&str>) -> rss::Item {
+        let mut item = rss::Item::default();
+        item.set_title(title.to_string());
+        item.set_link(link.to_string());
+        item.set_pub_date(pub_date.map(str::to_string));
+        item.set_description("body".to_string());
+        item
+    }
+
+    /// Each `fixtures/convert/*.html` item body must convert to the markdown in the `.md` file
+    /// next to it. After an intended change, regenerate them with
+    /// `cargo run --bin convert-fixture -- fixtures/convert/*.html` and review the diff.
+    #[test]
+    fn converts_fixture_corpus_to_expected_markdown() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/convert");
+        let mut fixtures: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "html"))
+            .collect();
+        fixtures.sort();
+        assert!(!fixtures.is_empty());
+
+        let mut mismatched = Vec::new();
+        for fixture in &fixtures {
+            let html = fs::read_to_string(fixture).unwrap();
+            let expected = fs::read_to_string(fixture.with_extension("md"))
+                .unwrap_or_else(|err| panic!("No expected markdown for {:?}: {}", fixture, err));
+            let item = rss::ItemBuilder::default().content(html).build();
+            let markdown = extract_markdown(&item);
+            if markdown != expected {
+                eprintln!(
+                    "--- {:?} expected\n{}\n--- converted\n{}",
+                    fixture, expected, markdown
+                );
+                mismatched.push(fixture.file_name().unwrap().to_string_lossy().into_owned());
+            }
+        }
+        assert!(
+            mismatched.is_empty(),
+            "Conversion changed for {:?}",
+            mismatched
+        );
+    }
+
+    #[tokio::test]
+    async fn index_round_trips_weird_titles_and_feed_names() {
+        let (db, _dir) = temp_database("fuzz_index").await;
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        let mut expected = HashMap::new();
+
+        for round in 0..20 {
+            let feed_name = format!("feed{}{}", round, rng.weird_string(12));
+            let items: Vec<_> = (0..10)
+                .map(|n| {
+                    item(
+                        &rng.weird_string(40),
+                        &format!("https://x/{}/{}", round, n),
+                        None,
+                    )
+                })
+                .collect();
+            let mut channel = Channel::default();
+            channel.set_items(items.clone());
+            db.store_channel(
+                &feed_name,
+                "https://x/feed",
+                &channel,
+                StoreOptions::default(),
+            )
+            .await
+            .unwrap();
+            for item in &items {
+                let id = item_id(&feed_name, "https://x/feed", item);
+                expected.insert(id, (item.title().unwrap().to_string(), feed_name.clone()));
+            }
+        }
+
+        let records = db.list_articles().unwrap();
+        assert_eq!(records.len(), expected.len());
+        for record in records {
+            let (title, feed_name) = &expected[&record.id];
+            assert_eq!(&record.title, title);
+            assert_eq!(&record.feed_name, feed_name);
+            assert!(db.read_article_markdown(&record).is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn list_articles_skips_corrupt_rows() {
+        let (db, _dir) = temp_database("corrupt_index").await;
+        let mut channel = Channel::default();
+        channel.set_items(vec![item("Kept", "https://x/1", None)]);
+        db.store_channel("Feed", "https://x/feed", &channel, StoreOptions::default())
+            .await
+            .unwrap();
+        let mut index = fs::read_to_string(&db.index_path).unwrap();
+        index.push_str("garbage\n\"unterminated,quote\n");
+        fs::write(&db.index_path, index).unwrap();
+
+        let records = db.list_articles().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].title, "Kept");
+    }
+
+    #[tokio::test]
+    async fn appends_follow_a_replaced_index() {
+        let (db, _dir) = temp_database("replaced_index").await;
+        let store = |title: &str, link: &str| {
+            let mut channel = Channel::default();
+            channel.set_items(vec![item(title, link, None)]);
+            let db = db.clone();
+            async move {
+                db.store_channel("Feed", "https://x/feed", &channel, StoreOptions::default())
+                    .await
+                    .unwrap();
+            }
+        };
+        store("First", "https://x/1").await;
+        db.rewrite_index(|_, _| true).unwrap();
+        store("Second", "https://x/2").await;
+        // Another process replacing the file, as `write_atomic` does.
+        let index = fs::read(&db.index_path).unwrap();
+        write_atomic(&db.index_path, &index).unwrap();
+        store("Third", "https://x/3").await;
+
+        let mut titles: Vec<String> = db
+            .list_articles()
+            .unwrap()
+            .into_iter()
+            .map(|record| record.title)
+            .collect();
+        titles.sort();
+        assert_eq!(titles, ["First", "Second", "Third"]);
+    }
+
+    #[tokio::test]
+    async fn query_articles_filters_by_feed_date_and_title() {
+        let (db, _dir) = temp_database("query_index").await;
+        for (feed_name, title, date) in [
+            (
+                "A",
+                "Rust, \"quoted\" release",
+                "Mon, 01 Jan 2024 00:00:00 GMT",
+            ),
+            ("A", "Other news", "Wed, 10 Jan 2024 00:00:00 GMT"),
+            ("B", "Rust elsewhere", "Sat, 20 Jan 2024 00:00:00 GMT"),
+        ] {
+            let mut channel = Channel::default();
+            channel.set_items(vec![item(
+                title,
+                &format!("https://x/{}", title),
+                Some(date),
+            )]);
+            db.store_channel(
+                feed_name,
+                "https://x/feed",
+                &channel,
+                StoreOptions::default(),
+            )
+            .await
+            .unwrap();
+        }
+
+        let titles = |query: ArticleQuery| -> Vec<String> {
+            db.query_articles(&query)
+                .unwrap()
+                .into_iter()
+                .map(|record| record.title)
+                .collect()
+        };
+        assert_eq!(
+            titles(ArticleQuery::feed("A")),
+            ["Other news", "Rust, \"quoted\" release"]
+        );
+        assert_eq!(
+            titles(ArticleQuery {
+                text: Some("rust".to_string()),
+                ..ArticleQuery::default()
+            }),
+            ["Rust elsewhere", "Rust, \"quoted\" release"]
+        );
+        assert_eq!(
+            titles(ArticleQuery {
+                since: Some("2024-01-05".to_string()),
+                until: Some("2024-01-15T00:00:00Z".to_string()),
+                ..ArticleQuery::default()
+            }),
+            ["Other news"]
+        );
+        assert_eq!(
+            titles(ArticleQuery {
+                limit: Some(1),
+                ..ArticleQuery::default()
+            }),
+            ["Rust elsewhere"]
+        );
+    }
+
+    #[tokio::test]
+    async fn store_quota_prunes_oldest_unstarred_articles() {
+        let (db, dir) = temp_database("store_quota").await;
+        for (title, date) in [
+            ("One", "Mon, 01 Jan 2024 00:00:00 GMT"),
+            ("Two", "Tue, 02 Jan 2024 00:00:00 GMT"),
+            ("Three", "Wed, 03 Jan 2024 00:00:00 GMT"),
+        ] {
+            let mut channel = Channel::default();
+            channel.set_items(vec![item(
+                title,
+                &format!("https://x/{}", title),
+                Some(date),
+            )]);
+            db.store_channel("A", "https://x/feed", &channel, StoreOptions::default())
+                .await
+                .unwrap();
+        }
+        let oldest = db.query_articles(&ArticleQuery::default()).unwrap()[2].clone();
+        db.set_starred(&oldest.id, true).unwrap();
+        let usage = db.disk_usage().unwrap();
+        assert_eq!(usage.total(), dir_size(&dir));
+        assert_eq!(usage.feeds[0].articles, 3);
+
+        db.enforce_quota(dir_size(&dir) - 1).unwrap();
+
+        let titles: Vec<String> = db
+            .query_articles(&ArticleQuery::default())
+            .unwrap()
+            .into_iter()
+            .map(|record| record.title)
+            .collect();
+        assert_eq!(titles, ["Three", "One"]);
+    }
+
+    #[tokio::test]
+    async fn unreachable_images_stay_remote_without_failing_the_store() {
+        let (db, _dir) = temp_database("unreachable_image").await;
+        let mut with_image = item("Pictured", "https://x/1", None);
+        with_image.set_description(
+            r#"<p>Text</p><img src="https://unreachable.invalid/a.png">"#.to_string(),
+        );
+        let mut channel = Channel::default();
+        channel.set_items(vec![with_image, item("Plain", "https://x/2", None)]);
+        db.store_channel("Feed", "https://x/feed", &channel, StoreOptions::default())
+            .await
+            .unwrap();
+
+        let records = db.list_articles().unwrap();
+        assert_eq!(records.len(), 2);
+        let pictured = records
+            .iter()
+            .find(|record| record.title == "Pictured")
+            .unwrap();
+        let markdown = db.read_article_markdown(pictured).unwrap();
+        assert!(markdown.contains("https://unreachable.invalid/a.png"));
+    }
+
+    #[tokio::test]
+    async fn feed_store_dir_keeps_articles_outside_the_store() {
+        let (db, dir) = temp_database("feed_store_dir").await;
+        let synced = TempDir::new("feed_store_dir_synced");
+        fs::create_dir_all(&synced).unwrap();
+        let mut channel = Channel::default();
+        channel.set_items(vec![item("Elsewhere", "https://x/1", None)]);
+        let options = StoreOptions {
+            store_dir: Some(synced.to_path_buf()),
+            ..StoreOptions::default()
+        };
+        db.store_channel("Work", "https://x/feed", &channel, options)
+            .await
+            .unwrap();
+
+        let records = db.list_articles().unwrap();
+        assert!(records[0].path.starts_with(&synced));
+        assert!(db.read_article_markdown(&records[0]).is_some());
+        assert_eq!(
+            db.disk_usage().unwrap().total(),
+            dir_size(&dir) + dir_size(&synced)
+        );
+
+        db.prune_feed("Work", 0).unwrap();
+        assert!(db.list_articles().unwrap().is_empty());
+        assert!(synced.exists());
+        assert_eq!(fs::read_dir(&synced).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn writes_wait_for_another_process_holding_the_store() {
+        let (db, dir) = temp_database("store_lock").await;
+        // A second instance has its own lock files open, like another process would.
+        let other = Database::initialize(&dir, None).await.unwrap();
+        let paused = db.pause_state_writes();
+
+        let (done, finished) = std::sync::mpsc::channel();
+        let writer = std::thread::spawn(move || {
+            other.set_starred(&"a".repeat(64), true).unwrap();
+            done.send(()).unwrap();
+        });
+        assert!(finished
+            .recv_timeout(std::time::Duration::from_millis(200))
+            .is_err());
+        drop(paused);
+        finished
+            .recv_timeout(std::time::Duration::from_secs(5))
+            .unwrap();
+        writer.join().unwrap();
+        assert_eq!(db.starred_ids().unwrap().len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn atomic_writes_go_through_symlinks() {
+        let dir = TempDir::new("symlink");
+        fs::create_dir_all(dir.join("synced")).unwrap();
+        let target = dir.join("synced").join("state.csv");
+        let link = dir.join("state.csv");
+        fs::write(&target, "old").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        write_atomic(&link, b"new").unwrap();
+
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+        assert_eq!(fs::read_dir(dir.join("synced")).unwrap().count(), 1);
+    }
+}
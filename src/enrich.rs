@@ -0,0 +1,147 @@
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use rss::Item;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Discussion thread of an item on an aggregator site.
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct Discussion {
+    pub site: String,
+    pub url: String,
+    pub comments: Option<u64>,
+}
+
+/// A site whose discussion threads can be looked up for feed items.
+pub trait Enricher: Send + Sync {
+    fn site(&self) -> &'static str;
+
+    /// Returns the thread's web URL and the JSON API URL carrying its comment count.
+    fn locate(&self, item: &Item) -> Option<(String, String)>;
+
+    fn comment_count(&self, json: &Value) -> Option<u64>;
+}
+
+/// Time a discussion lookup may take before it is given up.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(15);
+
+static HACKER_NEWS_ID: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"news\.ycombinator\.com/item\?id=(\d+)").unwrap());
+static REDDIT_ID: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"reddit\.com/r/[^/]+/comments/([a-z0-9]+)").unwrap());
+static LOBSTERS_ID: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"lobste\.rs/s/([a-z0-9]+)").unwrap());
+
+struct HackerNews;
+struct Reddit;
+struct Lobsters;
+
+impl Enricher for HackerNews {
+    fn site(&self) -> &'static str {
+        "Hacker News"
+    }
+
+    fn locate(&self, item: &Item) -> Option<(String, String)> {
+        let id = candidate_urls(item)
+            .find_map(|url| HACKER_NEWS_ID.captures(url).and_then(|caps| caps.get(1)))?
+            .as_str()
+            .to_string();
+        Some((
+            format!("https://news.ycombinator.com/item?id={}", id),
+            format!("https://hacker-news.firebaseio.com/v0/item/{}.json", id),
+        ))
+    }
+
+    fn comment_count(&self, json: &Value) -> Option<u64> {
+        json.get("descendants").and_then(Value::as_u64)
+    }
+}
+
+impl Enricher for Reddit {
+    fn site(&self) -> &'static str {
+        "Reddit"
+    }
+
+    fn locate(&self, item: &Item) -> Option<(String, String)> {
+        let id = candidate_urls(item)
+            .find_map(|url| REDDIT_ID.captures(url).and_then(|caps| caps.get(1)))?
+            .as_str()
+            .to_string();
+        Some((
+            format!("https://www.reddit.com/comments/{}", id),
+            format!("https://www.reddit.com/comments/{}.json", id),
+        ))
+    }
+
+    fn comment_count(&self, json: &Value) -> Option<u64> {
+        json.pointer("/0/data/children/0/data/num_comments")
+            .and_then(Value::as_u64)
+    }
+}
+
+impl Enricher for Lobsters {
+    fn site(&self) -> &'static str {
+        "Lobsters"
+    }
+
+    fn locate(&self, item: &Item) -> Option<(String, String)> {
+        let id = candidate_urls(item)
+            .find_map(|url| LOBSTERS_ID.captures(url).and_then(|caps| caps.get(1)))?
+            .as_str()
+            .to_string();
+        Some((
+            format!("https://lobste.rs/s/{}", id),
+            format!("https://lobste.rs/s/{}.json", id),
+        ))
+    }
+
+    fn comment_count(&self, json: &Value) -> Option<u64> {
+        json.get("comment_count").and_then(Value::as_u64)
+    }
+}
+
+fn candidate_urls(item: &Item) -> impl Iterator<Item = &str> {
+    [
+        item.comments(),
+        item.link(),
+        item.guid().map(|guid| guid.value()),
+    ]
+    .into_iter()
+    .flatten()
+}
+
+fn enrichers() -> [&'static dyn Enricher; 3] {
+    [&HackerNews, &Reddit, &Lobsters]
+}
+
+/// Looks up the item's discussion thread and its current comment count.
+/// Returns `None` when no enricher recognizes the item.
+pub async fn fetch_discussion(item: &Item) -> Result<Option<Discussion>> {
+    let Some((enricher, (url, api_url))) = enrichers()
+        .into_iter()
+        .find_map(|enricher| enricher.locate(item).map(|found| (enricher, found)))
+    else {
+        return Ok(None);
+    };
+
+    let json: Value = reqwest::Client::new()
+        .get(&api_url)
+        .header(reqwest::header::USER_AGENT, "rss_reader")
+        .timeout(FETCH_TIMEOUT)
+        .send()
+        .await
+        .context("Failed to fetch discussion")?
+        .error_for_status()?
+        .json()
+        .await
+        .context("Failed to parse discussion")?;
+
+    Ok(Some(Discussion {
+        site: enricher.site().to_string(),
+        url,
+        comments: enricher.comment_count(&json),
+    }))
+}
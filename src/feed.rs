@@ -1,10 +1,30 @@
-use std::io::Cursor;
+use std::cmp::{Ordering as CmpOrdering, Reverse};
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use std::fmt;
+use std::io::{BufRead, Cursor};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
-use rss::Channel;
+use chrono::{DateTime, Datelike, DurationRound, NaiveDate, TimeDelta, Timelike, Utc, Weekday};
+use quick_xml::events::Event;
+use quick_xml::{Reader, Writer, XmlVersion};
+use regex::Regex;
+use reqwest::header::{
+    HeaderMap, CONTENT_LENGTH, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use reqwest::StatusCode;
+use rss::{Channel, ChannelBuilder, Guid, Item, ItemBuilder};
+use scraper::{ElementRef, Html, Selector};
+use tokio::io::AsyncReadExt;
+use tokio_util::sync::CancellationToken;
 use url::Url;
 
-use crate::config::Feed;
+use crate::config::{Feed, ScraperSelectors, DEFAULT_FETCH_TIMEOUT_SECS, DEFAULT_MAX_FEED_SIZE_MB};
+use crate::fetcher::FeedFetcher;
+use crate::{blocklist, db, http_cache, media, microblog, newsletter, plugins};
 
 fn normalize_route(route: &str) -> String {
     if route.starts_with('/') {
@@ -32,30 +52,921 @@ pub fn build_feed_url(feed: &Feed) -> Result<String> {
     }
 }
 
-pub async fn fetch_channel(url: &str) -> Result<Channel> {
-    let client = reqwest::Client::new();
-    let response = client
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+/// Prints request/response details and parse timing to stderr for subsequent fetches.
+pub fn set_verbose(verbose: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+}
+
+pub fn is_verbose() -> bool {
+    VERBOSE.load(Ordering::Relaxed)
+}
+
+/// Response bytes downloaded per URL, until taken for a feed's bandwidth account.
+static DOWNLOADED: LazyLock<Mutex<HashMap<String, u64>>> = LazyLock::new(Default::default);
+
+fn count_download(url: &str, bytes: usize) {
+    let mut downloaded = DOWNLOADED.lock().unwrap_or_else(|err| err.into_inner());
+    *downloaded.entry(url.to_string()).or_default() += bytes as u64;
+}
+
+/// Bytes downloaded fetching `feed` since the last call.
+pub fn take_downloaded_bytes(feed: &Feed) -> u64 {
+    let Ok(url) = build_feed_url(feed) else {
+        return 0;
+    };
+    DOWNLOADED
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .remove(&url)
+        .unwrap_or_default()
+}
+
+/// Headers of the last full fetch of a feed URL, compared against a HEAD request to tell whether
+/// the feed changed without downloading it.
+#[derive(Debug, Clone, Default)]
+struct Validators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    content_length: Option<u64>,
+    /// Set once the host answered a HEAD request with an error; such feeds are always fetched.
+    head_unsupported: bool,
+}
+
+impl Validators {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+            content_length: header(CONTENT_LENGTH).and_then(|length| length.parse().ok()),
+            head_unsupported: false,
+        }
+    }
+
+    /// Compares the strongest validator both responses carry: ETag, then Last-Modified, then
+    /// Content-Length. `None` when they have none in common.
+    fn matches(&self, current: &Validators) -> Option<bool> {
+        fn same<T: PartialEq>(old: &Option<T>, new: &Option<T>) -> Option<bool> {
+            Some(old.as_ref()? == new.as_ref()?)
+        }
+        same(&self.etag, &current.etag)
+            .or_else(|| same(&self.last_modified, &current.last_modified))
+            .or_else(|| same(&self.content_length, &current.content_length))
+    }
+}
+
+static VALIDATORS: LazyLock<Mutex<HashMap<String, Validators>>> = LazyLock::new(Default::default);
+
+fn record_validators(url: &str, headers: &HeaderMap) {
+    let mut validators = VALIDATORS.lock().unwrap_or_else(|err| err.into_inner());
+    let head_unsupported = validators
         .get(url)
-        .send()
+        .is_some_and(|previous| previous.head_unsupported);
+    validators.insert(
+        url.to_string(),
+        Validators {
+            head_unsupported,
+            ..Validators::from_headers(headers)
+        },
+    );
+}
+
+/// Asks with a HEAD request whether `feed` changed since it was last fetched, sending the stored
+/// ETag and Last-Modified as conditions. Returns `false` whenever it can't tell: the feed was
+/// never fetched in this process, isn't a plain remote feed, or the host gives no validators.
+pub async fn is_unchanged(fetcher: &dyn FeedFetcher, feed: &Feed) -> bool {
+    if feed.newsletter.is_some() || feed.selectors.is_some() {
+        return false;
+    }
+    let Ok(url) = build_feed_url(feed) else {
+        return false;
+    };
+    let previous = VALIDATORS
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .get(&url)
+        .cloned();
+    let Some(previous) = previous.filter(|previous| !previous.head_unsupported) else {
+        return false;
+    };
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in [
+        (IF_NONE_MATCH, &previous.etag),
+        (IF_MODIFIED_SINCE, &previous.last_modified),
+    ] {
+        if let Some(value) = value.as_deref().and_then(|value| value.parse().ok()) {
+            headers.insert(name, value);
+        }
+    }
+    let response = match fetcher.head(&url, headers).await {
+        Ok(response) => response,
+        Err(err) => {
+            if is_verbose() {
+                eprintln!("[fetch] HEAD {} failed: {}", url, err);
+            }
+            return false;
+        }
+    };
+    let status = response.status;
+    let unchanged = if status == StatusCode::NOT_MODIFIED {
+        true
+    } else if status.is_success() {
+        previous
+            .matches(&Validators::from_headers(&response.headers))
+            .unwrap_or(false)
+    } else {
+        if let Some(validators) = VALIDATORS
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .get_mut(&url)
+        {
+            validators.head_unsupported = true;
+        }
+        false
+    };
+    if is_verbose() {
+        eprintln!(
+            "[fetch] HEAD {} {}: {}",
+            url,
+            status,
+            if unchanged { "unchanged" } else { "changed" }
+        );
+    }
+    unchanged
+}
+
+/// A channel's `<language>` (or a configured one) as a BCP 47 tag, e.g. `zh-CN` for `zh_cn`.
+/// Values that aren't language codes are dropped.
+pub fn language_tag(value: &str) -> Option<String> {
+    static TAG: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^[A-Za-z]{2,8}(-[A-Za-z0-9]{1,8})*$").unwrap());
+    let tag = value.trim().replace('_', "-");
+    if !TAG.is_match(&tag) {
+        return None;
+    }
+    let mut parts = tag.split('-');
+    let mut normalized = parts.next().unwrap_or_default().to_lowercase();
+    for part in parts {
+        normalized.push('-');
+        // Region subtags are upper case, script subtags title case (`zh-Hant-TW`).
+        match part.len() {
+            2 => normalized.push_str(&part.to_uppercase()),
+            4 => {
+                normalized.push_str(&part[..1].to_uppercase());
+                normalized.push_str(&part[1..].to_lowercase());
+            }
+            _ => normalized.push_str(part),
+        }
+    }
+    Some(normalized)
+}
+
+/// How often a channel asks to be fetched: at most once per `<ttl>` minutes, and never during
+/// its `<skipHours>` (GMT) or `<skipDays>`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UpdateHints {
+    pub ttl_minutes: Option<u64>,
+    pub skip_hours: Vec<u32>,
+    pub skip_days: Vec<Weekday>,
+}
+
+impl UpdateHints {
+    pub fn from_channel(channel: &Channel) -> Self {
+        Self::parse(
+            channel.ttl(),
+            channel.skip_hours().iter().map(String::as_str),
+            channel.skip_days().iter().map(String::as_str),
+        )
+    }
+
+    /// Reads the hints' text, ignoring values that aren't valid. Hour 24 is taken as midnight.
+    pub fn parse<'a>(
+        ttl: Option<&str>,
+        skip_hours: impl IntoIterator<Item = &'a str>,
+        skip_days: impl IntoIterator<Item = &'a str>,
+    ) -> Self {
+        let mut hours: Vec<u32> = skip_hours
+            .into_iter()
+            .filter_map(|hour| hour.trim().parse::<u32>().ok())
+            .filter(|hour| *hour <= 24)
+            .map(|hour| hour % 24)
+            .collect();
+        hours.sort_unstable();
+        hours.dedup();
+        let mut days: Vec<Weekday> = skip_days
+            .into_iter()
+            .filter_map(|day| day.trim().parse().ok())
+            .collect();
+        days.sort_by_key(Weekday::num_days_from_monday);
+        days.dedup();
+        Self {
+            ttl_minutes: ttl
+                .and_then(|ttl| ttl.trim().parse().ok())
+                .filter(|minutes| *minutes > 0),
+            skip_hours: hours,
+            skip_days: days,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ttl_minutes.is_none() && self.skip_hours.is_empty() && self.skip_days.is_empty()
+    }
+
+    /// Whether `at` is in one of the hours or days the channel asks not to be fetched.
+    pub fn is_skipped(&self, at: DateTime<Utc>) -> bool {
+        self.skip_hours.contains(&at.hour()) || self.skip_days.contains(&at.weekday())
+    }
+
+    /// When the channel, last fetched at `fetched_at`, wants to be fetched next, or `None` if it
+    /// may be fetched at `now`.
+    pub fn next_fetch(
+        &self,
+        fetched_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        let ttl = TimeDelta::minutes(self.ttl_minutes.unwrap_or_default() as i64);
+        let mut next = (fetched_at + ttl).max(now);
+        // Skipping every hour of the week would never allow a fetch; give up after a week.
+        for _ in 0..24 * 7 {
+            if !self.is_skipped(next) {
+                break;
+            }
+            let hour = TimeDelta::hours(1);
+            next = next.duration_trunc(hour).unwrap_or(next) + hour;
+        }
+        (next > now).then_some(next)
+    }
+}
+
+impl fmt::Display for UpdateHints {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(minutes) = self.ttl_minutes {
+            parts.push(format!("every {} min", minutes));
+        }
+        if !self.skip_hours.is_empty() {
+            let hours: Vec<String> = self
+                .skip_hours
+                .iter()
+                .map(|hour| format!("{:02}h", hour))
+                .collect();
+            parts.push(format!("skips {} GMT", hours.join(", ")));
+        }
+        if !self.skip_days.is_empty() {
+            let days: Vec<String> = self.skip_days.iter().map(Weekday::to_string).collect();
+            parts.push(format!("skips {}", days.join(", ")));
+        }
+        write!(f, "{}", parts.join("; "))
+    }
+}
+
+/// Source name that reads a feed from standard input.
+pub const STDIN_SOURCE: &str = "-";
+
+/// Caps on a single remote feed download.
+#[derive(Clone, Copy)]
+pub(crate) struct FetchLimits {
+    pub(crate) max_bytes: u64,
+    timeout: Duration,
+    /// Items kept while parsing, the newest ones as ranked for the archive.
+    max_items: Option<usize>,
+}
+
+impl Default for FetchLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_MAX_FEED_SIZE_MB * 1024 * 1024,
+            timeout: Duration::from_secs(DEFAULT_FETCH_TIMEOUT_SECS),
+            max_items: None,
+        }
+    }
+}
+
+impl FetchLimits {
+    fn for_feed(feed: &Feed) -> Self {
+        Self {
+            max_bytes: feed.max_feed_bytes(),
+            timeout: feed.fetch_timeout(),
+            max_items: feed.max_items,
+        }
+    }
+}
+
+/// A feed that took longer than its `timeout` to fetch.
+#[derive(Debug)]
+pub struct TimedOut {
+    timeout: Duration,
+    url: String,
+}
+
+impl fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Timed out after {}s fetching {}",
+            self.timeout.as_secs(),
+            self.url
+        )
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+fn timed_out(timeout: Duration, url: &str) -> anyhow::Error {
+    anyhow::Error::new(TimedOut {
+        timeout,
+        url: url.to_string(),
+    })
+}
+
+/// Reads a feed from an http(s) URL or a plugin source, limited to the default `max_size_mb` and
+/// `timeout`. Files and stdin are only read by the `read` command, through
+/// [`read_local_channel`], so a path given by a config or a web form is never opened.
+pub async fn fetch_channel(fetcher: &dyn FeedFetcher, url: &str) -> Result<Channel> {
+    fetch_channel_limited(fetcher, url, FetchLimits::default()).await
+}
+
+async fn fetch_channel_limited(
+    fetcher: &dyn FeedFetcher,
+    url: &str,
+    limits: FetchLimits,
+) -> Result<Channel> {
+    let mut channel = if plugins::is_plugin_source(url) {
+        plugins::fetch_source(url)?
+    } else {
+        check_remote_url(url)?;
+        tokio::time::timeout(limits.timeout, fetch_remote_channel(fetcher, url, limits))
+            .await
+            .unwrap_or_else(|_| Err(timed_out(limits.timeout, url)))?
+    };
+    plugins::transform_channel(&mut channel);
+    drop_blocked(&mut channel);
+    Ok(channel)
+}
+
+/// Drops the items the config's `blocklist` matches.
+fn drop_blocked(channel: &mut Channel) {
+    let dropped = blocklist::filter_channel(channel);
+    if dropped > 0 && is_verbose() {
+        eprintln!("[fetch] dropped {} blocklisted items", dropped);
+    }
+}
+
+async fn fetch_remote_channel(
+    fetcher: &dyn FeedFetcher,
+    url: &str,
+    limits: FetchLimits,
+) -> Result<Channel> {
+    let mut response = fetcher
+        .get_streaming(url, limits.max_bytes)
         .await
         .context("Failed to fetch RSS feed")?;
 
-    if !response.status().is_success() {
-        return Err(anyhow::anyhow!(
-            "Failed to fetch RSS feed: {}",
-            response.status()
+    if !response.status.is_success() {
+        return Err(http_cache::error_status(
+            "Failed to fetch RSS feed",
+            response.status,
         ));
     }
 
-    let content = response
-        .bytes()
+    // The body is parsed as it arrives, so a long feed is never held in memory in full.
+    let body = response.take_body();
+    let max_items = limits.max_items;
+    let parsed = tokio::task::spawn_blocking(move || parse_channel(body, max_items))
+        .await
+        .context("Failed to parse RSS feed")?;
+    // A body that couldn't be received in full fails the fetch rather than the parse.
+    let bytes = response
+        .finish()
         .await
-        .context("Failed to read response body")?;
+        .context("Failed to fetch RSS feed")?;
+    if is_verbose() {
+        eprintln!("[fetch] {} bytes", bytes);
+    }
+    let channel = parsed?;
+    if !response.from_cache {
+        count_download(url, bytes as usize);
+    }
+    record_validators(url, &response.headers);
+    Ok(channel)
+}
 
-    Channel::read_from(Cursor::new(content)).context("Failed to parse RSS feed")
+/// Fails unless `url` is an absolute `http` or `https` URL.
+pub fn check_remote_url(url: &str) -> Result<Url> {
+    match Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(parsed),
+        _ => Err(anyhow::anyhow!("Not an http(s) feed URL: {}", url)),
+    }
 }
 
-pub async fn fetch_configured_feed(feed: &Feed) -> Result<Channel> {
+/// Returns `Some(None)` for stdin and `Some(path)` for `file://` URLs and plain paths.
+pub fn local_feed_path(source: &str) -> Option<Option<PathBuf>> {
+    if source == STDIN_SOURCE {
+        return Some(None);
+    }
+    match Url::parse(source) {
+        Ok(url) if url.scheme() == "file" => url.to_file_path().ok().map(Some),
+        Ok(_) => None,
+        Err(_) => Some(Some(PathBuf::from(source))),
+    }
+}
+
+/// Reads a feed file, or stdin without a path, for the `read` command.
+pub async fn read_local_channel(path: Option<&Path>) -> Result<Channel> {
+    let content = match path {
+        Some(path) => tokio::fs::read(path)
+            .await
+            .context(format!("Failed to read feed file: {:?}", path))?,
+        None => {
+            let mut content = Vec::new();
+            tokio::io::stdin()
+                .read_to_end(&mut content)
+                .await
+                .context("Failed to read feed from stdin")?;
+            content
+        }
+    };
+    if is_verbose() {
+        eprintln!("[fetch] {} bytes", content.len());
+    }
+    let mut channel = parse_channel(Cursor::new(content), None)?;
+    plugins::transform_channel(&mut channel);
+    drop_blocked(&mut channel);
+    Ok(channel)
+}
+
+/// Parses a feed read from `source`, keeping only its `max_items` newest items if set.
+fn parse_channel<R: BufRead>(mut source: R, max_items: Option<usize>) -> Result<Channel> {
+    let verbose = is_verbose();
+    // The start of the document, for the messages below; not consumed.
+    let head = if verbose {
+        let head = source
+            .fill_buf()
+            .map(|buf| buf.to_vec())
+            .unwrap_or_default();
+        if let Some(encoding) = xml_declared_encoding(&head) {
+            eprintln!("[fetch] XML encoding: {}", encoding);
+        }
+        head
+    } else {
+        Vec::new()
+    };
+
+    let started = Instant::now();
+    let result = match max_items {
+        Some(max_items) => read_newest_items(source, max_items),
+        None => Channel::read_from(source),
+    };
+    if verbose {
+        match &result {
+            Ok(channel) => eprintln!(
+                "[fetch] parsed {} items in {:?}",
+                channel.items().len(),
+                started.elapsed()
+            ),
+            Err(err) => {
+                eprintln!("[fetch] parse error after {:?}: {}", started.elapsed(), err);
+                let head = String::from_utf8_lossy(&head[..head.len().min(200)]);
+                eprintln!("[fetch] body starts with: {}", head.trim());
+            }
+        }
+    }
+
+    let mut channel = result.context("Failed to parse RSS feed")?;
+    let namespaces = channel.namespaces().clone();
+    for item in channel.items_mut() {
+        fill_from_extensions(item, &namespaces);
+        media::enclose_embedded_audio(item);
+    }
+    microblog::normalize_channel(&mut channel);
+    Ok(channel)
+}
+
+/// Namespace of the RSS content module, whose `encoded` element holds an item's full HTML.
+const CONTENT_NAMESPACE: &str = "http://purl.org/rss/1.0/modules/content/";
+
+/// Fills in the content, author and date of an item that only has them as `content:encoded`,
+/// `dc:creator` and `dc:date`. The rss crate only recognizes the literal `content:` prefix and
+/// a declared Dublin Core namespace, so other prefixes for the content module, and `dc:`
+/// elements used without declaring it, are read from the item's other extensions.
+fn fill_from_extensions(item: &mut Item, namespaces: &BTreeMap<String, String>) {
+    let mut content_prefixes: Vec<&str> = namespaces
+        .iter()
+        .filter(|(_, uri)| uri.as_str() == CONTENT_NAMESPACE)
+        .map(|(prefix, _)| prefix.as_str())
+        .collect();
+    content_prefixes.push("content");
+
+    if item.content().is_none() {
+        let content = extension_text(item, &content_prefixes, "encoded");
+        item.set_content(content);
+    }
+    if item.author().is_none() {
+        let creator = item
+            .dublin_core_ext()
+            .and_then(|dc| dc.creators().first().cloned())
+            .or_else(|| extension_text(item, &["dc"], "creator"));
+        item.set_author(creator);
+    }
+    if item.pub_date().is_none() {
+        let date = item
+            .dublin_core_ext()
+            .and_then(|dc| dc.dates().first().cloned())
+            .or_else(|| extension_text(item, &["dc"], "date"));
+        // RSS dates are RFC 2822, so a parseable `dc:date` is converted.
+        let date = date.map(|date| match DateTime::parse_from_rfc3339(date.trim()) {
+            Ok(parsed) => parsed.to_rfc2822(),
+            Err(_) => date,
+        });
+        item.set_pub_date(date);
+    }
+}
+
+/// Text of the item's first `name` extension element under any of `prefixes`.
+fn extension_text(item: &Item, prefixes: &[&str], name: &str) -> Option<String> {
+    prefixes.iter().find_map(|prefix| {
+        item.extensions()
+            .get(*prefix)?
+            .get(name)?
+            .first()?
+            .value()
+            .map(str::to_string)
+    })
+}
+
+/// An item parsed by [`read_newest_items`], ordered so the first one to drop is the greatest.
+struct RankedItem {
+    updated: Reverse<Option<String>>,
+    position: usize,
+    item: Item,
+}
+
+impl RankedItem {
+    fn key(&self) -> (&Reverse<Option<String>>, usize) {
+        (&self.updated, self.position)
+    }
+}
+
+impl PartialEq for RankedItem {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for RankedItem {}
+
+impl PartialOrd for RankedItem {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RankedItem {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// Parses a channel keeping only its `max_items` newest items, ranked by update or publication
+/// time like the archive ranks them, with undated items last in feed order.
+///
+/// Items are read one at a time and dropped as soon as `max_items` newer ones were seen, so a
+/// long feed never has all its parsed items at once, and the document is read as it arrives.
+/// Everything else in it is copied aside and parsed as a channel without items.
+fn read_newest_items<R: BufRead>(source: R, max_items: usize) -> Result<Channel, rss::Error> {
+    let mut reader = Reader::from_reader(source);
+    reader.config_mut().expand_empty_elements = true;
+    let mut skeleton = Writer::new(Vec::new());
+    // Names and in-scope namespace declarations of the open elements.
+    let mut open: Vec<(Vec<u8>, BTreeMap<String, String>)> = Vec::new();
+    let mut newest = BinaryHeap::new();
+    let mut position = 0;
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(element)
+                if element.name().as_ref() == b"item"
+                    && (open.len() == 1
+                        || open.last().is_some_and(|(name, _)| name == b"channel")) =>
+            {
+                let namespaces = open.last().map(|(_, namespaces)| namespaces.clone());
+                let namespaces = namespaces.unwrap_or_default();
+                let mut item = Item::from_xml(&namespaces, &mut reader, element.attributes())?;
+                fill_from_extensions(&mut item, &namespaces);
+                let updated =
+                    db::item_updated(&item).or_else(|| db::parse_pub_date(item.pub_date()));
+                newest.push(RankedItem {
+                    updated: Reverse(updated),
+                    position,
+                    item,
+                });
+                if newest.len() > max_items {
+                    newest.pop();
+                }
+                position += 1;
+            }
+            Event::Start(element) => {
+                let mut namespaces = open
+                    .last()
+                    .map(|(_, namespaces)| namespaces.clone())
+                    .unwrap_or_default();
+                for attr in element.attributes().with_checks(false).flatten() {
+                    if let Some(prefix) = attr.key.as_ref().strip_prefix(b"xmlns:") {
+                        let uri = attr.decoded_and_normalized_value(
+                            XmlVersion::Implicit1_0,
+                            reader.decoder(),
+                        )?;
+                        namespaces.insert(
+                            String::from_utf8_lossy(prefix).into_owned(),
+                            uri.into_owned(),
+                        );
+                    }
+                }
+                open.push((element.name().as_ref().to_vec(), namespaces));
+                skeleton
+                    .write_event(Event::Start(element))
+                    .map_err(quick_xml::Error::from)?;
+            }
+            Event::End(element) => {
+                open.pop();
+                skeleton
+                    .write_event(Event::End(element))
+                    .map_err(quick_xml::Error::from)?;
+            }
+            Event::Eof => break,
+            event => skeleton
+                .write_event(event)
+                .map_err(quick_xml::Error::from)?,
+        }
+        buf.clear();
+    }
+
+    let mut channel = Channel::read_from(Cursor::new(skeleton.into_inner()))?;
+    let mut items = newest.into_vec();
+    items.sort_by_key(|ranked| ranked.position);
+    channel.set_items(
+        items
+            .into_iter()
+            .map(|ranked| ranked.item)
+            .collect::<Vec<_>>(),
+    );
+    Ok(channel)
+}
+
+/// `encoding` attribute of the `<?xml ...?>` declaration, if any.
+fn xml_declared_encoding(content: &[u8]) -> Option<String> {
+    let head = String::from_utf8_lossy(&content[..content.len().min(200)]);
+    let re = Regex::new(r#"^\s*<\?xml[^>]*encoding=["']([^"']+)["']"#).unwrap();
+    re.captures(&head)
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str().to_string())
+}
+
+pub async fn fetch_configured_feed(fetcher: &dyn FeedFetcher, feed: &Feed) -> Result<Channel> {
+    if let Some(newsletter) = &feed.newsletter {
+        let mut channel = newsletter::fetch_channel(newsletter).await?;
+        plugins::transform_channel(&mut channel);
+        drop_blocked(&mut channel);
+        return Ok(channel);
+    }
+
     let url = build_feed_url(feed)?;
-    fetch_channel(&url).await
+    let fetch = async {
+        match &feed.selectors {
+            Some(selectors) => {
+                let mut channel =
+                    scrape_channel(fetcher, &url, selectors, feed.max_feed_bytes()).await?;
+                plugins::transform_channel(&mut channel);
+                drop_blocked(&mut channel);
+                Ok(channel)
+            }
+            None => fetch_channel_limited(fetcher, &url, FetchLimits::for_feed(feed)).await,
+        }
+    };
+    let timeout = feed.fetch_timeout();
+    tokio::time::timeout(timeout, fetch)
+        .await
+        .unwrap_or_else(|_| Err(timed_out(timeout, &url)))
+}
+
+/// Fetches the feed like `fetch_configured_feed`, giving up as soon as `cancel` is triggered.
+pub async fn fetch_cancellable(
+    fetcher: &dyn FeedFetcher,
+    feed: &Feed,
+    cancel: &CancellationToken,
+) -> Result<Channel> {
+    tokio::select! {
+        result = fetch_configured_feed(fetcher, feed) => result,
+        () = cancel.cancelled() => Err(anyhow::anyhow!("Cancelled fetching {}", feed.url)),
+    }
+}
+
+/// Fetches an HTML page and builds a synthetic channel from it using CSS selectors.
+async fn scrape_channel(
+    fetcher: &dyn FeedFetcher,
+    url: &str,
+    selectors: &ScraperSelectors,
+    max_bytes: u64,
+) -> Result<Channel> {
+    let base = Url::parse(url).context("Invalid page URL")?;
+    let response = fetcher
+        .get(url, max_bytes)
+        .await
+        .context("Failed to fetch page")?;
+    if !response.status.is_success() {
+        return Err(http_cache::error_status(
+            "Failed to fetch page",
+            response.status,
+        ));
+    }
+    if !response.from_cache {
+        count_download(url, response.body.len());
+    }
+    let html = String::from_utf8_lossy(&response.body);
+    scrape_html(&html, &base, selectors)
+}
+
+fn scrape_html(html: &str, base: &Url, selectors: &ScraperSelectors) -> Result<Channel> {
+    let parse = |selector: &str| {
+        Selector::parse(selector)
+            .map_err(|err| anyhow::anyhow!("Invalid CSS selector {:?}: {}", selector, err))
+    };
+    let item_selector = parse(&selectors.item)?;
+    let title_selector = parse(&selectors.title)?;
+    let link_selector = parse(&selectors.link)?;
+    let date_selector = selectors.date.as_deref().map(parse).transpose()?;
+    let description_selector = selectors.description.as_deref().map(parse).transpose()?;
+    let anchor_selector = parse("a[href]")?;
+
+    let document = Html::parse_document(html);
+    let page_title = parse("title")?;
+    let channel_title = document
+        .select(&page_title)
+        .next()
+        .map(|element| element_text(&element))
+        .unwrap_or_else(|| base.to_string());
+
+    let items = document
+        .select(&item_selector)
+        .filter_map(|element| {
+            let title = element
+                .select(&title_selector)
+                .next()
+                .map(|title| element_text(&title))?;
+            let link = element.select(&link_selector).next().and_then(|link| {
+                link.value()
+                    .attr("href")
+                    .or_else(|| {
+                        link.select(&anchor_selector)
+                            .next()
+                            .and_then(|anchor| anchor.value().attr("href"))
+                    })
+                    .and_then(|href| base.join(href).ok())
+                    .map(|url| url.to_string())
+            });
+            let pub_date = date_selector.as_ref().and_then(|selector| {
+                element.select(selector).next().map(|date| {
+                    let raw = date
+                        .value()
+                        .attr("datetime")
+                        .map(str::to_string)
+                        .unwrap_or_else(|| element_text(&date));
+                    normalize_date(&raw)
+                })
+            });
+            let description = description_selector
+                .as_ref()
+                .and_then(|selector| element.select(selector).next())
+                .map(|description| description.inner_html());
+
+            Some(
+                ItemBuilder::default()
+                    .title(Some(title))
+                    .guid(link.clone().map(|value| Guid {
+                        value,
+                        permalink: true,
+                    }))
+                    .link(link)
+                    .pub_date(pub_date)
+                    .description(description)
+                    .build(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    Ok(ChannelBuilder::default()
+        .title(channel_title)
+        .link(base.to_string())
+        .items(items)
+        .build())
+}
+
+fn element_text(element: &ElementRef<'_>) -> String {
+    element
+        .text()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Converts common scraped date formats to RFC 2822; unknown formats pass through unchanged.
+fn normalize_date(raw: &str) -> String {
+    let raw = raw.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(raw) {
+        return date.to_rfc2822();
+    }
+    if DateTime::parse_from_rfc2822(raw).is_ok() {
+        return raw.to_string();
+    }
+    for format in ["%Y-%m-%d", "%B %d, %Y", "%b %d, %Y", "%d %B %Y", "%Y/%m/%d"] {
+        if let Ok(date) = NaiveDate::parse_from_str(raw, format) {
+            if let Some(datetime) = date.and_hms_opt(0, 0, 0) {
+                return datetime.and_utc().to_rfc2822();
+            }
+        }
+    }
+    raw.to_string()
+}
+
+/// A feed advertised by (or located at) a page URL.
+#[derive(Debug, Clone)]
+pub struct DiscoveredFeed {
+    pub url: String,
+    pub title: String,
+}
+
+/// Finds RSS feeds for a page: the URL itself if it is a feed, otherwise its
+/// `<link rel="alternate" type="application/rss+xml">` entries.
+pub async fn discover_feeds(page_url: &str) -> Result<Vec<DiscoveredFeed>> {
+    let base = Url::parse(page_url).context("Invalid page URL")?;
+    let response = http_cache::get_untrusted(page_url, FetchLimits::default().max_bytes)
+        .await
+        .context("Failed to fetch page")?;
+    if !response.status.is_success() {
+        return Err(http_cache::error_status(
+            "Failed to fetch page",
+            response.status,
+        ));
+    }
+    let body = response.body;
+
+    if let Ok(channel) = Channel::read_from(Cursor::new(&body)) {
+        return Ok(vec![DiscoveredFeed {
+            url: page_url.to_string(),
+            title: channel.title().to_string(),
+        }]);
+    }
+
+    let html = String::from_utf8_lossy(&body);
+    Ok(discover_feed_links(&html, &base))
+}
+
+fn discover_feed_links(html: &str, base: &Url) -> Vec<DiscoveredFeed> {
+    let link_tag = Regex::new(r"(?is)<link\b[^>]*>").unwrap();
+    let attr = |tag: &str, name: &str| -> Option<String> {
+        let pattern = format!(r#"(?is)\b{}\s*=\s*["']([^"']*)["']"#, name);
+        Regex::new(&pattern)
+            .ok()?
+            .captures(tag)
+            .and_then(|caps| caps.get(1))
+            .map(|value| value.as_str().to_string())
+    };
+
+    let mut feeds: Vec<DiscoveredFeed> = Vec::new();
+    for tag in link_tag.find_iter(html).map(|m| m.as_str()) {
+        let is_alternate = attr(tag, "rel")
+            .map(|rel| rel.to_ascii_lowercase().contains("alternate"))
+            .unwrap_or(false);
+        let is_rss = attr(tag, "type")
+            .map(|kind| kind.eq_ignore_ascii_case("application/rss+xml"))
+            .unwrap_or(false);
+        if !is_alternate || !is_rss {
+            continue;
+        }
+        let Some(href) = attr(tag, "href") else {
+            continue;
+        };
+        let Ok(url) = base.join(&href) else {
+            continue;
+        };
+        let url = url.to_string();
+        if feeds.iter().any(|feed| feed.url == url) {
+            continue;
+        }
+        feeds.push(DiscoveredFeed {
+            title: attr(tag, "title").unwrap_or_else(|| url.clone()),
+            url,
+        });
+    }
+    feeds
 }
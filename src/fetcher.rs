@@ -0,0 +1,509 @@
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use reqwest::header::{HeaderMap, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE};
+use reqwest::StatusCode;
+
+use crate::feed::is_verbose;
+use crate::http_cache::{self, Response, StreamingResponse};
+
+/// Redirects followed before a fetch fails, matching reqwest's default policy.
+const MAX_REDIRECTS: usize = 10;
+
+/// Downloads feeds and pages for `feed`, so the fetching and refresh logic can run against
+/// recorded responses instead of the network.
+pub trait FeedFetcher: Send + Sync {
+    /// GETs `url`, failing once the body exceeds `max_bytes`.
+    fn get<'a>(&'a self, url: &'a str, max_bytes: u64) -> BoxFuture<'a, Result<Response>>;
+
+    /// Like [`get`](Self::get), but hands over the body as it arrives, so a large feed can be
+    /// parsed without holding all of it in memory. By default the body is read in full first.
+    fn get_streaming<'a>(
+        &'a self,
+        url: &'a str,
+        max_bytes: u64,
+    ) -> BoxFuture<'a, Result<StreamingResponse>> {
+        Box::pin(async move { Ok(self.get(url, max_bytes).await?.into()) })
+    }
+
+    /// Sends a HEAD request for `url` with the given conditional headers. The body is empty.
+    fn head<'a>(&'a self, url: &'a str, headers: HeaderMap) -> BoxFuture<'a, Result<Response>>;
+}
+
+/// Fetches over HTTP through the on-disk HTTP cache.
+pub struct HttpFetcher;
+
+impl HttpFetcher {
+    fn client(verbose: bool) -> Result<reqwest::Client> {
+        reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+                if verbose {
+                    eprintln!("[fetch] redirect {} -> {}", attempt.status(), attempt.url());
+                }
+                if attempt.previous().len() >= MAX_REDIRECTS {
+                    attempt.error("too many redirects")
+                } else {
+                    attempt.follow()
+                }
+            }))
+            .build()
+            .context("Failed to build HTTP client")
+    }
+
+    fn log_request(request: &reqwest::Request) {
+        eprintln!("[fetch] {} {}", request.method(), request.url());
+        for (name, value) in request.headers() {
+            eprintln!(
+                "[fetch] > {}: {}",
+                name,
+                value.to_str().unwrap_or("<binary>")
+            );
+        }
+    }
+
+    fn log_response(status: StatusCode, headers: &HeaderMap, from_cache: bool, started: Instant) {
+        eprintln!(
+            "[fetch] {}{} in {:?}",
+            status,
+            if from_cache {
+                " from the HTTP cache"
+            } else {
+                ""
+            },
+            started.elapsed()
+        );
+        for name in [CONTENT_TYPE, CONTENT_ENCODING, CONTENT_LENGTH] {
+            if let Some(value) = headers.get(&name) {
+                eprintln!(
+                    "[fetch] < {}: {}",
+                    name,
+                    value.to_str().unwrap_or("<binary>")
+                );
+            }
+        }
+    }
+}
+
+impl FeedFetcher for HttpFetcher {
+    fn get<'a>(&'a self, url: &'a str, max_bytes: u64) -> BoxFuture<'a, Result<Response>> {
+        Box::pin(async move {
+            let verbose = is_verbose();
+            let client = Self::client(verbose)?;
+            let request = client.get(url).build().context("Invalid feed URL")?;
+            if verbose {
+                Self::log_request(&request);
+            }
+
+            let started = Instant::now();
+            let response = http_cache::execute(&client, request, Some(max_bytes)).await?;
+            if verbose {
+                Self::log_response(
+                    response.status,
+                    &response.headers,
+                    response.from_cache,
+                    started,
+                );
+            }
+            Ok(response)
+        })
+    }
+
+    fn get_streaming<'a>(
+        &'a self,
+        url: &'a str,
+        max_bytes: u64,
+    ) -> BoxFuture<'a, Result<StreamingResponse>> {
+        Box::pin(async move {
+            let verbose = is_verbose();
+            let client = Self::client(verbose)?;
+            let request = client.get(url).build().context("Invalid feed URL")?;
+            if verbose {
+                Self::log_request(&request);
+            }
+
+            let started = Instant::now();
+            let response = http_cache::execute_streaming(&client, request, max_bytes).await?;
+            if verbose {
+                Self::log_response(
+                    response.status,
+                    &response.headers,
+                    response.from_cache,
+                    started,
+                );
+            }
+            Ok(response)
+        })
+    }
+
+    fn head<'a>(&'a self, url: &'a str, headers: HeaderMap) -> BoxFuture<'a, Result<Response>> {
+        Box::pin(async move {
+            let response = reqwest::Client::new()
+                .head(url)
+                .headers(headers)
+                .send()
+                .await?;
+            Ok(Response {
+                status: response.status(),
+                headers: response.headers().clone(),
+                body: Vec::new(),
+                from_cache: false,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+pub use mock::MockFetcher;
+
+#[cfg(test)]
+mod mock {
+    use std::collections::HashMap;
+    use std::path::Path;
+    use std::sync::Mutex;
+
+    use anyhow::{anyhow, Result};
+    use futures::future::BoxFuture;
+    use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+    use reqwest::{Method, StatusCode};
+
+    use super::FeedFetcher;
+    use crate::http_cache::Response;
+
+    /// Answers from canned responses by URL and records the requests made, in order.
+    #[derive(Default)]
+    pub struct MockFetcher {
+        responses: Mutex<HashMap<(Method, String), Response>>,
+        requests: Mutex<Vec<(Method, String)>>,
+    }
+
+    impl MockFetcher {
+        /// Answers GET `url` with the fixture file, relative to the crate root.
+        pub fn serve_fixture(self, url: &str, fixture: &str) -> Self {
+            let body = std::fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join(fixture))
+                .unwrap_or_else(|err| panic!("Missing fixture {}: {}", fixture, err));
+            self.respond(Method::GET, url, StatusCode::OK, &[], body)
+        }
+
+        pub fn respond(
+            self,
+            method: Method,
+            url: &str,
+            status: StatusCode,
+            headers: &[(&'static str, &str)],
+            body: impl Into<Vec<u8>>,
+        ) -> Self {
+            let headers = headers
+                .iter()
+                .map(|(name, value)| {
+                    (
+                        HeaderName::from_static(name),
+                        HeaderValue::from_str(value).unwrap(),
+                    )
+                })
+                .collect();
+            let response = Response {
+                status,
+                headers,
+                body: body.into(),
+                from_cache: false,
+            };
+            self.responses
+                .lock()
+                .unwrap()
+                .insert((method, url.to_string()), response);
+            self
+        }
+
+        pub fn requests(&self) -> Vec<(Method, String)> {
+            self.requests.lock().unwrap().clone()
+        }
+
+        fn answer(&self, method: Method, url: &str) -> Result<Response> {
+            self.requests
+                .lock()
+                .unwrap()
+                .push((method.clone(), url.to_string()));
+            self.responses
+                .lock()
+                .unwrap()
+                .get(&(method.clone(), url.to_string()))
+                .cloned()
+                .ok_or_else(|| anyhow!("No recorded response for {} {}", method, url))
+        }
+    }
+
+    impl FeedFetcher for MockFetcher {
+        fn get<'a>(&'a self, url: &'a str, max_bytes: u64) -> BoxFuture<'a, Result<Response>> {
+            Box::pin(async move {
+                let response = self.answer(Method::GET, url)?;
+                if response.body.len() as u64 > max_bytes {
+                    return Err(anyhow!("Response is larger than {} bytes", max_bytes));
+                }
+                Ok(response)
+            })
+        }
+
+        fn head<'a>(&'a self, url: &'a str, _: HeaderMap) -> BoxFuture<'a, Result<Response>> {
+            Box::pin(async move {
+                let response = self.answer(Method::HEAD, url)?;
+                Ok(Response {
+                    body: Vec::new(),
+                    ..response
+                })
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use reqwest::{Method, StatusCode};
+
+    use super::{HttpFetcher, MockFetcher};
+    use crate::config::{Config, Feed, FeedItem};
+    use crate::feed;
+    use crate::http_cache;
+    use crate::replay::{RecordingFetcher, ReplayFetcher};
+    use crate::test_util::{temp_database, TempDir};
+
+    fn feed(name: &str, url: &str) -> Feed {
+        FeedItem::new(name, url).to_feed(None)
+    }
+
+    #[tokio::test]
+    async fn fetches_and_parses_recorded_feed() {
+        let url = "https://blog.example.com/feed/";
+        let fetcher = MockFetcher::default().serve_fixture(url, "fixtures/fetch/blog.xml");
+
+        let channel = feed::fetch_configured_feed(&fetcher, &feed("Blog", url))
+            .await
+            .unwrap();
+        assert_eq!(channel.title(), "Example Blog");
+        let items = channel.items();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].author(), Some("Alice"));
+        assert_eq!(
+            items[0].content(),
+            Some("<p>The <strong>second</strong> post.</p>")
+        );
+        assert_eq!(fetcher.requests(), [(Method::GET, url.to_string())]);
+    }
+
+    #[tokio::test]
+    async fn max_items_keeps_the_newest_in_document_order() {
+        let url = "https://blog.example.com/feed/?limited";
+        let fetcher = MockFetcher::default().serve_fixture(url, "fixtures/fetch/blog.xml");
+        let mut limited = feed("Blog", url);
+        limited.max_items = Some(2);
+
+        let channel = feed::fetch_configured_feed(&fetcher, &limited)
+            .await
+            .unwrap();
+        let titles: Vec<_> = channel
+            .items()
+            .iter()
+            .filter_map(|item| item.title())
+            .collect();
+        assert_eq!(titles, ["Second post", "Third post"]);
+    }
+
+    #[tokio::test]
+    async fn error_status_fails_the_fetch() {
+        let url = "https://down.example.com/feed";
+        let fetcher = MockFetcher::default().respond(
+            Method::GET,
+            url,
+            StatusCode::SERVICE_UNAVAILABLE,
+            &[],
+            "busy",
+        );
+
+        let err = feed::fetch_configured_feed(&fetcher, &feed("Down", url))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to fetch RSS feed: 503 Service Unavailable"
+        );
+    }
+
+    #[tokio::test]
+    async fn head_request_detects_unchanged_feed() {
+        let url = "https://etag.example.com/feed";
+        let body = fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fixtures/fetch/blog.xml"
+        ))
+        .unwrap();
+        let fetcher = MockFetcher::default()
+            .respond(
+                Method::GET,
+                url,
+                StatusCode::OK,
+                &[("etag", "\"v1\"")],
+                body,
+            )
+            .respond(Method::HEAD, url, StatusCode::NOT_MODIFIED, &[], "");
+        let feed = feed("Etag", url);
+
+        assert!(!feed::is_unchanged(&fetcher, &feed).await);
+        feed::fetch_configured_feed(&fetcher, &feed).await.unwrap();
+        assert!(feed::is_unchanged(&fetcher, &feed).await);
+        assert_eq!(
+            fetcher.requests(),
+            [
+                (Method::GET, url.to_string()),
+                (Method::HEAD, url.to_string())
+            ]
+        );
+    }
+
+    /// Serves `body` over HTTP without a `Content-Length`, so its size is only known once read.
+    async fn serve_unsized(body: Vec<u8>) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/feed.xml", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let Ok(read @ 1..) = socket.read(&mut buf).await else {
+                        break;
+                    };
+                    request.extend_from_slice(&buf[..read]);
+                }
+                let head = "HTTP/1.1 200 OK\r\nContent-Type: application/rss+xml\r\n\
+                            Connection: close\r\n\r\n";
+                let _ = socket.write_all(head.as_bytes()).await;
+                for chunk in body.chunks(16 * 1024) {
+                    let _ = socket.write_all(chunk).await;
+                }
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn large_feeds_are_parsed_as_they_arrive_up_to_their_limit() {
+        let dir = TempDir::new("streamed_feed");
+        http_cache::set_dir(dir.to_path_buf());
+        let mut body = String::from("<rss version=\"2.0\"><channel><title>Big</title>");
+        for n in 0..6000 {
+            body.push_str(&format!(
+                "<item><title>Item {n}</title><link>https://big.example.com/{n}</link>\
+                 <description>{}</description></item>",
+                "x".repeat(200)
+            ));
+        }
+        body.push_str("</channel></rss>");
+        assert!(body.len() > 1024 * 1024);
+        let url = serve_unsized(body.into_bytes()).await;
+
+        let mut big = feed("Big", &url);
+        big.max_items = Some(2);
+        let channel = feed::fetch_configured_feed(&HttpFetcher, &big)
+            .await
+            .unwrap();
+        let titles: Vec<_> = channel
+            .items()
+            .iter()
+            .filter_map(|item| item.title())
+            .collect();
+        assert_eq!(titles, ["Item 0", "Item 1"]);
+        assert!(http_cache::size(&dir) > 1024 * 1024);
+
+        http_cache::set_enabled(false);
+        big.max_size_mb = Some(1);
+        let err = feed::fetch_configured_feed(&HttpFetcher, &big)
+            .await
+            .unwrap_err();
+        http_cache::set_enabled(true);
+        assert!(err.chain().any(|cause| cause.is::<http_cache::TooLarge>()));
+    }
+
+    #[tokio::test]
+    async fn refresh_stores_items_and_honours_ttl() {
+        let (db, _dir) = temp_database("fetcher_refresh").await;
+        let blog = "https://blog.example.com/feed/?refresh";
+        let hourly = "https://hourly.example.com/feed";
+        let fetcher = MockFetcher::default()
+            .serve_fixture(blog, "fixtures/fetch/blog.xml")
+            .serve_fixture(hourly, "fixtures/fetch/hourly.xml");
+        let config: Config = toml::from_str(&format!(
+            "[[rss]]\nname = \"Blog\"\nurl = \"{}\"\n\n[[rss]]\nname = \"Hourly\"\nurl = \"{}\"\n",
+            blog, hourly
+        ))
+        .unwrap();
+
+        crate::refresh_feeds(&fetcher, &config, Some(&db), None, false)
+            .await
+            .unwrap();
+        let mut titles: Vec<_> = db
+            .list_articles()
+            .unwrap()
+            .into_iter()
+            .map(|record| record.title)
+            .collect();
+        titles.sort();
+        assert_eq!(
+            titles,
+            ["First post", "Second post", "Third post", "Update"]
+        );
+
+        // Within its hour, only the feed without a `<ttl>` is fetched again, unless forced.
+        crate::refresh_feeds(&fetcher, &config, Some(&db), None, false)
+            .await
+            .unwrap();
+        crate::refresh_feeds(&fetcher, &config, Some(&db), None, true)
+            .await
+            .unwrap();
+        let fetched = |url: &str| {
+            fetcher
+                .requests()
+                .iter()
+                .filter(|(_, requested)| requested == url)
+                .count()
+        };
+        assert_eq!(fetched(blog), 3);
+        assert_eq!(fetched(hourly), 2);
+        assert_eq!(db.list_articles().unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn recorded_responses_replay_without_the_network() {
+        let dir = TempDir::new("replay");
+        let blog = "https://blog.example.com/feed/?recorded";
+        let down = "https://down.example.com/feed?recorded";
+        let fetcher = MockFetcher::default()
+            .serve_fixture(blog, "fixtures/fetch/blog.xml")
+            .respond(Method::GET, down, StatusCode::BAD_GATEWAY, &[], "");
+        let recorder = RecordingFetcher::new(&fetcher, &dir).unwrap();
+        feed::fetch_configured_feed(&recorder, &feed("Blog", blog))
+            .await
+            .unwrap();
+        feed::fetch_configured_feed(&recorder, &feed("Down", down))
+            .await
+            .unwrap_err();
+
+        let replayer = ReplayFetcher::new(&dir);
+        let channel = feed::fetch_configured_feed(&replayer, &feed("Blog", blog))
+            .await
+            .unwrap();
+        assert_eq!(channel.items().len(), 3);
+        let err = feed::fetch_configured_feed(&replayer, &feed("Down", down))
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Failed to fetch RSS feed: 502 Bad Gateway");
+        let missing = "https://blog.example.com/other";
+        assert!(
+            feed::fetch_configured_feed(&replayer, &feed("Other", missing))
+                .await
+                .is_err()
+        );
+    }
+}
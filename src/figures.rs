@@ -0,0 +1,69 @@
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+
+use crate::hygiene::attr;
+
+static PICTURE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<picture\b[^>]*>(.*?)</picture\s*>").unwrap());
+static SOURCE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?is)<source\b([^>]*)>").unwrap());
+static IMG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?is)<img\b([^>]*?)\s*/?>").unwrap());
+static FIGURE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<figure\b[^>]*>(.*?)</figure\s*>").unwrap());
+static FIGCAPTION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<figcaption\b[^>]*>(.*?)</figcaption\s*>").unwrap());
+/// Block tags inside a caption, which would break its italic line apart.
+static CAPTION_BLOCK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)</?(?:p|div|br)\b[^>]*>").unwrap());
+
+/// Rewrites pictures and figures in article HTML into what the markdown conversion keeps: each
+/// `<picture>` becomes its `<img>` (given the first `<source>`'s `srcset` when it has no image
+/// of its own), and each `<figure>` a block of its content followed by its `<figcaption>` as an
+/// italic paragraph, so the caption is its own line under the image instead of text run into
+/// it.
+pub fn convert_html(html: &str) -> String {
+    let html = PICTURE.replace_all(html, |caps: &Captures| picture_image(&caps[1]));
+    let html = FIGURE.replace_all(&html, |caps: &Captures| {
+        let content = FIGCAPTION.replace_all(&caps[1], "");
+        let captions: String = FIGCAPTION
+            .captures_iter(&caps[1])
+            .map(|caption| caption_paragraph(&caption[1]))
+            .collect();
+        format!("<div>{}{}</div>", content, captions)
+    });
+    // Captions outside any figure still get their own line.
+    FIGCAPTION
+        .replace_all(&html, |caps: &Captures| caption_paragraph(&caps[1]))
+        .into_owned()
+}
+
+fn picture_image(content: &str) -> String {
+    let srcset = SOURCE
+        .captures_iter(content)
+        .find_map(|caps| attr(&caps[1], "srcset").or_else(|| attr(&caps[1], "data-srcset")));
+    let Some(img) = IMG.captures(content) else {
+        return srcset
+            .map(|srcset| format!("<img srcset=\"{}\">", srcset.replace('"', "&quot;")))
+            .unwrap_or_default();
+    };
+    let has_image = ["src", "srcset", "data-src", "data-srcset", "data-original"]
+        .iter()
+        .any(|name| attr(&img[1], name).is_some_and(|value| !value.trim().is_empty()));
+    match srcset {
+        Some(srcset) if !has_image => format!(
+            "<img{} srcset=\"{}\">",
+            &img[1],
+            srcset.replace('"', "&quot;")
+        ),
+        _ => img[0].to_string(),
+    }
+}
+
+fn caption_paragraph(caption: &str) -> String {
+    let caption = CAPTION_BLOCK.replace_all(caption, " ");
+    let caption = caption.trim();
+    if caption.is_empty() {
+        return String::new();
+    }
+    format!("<p><em>{}</em></p>", caption)
+}
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+
+use crate::hygiene::attr;
+
+/// A footnote reference: a link to the note inside `<sup>`, or `<sup>` inside the link.
+static REFERENCE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?is)<sup\b[^>]*>\s*<a\b([^>]*)>(?:.*?)</a>\s*</sup>|<a\b([^>]*)>\s*<sup\b[^>]*>(?:.*?)</sup>\s*</a>",
+    )
+    .unwrap()
+});
+static LIST_ITEM: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<li\b([^>]*)>(.*?)</li\s*>").unwrap());
+static IN_PAGE_LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<a\b([^>]*)>(.*?)</a\s*>").unwrap());
+/// A footnote list left empty once its notes are taken out, with the rule usually above it.
+static EMPTY_LIST: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)(?:<hr\b[^>]*>\s*)?<ol\b[^>]*>\s*</ol\s*>").unwrap());
+
+/// A markdown footnote reference, `[^label]`.
+static MARKDOWN_REFERENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[\^([^\]\s]+)\]").unwrap());
+/// The first line of a markdown footnote definition, `[^label]: text`.
+static MARKDOWN_DEFINITION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\[\^([^\]\s]+)\]:\s?(.*)$").unwrap());
+
+/// Finds the footnotes of article HTML: references linking to a list item of the same page, as
+/// written by WordPress, Jekyll, Hugo, Pandoc and most blog engines. Returns the HTML with each
+/// reference replaced by `[^n]` and the notes taken out of their list, and the HTML of each note
+/// (without its back links) in order of first reference.
+pub fn extract_html(html: &str) -> (String, Vec<String>) {
+    let mut notes: HashMap<String, String> = LIST_ITEM
+        .captures_iter(html)
+        .filter_map(|caps| Some((attr(&caps[1], "id")?, caps[2].to_string())))
+        .collect();
+    if notes.is_empty() {
+        return (html.to_string(), Vec::new());
+    }
+
+    let mut order: Vec<String> = Vec::new();
+    let html = REFERENCE.replace_all(html, |caps: &Captures| {
+        let attrs = caps.get(1).or_else(|| caps.get(2)).unwrap().as_str();
+        let Some(id) =
+            attr(attrs, "href").and_then(|href| href.strip_prefix('#').map(String::from))
+        else {
+            return caps[0].to_string();
+        };
+        if !notes.contains_key(&id) {
+            return caps[0].to_string();
+        }
+        let number = match order.iter().position(|seen| *seen == id) {
+            Some(index) => index + 1,
+            None => {
+                order.push(id);
+                order.len()
+            }
+        };
+        format!("[^{}]", number)
+    });
+    if order.is_empty() {
+        return (html.into_owned(), Vec::new());
+    }
+
+    let html = LIST_ITEM.replace_all(&html, |caps: &Captures| match attr(&caps[1], "id") {
+        Some(id) if order.contains(&id) => String::new(),
+        _ => caps[0].to_string(),
+    });
+    let html = EMPTY_LIST.replace_all(&html, "");
+    let notes = order
+        .iter()
+        .map(|id| strip_back_links(&notes.remove(id).unwrap_or_default()))
+        .collect();
+    (html.into_owned(), notes)
+}
+
+/// Links within the page inside a note, which point back to its reference (`↩`).
+fn strip_back_links(note: &str) -> String {
+    IN_PAGE_LINK
+        .replace_all(note, |caps: &Captures| match attr(&caps[1], "href") {
+            Some(href) if href.starts_with('#') => String::new(),
+            _ => caps[0].to_string(),
+        })
+        .into_owned()
+}
+
+/// A markdown footnote definition for note `number`, continuation lines indented under it.
+pub fn definition(number: usize, markdown: &str) -> String {
+    let mut lines = markdown.trim().lines();
+    let mut definition = format!("[^{}]: {}", number, lines.next().unwrap_or_default());
+    for line in lines {
+        definition.push('\n');
+        if !line.trim().is_empty() {
+            definition.push_str("    ");
+            definition.push_str(line);
+        }
+    }
+    definition
+}
+
+/// Markdown with its footnote definitions taken out and each reference `[^label]` replaced by
+/// the note's number as superscript, for renderers without footnote support. Returns the notes'
+/// text numbered in order of first reference, followed by any notes never referenced.
+pub fn number_markdown(markdown: &str) -> (String, Vec<String>) {
+    let mut body = Vec::new();
+    let mut definitions: Vec<(String, String)> = Vec::new();
+    let mut lines = markdown.lines().peekable();
+    let mut in_fence = false;
+    while let Some(line) = lines.next() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+        }
+        let Some(caps) = MARKDOWN_DEFINITION.captures(line).filter(|_| !in_fence) else {
+            body.push(line);
+            continue;
+        };
+        let mut text = caps[2].trim().to_string();
+        // Continuation paragraphs are indented, possibly after blank lines.
+        while let Some(next) = lines.peek() {
+            if next.trim().is_empty() {
+                let mut ahead = lines.clone();
+                ahead.next();
+                if !ahead.peek().is_some_and(|line| is_indented(line)) {
+                    break;
+                }
+            } else if !is_indented(next) {
+                break;
+            }
+            let next = lines.next().unwrap_or_default().trim();
+            if !next.is_empty() {
+                text.push(' ');
+                text.push_str(next);
+            }
+        }
+        definitions.push((caps[1].to_string(), text));
+    }
+    if definitions.is_empty() {
+        return (markdown.to_string(), Vec::new());
+    }
+
+    let mut order: Vec<usize> = Vec::new();
+    let mut in_fence = false;
+    let body: Vec<String> = body
+        .into_iter()
+        .map(|line| {
+            if line.trim_start().starts_with("```") {
+                in_fence = !in_fence;
+            }
+            if in_fence {
+                return line.to_string();
+            }
+            MARKDOWN_REFERENCE
+                .replace_all(line, |caps: &Captures| {
+                    let Some(index) = definitions.iter().position(|(label, _)| *label == caps[1])
+                    else {
+                        return caps[0].to_string();
+                    };
+                    let number = match order.iter().position(|seen| *seen == index) {
+                        Some(position) => position + 1,
+                        None => {
+                            order.push(index);
+                            order.len()
+                        }
+                    };
+                    superscript(number)
+                })
+                .into_owned()
+        })
+        .collect();
+    let unreferenced: Vec<usize> = (0..definitions.len())
+        .filter(|index| !order.contains(index))
+        .collect();
+    order.extend(unreferenced);
+    let notes = order
+        .into_iter()
+        .map(|index| std::mem::take(&mut definitions[index].1))
+        .collect();
+    (body.join("\n").trim_end().to_string(), notes)
+}
+
+fn is_indented(line: &str) -> bool {
+    line.starts_with("    ") || line.starts_with('\t')
+}
+
+/// `number` in superscript digits, e.g. `¹²`.
+pub fn superscript(number: usize) -> String {
+    number
+        .to_string()
+        .chars()
+        .map(|digit| match digit {
+            '0' => '⁰',
+            '1' => '¹',
+            '2' => '²',
+            '3' => '³',
+            '4' => '⁴',
+            '5' => '⁵',
+            '6' => '⁶',
+            '7' => '⁷',
+            '8' => '⁸',
+            _ => '⁹',
+        })
+        .collect()
+}
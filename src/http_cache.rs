@@ -0,0 +1,944 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Cursor, Read};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{LazyLock, RwLock};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, CACHE_CONTROL, CONTENT_TYPE, ETAG, EXPIRES,
+    IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use reqwest::{redirect, Client, Method, Request, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+static ALLOW_PRIVATE: AtomicBool = AtomicBool::new(false);
+
+/// Redirects [`get_untrusted`] follows before giving up.
+const MAX_UNTRUSTED_REDIRECTS: usize = 5;
+/// Time [`get_untrusted`] waits for a connection to a host from feed content.
+const UNTRUSTED_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Time a whole [`get_untrusted`] request may take, body included, so a host that never
+/// finishes its response can't hold up storing a feed.
+const UNTRUSTED_TIMEOUT: Duration = Duration::from_secs(60);
+/// Time a whole [`download_untrusted`] may take; large files get longer than other requests.
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+/// Time [`download_untrusted`] waits for the next part of the body before giving up.
+const DOWNLOAD_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Suffix of the next temporary file, so concurrent writes of one URL don't share it.
+static NEXT_TEMP: AtomicUsize = AtomicUsize::new(0);
+
+/// Name of the cache directory inside the article store.
+pub const DIR_NAME: &str = "http-cache";
+
+static DIR: LazyLock<RwLock<PathBuf>> =
+    LazyLock::new(|| RwLock::new(crate::db::default_store_dir().join(DIR_NAME)));
+
+/// Size the cache is pruned back to, least recently used entries first.
+const MAX_CACHE_BYTES: u64 = 200 * 1024 * 1024;
+/// Responses stored between two prunes, so the directory isn't listed on every write.
+const PRUNE_EVERY: usize = 50;
+
+static STORED: AtomicUsize = AtomicUsize::new(0);
+
+/// Chunks of a streamed body read ahead of its reader.
+const STREAM_CHUNKS: usize = 16;
+
+/// Turns the cache on or off for subsequent requests. It is off while articles are encrypted,
+/// so that plain copies of feeds and images aren't kept next to the encrypted store.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Lets [`get_untrusted`] reach loopback and private network addresses, for feeds whose content
+/// legitimately points at hosts on the local network.
+pub fn set_allow_private(allow: bool) {
+    ALLOW_PRIVATE.store(allow, Ordering::Relaxed);
+}
+
+/// Keeps the cache in `dir`, the [`DIR_NAME`] directory of the article store in use.
+pub fn set_dir(dir: PathBuf) {
+    *DIR.write().unwrap_or_else(|err| err.into_inner()) = dir;
+}
+
+pub fn cache_dir() -> PathBuf {
+    DIR.read().unwrap_or_else(|err| err.into_inner()).clone()
+}
+
+/// Bytes the cache in `dir` takes.
+pub fn size(dir: &Path) -> u64 {
+    entries(dir).iter().map(|(_, _, bytes)| bytes).sum()
+}
+
+/// Removes the least recently used entries of the cache in `dir` until it takes at most
+/// `max_bytes`, and returns the bytes freed.
+pub fn prune(dir: &Path, max_bytes: u64) -> u64 {
+    let mut entries = entries(dir);
+    let mut total: u64 = entries.iter().map(|(_, _, bytes)| bytes).sum();
+    entries.sort_by_key(|(_, used, _)| *used);
+    let mut freed = 0;
+    for (key, _, bytes) in entries {
+        if total <= max_bytes {
+            break;
+        }
+        for extension in ["body", "json"] {
+            let _ = std::fs::remove_file(dir.join(format!("{}.{}", key, extension)));
+        }
+        total -= bytes;
+        freed += bytes;
+    }
+    freed
+}
+
+/// Each cached URL's key, when it was last used, and the bytes of its body and metadata.
+fn entries(dir: &Path) -> Vec<(String, SystemTime, u64)> {
+    let mut entries: HashMap<String, (SystemTime, u64)> = HashMap::new();
+    for file in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = file.path();
+        let (Some(key), Ok(meta)) = (path.file_stem(), file.metadata()) else {
+            continue;
+        };
+        let entry = entries
+            .entry(key.to_string_lossy().into_owned())
+            .or_insert((SystemTime::UNIX_EPOCH, 0));
+        entry.1 += meta.len();
+        // Reading an entry touches its body, so the body's time is when it was last used.
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "body")
+        {
+            entry.0 = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+        }
+    }
+    entries
+        .into_iter()
+        .map(|(key, (used, bytes))| (key, used, bytes))
+        .collect()
+}
+
+/// A response read in full, from the network or from the cache.
+#[derive(Clone)]
+pub struct Response {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+    /// Whether the body came from disk, either still fresh or revalidated with a 304.
+    pub from_cache: bool,
+}
+
+impl Response {
+    /// Reads the body, failing as soon as it is known to exceed `max_bytes`.
+    async fn read(mut response: reqwest::Response, max_bytes: Option<u64>) -> Result<Self> {
+        let limit = max_bytes.unwrap_or(u64::MAX);
+        if response
+            .content_length()
+            .is_some_and(|length| length > limit)
+        {
+            return Err(too_large(limit));
+        }
+        let status = response.status();
+        let headers = response.headers().clone();
+        let mut body = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .context("Failed to read response body")?
+        {
+            if (body.len() + chunk.len()) as u64 > limit {
+                return Err(too_large(limit));
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(Self {
+            status,
+            headers,
+            body,
+            from_cache: false,
+        })
+    }
+}
+
+/// A response whose body is read as it arrives, so it can be parsed without holding all of it in
+/// memory. Dropping it stops reading the body from the network.
+pub struct StreamingResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    /// Whether the body comes from disk, either still fresh or revalidated with a 304.
+    pub from_cache: bool,
+    body: Option<Box<dyn BufRead + Send>>,
+    source: BodySource,
+}
+
+enum BodySource {
+    /// A body already read, of this many bytes.
+    Read(u64),
+    /// A body still being received by a [`pump`], which returns the bytes it received.
+    Network(AbortOnDrop<Result<u64>>),
+}
+
+struct AbortOnDrop<T>(JoinHandle<T>);
+
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+impl StreamingResponse {
+    /// The body, once. Reading it blocks until the next part arrives, so it is read on a
+    /// blocking thread.
+    pub fn take_body(&mut self) -> Box<dyn BufRead + Send> {
+        self.body.take().unwrap_or_else(|| Box::new(io::empty()))
+    }
+
+    /// Waits until the body has been received in full and returns its size. Fails if it couldn't
+    /// be, such as when it is larger than the limit it was requested with.
+    pub async fn finish(&mut self) -> Result<u64> {
+        match &mut self.source {
+            BodySource::Read(bytes) => Ok(*bytes),
+            BodySource::Network(pump) => match (&mut pump.0).await {
+                Ok(result) => result,
+                Err(err) => Err(anyhow!("Failed to read response body: {}", err)),
+            },
+        }
+    }
+}
+
+impl From<Response> for StreamingResponse {
+    fn from(response: Response) -> Self {
+        Self {
+            status: response.status,
+            headers: response.headers,
+            from_cache: response.from_cache,
+            source: BodySource::Read(response.body.len() as u64),
+            body: Some(Box::new(Cursor::new(response.body))),
+        }
+    }
+}
+
+/// Reads the chunks a [`pump`] sends, blocking until the next one arrives.
+struct ChunkReader {
+    chunks: mpsc::Receiver<io::Result<Vec<u8>>>,
+    chunk: Vec<u8>,
+    position: usize,
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.chunk.len() {
+            match self.chunks.blocking_recv() {
+                Some(chunk) => {
+                    self.chunk = chunk?;
+                    self.position = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+        let read = buf.len().min(self.chunk.len() - self.position);
+        buf[..read].copy_from_slice(&self.chunk[self.position..self.position + read]);
+        self.position += read;
+        Ok(read)
+    }
+}
+
+pub fn too_large(limit: u64) -> anyhow::Error {
+    anyhow::Error::new(TooLarge(limit))
+}
+
+/// Metadata stored next to a cached body.
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    url: String,
+    stored_at: DateTime<Utc>,
+    headers: Vec<(String, String)>,
+}
+
+impl Entry {
+    fn new(url: &str, headers: &HeaderMap) -> Self {
+        Self {
+            url: url.to_string(),
+            stored_at: Utc::now(),
+            headers: headers
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.to_string(), value.to_str().ok()?.to_string()))
+                })
+                .collect(),
+        }
+    }
+
+    fn header(&self, name: &HeaderName) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name.as_str()))
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn header_map(&self) -> HeaderMap {
+        self.headers
+            .iter()
+            .filter_map(|(name, value)| {
+                Some((
+                    HeaderName::from_bytes(name.as_bytes()).ok()?,
+                    HeaderValue::from_str(value).ok()?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Replaces the stored headers with those of a 304 response and restarts the entry's age.
+    fn revalidated(mut self, headers: &HeaderMap) -> Self {
+        let updated = Entry::new(&self.url, headers);
+        self.headers.retain(|(name, _)| {
+            !updated
+                .headers
+                .iter()
+                .any(|(key, _)| key.eq_ignore_ascii_case(name))
+        });
+        self.headers.extend(updated.headers);
+        self.stored_at = updated.stored_at;
+        self
+    }
+
+    /// How long after `stored_at` the response may be used without asking the server:
+    /// `Cache-Control: max-age`, or else `Expires`. Zero with `no-cache`.
+    fn freshness(&self) -> Duration {
+        let directives = cache_directives(self.header(&CACHE_CONTROL));
+        if directives.iter().any(|directive| directive == "no-cache") {
+            return Duration::ZERO;
+        }
+        let max_age = directives.iter().find_map(|directive| {
+            directive
+                .strip_prefix("max-age=")?
+                .trim_matches('"')
+                .parse()
+                .ok()
+        });
+        if let Some(seconds) = max_age {
+            return Duration::from_secs(seconds);
+        }
+        self.header(&EXPIRES)
+            .and_then(|expires| DateTime::parse_from_rfc2822(expires).ok())
+            .and_then(|expires| (expires.with_timezone(&Utc) - self.stored_at).to_std().ok())
+            .unwrap_or_default()
+    }
+
+    fn is_fresh(&self) -> bool {
+        (Utc::now() - self.stored_at)
+            .to_std()
+            .is_ok_and(|age| age < self.freshness())
+    }
+}
+
+/// Lowercased `Cache-Control` directives.
+fn cache_directives(value: Option<&str>) -> Vec<String> {
+    value
+        .unwrap_or_default()
+        .split(',')
+        .map(|directive| directive.trim().to_lowercase())
+        .filter(|directive| !directive.is_empty())
+        .collect()
+}
+
+/// Fetches a URL taken from feed content, such as an article's image, through the cache.
+///
+/// Feed content is untrusted, so it refuses hosts that are or resolve to
+/// loopback, private, link-local or other non-public addresses, checks every redirect the same
+/// way and follows at most [`MAX_UNTRUSTED_REDIRECTS`] of them. A refusal is a
+/// [`ForbiddenAddress`] in the error's chain. Bodies larger than `max_bytes` fail the request, as
+/// do requests that take longer than [`UNTRUSTED_TIMEOUT`].
+pub async fn get_untrusted(url: &str, max_bytes: u64) -> Result<Response> {
+    let client = untrusted_client()?;
+    let request = client.get(url).build().context("Invalid URL")?;
+    check_url(request.url())?;
+    execute(client, request, Some(max_bytes)).await
+}
+
+/// Like [`get_untrusted`], but never reads or writes the cache, for responses that are kept
+/// elsewhere, such as images saved to the article store.
+pub async fn get_untrusted_uncached(url: &str, max_bytes: u64) -> Result<Response> {
+    let client = untrusted_client()?;
+    let request = client.get(url).build().context("Invalid URL")?;
+    check_url(request.url())?;
+    Response::read(client.execute(request).await?, Some(max_bytes)).await
+}
+
+/// Streams a file from a URL taken from feed content to `path`, with the same address checks as
+/// [`get_untrusted`], and returns its size. Fails once the body exceeds `max_bytes` or stalls
+/// for [`DOWNLOAD_IDLE_TIMEOUT`]; `path` may then hold part of it.
+pub async fn download_untrusted(url: &str, path: &Path, max_bytes: u64) -> Result<u64> {
+    use tokio::io::AsyncWriteExt;
+
+    let client = untrusted_client()?;
+    let request = client
+        .get(url)
+        .timeout(DOWNLOAD_TIMEOUT)
+        .build()
+        .context("Invalid URL")?;
+    check_url(request.url())?;
+    let mut response = client.execute(request).await?;
+    if !response.status().is_success() {
+        return Err(error_status(
+            format!("Failed to download {}", url),
+            response.status(),
+        ));
+    }
+    if response
+        .content_length()
+        .is_some_and(|length| length > max_bytes)
+    {
+        return Err(too_large(max_bytes));
+    }
+    let mut file = tokio::fs::File::create(path)
+        .await
+        .context(format!("Failed to create {:?}", path))?;
+    let mut written = 0;
+    loop {
+        let chunk = tokio::time::timeout(DOWNLOAD_IDLE_TIMEOUT, response.chunk())
+            .await
+            .map_err(|_| anyhow!("{} stopped sending data", url))?
+            .context("Failed to read response body")?;
+        let Some(chunk) = chunk else {
+            break;
+        };
+        written += chunk.len() as u64;
+        if written > max_bytes {
+            return Err(too_large(max_bytes));
+        }
+        file.write_all(&chunk)
+            .await
+            .context(format!("Failed to write {:?}", path))?;
+    }
+    file.flush()
+        .await
+        .context(format!("Failed to write {:?}", path))?;
+    Ok(written)
+}
+
+/// Posts a form to a URL taken from feed content, refusing the same addresses and with the same
+/// limits as [`get_untrusted`].
+pub async fn post_untrusted(url: &str, form: &[(&str, &str)], max_bytes: u64) -> Result<Response> {
+    let client = untrusted_client()?;
+    let body = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(form)
+        .finish();
+    let request = client
+        .post(url)
+        .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(body)
+        .build()
+        .context("Invalid URL")?;
+    check_url(request.url())?;
+    execute(client, request, Some(max_bytes)).await
+}
+
+fn untrusted_client() -> Result<&'static Client> {
+    static CLIENT: LazyLock<Result<Client, String>> = LazyLock::new(|| {
+        Client::builder()
+            .dns_resolver(PublicResolver)
+            .connect_timeout(UNTRUSTED_CONNECT_TIMEOUT)
+            .timeout(UNTRUSTED_TIMEOUT)
+            .redirect(redirect::Policy::custom(|attempt| {
+                if attempt.previous().len() >= MAX_UNTRUSTED_REDIRECTS {
+                    attempt.error("too many redirects")
+                } else if let Err(err) = check_url(attempt.url()) {
+                    attempt.error(err)
+                } else {
+                    attempt.follow()
+                }
+            }))
+            .build()
+            .map_err(|err| err.to_string())
+    });
+    CLIENT
+        .as_ref()
+        .map_err(|err| anyhow!("Failed to build HTTP client: {}", err))
+}
+
+/// A URL refused by [`get_untrusted`] because it points at this host or its private network.
+#[derive(Debug)]
+pub struct ForbiddenAddress(String);
+
+impl fmt::Display for ForbiddenAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is not a public address", self.0)
+    }
+}
+
+impl std::error::Error for ForbiddenAddress {}
+
+/// A response body larger than the limit, in bytes, it was read with.
+#[derive(Debug)]
+pub struct TooLarge(u64);
+
+impl fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Response is larger than the {} MiB limit",
+            self.0 as f64 / (1024.0 * 1024.0)
+        )
+    }
+}
+
+impl std::error::Error for TooLarge {}
+
+/// A response whose status isn't a success, reported as an error of what was being fetched.
+#[derive(Debug)]
+pub struct ErrorStatus {
+    what: String,
+    status: StatusCode,
+}
+
+impl fmt::Display for ErrorStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.what, self.status)
+    }
+}
+
+impl std::error::Error for ErrorStatus {}
+
+/// Error for a response with a non-success `status`, e.g. "Failed to fetch RSS feed: 404 Not
+/// Found".
+pub fn error_status(what: impl Into<String>, status: StatusCode) -> anyhow::Error {
+    anyhow::Error::new(ErrorStatus {
+        what: what.into(),
+        status,
+    })
+}
+
+/// Whether `err` comes from [`get_untrusted`] refusing an address.
+pub fn is_forbidden(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| cause.is::<ForbiddenAddress>())
+}
+
+/// Refuses schemes other than HTTP(S) and IP literal hosts outside the public internet. Named
+/// hosts are left to [`PublicResolver`], since only their resolved addresses tell.
+fn check_url(url: &Url) -> Result<(), ForbiddenAddress> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(ForbiddenAddress(url.to_string()));
+    }
+    let ip = match url.host() {
+        Some(url::Host::Ipv4(ip)) => IpAddr::V4(ip),
+        Some(url::Host::Ipv6(ip)) => IpAddr::V6(ip),
+        _ => return Ok(()),
+    };
+    if is_public(ip) || ALLOW_PRIVATE.load(Ordering::Relaxed) {
+        Ok(())
+    } else {
+        Err(ForbiddenAddress(ip.to_string()))
+    }
+}
+
+/// Resolves names with the system resolver and keeps only public addresses, so a name can't be
+/// pointed at an internal service, including by changing its records between requests.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let addrs: Vec<_> = tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            let allow_private = ALLOW_PRIVATE.load(Ordering::Relaxed);
+            let public: Vec<_> = addrs
+                .into_iter()
+                .filter(|addr| allow_private || is_public(addr.ip()))
+                .collect();
+            if public.is_empty() {
+                return Err(ForbiddenAddress(host).into());
+            }
+            Ok(Box::new(public.into_iter()) as Addrs)
+        })
+    }
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_v4(ip),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        // "This network", carrier-grade NAT and the IETF protocol assignments.
+        || a == 0
+        || (a == 100 && (64..128).contains(&b))
+        || (a == 192 && b == 0 && ip.octets()[2] == 0))
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        || ip.is_unique_local()
+        || ip.is_unicast_link_local())
+}
+
+/// Sends a GET request through the on-disk cache: a stored response is returned without
+/// contacting the server while `Cache-Control`/`Expires` say it is fresh, and revalidated with
+/// `If-None-Match`/`If-Modified-Since` once stale. Successful responses are stored unless marked
+/// `no-store`. Bodies larger than `max_bytes` fail the request.
+pub async fn execute(
+    client: &Client,
+    mut request: Request,
+    max_bytes: Option<u64>,
+) -> Result<Response> {
+    if !ENABLED.load(Ordering::Relaxed) || request.method() != Method::GET {
+        return Response::read(client.execute(request).await?, max_bytes).await;
+    }
+
+    let url = request.url().to_string();
+    let cached = load(&url).await;
+    if let Some((entry, body)) = &cached {
+        if entry.is_fresh() {
+            return Ok(Response {
+                status: StatusCode::OK,
+                headers: entry.header_map(),
+                body: body.clone(),
+                from_cache: true,
+            });
+        }
+        for (name, validator) in [(IF_NONE_MATCH, ETAG), (IF_MODIFIED_SINCE, LAST_MODIFIED)] {
+            if let Some(value) = entry
+                .header(&validator)
+                .and_then(|value| HeaderValue::from_str(value).ok())
+            {
+                request.headers_mut().entry(name).or_insert(value);
+            }
+        }
+    }
+
+    let response = client.execute(request).await?;
+    if response.status() == StatusCode::NOT_MODIFIED {
+        if let Some((entry, body)) = cached {
+            let entry = entry.revalidated(response.headers());
+            if let Err(err) = save_entry(&entry).await {
+                tracing::warn!("Failed to update HTTP cache for {}: {:#}", url, err);
+            }
+            return Ok(Response {
+                status: StatusCode::OK,
+                headers: entry.header_map(),
+                body,
+                from_cache: true,
+            });
+        }
+    }
+
+    let response = Response::read(response, max_bytes).await?;
+    let no_store = cache_directives(
+        response
+            .headers
+            .get(CACHE_CONTROL)
+            .and_then(|value| value.to_str().ok()),
+    )
+    .iter()
+    .any(|directive| directive == "no-store");
+    if response.status.is_success() && !no_store {
+        if let Err(err) = store(&url, &response).await {
+            tracing::warn!("Failed to write HTTP cache for {}: {:#}", url, err);
+        }
+    }
+    Ok(response)
+}
+
+/// Like [`execute`], but returns before reading the body and hands it over as it arrives. A fresh
+/// or revalidated cached body is read from its file; one from the network is written to the cache
+/// as it is read, and only kept there once it was received in full.
+pub async fn execute_streaming(
+    client: &Client,
+    mut request: Request,
+    max_bytes: u64,
+) -> Result<StreamingResponse> {
+    let cacheable = ENABLED.load(Ordering::Relaxed) && request.method() == Method::GET;
+    let url = request.url().to_string();
+    let cached = if cacheable { open(&url).await } else { None };
+    if let Some((entry, _)) = &cached {
+        for (name, validator) in [(IF_NONE_MATCH, ETAG), (IF_MODIFIED_SINCE, LAST_MODIFIED)] {
+            if let Some(value) = entry
+                .header(&validator)
+                .and_then(|value| HeaderValue::from_str(value).ok())
+            {
+                request.headers_mut().entry(name).or_insert(value);
+            }
+        }
+    }
+    let cached_response = |entry: &Entry, file: std::fs::File| {
+        let length = file.metadata().map(|meta| meta.len()).unwrap_or_default();
+        StreamingResponse {
+            status: StatusCode::OK,
+            headers: entry.header_map(),
+            from_cache: true,
+            body: Some(Box::new(BufReader::new(file))),
+            source: BodySource::Read(length),
+        }
+    };
+    if let Some((entry, file)) = cached {
+        if entry.is_fresh() {
+            return Ok(cached_response(&entry, file));
+        }
+        let response = client.execute(request).await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            let entry = entry.revalidated(response.headers());
+            if let Err(err) = save_entry(&entry).await {
+                tracing::warn!("Failed to update HTTP cache for {}: {:#}", url, err);
+            }
+            return Ok(cached_response(&entry, file));
+        }
+        return stream(response, max_bytes, cacheable.then_some(url));
+    }
+    let response = client.execute(request).await?;
+    stream(response, max_bytes, cacheable.then_some(url))
+}
+
+/// Hands over the body of `response` as it arrives, storing it in the cache as `url` if given and
+/// the response allows it.
+fn stream(
+    response: reqwest::Response,
+    max_bytes: u64,
+    url: Option<String>,
+) -> Result<StreamingResponse> {
+    if response
+        .content_length()
+        .is_some_and(|length| length > max_bytes)
+    {
+        return Err(too_large(max_bytes));
+    }
+    let status = response.status();
+    let headers = response.headers().clone();
+    let no_store = cache_directives(
+        headers
+            .get(CACHE_CONTROL)
+            .and_then(|value| value.to_str().ok()),
+    )
+    .iter()
+    .any(|directive| directive == "no-store");
+    let url = url.filter(|_| status.is_success() && !no_store);
+    let (sender, chunks) = mpsc::channel(STREAM_CHUNKS);
+    let pump = tokio::spawn(pump(response, max_bytes, url, sender));
+    Ok(StreamingResponse {
+        status,
+        headers,
+        from_cache: false,
+        body: Some(Box::new(BufReader::new(ChunkReader {
+            chunks,
+            chunk: Vec::new(),
+            position: 0,
+        }))),
+        source: BodySource::Network(AbortOnDrop(pump)),
+    })
+}
+
+/// Sends the body of `response` to a [`ChunkReader`] and returns its size, failing once it
+/// exceeds `max_bytes`. With a `url`, the body is also written to the cache, so it is still stored
+/// when the reader stops early.
+async fn pump(
+    mut response: reqwest::Response,
+    max_bytes: u64,
+    url: Option<String>,
+    sender: mpsc::Sender<io::Result<Vec<u8>>>,
+) -> Result<u64> {
+    let headers = response.headers().clone();
+    let mut cache = match url {
+        Some(url) => match CacheWriter::create(url).await {
+            Ok(writer) => Some(writer),
+            Err(err) => {
+                tracing::warn!("Failed to write HTTP cache: {:#}", err);
+                None
+            }
+        },
+        None => None,
+    };
+    let mut received = 0;
+    loop {
+        let chunk = match response.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(err) => {
+                let _ = sender.send(Err(io::Error::other(err.to_string()))).await;
+                return Err(err).context("Failed to read response body");
+            }
+        };
+        received += chunk.len() as u64;
+        if received > max_bytes {
+            let _ = sender
+                .send(Err(io::Error::other(TooLarge(max_bytes))))
+                .await;
+            return Err(too_large(max_bytes));
+        }
+        if let Some(writer) = &mut cache {
+            if let Err(err) = writer.write(&chunk).await {
+                tracing::warn!("Failed to write HTTP cache for {}: {:#}", writer.url, err);
+                cache = None;
+            }
+        }
+        if sender.send(Ok(chunk.to_vec())).await.is_err() && cache.is_none() {
+            return Ok(received);
+        }
+    }
+    if let Some(writer) = cache {
+        let url = writer.url.clone();
+        if let Err(err) = writer.commit(&headers).await {
+            tracing::warn!("Failed to write HTTP cache for {}: {:#}", url, err);
+        }
+    }
+    Ok(received)
+}
+
+/// A body being written to the cache as it arrives. The partial file is removed unless
+/// [`commit`](Self::commit) is called once the body was received in full.
+struct CacheWriter {
+    url: String,
+    temp: PathBuf,
+    file: Option<tokio::fs::File>,
+}
+
+impl CacheWriter {
+    async fn create(url: String) -> Result<Self> {
+        tokio::fs::create_dir_all(cache_dir())
+            .await
+            .context("Failed to create HTTP cache directory")?;
+        let temp = temp_path(&entry_path(&url, "body"));
+        let file = tokio::fs::File::create(&temp)
+            .await
+            .context(format!("Failed to write {:?}", temp))?;
+        Ok(Self {
+            url,
+            temp,
+            file: Some(file),
+        })
+    }
+
+    async fn write(&mut self, chunk: &[u8]) -> Result<()> {
+        use tokio::io::AsyncWriteExt;
+
+        if let Some(file) = &mut self.file {
+            file.write_all(chunk)
+                .await
+                .context(format!("Failed to write {:?}", self.temp))?;
+        }
+        Ok(())
+    }
+
+    async fn commit(mut self, headers: &HeaderMap) -> Result<()> {
+        use tokio::io::AsyncWriteExt;
+
+        if let Some(file) = &mut self.file {
+            file.flush()
+                .await
+                .context(format!("Failed to write {:?}", self.temp))?;
+        }
+        tokio::fs::rename(&self.temp, entry_path(&self.url, "body"))
+            .await
+            .context(format!("Failed to write {:?}", self.temp))?;
+        self.file = None;
+        stored(&Entry::new(&self.url, headers)).await
+    }
+}
+
+impl Drop for CacheWriter {
+    fn drop(&mut self) {
+        if self.file.is_some() {
+            let _ = std::fs::remove_file(&self.temp);
+        }
+    }
+}
+
+fn entry_path(url: &str, extension: &str) -> PathBuf {
+    let key = hex::encode(Sha256::digest(url.as_bytes()));
+    cache_dir().join(format!("{}.{}", key, extension))
+}
+
+async fn load(url: &str) -> Option<(Entry, Vec<u8>)> {
+    use tokio::io::AsyncReadExt;
+
+    let (entry, file) = open(url).await?;
+    let mut body = Vec::new();
+    tokio::fs::File::from_std(file)
+        .read_to_end(&mut body)
+        .await
+        .ok()?;
+    Some((entry, body))
+}
+
+/// The cached entry of `url` and its body file, which is marked as just used.
+async fn open(url: &str) -> Option<(Entry, std::fs::File)> {
+    let meta = tokio::fs::read(entry_path(url, "json")).await.ok()?;
+    let entry: Entry = serde_json::from_slice(&meta).ok()?;
+    if entry.url != url {
+        return None;
+    }
+    let body_path = entry_path(url, "body");
+    let file = tokio::fs::File::options()
+        .read(true)
+        .write(true)
+        .open(&body_path)
+        .await
+        .ok()?
+        .into_std()
+        .await;
+    let _ = file.set_modified(SystemTime::now());
+    Some((entry, file))
+}
+
+async fn store(url: &str, response: &Response) -> Result<()> {
+    tokio::fs::create_dir_all(cache_dir())
+        .await
+        .context("Failed to create HTTP cache directory")?;
+    write_atomic(&entry_path(url, "body"), &response.body).await?;
+    stored(&Entry::new(url, &response.headers)).await
+}
+
+/// Saves the metadata of a body just written to the cache, and prunes it now and then.
+async fn stored(entry: &Entry) -> Result<()> {
+    save_entry(entry).await?;
+    if STORED.fetch_add(1, Ordering::Relaxed) % PRUNE_EVERY == PRUNE_EVERY - 1 {
+        let dir = cache_dir();
+        tokio::task::spawn_blocking(move || prune(&dir, MAX_CACHE_BYTES));
+    }
+    Ok(())
+}
+
+async fn save_entry(entry: &Entry) -> Result<()> {
+    let meta = serde_json::to_vec(entry).context("Failed to encode HTTP cache entry")?;
+    write_atomic(&entry_path(&entry.url, "json"), &meta).await
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    path.with_extension(format!(
+        "tmp{}-{}",
+        std::process::id(),
+        NEXT_TEMP.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+async fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let temp = temp_path(path);
+    tokio::fs::write(&temp, contents)
+        .await
+        .context(format!("Failed to write {:?}", temp))?;
+    tokio::fs::rename(&temp, path)
+        .await
+        .context(format!("Failed to write {:?}", path))
+}
@@ -0,0 +1,175 @@
+use std::sync::{LazyLock, RwLock, RwLockReadGuard};
+
+use regex::{Captures, Regex};
+use url::Url;
+
+/// Hosts serving analytics, tracking pixels and ads. Subdomains match too.
+const TRACKER_HOSTS: &[&str] = &[
+    "google-analytics.com",
+    "googletagmanager.com",
+    "googlesyndication.com",
+    "googleadservices.com",
+    "adservice.google.com",
+    "doubleclick.net",
+    "facebook.net",
+    "scorecardresearch.com",
+    "quantserve.com",
+    "amazon-adsystem.com",
+    "adnxs.com",
+    "criteo.com",
+    "taboola.com",
+    "outbrain.com",
+    "chartbeat.com",
+    "hotjar.com",
+    "mixpanel.com",
+    "mc.yandex.ru",
+    "stats.wp.com",
+    "pixel.wp.com",
+    "ads-twitter.com",
+    "analytics.twitter.com",
+];
+
+/// Hosts from the config's `tracker_hosts`, checked along with `TRACKER_HOSTS`.
+static EXTRA_TRACKER_HOSTS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Calls that mark an inline script as analytics.
+const TRACKER_SCRIPT_CALLS: &[&str] = &["gtag(", "fbq(", "_gaq", "_paq", "ga('create'"];
+
+/// Marker line appended to stored markdown, recording how many elements were removed.
+const MARKER_PREFIX: &str = "<!-- trackers removed: ";
+const MARKER_SUFFIX: &str = " -->";
+
+static SCRIPT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<script\b([^>]*)>(.*?)</script\s*>").unwrap());
+static IFRAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<iframe\b([^>]*?)(?:/>|>.*?</iframe\s*>|>)").unwrap());
+static IMG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?is)<img\b([^>]*)>").unwrap());
+static PIXEL_STYLE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)width\s*:\s*[01]px.*height\s*:\s*[01]px|height\s*:\s*[01]px.*width\s*:\s*[01]px",
+    )
+    .unwrap()
+});
+
+/// Removes tracking pixels (1x1 images), analytics scripts and ad iframes from article HTML,
+/// returning the cleaned HTML and the number of elements removed.
+pub fn strip_trackers(html: &str) -> (String, usize) {
+    let mut removed = 0;
+    let mut strip = |caps: &Captures, is_tracker: fn(&Captures) -> bool| {
+        if is_tracker(caps) {
+            removed += 1;
+            String::new()
+        } else {
+            caps[0].to_string()
+        }
+    };
+    let html = SCRIPT.replace_all(html, |caps: &Captures| strip(caps, is_tracker_script));
+    let html = IFRAME.replace_all(&html, |caps: &Captures| {
+        strip(caps, |caps| {
+            attr(&caps[1], "src").is_some_and(is_tracker_url)
+        })
+    });
+    let html = IMG.replace_all(&html, |caps: &Captures| strip(caps, is_tracker_image));
+    (html.into_owned(), removed)
+}
+
+/// Sets the hosts treated as trackers on top of the built-in list, from the config's
+/// `tracker_hosts`.
+pub fn set_tracker_hosts(hosts: &[String]) {
+    *EXTRA_TRACKER_HOSTS
+        .write()
+        .unwrap_or_else(|err| err.into_inner()) = hosts
+        .iter()
+        .map(|host| host.trim().trim_start_matches('.').to_lowercase())
+        .filter(|host| !host.is_empty())
+        .collect();
+}
+
+/// Line recording `count` removed elements, kept at the end of the stored markdown.
+pub fn removed_marker(count: usize) -> String {
+    format!("{}{}{}", MARKER_PREFIX, count, MARKER_SUFFIX)
+}
+
+/// Number of elements removed from a stored article, read from its marker line.
+pub fn removed_count(markdown: &str) -> usize {
+    markdown
+        .lines()
+        .rev()
+        .find_map(marker_count)
+        .unwrap_or_default()
+}
+
+pub fn is_marker(line: &str) -> bool {
+    marker_count(line).is_some()
+}
+
+fn marker_count(line: &str) -> Option<usize> {
+    line.trim()
+        .strip_prefix(MARKER_PREFIX)?
+        .strip_suffix(MARKER_SUFFIX)?
+        .parse()
+        .ok()
+}
+
+fn is_tracker_script(caps: &Captures) -> bool {
+    match attr(&caps[1], "src") {
+        Some(src) => is_tracker_url(&src),
+        None => {
+            let body = &caps[2];
+            TRACKER_SCRIPT_CALLS.iter().any(|call| body.contains(call))
+                || TRACKER_HOSTS.iter().any(|host| body.contains(host))
+                || extra_tracker_hosts()
+                    .iter()
+                    .any(|host| body.contains(host.as_str()))
+        }
+    }
+}
+
+fn is_tracker_image(caps: &Captures) -> bool {
+    let attrs = &caps[1];
+    let tiny = |name| attr(attrs, name).is_some_and(|value| matches!(value.trim(), "0" | "1"));
+    (tiny("width") && tiny("height"))
+        || attr(attrs, "style").is_some_and(|style| PIXEL_STYLE.is_match(&style))
+        || attr(attrs, "src").is_some_and(is_tracker_url)
+}
+
+fn is_tracker_url(src: impl AsRef<str>) -> bool {
+    let src = src.as_ref();
+    let url = match src.strip_prefix("//") {
+        Some(rest) => Url::parse(&format!("https://{}", rest)),
+        None => Url::parse(src),
+    };
+    let Some(host) = url
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_lowercase))
+    else {
+        return false;
+    };
+    let matches = |tracker: &str| {
+        host == tracker
+            || host
+                .strip_suffix(tracker)
+                .is_some_and(|prefix| prefix.ends_with('.'))
+    };
+    TRACKER_HOSTS.iter().any(|tracker| matches(tracker))
+        || extra_tracker_hosts().iter().any(|tracker| matches(tracker))
+}
+
+fn extra_tracker_hosts() -> RwLockReadGuard<'static, Vec<String>> {
+    EXTRA_TRACKER_HOSTS
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+}
+
+/// Value of the HTML attribute `name` within a tag's attribute text.
+pub fn attr(attrs: &str, name: &str) -> Option<String> {
+    let pattern = format!(
+        r#"(?i)(?:^|\s){}\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#,
+        regex::escape(name)
+    );
+    let caps = Regex::new(&pattern).ok()?.captures(attrs)?;
+    caps.get(1)
+        .or_else(|| caps.get(2))
+        .or_else(|| caps.get(3))
+        .map(|value| value.as_str().to_string())
+}
@@ -0,0 +1,619 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::LazyLock;
+
+/// Languages the TUI, web UI and CLI help are translated into. English strings are written in
+/// the code and serve as the keys of the other tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Locale {
+    En,
+    Zh,
+}
+
+impl Locale {
+    /// The locale of a language tag or POSIX locale name such as `zh`, `zh-TW` or
+    /// `zh_CN.UTF-8`. Languages without a translation fall back to English.
+    pub fn parse(language: &str) -> Self {
+        if language.trim().to_lowercase().starts_with("zh") {
+            Locale::Zh
+        } else {
+            Locale::En
+        }
+    }
+
+    /// The locale named by `LC_ALL`, `LC_MESSAGES` or `LANG`, whichever is set first.
+    fn from_env() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .map_or(Locale::En, |value| Locale::parse(&value))
+    }
+
+    pub fn tag(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Zh => "zh",
+        }
+    }
+
+    fn table(self) -> Option<&'static HashMap<&'static str, &'static str>> {
+        match self {
+            Locale::En => None,
+            Locale::Zh => Some(&ZH_TABLE),
+        }
+    }
+}
+
+static LOCALE: LazyLock<AtomicU8> = LazyLock::new(|| AtomicU8::new(Locale::from_env() as u8));
+
+/// Overrides the locale taken from the environment, from the config's `language`.
+pub fn set_language(language: &str) {
+    LOCALE.store(Locale::parse(language) as u8, Ordering::Relaxed);
+}
+
+pub fn locale() -> Locale {
+    if LOCALE.load(Ordering::Relaxed) == Locale::Zh as u8 {
+        Locale::Zh
+    } else {
+        Locale::En
+    }
+}
+
+/// `text` in the current locale, or `text` itself when it has no translation.
+pub fn translate(text: &str) -> &str {
+    locale()
+        .table()
+        .and_then(|table| table.get(text).copied())
+        .unwrap_or(text)
+}
+
+/// Replaces each `{}` in `template` with the next of `args`, and each `{N}` with the `N`th
+/// (counting from 0), so translations can reorder them.
+pub fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut out = String::new();
+    let mut next = 0;
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let index = after
+            .find('}')
+            .map(|end| &after[..end])
+            .filter(|digits| digits.chars().all(|c| c.is_ascii_digit()));
+        let Some(digits) = index else {
+            out.push('{');
+            rest = after;
+            continue;
+        };
+        let index = digits.parse().unwrap_or_else(|_| {
+            next += 1;
+            next - 1
+        });
+        if let Some(arg) = args.get(index) {
+            out.push_str(&arg.to_string());
+        }
+        rest = &after[digits.len() + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Translations of the web UI's strings as a JSON object, for the page's script.
+pub fn web_strings() -> String {
+    let strings: HashMap<&str, &str> = locale()
+        .table()
+        .map(|table| {
+            WEB_STRINGS
+                .iter()
+                .filter_map(|text| Some((*text, *table.get(text)?)))
+                .collect()
+        })
+        .unwrap_or_default();
+    serde_json::to_string(&strings).unwrap_or_else(|_| String::from("{}"))
+}
+
+/// Translates the about text and argument help of `command` and all of its subcommands.
+pub fn localize_command(command: clap::Command) -> clap::Command {
+    if locale() == Locale::En {
+        return command;
+    }
+    let about = command
+        .get_about()
+        .map(|about| translate(&about.to_string()).to_string());
+    let command = match about {
+        Some(about) => command.about(about),
+        None => command,
+    };
+    command
+        .mut_args(|arg| match arg.get_help().map(ToString::to_string) {
+            Some(help) => {
+                let translated = translate(&help).to_string();
+                arg.help(translated)
+            }
+            None => arg,
+        })
+        .mut_subcommands(localize_command)
+}
+
+static ZH_TABLE: LazyLock<HashMap<&'static str, &'static str>> =
+    LazyLock::new(|| ZH.iter().copied().collect());
+
+/// Strings the web UI's script passes through its `t()` lookup.
+const WEB_STRINGS: &[&str] = &[
+    "Sidebar navigation for feeds and items with a focused article view.",
+    "Subscribe",
+    "(drag to your bookmarks bar).",
+    "Feeds",
+    "Items",
+    "Article",
+    "Back",
+    "Refresh",
+    "Mute",
+    "Unmute",
+    "Muted until {}",
+    "{} new",
+    "Full archive",
+    "Latest from feed",
+    "Select a feed and item to read.",
+    "No items.",
+    "Untitled",
+    "Load more",
+    "Loading...",
+    "Loading article...",
+    "Failed to load more. Click to retry.",
+    "Open link",
+    "by {}",
+    "Permalink",
+    "Export as Markdown",
+    "Export as HTML",
+    "Print",
+    "Star",
+    "Unstar",
+    "Comments",
+    "updated",
+    "tracker(s) removed",
+    "No discussion found",
+    "Failed to load comments",
+    "Full content",
+    "Feed content",
+    "Failed to load full content",
+    "Contents",
+    "Related from your archive",
+    "Previously read on {} in {}",
+    "Previously stored on {} in {}",
+    "comments on",
+    "Failed to load article.",
+    "Failed to load feed.",
+    "never",
+    "Feed unreachable, showing archived items (last fetched: {})",
+];
+
+const ZH: &[(&str, &str)] = &[
+    // TUI
+    (
+        "Press 'q' to quit, 'Enter' to select, 'Esc' to go back",
+        "按 'q' 退出，'Enter' 选择，'Esc' 返回",
+    ),
+    ("Fetching {}... Press 'Esc' to cancel.", "正在获取 {}…… 按 'Esc' 取消。"),
+    ("Cancelled fetching {}", "已取消获取 {}"),
+    ("Error: {}", "错误：{}"),
+    ("Error: failed to open {}: {}", "错误：无法打开 {}：{}"),
+    (
+        "Loaded feed and {} older articles from the archive. Press 'Enter' to view article, 'Esc' to back.",
+        "已加载订阅源及存档中的 {} 篇旧文章。按 'Enter' 查看文章，'Esc' 返回。",
+    ),
+    (
+        "Loaded feed. Press 'Enter' to view article, 'Esc' to back.",
+        "已加载订阅源。按 'Enter' 查看文章，'Esc' 返回。",
+    ),
+    ("All {} items are listed", "已列出全部 {} 个条目"),
+    ("Listing {} of {} items", "已列出 {} / {} 个条目"),
+    ("Opened {}", "已打开 {}"),
+    ("No cached copy of this feed.", "此订阅源没有缓存副本。"),
+    ("Already subscribed as {}.", "已订阅，名称为 {}。"),
+    ("Opened {}. Press 'S' to subscribe.", "已打开 {}。按 'S' 订阅。"),
+    ("Subscribed to {}", "已订阅 {}"),
+    ("Paused {}", "已暂停 {}"),
+    ("Muted {} until {}", "已将 {} 静音至 {}"),
+    (
+        "Closed the viewer. Press 'Enter' to view article, 'Esc' to back.",
+        "已关闭查看器。按 'Enter' 查看文章，'Esc' 返回。",
+    ),
+    ("{} exited with {}", "{} 退出，{}"),
+    ("No config file to edit", "没有可编辑的配置文件"),
+    ("Reloaded {}: {} feeds", "已重新加载 {}：{} 个订阅源"),
+    ("Kept the previous config", "已保留之前的配置"),
+    ("[e] Edit again  [Esc] Keep the previous config", "[e] 重新编辑  [Esc] 保留之前的配置"),
+    ("Invalid config", "配置无效"),
+    ("Unmuted {}", "已取消 {} 的静音"),
+    ("muted until {}", "静音至 {}"),
+    (
+        "Offline: showing archived items, last fetched {}",
+        "离线：显示存档条目，上次获取于 {}",
+    ),
+    ("never", "从未"),
+    ("Loading article...", "正在加载文章……"),
+    (
+        "Reading article. Press 'Esc' or 'q' to back.",
+        "正在阅读文章。按 'Esc' 或 'q' 返回。",
+    ),
+    (
+        "Feed items. Press 'Enter' to read, 'Esc' to feeds.",
+        "订阅条目。按 'Enter' 阅读，'Esc' 返回订阅列表。",
+    ),
+    ("Select a feed. Press 'Enter' to open.", "请选择订阅源。按 'Enter' 打开。"),
+    ("Marked {} article(s) in {} as read", "已将 {1} 中的 {0} 篇文章标为已读"),
+    ("Moved {} up", "已上移 {}"),
+    ("Moved {} down", "已下移 {}"),
+    ("Moved {} to {}", "已将 {} 移至 {}"),
+    ("uncategorized", "未分类"),
+    ("{} is already at the top of the list", "{} 已在列表顶部"),
+    ("{} is already at the bottom of the list", "{} 已在列表底部"),
+    (
+        "Attachment {}: {}. Press 'o' to open, 's' to save.",
+        "附件 {}：{}。按 'o' 打开，'s' 保存。",
+    ),
+    ("No attachment {}", "没有附件 {}"),
+    ("Saved {} to {}", "已将 {} 保存到 {}"),
+    ("Downloading {}... Press 'Esc' to cancel.", "正在下载 {}…… 按 'Esc' 取消。"),
+    ("Download cancelled.", "已取消下载。"),
+    ("A download is already in progress.", "已有下载正在进行。"),
+    ("This item has no link", "此条目没有链接"),
+    ("Copied {}", "已复制 {}"),
+    ("Copied {} (OSC 52)", "已复制 {}（OSC 52）"),
+    ("Fetching comments...", "正在获取评论……"),
+    ("No discussion found for this item.", "未找到此条目的讨论。"),
+    ("Unknown Feed", "未知订阅源"),
+    ("Feeds Configuration", "订阅源"),
+    ("Feeds Configuration (prefetching, {} left)", "订阅源（预取中，剩余 {} 个）"),
+    ("({} unread)", "（{} 未读）"),
+    ("{} items", "{} 个条目"),
+    ("failed", "失败"),
+    ("Feed Items", "订阅条目"),
+    ("{} ({} of {}, 'm' for more)", "{}（{} / {}，按 'm' 显示更多）"),
+    ("No Title", "无标题"),
+    ("Title: ", "标题："),
+    ("Link: ", "链接："),
+    ("Date: ", "日期："),
+    ("Author: ", "作者："),
+    ("Updated: ", "更新："),
+    ("Comments: ", "评论："),
+    ("{} comments", "{} 条评论"),
+    ("comments", "评论"),
+    ("No content.", "无内容。"),
+    ("Notes", "注释"),
+    ("Content is still processing...", "内容仍在处理中……"),
+    ("Attachments", "附件"),
+    ("1-9 select, o open, s save to data/downloads", "1-9 选择，o 打开，s 保存到 data/downloads"),
+    ("No item selected", "未选择条目"),
+    (
+        "Terminal too small ({}x{}), need at least {}x{}",
+        "终端太小（{}x{}），至少需要 {}x{}",
+    ),
+    ("Article View", "文章"),
+    ("Channel Details (Esc or 'i' to close)", "频道详情（按 Esc 或 'i' 关闭）"),
+    (
+        "Contents (Enter to jump, Esc to close)",
+        "目录（按 Enter 跳转，Esc 关闭）",
+    ),
+    (
+        "No table of contents: the article has fewer than {} headings",
+        "没有目录：文章的标题少于 {} 个",
+    ),
+    ("Title", "标题"),
+    ("Description", "描述"),
+    ("Site", "网站"),
+    ("Language", "语言"),
+    ("Last build", "最后构建"),
+    ("Published", "发布"),
+    ("Image", "图片"),
+    ("TTL", "TTL"),
+    ("Generator", "生成器"),
+    ("Items: ", "条目："),
+    ("{} minutes", "{} 分钟"),
+    ("[v] View cached copy  ", "[v] 查看缓存副本  "),
+    (
+        "[r] Retry  [o] Open in browser  {}[p] Pause feed  [Esc] Dismiss",
+        "[r] 重试  [o] 在浏览器中打开  {}[p] 暂停订阅源  [Esc] 关闭",
+    ),
+    ("Failed to fetch {}", "获取 {} 失败"),
+    (
+        "Open URL or RSSHub route (Enter to open, Esc to cancel)",
+        "打开 URL 或 RSSHub 路由（Enter 打开，Esc 取消）",
+    ),
+    (
+        "Find in item titles (Enter to find next, Esc to cancel)",
+        "在条目标题中查找（Enter 查找下一个，Esc 取消）",
+    ),
+    ("No listed item matches \"{}\"", "没有列出的条目匹配“{}”"),
+    (
+        "Find in the article (Enter to search, Esc to cancel)",
+        "在文章中查找（按 Enter 搜索，Esc 取消）",
+    ),
+    ("No match for \"{}\" in the article", "文章中没有匹配“{}”的内容"),
+    (
+        "Match {} of {} for \"{}\" (n next, N previous, Esc to clear)",
+        "“{2}”的第 {0} 个匹配，共 {1} 个（n 下一个，N 上一个，Esc 清除）",
+    ),
+    ("Status", "状态"),
+    ("just now", "刚刚"),
+    ("{}m ago", "{} 分钟前"),
+    ("{}h ago", "{} 小时前"),
+    ("yesterday", "昨天"),
+    ("{} days ago", "{} 天前"),
+    ("Starred articles", "收藏的文章"),
+    ("By date", "按日期"),
+    ("By feed", "按订阅源"),
+    ("By category", "按分类"),
+    ("No starred articles.", "没有收藏的文章。"),
+    // Web UI
+    (
+        "Sidebar navigation for feeds and items with a focused article view.",
+        "侧边栏浏览订阅源和条目，专注阅读文章。",
+    ),
+    ("Subscribe", "订阅"),
+    ("(drag to your bookmarks bar).", "（拖到书签栏）。"),
+    ("Feeds", "订阅源"),
+    ("Items", "条目"),
+    ("Article", "文章"),
+    ("Back", "返回"),
+    ("Refresh", "刷新"),
+    ("Mute", "静音"),
+    ("Unmute", "取消静音"),
+    ("Muted until {}", "静音至 {}"),
+    ("{} new", "{} 条新"),
+    ("Full archive", "完整存档"),
+    ("Latest from feed", "订阅源最新"),
+    ("Select a feed and item to read.", "请选择订阅源和条目进行阅读。"),
+    ("No items.", "没有条目。"),
+    ("Untitled", "无标题"),
+    ("Load more", "加载更多"),
+    ("Loading...", "加载中……"),
+    ("Failed to load more. Click to retry.", "加载失败，点击重试。"),
+    ("Open link", "打开链接"),
+    ("by {}", "作者：{}"),
+    ("Permalink", "永久链接"),
+    ("Export as Markdown", "导出为 Markdown"),
+    ("Export as HTML", "导出为 HTML"),
+    ("Print", "打印"),
+    ("Star", "收藏"),
+    ("Unstar", "取消收藏"),
+    ("Comments", "评论"),
+    ("updated", "更新于"),
+    ("tracker(s) removed", "个跟踪器已移除"),
+    ("No discussion found", "未找到讨论"),
+    ("Failed to load comments", "加载评论失败"),
+    ("Full content", "全文"),
+    ("Feed content", "订阅源内容"),
+    ("Failed to load full content", "加载全文失败"),
+    ("Contents", "目录"),
+    ("Related from your archive", "存档中的相关文章"),
+    ("Previously read on {} in {}", "已于 {} 在“{}”中读过"),
+    ("Previously stored on {} in {}", "已于 {} 存入“{}”"),
+    ("comments on", "条评论，来自"),
+    ("Failed to load article.", "加载文章失败。"),
+    ("Failed to load feed.", "加载订阅源失败。"),
+    (
+        "Feed unreachable, showing archived items (last fetched: {})",
+        "订阅源无法访问，显示存档条目（上次获取：{}）",
+    ),
+    // CLI help
+    ("A simple RSS reader CLI in Rust", "用 Rust 编写的简单 RSS 阅读器"),
+    ("Suppress all non-error output", "只输出错误信息"),
+    (
+        "Encrypt stored articles and the index with the key in this file (created if missing). Alternatively set RSS_READER_PASSPHRASE to derive the key from a passphrase",
+        "使用此文件中的密钥加密存储的文章和索引（不存在时创建）。也可以设置 RSS_READER_PASSPHRASE，从口令派生密钥",
+    ),
+    (
+        "Let URLs from feed content, such as article images, reach loopback and private network addresses, which are refused by default",
+        "允许来自订阅内容的 URL（如文章图片）访问本机回环和私有网络地址，默认拒绝",
+    ),
+    (
+        "Directory of the article store, in place of the config's store_dir or data/articles",
+        "文章存储目录，代替配置中的 store_dir 或 data/articles",
+    ),
+    (
+        "Subscribe/Read a direct RSS URL, a local file, or stdin",
+        "订阅或阅读 RSS 地址、本地文件或标准输入",
+    ),
+    (
+        "The URL of the RSS feed, a path to a feed file, or \"-\" to read from stdin",
+        "RSS 订阅源的 URL、订阅源文件路径，或用 \"-\" 从标准输入读取",
+    ),
+    (
+        "Number of items to show (in the TUI, the initial page; 'm' lists more)",
+        "显示的条目数（在 TUI 中为首页条目数，按 'm' 显示更多）",
+    ),
+    ("Open in TUI mode", "以 TUI 模式打开"),
+    (
+        "Print request, response and parse diagnostics to stderr",
+        "将请求、响应和解析诊断信息输出到标准错误",
+    ),
+    ("Fetch and print without storing articles", "获取并输出，但不存储文章"),
+    (
+        "Feed name to archive the articles under (default: the channel title)",
+        "存档文章所用的订阅源名称（默认：频道标题）",
+    ),
+    ("Mark the printed articles as read in the archive", "在存档中将输出的文章标为已读"),
+    ("Read from RSSHub", "从 RSSHub 阅读"),
+    ("The route (e.g., /bilibili/user/video/2267573)", "路由（例如 /bilibili/user/video/2267573）"),
+    (
+        "Optional RSSHub instance URL (default: https://rsshub.app)",
+        "可选的 RSSHub 实例 URL（默认：https://rsshub.app）",
+    ),
+    ("Fetch every configured feed and store new articles", "获取所有已配置的订阅源并存储新文章"),
+    ("Path to config file (default: feeds.toml)", "配置文件路径（默认：feeds.toml）"),
+    ("Fetch without storing articles", "获取但不存储文章"),
+    (
+        "Also fetch feeds whose `<ttl>`, `<skipHours>` or `<skipDays>` ask not to be fetched yet",
+        "同时获取其 `<ttl>`、`<skipHours>` 或 `<skipDays>` 要求暂不获取的订阅",
+    ),
+    (
+        "Import a feed's older posts from its archive pages (`rel=\"prev-archive\"`/`next` links, or WordPress' `?paged=N`)",
+        "从存档页面导入订阅源的旧文章（`rel=\"prev-archive\"`/`next` 链接，或 WordPress 的 `?paged=N`）",
+    ),
+    ("Name or URL of a configured feed", "已配置订阅源的名称或 URL"),
+    ("Maximum number of pages to fetch", "最多获取的页数"),
+    (
+        "Show bytes downloaded per feed in a month and how much of each budget is used",
+        "显示每个订阅源一个月内下载的字节数及预算使用情况",
+    ),
+    (
+        "Month to report as YYYY-MM (default: the current month)",
+        "统计的月份，格式为 YYYY-MM（默认：当月）",
+    ),
+    ("Manage subscriptions in the config file", "管理配置文件中的订阅"),
+    (
+        "List stored articles by interest score, with how each score adds up",
+        "按兴趣分数列出存储的文章，并显示每个分数的构成",
+    ),
+    ("Only score articles from this feed", "只为此订阅源的文章评分"),
+    (
+        "Print the newly stored items to stdout in this format instead of the progress report (with --dry-run, every fetched item)",
+        "以此格式将新存储的条目输出到标准输出，而不是进度报告（使用 --dry-run 时输出所有获取的条目）",
+    ),
+    (
+        "List recently opened articles, grouped by the day they were read",
+        "按阅读日期分组列出最近打开的文章",
+    ),
+    ("Number of articles to list", "列出的文章数"),
+    (
+        "Only list articles whose title or feed name contains this text",
+        "只列出标题或订阅源名称包含此文本的文章",
+    ),
+    (
+        "List recent `refresh` runs: when they ran, what each feed fetched, added or failed with, and which feeds were skipped",
+        "列出最近的 `refresh` 运行：运行时间、各订阅源获取和新增了什么或因何失败，以及跳过了哪些订阅源",
+    ),
+    ("Number of runs to list", "列出的运行次数"),
+    (
+        "Only show what the runs did with this feed, whatever the outcome",
+        "只显示各次运行对此订阅源的处理结果，无论成功与否",
+    ),
+    (
+        "Also save the raw feed responses and the config to this directory, for `replay`",
+        "同时将原始订阅源响应和配置保存到此目录，供 `replay` 使用",
+    ),
+    (
+        "Run feed responses saved by `refresh --record` through parsing and storing again, into a fresh store inside the recording, without the network",
+        "不经网络，将 `refresh --record` 保存的订阅源响应重新解析并存储到录制目录内的全新存储中",
+    ),
+    ("Directory the responses were recorded to", "响应录制到的目录"),
+    (
+        "Config to replay with instead of the one saved in the recording",
+        "重放时使用的配置，代替录制中保存的配置",
+    ),
+    ("Print parse diagnostics to stderr", "将解析诊断信息输出到标准错误"),
+    (
+        "Mute a noisy feed, or hide items matching a text, for a while",
+        "暂时静音嘈杂的订阅源，或隐藏匹配某段文本的条目",
+    ),
+    (
+        "Mute a feed, or hide the items whose title contains --matching (in every feed unless a feed is given)",
+        "静音订阅源，或隐藏标题包含 --matching 文本的条目（未指定订阅源时作用于所有订阅源）",
+    ),
+    ("Name of the feed", "订阅源名称"),
+    ("Hide only items whose title contains this text", "只隐藏标题包含此文本的条目"),
+    ("How long, e.g. 12h, 7d or 4w", "持续时间，例如 12h、7d 或 4w"),
+    ("List snoozes that haven't expired", "列出未过期的静音"),
+    ("Lift a snooze before it expires", "在到期前取消静音"),
+    ("Text of the snoozed item filter", "被隐藏条目的过滤文本"),
+    ("Open the TUI reader with feeds from config file", "使用配置文件中的订阅源打开 TUI 阅读器"),
+    (
+        "Sync read state, stars and stored articles with a remote server instance",
+        "与远程服务器实例同步已读状态、收藏和存储的文章",
+    ),
+    (
+        "Base URL of the remote server (e.g., http://nas.local:7878)",
+        "远程服务器的基础 URL（例如 http://nas.local:7878）",
+    ),
+    ("Only pull changes from the remote", "只从远程拉取更改"),
+    ("Only push local changes to the remote", "只将本地更改推送到远程"),
+    ("Run the web server and open a browser UI", "运行 Web 服务器并打开浏览器界面"),
+    ("Host to bind (default: 127.0.0.1)", "绑定的主机（默认：127.0.0.1）"),
+    ("Port to bind (default: 7878)", "绑定的端口（默认：7878）"),
+    (
+        "Address to listen on instead of --host/--port: HOST:PORT, or unix:PATH for a Unix socket",
+        "代替 --host/--port 监听的地址：HOST:PORT，或 unix:PATH 表示 Unix 套接字",
+    ),
+    (
+        "Path prefix of every URL when served under a subpath by a reverse proxy, e.g. /rss",
+        "由反向代理托管在子路径下时所有 URL 的路径前缀，例如 /rss",
+    ),
+    (
+        "URL the server is reachable at from the internet, e.g. https://example.com/rss; lets feeds with an rssCloud `<cloud>` push their updates instead of being polled",
+        "服务器在互联网上的访问地址，例如 https://example.com/rss；带有 rssCloud `<cloud>` 的订阅可据此推送更新，而无需轮询",
+    ),
+    ("Disable auto-opening the browser", "不自动打开浏览器"),
+    (
+        "Allowed CORS origin for the API (repeatable, \"*\" allows any origin)",
+        "API 允许的 CORS 来源（可重复，\"*\" 允许任意来源）",
+    ),
+    (
+        "Maximum API requests per client IP per minute (0 disables)",
+        "每个客户端 IP 每分钟的最大 API 请求数（0 表示不限制）",
+    ),
+    ("Seconds before a request is aborted with 408", "请求超过此秒数后以 408 中止"),
+    (
+        "Subscribe to an RSS feed; without --name the channel title is used",
+        "订阅 RSS 订阅源；未指定 --name 时使用频道标题",
+    ),
+    (
+        "Subscribe even if the URL matches an existing subscription",
+        "即使 URL 与已有订阅重复也订阅",
+    ),
+    (
+        "Refresh auto-filled names, descriptions and site links from the feeds themselves",
+        "根据订阅源本身更新自动填写的名称、描述和网站链接",
+    ),
+    ("Also rename feeds whose names were set by hand", "同时重命名手动设置名称的订阅源"),
+    (
+        "Subscribe to the feeds of an OPML file, keeping its folders as (nested) categories",
+        "订阅 OPML 文件中的订阅源，并将其文件夹保留为（嵌套）分类",
+    ),
+    (
+        "Write subscriptions as OPML, with nested categories as nested folders",
+        "将订阅导出为 OPML，嵌套分类导出为嵌套文件夹",
+    ),
+    ("File to write instead of standard output", "写入的文件（默认为标准输出）"),
+    ("Inspect the article store", "查看文章存储"),
+    (
+        "Show the space stored articles take, by feed and by kind (markdown or images)",
+        "按订阅源和类型（markdown 或图片）显示存储文章占用的空间",
+    ),
+    (
+        "Write the config and the article store (articles, images, read state and history) to a compressed archive",
+        "将配置和文章存储（文章、图片、阅读状态和历史）写入压缩归档",
+    ),
+    (
+        "Archive to write (default: rss_reader-<time>.tar.zst in the current directory)",
+        "写入的归档（默认：当前目录下的 rss_reader-<时间>.tar.zst）",
+    ),
+    (
+        "Replace the config and the article store with the ones in a backup",
+        "用备份中的配置和文章存储替换当前的",
+    ),
+    ("Archive written by `backup`", "由 `backup` 写入的归档"),
+    ("Replace stored articles that already exist", "替换已存在的存储文章"),
+    ("Export stored articles", "导出存储的文章"),
+    (
+        "Write starred articles as a static HTML site, indexed by date, feed and category, with their images copied alongside",
+        "将收藏的文章导出为静态 HTML 网站，按日期、订阅源和分类建立索引，并复制其中的图片",
+    ),
+    ("Directory to write the site to (created if missing)", "网站的输出目录（不存在时创建）"),
+    (
+        "Fetch every feed, flag broken or stale ones, and offer to remove or pause them",
+        "获取所有订阅源，标出失效或长期未更新的订阅源，并提供删除或暂停的选项",
+    ),
+    (
+        "Flag feeds with no new items in this many months",
+        "标出在这么多个月内没有新条目的订阅源",
+    ),
+    ("Only print the report, without prompting", "只输出报告，不进行询问"),
+    ("Print help", "显示帮助"),
+    ("Print version", "显示版本"),
+    ("Print this message or the help of the given subcommand(s)", "显示此信息或指定子命令的帮助"),
+];
@@ -0,0 +1,106 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::config::Feed;
+use crate::db;
+
+const VERSION: &str = "https://jsonfeed.org/version/1.1";
+
+/// A JSON Feed 1.1 document (https://www.jsonfeed.org/version/1.1/).
+#[derive(Debug, Serialize)]
+pub struct JsonFeed {
+    version: &'static str,
+    title: String,
+    items: Vec<JsonFeedItem>,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonFeedItem {
+    /// The article ID in the archive.
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    content_html: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    date_published: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    date_modified: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    authors: Vec<Author>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    /// The subscription the item came from; JSON Feed extensions start with `_`.
+    #[serde(rename = "_rss_reader")]
+    source: Source,
+}
+
+#[derive(Debug, Serialize)]
+struct Author {
+    name: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Source {
+    feed_name: String,
+    feed_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    category: Option<String>,
+}
+
+impl JsonFeed {
+    pub fn new(title: &str) -> Self {
+        Self {
+            version: VERSION,
+            title: title.to_string(),
+            items: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, feed: &Feed, item: &rss::Item) {
+        let description = item.description().map(str::to_string);
+        let (content_html, summary) = match item.content() {
+            Some(content) => (content.to_string(), description),
+            None => (description.unwrap_or_default(), None),
+        };
+        let author = item.author().or_else(|| {
+            item.dublin_core_ext()?
+                .creators()
+                .first()
+                .map(String::as_str)
+        });
+        self.items.push(JsonFeedItem {
+            id: db::item_id(&feed.name, &feed.url, item),
+            url: item.link().map(str::to_string),
+            title: item.title().map(str::to_string),
+            content_html,
+            summary,
+            date_published: db::parse_pub_date(item.pub_date()),
+            date_modified: db::item_updated(item)
+                .and_then(|updated| db::parse_pub_date(Some(&updated))),
+            authors: author
+                .map(|name| Author {
+                    name: name.to_string(),
+                })
+                .into_iter()
+                .collect(),
+            tags: item
+                .categories()
+                .iter()
+                .map(|category| category.name().to_string())
+                .collect(),
+            source: Source {
+                feed_name: feed.name.clone(),
+                feed_url: feed.url.clone(),
+                category: feed.category.clone(),
+            },
+        });
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).context("Failed to serialize JSON Feed")
+    }
+}
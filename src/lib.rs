@@ -0,0 +1,221 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::AtomicBool;
+
+/// Set by `--quiet`; suppresses everything printed through `say!`.
+pub static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// `println!` that stays silent under `--quiet`. Errors go to stderr and are never suppressed.
+#[macro_export]
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if !$crate::QUIET.load(::std::sync::atomic::Ordering::Relaxed) {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// A UI string in the current locale (see `i18n`). With arguments, each `{}` in the translated
+/// text is replaced by the next one.
+#[macro_export]
+macro_rules! tr {
+    ($text:literal) => {
+        $crate::i18n::translate($text)
+    };
+    ($text:literal, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::fill(
+            $crate::i18n::translate($text),
+            &[$(&$arg as &dyn ::std::fmt::Display),+],
+        )
+    };
+}
+
+pub mod audit;
+pub mod backfill;
+pub mod backup;
+pub mod blocklist;
+pub mod cache;
+pub mod cloud;
+pub mod config;
+pub mod crypto;
+pub mod dates;
+pub mod db;
+pub mod enrich;
+pub mod feed;
+pub mod fetcher;
+pub mod figures;
+pub mod footnotes;
+pub mod http_cache;
+pub mod hygiene;
+pub mod i18n;
+pub mod jsonfeed;
+pub mod math;
+pub mod media;
+pub mod microblog;
+pub mod newsletter;
+pub mod opml;
+pub mod plugins;
+pub mod readability;
+pub mod related;
+pub mod replay;
+pub mod scoring;
+pub mod server;
+pub mod site;
+pub mod sync;
+#[cfg(test)]
+mod test_util;
+pub mod tui;
+pub mod writer;
+
+/// Returned by `refresh` when some configured feeds could not be fetched.
+#[derive(Debug)]
+pub struct PartialRefresh {
+    failed: usize,
+    total: usize,
+}
+
+impl fmt::Display for PartialRefresh {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} feeds failed to refresh",
+            self.failed, self.total
+        )
+    }
+}
+
+impl std::error::Error for PartialRefresh {}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum EmitFormat {
+    /// JSON Feed 1.1
+    Jsonfeed,
+}
+
+/// Fetches every configured feed that is due and stores its items, returning `PartialRefresh`
+/// when some failed. When storing, the run is journaled feed by feed (see `Database::runs`).
+pub async fn refresh_feeds(
+    fetcher: &dyn fetcher::FeedFetcher,
+    cfg: &config::Config,
+    db: Option<&db::Database>,
+    emit: Option<EmitFormat>,
+    force: bool,
+) -> Result<()> {
+    let feeds = cfg.get_all_feeds();
+    let hints = match (db, force) {
+        (Some(database), false) => database.update_hints()?,
+        _ => HashMap::new(),
+    };
+    let now = chrono::Utc::now();
+    let run = db.map(db::Database::start_run).transpose()?;
+    let journal = |feed: &config::Feed,
+                   outcome: db::RunOutcome,
+                   items: usize,
+                   added: usize,
+                   message: Option<String>| {
+        match (db, &run) {
+            (Some(database), Some(run)) => {
+                database.record_run_feed(run, &feed.name, outcome, items, added, message)
+            }
+            _ => Ok(()),
+        }
+    };
+    let mut failed = 0;
+    let mut skipped = 0;
+    let mut new_items = jsonfeed::JsonFeed::new("RSS Reader: New items");
+    for feed in &feeds {
+        let next_fetch = hints
+            .get(&feed.name)
+            .and_then(|(fetched_at, hints)| Some((hints.next_fetch(*fetched_at, now)?, hints)));
+        if let Some((next_fetch, hints)) = next_fetch {
+            skipped += 1;
+            let reason = format!(
+                "not due until {} ({})",
+                dates::absolute(&next_fetch.with_timezone(&chrono::Local)),
+                hints
+            );
+            say!("{}: {}", feed.name, reason);
+            journal(feed, db::RunOutcome::Skipped, 0, 0, Some(reason))?;
+            continue;
+        }
+        match feed::fetch_configured_feed(fetcher, feed).await {
+            Ok(channel) => {
+                let added = match db {
+                    Some(database) => {
+                        database
+                            .store_channel(&feed.name, &feed.url, &channel, feed.store_options())
+                            .await?
+                    }
+                    None => channel.items().to_vec(),
+                };
+                journal(
+                    feed,
+                    db::RunOutcome::Fetched,
+                    channel.items().len(),
+                    added.len(),
+                    None,
+                )?;
+                if emit.is_some() {
+                    for item in &added {
+                        new_items.push(feed, item);
+                    }
+                }
+                say!("{}: {} items", feed.name, channel.items().len());
+            }
+            Err(err) => {
+                failed += 1;
+                eprintln!("{}: {:#}", feed.name, err);
+                journal(
+                    feed,
+                    db::RunOutcome::Failed,
+                    0,
+                    0,
+                    Some(format!("{:#}", err)),
+                )?;
+            }
+        }
+    }
+    if let (Some(database), Some(run)) = (db, &run) {
+        database.finish_run(run)?;
+    }
+    say!(
+        "Refreshed {} of {} feeds",
+        feeds.len() - failed - skipped,
+        feeds.len() - skipped
+    );
+    if skipped > 0 {
+        say!(
+            "Skipped {} feeds that asked not to be fetched yet (--force fetches them)",
+            skipped
+        );
+    }
+    match emit {
+        Some(EmitFormat::Jsonfeed) => println!("{}", new_items.to_json()?),
+        None => {}
+    }
+    if failed > 0 {
+        return Err(PartialRefresh {
+            failed,
+            total: feeds.len() - skipped,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
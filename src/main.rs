@@ -1,34 +1,89 @@
 use anyhow::Result;
-use clap::{ArgAction, Parser, Subcommand};
+use clap::{ArgAction, CommandFactory, FromArgMatches, Parser, Subcommand};
 use rss::Channel;
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::Ordering;
 
-mod config;
-mod db;
-mod feed;
-mod server;
-mod tui;
+use rss_reader::{
+    audit, backfill, backup, config, crypto, dates, db, feed, fetcher, format_bytes, http_cache,
+    i18n, opml, refresh_feeds, replay, say, scoring, server, site, sync, tui, EmitFormat,
+    PartialRefresh, QUIET,
+};
 
 #[derive(Parser)]
 #[command(name = "rss_reader")]
 #[command(about = "A simple RSS reader CLI in Rust", long_about = None)]
 struct Cli {
+    /// Suppress all non-error output
+    #[arg(short, long, global = true)]
+    quiet: bool,
+    /// Encrypt stored articles and the index with the key in this file (created if missing).
+    /// Alternatively set RSS_READER_PASSPHRASE to derive the key from a passphrase
+    #[arg(long, global = true, env = "RSS_READER_KEY_FILE")]
+    key_file: Option<PathBuf>,
+    /// Let URLs from feed content, such as article images, reach loopback and private network
+    /// addresses, which are refused by default
+    #[arg(long, global = true)]
+    allow_private_addresses: bool,
+    /// Directory of the article store, in place of the config's store_dir or data/articles
+    #[arg(long, global = true, env = "RSS_READER_STORE_DIR")]
+    store_dir: Option<PathBuf>,
     #[command(subcommand)]
     command: Commands,
 }
 
+// Exit codes beyond 0 (success) and 2 (invalid arguments, reported by clap).
+const EXIT_ERROR: u8 = 1;
+const EXIT_NETWORK_ERROR: u8 = 3;
+const EXIT_PARSE_ERROR: u8 = 4;
+const EXIT_PARTIAL_REFRESH: u8 = 5;
+
+fn exit_code(err: &anyhow::Error) -> u8 {
+    if err.is::<PartialRefresh>() {
+        return EXIT_PARTIAL_REFRESH;
+    }
+    for cause in err.chain() {
+        if cause.is::<reqwest::Error>()
+            || cause.is::<async_imap::error::Error>()
+            || cause.is::<http_cache::ErrorStatus>()
+            || cause.is::<http_cache::TooLarge>()
+            || cause.is::<feed::TimedOut>()
+        {
+            return EXIT_NETWORK_ERROR;
+        }
+        if cause.is::<rss::Error>() {
+            return EXIT_PARSE_ERROR;
+        }
+    }
+    EXIT_ERROR
+}
+
 #[derive(Subcommand)]
 enum Commands {
-    /// Subscribe/Read a direct RSS URL
+    /// Subscribe/Read a direct RSS URL, a local file, or stdin
     Read {
-        /// The URL of the RSS feed
+        /// The URL of the RSS feed, a path to a feed file, or "-" to read from stdin
         url: String,
-        /// Number of items to show
+        /// Number of items to show (in the TUI, the initial page; 'm' lists more)
         #[arg(short, long, default_value_t = 5)]
         limit: usize,
         /// Open in TUI mode
         #[arg(long, default_value_t = false)]
         tui: bool,
+        /// Print request, response and parse diagnostics to stderr
+        #[arg(short, long)]
+        verbose: bool,
+        /// Fetch and print without storing articles
+        #[arg(long)]
+        dry_run: bool,
+        /// Feed name to archive the articles under (default: the channel title)
+        #[arg(long, conflicts_with = "dry_run")]
+        name: Option<String>,
+        /// Mark the printed articles as read in the archive
+        #[arg(long, conflicts_with = "dry_run")]
+        mark_read: bool,
     },
     /// Read from RSSHub
     Rsshub {
@@ -37,12 +92,154 @@ enum Commands {
         /// Optional RSSHub instance URL (default: https://rsshub.app)
         #[arg(long, default_value = "https://rsshub.app")]
         host: String,
-        /// Number of items to show
+        /// Number of items to show (in the TUI, the initial page; 'm' lists more)
         #[arg(short, long, default_value_t = 5)]
         limit: usize,
         /// Open in TUI mode
         #[arg(long, default_value_t = false)]
         tui: bool,
+        /// Print request, response and parse diagnostics to stderr
+        #[arg(short, long)]
+        verbose: bool,
+        /// Fetch and print without storing articles
+        #[arg(long)]
+        dry_run: bool,
+        /// Feed name to archive the articles under (default: the channel title)
+        #[arg(long, conflicts_with = "dry_run")]
+        name: Option<String>,
+        /// Mark the printed articles as read in the archive
+        #[arg(long, conflicts_with = "dry_run")]
+        mark_read: bool,
+    },
+    /// Fetch every configured feed and store new articles
+    Refresh {
+        /// Path to config file (default: feeds.toml)
+        #[arg(short, long, default_value = "feeds.toml")]
+        config: PathBuf,
+        /// Print request, response and parse diagnostics to stderr
+        #[arg(short, long)]
+        verbose: bool,
+        /// Fetch without storing articles
+        #[arg(long)]
+        dry_run: bool,
+        /// Also fetch feeds whose `<ttl>`, `<skipHours>` or `<skipDays>` ask not to be fetched yet
+        #[arg(long)]
+        force: bool,
+        /// Print the newly stored items to stdout in this format instead of the progress report
+        /// (with --dry-run, every fetched item)
+        #[arg(long, value_enum)]
+        emit: Option<EmitFormat>,
+        /// Also save the raw feed responses and the config to this directory, for `replay`
+        #[arg(long)]
+        record: Option<PathBuf>,
+    },
+    /// Import a feed's older posts from its archive pages (`rel="prev-archive"`/`next` links, or
+    /// WordPress' `?paged=N`)
+    Backfill {
+        /// Name or URL of a configured feed
+        feed: String,
+        /// Path to config file (default: feeds.toml)
+        #[arg(short, long, default_value = "feeds.toml")]
+        config: PathBuf,
+        /// Maximum number of pages to fetch
+        #[arg(long, default_value_t = 20)]
+        max_pages: usize,
+    },
+    /// Show bytes downloaded per feed in a month and how much of each budget is used
+    Stats {
+        /// Path to config file (default: feeds.toml)
+        #[arg(short, long, default_value = "feeds.toml")]
+        config: PathBuf,
+        /// Month to report as YYYY-MM (default: the current month)
+        #[arg(long)]
+        month: Option<String>,
+    },
+    /// List stored articles by interest score, with how each score adds up
+    Scores {
+        /// Path to config file (default: feeds.toml)
+        #[arg(short, long, default_value = "feeds.toml")]
+        config: PathBuf,
+        /// Number of articles to list
+        #[arg(short, long, default_value_t = 20)]
+        limit: usize,
+        /// Only score articles from this feed
+        #[arg(long)]
+        feed: Option<String>,
+    },
+    /// List recently opened articles, grouped by the day they were read
+    History {
+        /// Number of articles to list
+        #[arg(short, long, default_value_t = 30)]
+        limit: usize,
+        /// Only list articles whose title or feed name contains this text
+        #[arg(short, long)]
+        search: Option<String>,
+    },
+    /// List recent `refresh` runs: when they ran, what each feed fetched, added or failed with,
+    /// and which feeds were skipped
+    Runs {
+        /// Number of runs to list
+        #[arg(short, long, default_value_t = 10)]
+        limit: usize,
+        /// Only show what the runs did with this feed, whatever the outcome
+        #[arg(long)]
+        feed: Option<String>,
+    },
+    /// Run feed responses saved by `refresh --record` through parsing and storing again, into a
+    /// fresh store inside the recording, without the network
+    Replay {
+        /// Directory the responses were recorded to
+        dir: PathBuf,
+        /// Config to replay with instead of the one saved in the recording
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        /// Print parse diagnostics to stderr
+        #[arg(short, long)]
+        verbose: bool,
+    },
+    /// Mute a noisy feed, or hide items matching a text, for a while
+    Snooze {
+        #[command(subcommand)]
+        command: SnoozeCommand,
+    },
+    /// Manage subscriptions in the config file
+    Feeds {
+        /// Path to config file (default: feeds.toml)
+        #[arg(short, long, default_value = "feeds.toml", global = true)]
+        config: PathBuf,
+        #[command(subcommand)]
+        command: FeedsCommand,
+    },
+    /// Inspect the article store
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+    /// Write the config and the article store (articles, images, read state and history) to a
+    /// compressed archive
+    Backup {
+        /// Archive to write (default: rss_reader-<time>.tar.zst in the current directory)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Path to config file (default: feeds.toml)
+        #[arg(short, long, default_value = "feeds.toml")]
+        config: PathBuf,
+    },
+    /// Replace the config and the article store with the ones in a backup
+    Restore {
+        /// Archive written by `backup`
+        file: PathBuf,
+        /// Path to config file (default: feeds.toml)
+        #[arg(short, long, default_value = "feeds.toml")]
+        config: PathBuf,
+        /// Replace stored articles that already exist
+        #[arg(long)]
+        force: bool,
+    },
+    /// Export stored articles
+    Export {
+        #[command(subcommand)]
+        command: ExportCommand,
     },
     /// Open the TUI reader with feeds from config file
     Ui {
@@ -50,6 +247,17 @@ enum Commands {
         #[arg(short, long, default_value = "feeds.toml")]
         config: PathBuf,
     },
+    /// Sync read state, stars and stored articles with a remote server instance
+    Sync {
+        /// Base URL of the remote server (e.g., http://nas.local:7878)
+        remote: String,
+        /// Only pull changes from the remote
+        #[arg(long, conflicts_with = "push_only")]
+        pull_only: bool,
+        /// Only push local changes to the remote
+        #[arg(long)]
+        push_only: bool,
+    },
     /// Run the web server and open a browser UI
     Server {
         /// Path to config file (default: feeds.toml)
@@ -61,104 +269,830 @@ enum Commands {
         /// Port to bind (default: 7878)
         #[arg(long, default_value_t = 7878)]
         port: u16,
+        /// Address to listen on instead of --host/--port: HOST:PORT, or unix:PATH for a Unix socket
+        #[arg(long, conflicts_with_all = ["host", "port"])]
+        bind: Option<server::Bind>,
+        /// Path prefix of every URL when served under a subpath by a reverse proxy, e.g. /rss
+        #[arg(long)]
+        base_path: Option<String>,
+        /// URL the server is reachable at from the internet, e.g. https://example.com/rss; used
+        /// for links in the republished feeds, and lets feeds with an rssCloud `<cloud>` push
+        /// their updates instead of being polled
+        #[arg(long)]
+        public_url: Option<url::Url>,
         /// Disable auto-opening the browser
         #[arg(long, action = ArgAction::SetFalse, default_value_t = true)]
         open: bool,
+        /// Allowed CORS origin for the API (repeatable, "*" allows any origin)
+        #[arg(long = "cors-origin")]
+        cors_origins: Vec<String>,
+        /// Maximum API requests per client IP per minute (0 disables)
+        #[arg(long, default_value_t = 120)]
+        rate_limit: u32,
+        /// Seconds before a request is aborted with 408
+        #[arg(long, default_value_t = 30)]
+        request_timeout: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum FeedsCommand {
+    /// Subscribe to an RSS feed; without --name the channel title is used
+    Add {
+        url: String,
+        #[arg(short, long)]
+        name: Option<String>,
+        /// Subscribe even if the URL matches an existing subscription
+        #[arg(long)]
+        force: bool,
+    },
+    /// Refresh auto-filled names, descriptions and site links from the feeds themselves
+    SyncTitles {
+        /// Also rename feeds whose names were set by hand
+        #[arg(long)]
+        all: bool,
+    },
+    /// Subscribe to the feeds of an OPML file, keeping its folders as (nested) categories
+    ImportOpml { file: PathBuf },
+    /// Write subscriptions as OPML, with nested categories as nested folders
+    ExportOpml {
+        /// File to write instead of standard output
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Fetch every feed, flag broken or stale ones, and offer to remove or pause them
+    Audit {
+        /// Flag feeds with no new items in this many months
+        #[arg(long, default_value_t = 6, value_parser = clap::value_parser!(u32).range(1..=1200))]
+        stale_months: u32,
+        /// Only print the report, without prompting
+        #[arg(long)]
+        report_only: bool,
     },
 }
 
+#[derive(Subcommand)]
+enum DbCommand {
+    /// Show the space stored articles take, by feed and by kind (markdown or images)
+    Du {
+        /// Path to config file (default: feeds.toml)
+        #[arg(short, long, default_value = "feeds.toml")]
+        config: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExportCommand {
+    /// Write starred articles as a static HTML site, indexed by date, feed and category, with
+    /// their images copied alongside
+    Site {
+        /// Directory to write the site to (created if missing)
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Path to config file (default: feeds.toml)
+        #[arg(short, long, default_value = "feeds.toml")]
+        config: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum SnoozeCommand {
+    /// Mute a feed, or hide the items whose title contains --matching (in every feed unless a
+    /// feed is given)
+    Add {
+        /// Name of the feed
+        #[arg(required_unless_present = "matching")]
+        feed: Option<String>,
+        /// Hide only items whose title contains this text
+        #[arg(short, long)]
+        matching: Option<String>,
+        /// How long, e.g. 12h, 7d or 4w
+        #[arg(long = "for", default_value = "7d", value_parser = parse_snooze_duration)]
+        duration: chrono::Duration,
+    },
+    /// List snoozes that haven't expired
+    List,
+    /// Lift a snooze before it expires
+    Remove {
+        /// Name of the feed
+        #[arg(required_unless_present = "matching")]
+        feed: Option<String>,
+        /// Text of the snoozed item filter
+        #[arg(short, long)]
+        matching: Option<String>,
+    },
+}
+
+/// Parses a snooze length such as `30m`, `12h`, `7d` or `4w`.
+fn parse_snooze_duration(raw: &str) -> Result<chrono::Duration, String> {
+    let raw = raw.trim();
+    let split = raw.len() - raw.chars().last().map_or(0, char::len_utf8);
+    let (number, unit) = raw.split_at(split);
+    let number: i64 = number
+        .parse()
+        .map_err(|_| format!("expected a number followed by m, h, d or w, got {:?}", raw))?;
+    match unit {
+        "m" => Ok(chrono::Duration::minutes(number)),
+        "h" => Ok(chrono::Duration::hours(number)),
+        "d" => Ok(chrono::Duration::days(number)),
+        "w" => Ok(chrono::Duration::weeks(number)),
+        _ => Err(format!("unknown unit {:?}, expected m, h, d or w", unit)),
+    }
+}
+
 #[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
-    let database = db::Database::initialize(&db::default_store_dir()).await?;
+async fn main() -> ExitCode {
+    let mut command = Cli::command();
+    command.build();
+    let cli = match Cli::from_arg_matches(&i18n::localize_command(command).get_matches()) {
+        Ok(cli) => cli,
+        Err(err) => err.exit(),
+    };
+    QUIET.store(cli.quiet, Ordering::Relaxed);
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {:?}", err);
+            ExitCode::from(exit_code(&err))
+        }
+    }
+}
+
+impl Commands {
+    /// The config file the command reads, or the default one for commands without a --config.
+    fn config_path(&self) -> PathBuf {
+        match self {
+            Commands::Refresh { config, .. }
+            | Commands::Backfill { config, .. }
+            | Commands::Stats { config, .. }
+            | Commands::Scores { config, .. }
+            | Commands::Feeds { config, .. }
+            | Commands::Backup { config, .. }
+            | Commands::Restore { config, .. }
+            | Commands::Ui { config, .. }
+            | Commands::Server { config, .. }
+            | Commands::Db {
+                command: DbCommand::Du { config },
+            }
+            | Commands::Export {
+                command: ExportCommand::Site { config, .. },
+            } => config.clone(),
+            _ => PathBuf::from("feeds.toml"),
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let store_dir = cli
+        .store_dir
+        .clone()
+        .or_else(|| {
+            config::Config::load(&cli.command.config_path())
+                .ok()?
+                .store_dir
+        })
+        .unwrap_or_else(db::default_store_dir);
+    let cipher = match (&cli.key_file, std::env::var(crypto::PASSPHRASE_ENV)) {
+        (Some(key_file), _) => Some(crypto::Cipher::from_key_file(key_file)?),
+        (None, Ok(passphrase)) if !passphrase.is_empty() => Some(crypto::Cipher::from_passphrase(
+            &passphrase,
+            &store_dir.join("key.salt"),
+        )?),
+        _ => None,
+    };
+    http_cache::set_enabled(cipher.is_none());
+    http_cache::set_dir(store_dir.join(http_cache::DIR_NAME));
+    http_cache::set_allow_private(cli.allow_private_addresses);
+    let database = db::Database::initialize(&store_dir, cipher).await?;
 
     match cli.command {
-        Commands::Read { url, limit, tui } => {
-            println!("Fetching RSS from: {}", url);
-            let channel = feed::fetch_channel(&url).await?;
-            let feed_name = if channel.title().is_empty() {
-                url.clone()
-            } else {
-                channel.title().to_string()
+        Commands::Read {
+            url,
+            limit,
+            tui,
+            verbose,
+            dry_run,
+            name,
+            mark_read,
+        } => {
+            feed::set_verbose(verbose);
+            let channel = match feed::local_feed_path(&url) {
+                Some(None) => {
+                    say!("Reading RSS from stdin");
+                    feed::read_local_channel(None).await?
+                }
+                Some(Some(path)) => {
+                    say!("Reading RSS from: {}", path.display());
+                    feed::read_local_channel(Some(&path)).await?
+                }
+                None => {
+                    say!("Fetching RSS from: {}", url);
+                    feed::fetch_channel(&fetcher::HttpFetcher, &url).await?
+                }
             };
-            process_channel(channel, limit, tui, Some(&database), &feed_name, &url).await?;
+            let feed_name = name.unwrap_or_else(|| {
+                if channel.title().is_empty() {
+                    url.clone()
+                } else {
+                    channel.title().to_string()
+                }
+            });
+            let feed = config::FeedItem::new(&feed_name, &url).to_feed(None);
+            let db = (!dry_run).then_some(&database);
+            process_channel(channel, limit, tui, db, &feed, mark_read).await?;
         }
         Commands::Rsshub {
             route,
             host,
             limit,
             tui,
+            verbose,
+            dry_run,
+            name,
+            mark_read,
         } => {
+            feed::set_verbose(verbose);
             let url_str = feed::build_rsshub_url(&host, &route)?;
-            println!("Fetching RSSHub route: {} (full URL: {})", route, url_str);
-            let channel = feed::fetch_channel(&url_str).await?;
-            let feed_name = if channel.title().is_empty() {
-                route.clone()
-            } else {
-                channel.title().to_string()
+            say!("Fetching RSSHub route: {} (full URL: {})", route, url_str);
+            let channel = feed::fetch_channel(&fetcher::HttpFetcher, &url_str).await?;
+            let feed_name = name.unwrap_or_else(|| {
+                if channel.title().is_empty() {
+                    route.clone()
+                } else {
+                    channel.title().to_string()
+                }
+            });
+            let feed = config::FeedItem::new(&feed_name, &url_str).to_feed(None);
+            let db = (!dry_run).then_some(&database);
+            process_channel(channel, limit, tui, db, &feed, mark_read).await?;
+        }
+        Commands::Refresh {
+            config,
+            verbose,
+            dry_run,
+            force,
+            emit,
+            record,
+        } => {
+            feed::set_verbose(verbose);
+            if emit.is_some() {
+                QUIET.store(true, Ordering::Relaxed);
+            }
+            let cfg = config::load_or_create_config(&config)?;
+            config::install_plugins(&cfg)?;
+            let db = (!dry_run).then_some(&database);
+            match record {
+                Some(dir) => {
+                    let recorder = replay::RecordingFetcher::new(&fetcher::HttpFetcher, &dir)?;
+                    replay::save_recording_config(&dir, &cfg)?;
+                    refresh_feeds(&recorder, &cfg, db, emit, force).await?;
+                }
+                None => refresh_feeds(&fetcher::HttpFetcher, &cfg, db, emit, force).await?,
+            }
+        }
+        Commands::Replay {
+            dir,
+            config,
+            verbose,
+        } => {
+            feed::set_verbose(verbose);
+            let config = config.unwrap_or_else(|| replay::recording_config_path(&dir));
+            anyhow::ensure!(config.exists(), "No config file at {:?}", config);
+            let cfg = config::load_or_create_config(&config)?;
+            config::install_plugins(&cfg)?;
+            replay::replay(&dir, &cfg).await?;
+        }
+        Commands::Backfill {
+            feed,
+            config,
+            max_pages,
+        } => {
+            let cfg = config::load_or_create_config(&config)?;
+            config::install_plugins(&cfg)?;
+            let Some(feed) = cfg
+                .get_all_feeds()
+                .into_iter()
+                .find(|candidate| candidate.name == feed || candidate.url == feed)
+            else {
+                anyhow::bail!("No feed named {} in {:?}", feed, config);
             };
-            process_channel(channel, limit, tui, Some(&database), &feed_name, &url_str).await?;
+            let added = backfill::backfill(&feed, &database, max_pages).await?;
+            say!("Added {} articles to {}", added, feed.name);
+        }
+        Commands::Stats { config, month } => {
+            let cfg = config::load_or_create_config(&config)?;
+            let month = month.unwrap_or_else(db::current_month);
+            print_bandwidth(&cfg, &database.bandwidth(&month)?, &month);
+            print_update_hints(&cfg, &database.update_hints()?);
         }
+        Commands::Scores {
+            config,
+            limit,
+            feed,
+        } => {
+            let cfg = config::load_or_create_config(&config)?;
+            let query = db::ArticleQuery {
+                feed_name: feed,
+                ..db::ArticleQuery::default()
+            };
+            let scorer = scoring::Scorer::new(&cfg, &database)?;
+            let ranked = scorer.rank(database.query_articles(&query)?);
+            print_scores(&ranked[..limit.min(ranked.len())]);
+        }
+        Commands::History { limit, search } => {
+            let search = search.map(|text| text.to_lowercase());
+            let history: Vec<_> = database
+                .reading_history()?
+                .into_iter()
+                .filter(|(_, record)| {
+                    search.as_ref().is_none_or(|text| {
+                        record.title.to_lowercase().contains(text)
+                            || record.feed_name.to_lowercase().contains(text)
+                    })
+                })
+                .take(limit)
+                .collect();
+            print_history(&history);
+        }
+        Commands::Runs { limit, feed } => {
+            let runs = database.runs()?;
+            print_runs(&runs[..limit.min(runs.len())], feed.as_deref());
+        }
+        Commands::Snooze { command } => match command {
+            SnoozeCommand::Add {
+                feed,
+                matching,
+                duration,
+            } => {
+                let until = chrono::Utc::now() + duration;
+                database.snooze(db::Snooze {
+                    feed_name: feed,
+                    pattern: matching,
+                    until: until.to_rfc3339(),
+                })?;
+                say!(
+                    "Snoozed until {}",
+                    dates::absolute(&until.with_timezone(&chrono::Local))
+                );
+            }
+            SnoozeCommand::List => print_snoozes(&database.snoozes()?),
+            SnoozeCommand::Remove { feed, matching } => {
+                if !database.unsnooze(feed.as_deref(), matching.as_deref())? {
+                    anyhow::bail!("No such snooze; `snooze list` shows the active ones");
+                }
+                say!("Snooze lifted");
+            }
+        },
+        Commands::Feeds { config, command } => {
+            let mut cfg = config::load_or_create_config(&config)?;
+            config::install_plugins(&cfg)?;
+            match command {
+                FeedsCommand::Add { url, name, force } => {
+                    if let Some(existing) = cfg.find_duplicate(&url).filter(|_| !force) {
+                        anyhow::bail!(
+                            "Already subscribed to {} as \"{}\" ({}); use --force to add it anyway",
+                            url,
+                            existing.name,
+                            existing.url
+                        );
+                    }
+                    let channel = feed::fetch_channel(&fetcher::HttpFetcher, &url).await?;
+                    let mut item = config::FeedItem::from_channel(&url, &channel);
+                    if let Some(name) = name {
+                        item.name = name;
+                        item.auto_name = false;
+                    }
+                    say!("Subscribed to {} ({})", item.name, url);
+                    cfg.add_feed_item(item);
+                }
+                FeedsCommand::SyncTitles { all } => sync_titles(&mut cfg, all).await,
+                FeedsCommand::ImportOpml { file } => {
+                    let (added, existing) = opml::import(&mut cfg, &file)?;
+                    say!("Imported {} feeds ({} already subscribed)", added, existing);
+                }
+                FeedsCommand::ExportOpml { output } => {
+                    let document = opml::export(&mut cfg)?;
+                    match output {
+                        Some(path) => {
+                            std::fs::write(&path, document)?;
+                            say!("Wrote {:?}", path);
+                        }
+                        None => print!("{}", document),
+                    }
+                    return Ok(());
+                }
+                FeedsCommand::Audit {
+                    stale_months,
+                    report_only,
+                } => {
+                    let options = audit::AuditOptions {
+                        stale_months,
+                        interactive: !report_only,
+                    };
+                    if !audit::audit_feeds(&mut cfg, &options).await? {
+                        return Ok(());
+                    }
+                }
+            }
+            cfg.save(&config)?;
+        }
+        Commands::Db { command } => match command {
+            DbCommand::Du { config } => {
+                let cfg = config::load_or_create_config(&config)?;
+                print_disk_usage(&database.disk_usage()?, cfg.max_store_mb);
+            }
+        },
+        Commands::Backup { output, config } => {
+            let output = output.unwrap_or_else(|| PathBuf::from(backup::default_file_name()));
+            let bytes = backup::create(&database, &config, &output)?;
+            say!("Backed up to {:?} ({})", output, format_bytes(bytes));
+        }
+        Commands::Restore {
+            file,
+            config,
+            force,
+        } => {
+            let stored = database.list_articles()?.len();
+            if stored > 0 && !force {
+                anyhow::bail!(
+                    "The store already holds {} articles; use --force to replace them with the backup",
+                    stored
+                );
+            }
+            backup::restore(&file, &config, &store_dir)?;
+            say!("Restored {:?}", file);
+        }
+        Commands::Export { command } => match command {
+            ExportCommand::Site { output, config } => {
+                let cfg = config::load_or_create_config(&config)?;
+                let export = site::export(&cfg, &database, &output)?;
+                say!(
+                    "Exported {} starred articles and {} images to {:?}",
+                    export.articles,
+                    export.images,
+                    output
+                );
+            }
+        },
         Commands::Ui { config } => {
             let cfg = config::load_or_create_config(&config)?;
-            tui::run_tui(tui::App::with_config_and_db(cfg, Some(database.clone()))).await?;
+            config::install_plugins(&cfg)?;
+            let mut app = tui::App::with_config_and_db(cfg, config, Some(database.clone()));
+            app.start_prefetch();
+            tui::run_tui(app).await?;
+        }
+        Commands::Sync {
+            remote,
+            pull_only,
+            push_only,
+        } => {
+            let options = sync::SyncOptions {
+                pull: !push_only,
+                push: !pull_only,
+            };
+            let report = sync::sync_with_remote(&database, &remote, &options).await?;
+            say!(
+                "Synced with {}: {} state entries merged, {} articles pulled, {} pushed, {} images transferred",
+                remote,
+                report.states_merged,
+                report.articles_pulled,
+                report.articles_pushed,
+                report.images_transferred
+            );
         }
         Commands::Server {
             config,
             host,
             port,
+            bind,
+            base_path,
+            public_url,
             open,
+            cors_origins,
+            rate_limit,
+            request_timeout,
         } => {
             let cfg = config::load_or_create_config(&config)?;
-            server::run_server(cfg, host, port, open, database.clone()).await?;
+            config::install_plugins(&cfg)?;
+            let options = server::ServerOptions {
+                config_path: config,
+                host,
+                port,
+                bind,
+                base_path,
+                public_url,
+                open_browser: open,
+                cors_origins,
+                rate_limit_per_minute: rate_limit,
+                request_timeout: std::time::Duration::from_secs(request_timeout),
+            };
+            server::run_server(cfg, options, database.clone()).await?;
         }
     }
 
     Ok(())
 }
 
+/// Shows a one-off channel in the TUI or prints it, archiving it under `feed`'s name when `db`
+/// is set. With `mark_read`, the printed items are marked read.
 async fn process_channel(
     channel: Channel,
     limit: usize,
     use_tui: bool,
     db: Option<&db::Database>,
-    feed_name: &str,
-    feed_url: &str,
+    feed: &config::Feed,
+    mark_read: bool,
 ) -> Result<()> {
     if use_tui {
         let app = tui::App::with_channel_and_db(
             channel,
             db.cloned(),
-            Some(feed_name.to_string()),
-            Some(feed_url.to_string()),
-        );
+            Some(feed.name.clone()),
+            Some(feed.url.clone()),
+        )
+        .with_item_limit(Some(limit));
         tui::run_tui(app).await?;
     } else {
         if let Some(database) = db {
             database
-                .store_channel(feed_name, feed_url, &channel)
+                .store_channel(&feed.name, &feed.url, &channel, feed.store_options())
                 .await?;
+            if mark_read {
+                for item in channel.items().iter().take(limit) {
+                    database.mark_read(&db::item_id(&feed.name, &feed.url, item))?;
+                }
+            }
         }
         print_channel(&channel, limit);
     }
     Ok(())
 }
 
+async fn sync_titles(cfg: &mut config::Config, rename_all: bool) {
+    for (item, feed) in cfg.subscriptions_mut() {
+        match feed::fetch_configured_feed(&fetcher::HttpFetcher, &feed).await {
+            Ok(channel) => {
+                let old_name = item.name.clone();
+                if item.update_from_channel(&channel, rename_all) {
+                    if old_name != item.name {
+                        say!("{} -> {}", old_name, item.name);
+                    } else {
+                        say!("{}: metadata updated", item.name);
+                    }
+                }
+            }
+            Err(err) => eprintln!("{}: {:#}", item.name, err),
+        }
+    }
+}
+
+fn print_bandwidth(cfg: &config::Config, records: &[db::BandwidthRecord], month: &str) {
+    if records.is_empty() {
+        say!("Nothing downloaded in {}", month);
+        return;
+    }
+    let feeds = cfg.get_all_feeds();
+    say!("Bandwidth used in {}:", month);
+    for record in records {
+        let budget = feeds
+            .iter()
+            .find(|feed| feed.name == record.feed_name)
+            .and_then(config::Feed::monthly_budget_bytes);
+        let usage = match budget {
+            Some(budget) if record.total() >= budget => format!(
+                " of {} budget, images no longer localized",
+                format_bytes(budget)
+            ),
+            Some(budget) => format!(" of {} budget", format_bytes(budget)),
+            None => String::new(),
+        };
+        say!(
+            "  {}: {} (feed {}, images {}){}",
+            record.feed_name,
+            format_bytes(record.total()),
+            format_bytes(record.feed_bytes),
+            format_bytes(record.image_bytes),
+            usage
+        );
+    }
+}
+
+fn print_disk_usage(usage: &db::DiskUsage, max_store_mb: Option<u64>) {
+    let quota = match max_store_mb {
+        Some(mb) => format!(" of {} quota", format_bytes(mb * 1024 * 1024)),
+        None => String::new(),
+    };
+    say!(
+        "Article store: {}{} (markdown {}, images {}, index and logs {}, HTTP cache {})",
+        format_bytes(usage.total()),
+        quota,
+        format_bytes(usage.markdown_bytes()),
+        format_bytes(usage.image_bytes()),
+        format_bytes(usage.metadata_bytes),
+        format_bytes(usage.http_cache_bytes)
+    );
+    for feed in &usage.feeds {
+        say!(
+            "  {}: {} in {} articles (markdown {}, images {})",
+            feed.feed_name,
+            format_bytes(feed.total()),
+            feed.articles,
+            format_bytes(feed.markdown_bytes),
+            format_bytes(feed.image_bytes)
+        );
+    }
+    if usage.shared_image_bytes > 0 {
+        say!(
+            "  Images shown by several feeds: {}",
+            format_bytes(usage.shared_image_bytes)
+        );
+    }
+    if usage.unreferenced_image_bytes > 0 {
+        say!(
+            "  Images no article shows: {}",
+            format_bytes(usage.unreferenced_image_bytes)
+        );
+    }
+}
+
+/// The update cadence configured feeds declared when last fetched, and when they are due again.
+fn print_update_hints(
+    cfg: &config::Config,
+    hints: &HashMap<String, (chrono::DateTime<chrono::Utc>, feed::UpdateHints)>,
+) {
+    let now = chrono::Utc::now();
+    let declared: Vec<_> = cfg
+        .get_all_feeds()
+        .into_iter()
+        .filter_map(|feed| {
+            let (fetched_at, hints) = hints.get(&feed.name)?;
+            (!hints.is_empty()).then_some((feed.name, *fetched_at, hints))
+        })
+        .collect();
+    if declared.is_empty() {
+        return;
+    }
+    say!("Update cadence declared by feeds:");
+    for (name, fetched_at, hints) in declared {
+        let due = match hints.next_fetch(fetched_at, now) {
+            Some(next_fetch) => format!(
+                "next fetch {}",
+                dates::absolute(&next_fetch.with_timezone(&chrono::Local))
+            ),
+            None => "due now".to_string(),
+        };
+        say!("  {}: {} ({})", name, hints, due);
+    }
+}
+
+fn print_scores(ranked: &[(scoring::Score, db::ArticleRecord)]) {
+    if ranked.is_empty() {
+        say!("No stored articles");
+        return;
+    }
+    say!(
+        "{:>7} {:>7} {:>8} {:>7}  Article",
+        "Score",
+        "Feed",
+        "Keywords",
+        "History"
+    );
+    for (score, record) in ranked {
+        say!(
+            "{:>7.2} {:>7.2} {:>8.2} {:>7.2}  {} ({})",
+            score.total,
+            score.feed,
+            score.keywords,
+            score.history,
+            record.title,
+            record.feed_name
+        );
+    }
+}
+
+fn print_history(history: &[(String, db::ArticleRecord)]) {
+    if history.is_empty() {
+        say!("No articles read yet");
+        return;
+    }
+    let mut day = None;
+    for (opened_at, record) in history {
+        let opened = dates::parse(opened_at);
+        let opened_day = opened.map(|date| date.date_naive());
+        if day != Some(opened_day) {
+            day = Some(opened_day);
+            match opened {
+                Some(date) => say!("\n{}", date.format("%A, %Y-%m-%d")),
+                None => say!("\n{}", opened_at),
+            }
+        }
+        say!(
+            "  {}  {} ({})",
+            opened.map_or_else(String::new, |date| date.format("%H:%M").to_string()),
+            record.title,
+            record.feed_name
+        );
+        if let Some(link) = &record.link {
+            say!("         {}", link);
+        }
+    }
+}
+
+/// Each run with its counts, followed by the feeds that added items, failed or were skipped, or
+/// with `feed`, by that feed whatever happened to it.
+fn print_runs(runs: &[db::Run], feed: Option<&str>) {
+    if runs.is_empty() {
+        say!("No refresh runs recorded yet");
+        return;
+    }
+    for run in runs {
+        let finished = match &run.finished_at {
+            Some(finished_at) => dates::display(finished_at, false),
+            None => "unfinished".to_string(),
+        };
+        say!(
+            "\n{} – {}: {} feeds, {} failed, {} skipped, {} new items",
+            dates::display(&run.started_at, false),
+            finished,
+            run.feeds.len(),
+            run.count(db::RunOutcome::Failed),
+            run.count(db::RunOutcome::Skipped),
+            run.added()
+        );
+        for result in &run.feeds {
+            let shown = match feed {
+                Some(feed) => result.feed_name == feed,
+                None => result.added > 0 || result.outcome != db::RunOutcome::Fetched,
+            };
+            if !shown {
+                continue;
+            }
+            match result.outcome {
+                db::RunOutcome::Fetched => say!(
+                    "  {}: {} new of {} items",
+                    result.feed_name,
+                    result.added,
+                    result.items
+                ),
+                db::RunOutcome::Failed => say!(
+                    "  {}: failed: {}",
+                    result.feed_name,
+                    result.message.as_deref().unwrap_or_default()
+                ),
+                db::RunOutcome::Skipped => say!(
+                    "  {}: skipped, {}",
+                    result.feed_name,
+                    result.message.as_deref().unwrap_or_default()
+                ),
+            }
+        }
+    }
+}
+
+fn print_snoozes(snoozes: &[db::Snooze]) {
+    if snoozes.is_empty() {
+        say!("Nothing is snoozed");
+        return;
+    }
+    for snooze in snoozes {
+        let until = dates::display(&snooze.until, false);
+        match (&snooze.feed_name, &snooze.pattern) {
+            (Some(feed), None) => say!("{} muted until {}", feed, until),
+            (Some(feed), Some(pattern)) => {
+                say!(
+                    "{} items matching {:?} hidden until {}",
+                    feed,
+                    pattern,
+                    until
+                )
+            }
+            (None, Some(pattern)) => say!("Items matching {:?} hidden until {}", pattern, until),
+            (None, None) => say!("All feeds muted until {}", until),
+        }
+    }
+}
+
 fn print_channel(channel: &Channel, limit: usize) {
-    println!("\nTitle: {}", channel.title());
+    say!("\nTitle: {}", channel.title());
     if !channel.description().is_empty() {
-        println!("Description: {}", channel.description());
+        say!("Description: {}", channel.description());
     }
-    println!("----------------------------------------");
+    say!("----------------------------------------");
 
     for (i, item) in channel.items().iter().take(limit).enumerate() {
-        println!("{}. {}", i + 1, item.title().unwrap_or("No Title"));
+        say!("{}. {}", i + 1, item.title().unwrap_or("No Title"));
         if let Some(link) = item.link() {
-            println!("   Link: {}", link);
+            say!("   Link: {}", link);
+        }
+        if let Some(author) = item.author() {
+            say!("   Author: {}", author);
         }
         if let Some(pub_date) = item.pub_date() {
-            println!("   Date: {}", pub_date);
+            say!("   Date: {}", dates::display_full(pub_date));
+        }
+        if let Some(updated) = db::item_updated(item) {
+            say!("   Updated: {}", dates::display_full(&updated));
         }
-        println!();
+        say!();
     }
 }
@@ -0,0 +1,116 @@
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+use scraper::Html;
+
+/// Code and preformatted text, where math delimiters are left alone.
+static CODE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<(pre|code)\b[^>]*>.*?</(?:pre|code)\s*>").unwrap());
+/// MathJax's script form, `<script type="math/tex">` (with `; mode=display` for display math).
+static SCRIPT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)<script\b[^>]*type\s*=\s*["']math/tex(; *mode=display)?["'][^>]*>(.*?)</script\s*>"#)
+        .unwrap()
+});
+/// MathML carrying its TeX source, as written by KaTeX, MathJax and pandoc.
+static MATHML: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?is)<math\b([^>]*)>.*?<annotation\b[^>]*encoding\s*=\s*["']application/x-tex["'][^>]*>(.*?)</annotation>.*?</math\s*>"#,
+    )
+    .unwrap()
+});
+/// TeX delimiters in text: `\[…\]` and `$$…$$` for display math, `\(…\)` inline. Single `$` is
+/// left alone, as MathJax does by default, since it is mostly prices.
+static DELIMITED: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)\\\[(.+?)\\\]|\$\$(.+?)\$\$|\\\((.+?)\\\)").unwrap());
+static PLACEHOLDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new("\u{e001}math-([0-9]+)\u{e001}").unwrap());
+
+/// TeX found in an article.
+pub struct Math {
+    tex: String,
+    display: bool,
+}
+
+/// Takes the equations out of article HTML before it is converted to markdown, which would escape
+/// their TeX. Each is replaced by a placeholder that `restore` later turns into markdown math.
+pub fn extract_html(html: &str) -> (String, Vec<Math>) {
+    let mut found = Vec::new();
+    let mut extracted = String::new();
+    let mut last = 0;
+    for code in CODE.find_iter(html) {
+        extracted.push_str(&extract_text(&html[last..code.start()], &mut found));
+        extracted.push_str(code.as_str());
+        last = code.end();
+    }
+    extracted.push_str(&extract_text(&html[last..], &mut found));
+    (extracted, found)
+}
+
+fn extract_text(html: &str, found: &mut Vec<Math>) -> String {
+    let mut placeholder = |tex: &str, display: bool| {
+        let tex = Html::parse_fragment(tex)
+            .root_element()
+            .text()
+            .collect::<String>()
+            .trim()
+            .to_string();
+        let index = found.len();
+        found.push(Math { tex, display });
+        let placeholder = format!("\u{e001}math-{}\u{e001}", index);
+        if display {
+            format!("<p>{}</p>", placeholder)
+        } else {
+            placeholder
+        }
+    };
+    let html = SCRIPT.replace_all(html, |caps: &Captures| {
+        placeholder(&caps[2], caps.get(1).is_some())
+    });
+    let html = MATHML.replace_all(&html, |caps: &Captures| {
+        let display = caps[1].contains("display=\"block\"") || caps[1].contains("display='block'");
+        placeholder(&caps[2], display)
+    });
+    let html = DELIMITED.replace_all(&html, |caps: &Captures| match caps.get(3) {
+        Some(inline) => placeholder(inline.as_str(), false),
+        None => placeholder(caps.get(1).or_else(|| caps.get(2)).unwrap().as_str(), true),
+    });
+    html.into_owned()
+}
+
+/// Replaces the placeholders left by `extract_html` in the converted markdown: display math
+/// standing on its own line becomes a ```` ```math ```` block, anything else `` $`…`$ ``.
+pub fn restore(markdown: &str, found: &[Math]) -> String {
+    if found.is_empty() {
+        return markdown.to_string();
+    }
+    let lines: Vec<String> = markdown
+        .lines()
+        .map(|line| {
+            let block = PLACEHOLDER
+                .captures(line.trim())
+                .filter(|caps| caps[0].len() == line.trim().len())
+                .and_then(|caps| found.get(caps[1].parse::<usize>().ok()?))
+                .filter(|math| math.display);
+            if let Some(math) = block {
+                return format!("```math\n{}\n```", math.tex);
+            }
+            PLACEHOLDER
+                .replace_all(line, |caps: &Captures| {
+                    match caps[1]
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|index| found.get(index))
+                    {
+                        Some(math) => format!("$`{}`$", math.tex.replace('\n', " ")),
+                        None => String::new(),
+                    }
+                })
+                .into_owned()
+        })
+        .collect();
+    let mut restored = lines.join("\n");
+    if markdown.ends_with('\n') {
+        restored.push('\n');
+    }
+    restored
+}
@@ -0,0 +1,290 @@
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use anyhow::{anyhow, Context, Result};
+use regex::{Captures, Regex};
+use rss::{Enclosure, Item};
+use tokio_util::sync::CancellationToken;
+use url::Url;
+
+use crate::db::escape_html;
+use crate::http_cache;
+use crate::hygiene::attr;
+
+/// Extensions of files linked from article bodies that are listed as attachments.
+const MEDIA_EXTENSIONS: &[&str] = &[
+    "mp3", "m4a", "aac", "ogg", "opus", "wav", "flac", "mp4", "m4v", "mov", "webm", "mkv", "pdf",
+    "epub", "zip",
+];
+
+/// An `<audio>` player, with or without a closing tag, and its attribute text and content.
+static AUDIO: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<audio\b([^>]*?)(?:/>|>(.*?)</audio\s*>)").unwrap());
+static SOURCE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?is)<source\b([^>]*)>").unwrap());
+
+/// A file attached to an item: its enclosure, `media:content` entries, or media files linked
+/// from the body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attachment {
+    pub url: String,
+    pub mime_type: Option<String>,
+    pub length: Option<u64>,
+}
+
+impl Attachment {
+    /// Last segment of the URL path, used as the name of the downloaded file.
+    pub fn file_name(&self) -> String {
+        Url::parse(&self.url)
+            .ok()
+            .and_then(|url| {
+                url.path_segments()?
+                    .rev()
+                    .find(|segment| !segment.is_empty())
+                    .map(str::to_string)
+            })
+            .unwrap_or_else(|| "attachment".to_string())
+    }
+
+    /// File name followed by the type and size, when the feed gives them.
+    pub fn describe(&self) -> String {
+        let details: Vec<String> = self
+            .mime_type
+            .clone()
+            .into_iter()
+            .chain(
+                self.length
+                    .filter(|length| *length > 0)
+                    .map(crate::format_bytes),
+            )
+            .collect();
+        if details.is_empty() {
+            self.file_name()
+        } else {
+            format!("{} ({})", self.file_name(), details.join(", "))
+        }
+    }
+}
+
+pub fn item_attachments(item: &Item) -> Vec<Attachment> {
+    let mut attachments = Vec::new();
+    let mut push = |attachment: Attachment| {
+        if !attachments
+            .iter()
+            .any(|existing: &Attachment| existing.url == attachment.url)
+        {
+            attachments.push(attachment);
+        }
+    };
+
+    if let Some(enclosure) = item.enclosure() {
+        push(Attachment {
+            url: enclosure.url().to_string(),
+            mime_type: Some(enclosure.mime_type().to_string()).filter(|kind| !kind.is_empty()),
+            length: enclosure.length().parse().ok(),
+        });
+    }
+
+    let media = item.extensions().get("media");
+    let contents = media
+        .and_then(|media| media.get("content"))
+        .into_iter()
+        .flatten()
+        .chain(
+            media
+                .and_then(|media| media.get("group"))
+                .into_iter()
+                .flatten()
+                .filter_map(|group| group.children().get("content"))
+                .flatten(),
+        );
+    for content in contents {
+        if let Some(url) = content.attrs().get("url") {
+            push(Attachment {
+                url: url.clone(),
+                mime_type: content.attrs().get("type").cloned(),
+                length: content
+                    .attrs()
+                    .get("fileSize")
+                    .and_then(|size| size.parse().ok()),
+            });
+        }
+    }
+
+    let body = item.content().or(item.description()).unwrap_or_default();
+    for audio in embedded_audio(body) {
+        push(audio.attachment);
+    }
+
+    let href = Regex::new(r#"(?i)href\s*=\s*["']([^"']+)["']"#).unwrap();
+    for caps in href.captures_iter(body) {
+        if is_media_link(&caps[1]) {
+            push(Attachment {
+                url: caps[1].to_string(),
+                mime_type: None,
+                length: None,
+            });
+        }
+    }
+
+    attachments
+}
+
+/// An audio file played by an `<audio>` element in an article body.
+pub struct EmbeddedAudio {
+    pub attachment: Attachment,
+    /// Length in seconds, from a `data-duration` or `duration` attribute.
+    pub duration: Option<u64>,
+}
+
+impl EmbeddedAudio {
+    /// Text of the link an `<audio>` element is stored as: `Audio: episode.mp3 (1:02:03)`.
+    pub fn label(&self) -> String {
+        match self.duration {
+            Some(duration) => format!(
+                "Audio: {} ({})",
+                self.attachment.file_name(),
+                format_duration(duration)
+            ),
+            None => format!("Audio: {}", self.attachment.file_name()),
+        }
+    }
+}
+
+/// The audio files of the `<audio>` elements in `html`: each element's `src`, or its first
+/// `<source>` when it has none.
+pub fn embedded_audio(html: &str) -> Vec<EmbeddedAudio> {
+    AUDIO
+        .captures_iter(html)
+        .filter_map(|caps| audio(&caps))
+        .collect()
+}
+
+fn audio(caps: &Captures) -> Option<EmbeddedAudio> {
+    let attrs = &caps[1];
+    let (url, mime_type) = match attr(attrs, "src").filter(|src| !src.trim().is_empty()) {
+        Some(src) => (src, attr(attrs, "type")),
+        None => SOURCE
+            .captures_iter(caps.get(2)?.as_str())
+            .find_map(|source| Some((attr(&source[1], "src")?, attr(&source[1], "type"))))?,
+    };
+    let duration = attr(attrs, "data-duration")
+        .or_else(|| attr(attrs, "duration"))
+        .and_then(|duration| parse_duration(&duration));
+    Some(EmbeddedAudio {
+        attachment: Attachment {
+            url: url.trim().to_string(),
+            mime_type,
+            length: None,
+        },
+        duration,
+    })
+}
+
+/// Replaces each `<audio>` element of article HTML with a paragraph linking to its file, since
+/// the markdown it is converted to can't hold a player.
+pub fn convert_audio_html(html: &str) -> String {
+    AUDIO
+        .replace_all(html, |caps: &Captures| match audio(caps) {
+            Some(audio) => format!(
+                r#"<p><a href="{}">{}</a></p>"#,
+                audio.attachment.url.replace('"', "&quot;"),
+                escape_html(&audio.label())
+            ),
+            None => String::new(),
+        })
+        .into_owned()
+}
+
+/// Makes the first embedded audio file of an item without an enclosure its enclosure, so
+/// podcasts that only carry a player in the body are downloaded like any other.
+pub fn enclose_embedded_audio(item: &mut Item) {
+    if item.enclosure().is_some() {
+        return;
+    }
+    let body = item.content().or(item.description()).unwrap_or_default();
+    let Some(audio) = embedded_audio(body).into_iter().next() else {
+        return;
+    };
+    let mut enclosure = Enclosure::default();
+    enclosure.set_url(audio.attachment.url);
+    enclosure.set_length("0");
+    enclosure.set_mime_type(audio.attachment.mime_type.unwrap_or_default());
+    item.set_enclosure(enclosure);
+}
+
+/// Seconds, as `3723` or `1:02:03` / `62:03`.
+fn parse_duration(text: &str) -> Option<u64> {
+    let text = text.trim();
+    if let Ok(seconds) = text.parse::<f64>() {
+        return (seconds >= 0.0).then_some(seconds.round() as u64);
+    }
+    let parts: Vec<u64> = text
+        .split(':')
+        .map(|part| part.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    (parts.len() <= 3).then(|| parts.iter().fold(0, |total, part| total * 60 + part))
+}
+
+fn format_duration(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+fn is_media_link(link: &str) -> bool {
+    let Ok(url) = Url::parse(link) else {
+        return false;
+    };
+    let path = url.path().to_lowercase();
+    path.rsplit_once('.')
+        .is_some_and(|(_, extension)| MEDIA_EXTENSIONS.contains(&extension))
+}
+
+/// Where attachments saved from the TUI are written.
+pub fn download_dir() -> PathBuf {
+    Path::new("data/downloads").to_path_buf()
+}
+
+/// Largest attachment saved, well above a long podcast episode or video.
+const MAX_DOWNLOAD_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+
+/// Downloads the attachment into `dir`, numbering the file name instead of overwriting an
+/// existing file. The body is streamed to a temporary file that is renamed once complete, and
+/// removed if the download fails or `cancel` fires. Returns the path written.
+pub async fn download(
+    attachment: &Attachment,
+    dir: &Path,
+    cancel: &CancellationToken,
+) -> Result<PathBuf> {
+    tokio::fs::create_dir_all(dir)
+        .await
+        .context(format!("Failed to create {:?}", dir))?;
+    let name = attachment.file_name();
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) => (stem.to_string(), format!(".{}", extension)),
+        None => (name.clone(), String::new()),
+    };
+    let mut path = dir.join(&name);
+    let mut copy = 1;
+    while tokio::fs::try_exists(&path).await.unwrap_or(false) {
+        path = dir.join(format!("{}-{}{}", stem, copy, extension));
+        copy += 1;
+    }
+
+    let partial = dir.join(format!(".{}.partial", name));
+    let downloaded = tokio::select! {
+        result = http_cache::download_untrusted(&attachment.url, &partial, MAX_DOWNLOAD_BYTES) => result,
+        _ = cancel.cancelled() => Err(anyhow!("Cancelled downloading {}", attachment.url)),
+    };
+    if let Err(err) = downloaded {
+        let _ = tokio::fs::remove_file(&partial).await;
+        return Err(err);
+    }
+    tokio::fs::rename(&partial, &path)
+        .await
+        .context(format!("Failed to write {:?}", path))?;
+    Ok(path)
+}
@@ -0,0 +1,151 @@
+use regex::Regex;
+use rss::extension::Extension;
+use rss::{Channel, Item};
+use scraper::Html;
+
+use crate::db::escape_html;
+
+/// Longest title derived from a post body.
+const MAX_DERIVED_TITLE_CHARS: usize = 80;
+
+/// Makes Mastodon and Nitter style feeds readable: untitled posts get a title from their text,
+/// `media:content` attachments are appended to the body, and Mastodon content warnings are
+/// wrapped in a `<details>` section.
+pub fn normalize_channel(channel: &mut Channel) {
+    for item in channel.items_mut() {
+        normalize_item(item);
+    }
+}
+
+fn normalize_item(item: &mut Item) {
+    let mut body = item
+        .content()
+        .or(item.description())
+        .unwrap_or_default()
+        .to_string();
+    let media = media_html(item);
+    if media.is_empty() && item.title().is_some() {
+        return;
+    }
+
+    let warning = if is_mastodon_status(item) {
+        split_content_warning(&body)
+    } else {
+        None
+    };
+    if item.title().is_none() {
+        let title = match &warning {
+            Some((warning, _)) => warning.clone(),
+            None => derive_title(&body),
+        };
+        item.set_title(title);
+    }
+    body = match warning {
+        Some((warning, rest)) => format!(
+            "<details><summary>{}</summary>{}{}</details>",
+            escape_html(&warning),
+            rest,
+            media
+        ),
+        None => format!("{}{}", body, media),
+    };
+
+    if item.content().is_some() {
+        item.set_content(body);
+    } else {
+        item.set_description(body);
+    }
+}
+
+fn is_mastodon_status(item: &Item) -> bool {
+    let re = Regex::new(r"/@[^/]+/\d+$").unwrap();
+    item.link().map(|link| re.is_match(link)).unwrap_or(false)
+}
+
+/// Mastodon prefixes posts that carry a content warning with
+/// `<p><strong>Content warning:</strong> text</p><hr />`, with the label localized.
+fn split_content_warning(body: &str) -> Option<(String, String)> {
+    let re = Regex::new(r"(?s)^\s*<p><strong>([^<]*)</strong>\s*(.*?)</p>\s*<hr\s*/?>").unwrap();
+    let caps = re.captures(body)?;
+    let label = caps.get(1)?.as_str().trim();
+    let text = html_text(caps.get(2)?.as_str());
+    let rest = body[caps.get(0)?.end()..].to_string();
+    Some((format!("{} {}", label, text), rest))
+}
+
+fn media_html(item: &Item) -> String {
+    let Some(contents) = item
+        .extensions()
+        .get("media")
+        .and_then(|media| media.get("content"))
+    else {
+        return String::new();
+    };
+
+    contents
+        .iter()
+        .filter_map(|content| {
+            let url = content.attrs().get("url")?;
+            let alt = media_description(content).unwrap_or_default();
+            let medium = content
+                .attrs()
+                .get("medium")
+                .map(String::as_str)
+                .or_else(|| {
+                    content
+                        .attrs()
+                        .get("type")
+                        .and_then(|kind| kind.split('/').next())
+                })
+                .unwrap_or("image");
+            Some(if medium == "image" {
+                format!(
+                    "<p><img src=\"{}\" alt=\"{}\"></p>",
+                    escape_html(url),
+                    escape_html(&alt)
+                )
+            } else {
+                let label = if alt.is_empty() {
+                    medium.to_string()
+                } else {
+                    alt
+                };
+                format!(
+                    "<p><a href=\"{}\">{}</a></p>",
+                    escape_html(url),
+                    escape_html(&label)
+                )
+            })
+        })
+        .collect()
+}
+
+fn media_description(content: &Extension) -> Option<String> {
+    content
+        .children()
+        .get("description")
+        .and_then(|descriptions| descriptions.first())
+        .and_then(|description| description.value())
+        .map(|value| value.trim().to_string())
+}
+
+fn derive_title(body: &str) -> String {
+    let text = html_text(body);
+    if text.is_empty() {
+        return "Untitled post".to_string();
+    }
+    if text.chars().count() <= MAX_DERIVED_TITLE_CHARS {
+        return text;
+    }
+    let truncated: String = text.chars().take(MAX_DERIVED_TITLE_CHARS).collect();
+    format!("{}…", truncated.trim_end())
+}
+
+fn html_text(html: &str) -> String {
+    let fragment = Html::parse_fragment(html);
+    let text: Vec<&str> = fragment.root_element().text().collect();
+    text.join(" ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
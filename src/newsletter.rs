@@ -0,0 +1,162 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use futures::TryStreamExt;
+use mail_parser::MessageParser;
+use rss::{Channel, ChannelBuilder, Guid, Item};
+use rustls_platform_verifier::ConfigVerifierExt;
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::rustls::ClientConfig;
+use tokio_rustls::TlsConnector;
+
+use crate::config::{self, Feed, NewsletterConfig};
+use crate::db::escape_html;
+use crate::feed;
+use crate::fetcher::HttpFetcher;
+use crate::writer::StoreWriter;
+
+/// Connects to the configured mailbox over IMAPS and turns the matching messages into a channel.
+/// Messages are fetched with `BODY.PEEK[]`, so they stay unread in the mail client.
+pub async fn fetch_channel(config: &NewsletterConfig) -> Result<Channel> {
+    let username = config::resolve_secret(&config.username).context("Invalid IMAP username")?;
+    let password = config::resolve_secret(&config.password).context("Invalid IMAP password")?;
+    let tcp = TcpStream::connect((config.host.as_str(), config.port))
+        .await
+        .context(format!(
+            "Failed to connect to {}:{}",
+            config.host, config.port
+        ))?;
+    let tls_config = ClientConfig::with_platform_verifier().context("Failed to set up TLS")?;
+    let server_name =
+        ServerName::try_from(config.host.clone()).context("Invalid IMAP host name")?;
+    let tls = TlsConnector::from(Arc::new(tls_config))
+        .connect(server_name, tcp)
+        .await
+        .context("TLS handshake with IMAP server failed")?;
+
+    let mut client = async_imap::Client::new(tls);
+    client
+        .read_response()
+        .await
+        .context("Failed to read IMAP greeting")?;
+    let mut session = client
+        .login(&username, &password)
+        .await
+        .map_err(|(err, _)| err)
+        .context("IMAP login failed")?;
+    session
+        .select(&config.mailbox)
+        .await
+        .context(format!("Failed to select mailbox {}", config.mailbox))?;
+
+    let mut seqs: Vec<u32> = session
+        .search(search_query(config))
+        .await
+        .context("IMAP search failed")?
+        .into_iter()
+        .collect();
+    seqs.sort_unstable();
+    let recent = &seqs[seqs.len().saturating_sub(config.max_messages)..];
+
+    let mut items = Vec::new();
+    if !recent.is_empty() {
+        let sequence_set = recent
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        let messages: Vec<_> = session
+            .fetch(sequence_set, "BODY.PEEK[]")
+            .await
+            .context("IMAP fetch failed")?
+            .try_collect()
+            .await
+            .context("IMAP fetch failed")?;
+        items = messages
+            .iter()
+            .filter_map(|message| message.body())
+            .filter_map(message_to_item)
+            .collect();
+        items.reverse();
+    }
+    let _ = session.logout().await;
+
+    Ok(ChannelBuilder::default()
+        .title(config.name.clone())
+        .link(format!("imap://{}/{}", config.host, config.mailbox))
+        .description(format!("Newsletters from {}", config.mailbox))
+        .items(items)
+        .build())
+}
+
+/// Periodically fetches a newsletter feed and queues new messages to be stored as articles.
+pub async fn poll(feed: Feed, store: StoreWriter) {
+    let Some(config) = feed.newsletter.clone() else {
+        return;
+    };
+    let mut interval = tokio::time::interval(Duration::from_secs(config.poll_interval.max(60)));
+    loop {
+        interval.tick().await;
+        match feed::fetch_configured_feed(&HttpFetcher, &feed).await {
+            Ok(channel) => {
+                store.store_channel(&feed.name, &feed.url, channel, feed.store_options())
+            }
+            Err(err) => tracing::warn!("Failed to poll newsletter {}: {:#}", feed.name, err),
+        }
+    }
+}
+
+fn search_query(config: &NewsletterConfig) -> String {
+    let mut criteria = Vec::new();
+    if let Some((last, rest)) = config.from.split_last() {
+        // IMAP's OR takes exactly two keys, so several senders nest: OR FROM a OR FROM b FROM c
+        let mut from = format!("FROM {}", quote(last));
+        for sender in rest.iter().rev() {
+            from = format!("OR FROM {} {}", quote(sender), from);
+        }
+        criteria.push(from);
+    }
+    if let Some(subject) = &config.subject {
+        criteria.push(format!("SUBJECT {}", quote(subject)));
+    }
+    if criteria.is_empty() {
+        "ALL".to_string()
+    } else {
+        criteria.join(" ")
+    }
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn message_to_item(raw: &[u8]) -> Option<Item> {
+    let message = MessageParser::default().parse(raw)?;
+    let content = message
+        .body_html(0)
+        .map(|html| html.into_owned())
+        .or_else(|| {
+            message
+                .body_text(0)
+                .map(|text| format!("<pre>{}</pre>", escape_html(&text)))
+        });
+    let author = message.from().and_then(|from| from.first()).map(|addr| {
+        match (addr.name(), addr.address()) {
+            (Some(name), Some(address)) => format!("{} <{}>", name, address),
+            (name, address) => name.or(address).unwrap_or_default().to_string(),
+        }
+    });
+
+    let mut item = Item::default();
+    item.set_title(message.subject().unwrap_or("No Subject").to_string());
+    item.set_author(author);
+    item.set_pub_date(message.date().map(|date| date.to_rfc822()));
+    item.set_content(content);
+    item.set_guid(message.message_id().map(|id| Guid {
+        value: id.to_string(),
+        permalink: false,
+    }));
+    Some(item)
+}
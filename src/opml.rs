@@ -0,0 +1,196 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{Reader, XmlVersion};
+
+use crate::config::{Config, FeedItem, CATEGORY_SEPARATOR};
+
+/// A subscription read from an OPML file. Its enclosing folders are joined into a nested
+/// category such as `Tech/Rust`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subscription {
+    pub title: String,
+    pub url: String,
+    pub site_link: Option<String>,
+    pub category: Option<String>,
+}
+
+/// Reads every `<outline>` with an `xmlUrl`. Outlines without one are folders, and their
+/// titles become the category path of the subscriptions inside them.
+pub fn parse(xml: &str) -> Result<Vec<Subscription>> {
+    let mut reader = Reader::from_str(xml);
+    let mut subscriptions = Vec::new();
+    // One entry per open outline: the folder title, or `None` for a subscription that has
+    // children of its own.
+    let mut open: Vec<Option<String>> = Vec::new();
+    loop {
+        let event = reader.read_event().context("Failed to parse OPML")?;
+        match event {
+            Event::Start(ref tag) | Event::Empty(ref tag) if is_outline(tag) => {
+                let title = attr(tag, "title")?
+                    .or(attr(tag, "text")?)
+                    .map(|title| title.trim().to_string())
+                    .filter(|title| !title.is_empty());
+                let url = attr(tag, "xmlUrl")?.filter(|url| !url.trim().is_empty());
+                if let Some(url) = &url {
+                    let category: Vec<&str> = open.iter().flatten().map(String::as_str).collect();
+                    subscriptions.push(Subscription {
+                        title: title.clone().unwrap_or_else(|| url.clone()),
+                        url: url.trim().to_string(),
+                        site_link: attr(tag, "htmlUrl")?.filter(|link| !link.trim().is_empty()),
+                        category: Some(category.join(&CATEGORY_SEPARATOR.to_string()))
+                            .filter(|category| !category.is_empty()),
+                    });
+                }
+                if matches!(event, Event::Start(_)) {
+                    // A separator inside a folder title would read back as another level.
+                    let folder = title.map(|title| title.replace(CATEGORY_SEPARATOR, "-"));
+                    open.push(if url.is_some() { None } else { folder });
+                }
+            }
+            Event::End(ref tag) if tag.local_name().as_ref() == b"outline" => {
+                open.pop();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(subscriptions)
+}
+
+fn is_outline(tag: &BytesStart) -> bool {
+    tag.local_name().as_ref() == b"outline"
+}
+
+fn attr(tag: &BytesStart, name: &str) -> Result<Option<String>> {
+    let Some(attribute) = tag
+        .try_get_attribute(name)
+        .context("Invalid OPML attribute")?
+    else {
+        return Ok(None);
+    };
+    let value = attribute
+        .normalized_value(XmlVersion::Implicit1_0)
+        .context("Invalid OPML attribute")?;
+    Ok(Some(value.into_owned()))
+}
+
+/// A category in the exported outline tree.
+#[derive(Default)]
+struct Folder<'a> {
+    name: String,
+    subscriptions: Vec<&'a Subscription>,
+    children: Vec<Folder<'a>>,
+}
+
+impl<'a> Folder<'a> {
+    fn insert(&mut self, path: &[&str], subscription: &'a Subscription) {
+        let Some((first, rest)) = path.split_first() else {
+            self.subscriptions.push(subscription);
+            return;
+        };
+        let index = match self.children.iter().position(|child| child.name == *first) {
+            Some(index) => index,
+            None => {
+                self.children.push(Folder {
+                    name: first.to_string(),
+                    ..Folder::default()
+                });
+                self.children.len() - 1
+            }
+        };
+        self.children[index].insert(rest, subscription);
+    }
+
+    fn write(&self, out: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+        for subscription in &self.subscriptions {
+            let title = escape(subscription.title.as_str());
+            out.push_str(&format!(
+                "{}<outline type=\"rss\" text=\"{}\" title=\"{}\" xmlUrl=\"{}\"",
+                indent,
+                title,
+                title,
+                escape(subscription.url.as_str())
+            ));
+            if let Some(link) = &subscription.site_link {
+                out.push_str(&format!(" htmlUrl=\"{}\"", escape(link.as_str())));
+            }
+            out.push_str("/>\n");
+        }
+        for child in &self.children {
+            let name = escape(child.name.as_str());
+            out.push_str(&format!(
+                "{}<outline text=\"{}\" title=\"{}\">\n",
+                indent, name, name
+            ));
+            child.write(out, depth + 1);
+            out.push_str(&format!("{}</outline>\n", indent));
+        }
+    }
+}
+
+/// OPML document listing `subscriptions`, with nested categories written as nested folders.
+pub fn render(title: &str, subscriptions: &[Subscription]) -> String {
+    let mut root = Folder::default();
+    for subscription in subscriptions {
+        let path: Vec<&str> = subscription
+            .category
+            .as_deref()
+            .map(|category| category.split(CATEGORY_SEPARATOR).collect())
+            .unwrap_or_default();
+        root.insert(&path, subscription);
+    }
+    let mut out = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<opml version=\"2.0\">\n  <head>\n    <title>{}</title>\n  </head>\n  <body>\n",
+        escape(title)
+    );
+    root.write(&mut out, 2);
+    out.push_str("  </body>\n</opml>\n");
+    out
+}
+
+/// Subscribes to every feed of the OPML file at `path` that isn't subscribed yet, filed under
+/// the category of its folder. Returns how many were added and how many were already there.
+pub fn import(config: &mut Config, path: &Path) -> Result<(usize, usize)> {
+    let xml = std::fs::read_to_string(path).context(format!("Failed to read {:?}", path))?;
+    let (mut added, mut existing) = (0, 0);
+    for subscription in parse(&xml)? {
+        if let Some(feed) = config.find_duplicate(&subscription.url) {
+            say!(
+                "Already subscribed to {} as \"{}\"",
+                subscription.url,
+                feed.name
+            );
+            existing += 1;
+            continue;
+        }
+        let mut item = FeedItem::new(&subscription.title, &subscription.url);
+        item.site_link = subscription.site_link;
+        item.category = subscription.category;
+        config.add_feed_item(item);
+        added += 1;
+    }
+    Ok((added, existing))
+}
+
+/// The config's feed subscriptions, paused ones included, as OPML. Scraped pages have no feed
+/// URL and are left out.
+pub fn export(config: &mut Config) -> Result<String> {
+    let subscriptions = config
+        .subscriptions_mut()
+        .into_iter()
+        .filter(|(_, feed)| feed.selectors.is_none())
+        .map(|(item, feed)| {
+            Ok(Subscription {
+                title: item.name.clone(),
+                url: crate::feed::build_feed_url(&feed)?,
+                site_link: item.site_link.clone(),
+                category: item.category.clone(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(render("RSS Reader subscriptions", &subscriptions))
+}
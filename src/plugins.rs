@@ -0,0 +1,254 @@
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result};
+use rhai::{Array, Dynamic, Engine, Map, Scope, AST};
+use rss::{Channel, ChannelBuilder, Item};
+
+use crate::config::DEFAULT_MAX_FEED_SIZE_MB;
+
+/// Feed URLs starting with this prefix are served by a plugin's `fetch(arg)` hook,
+/// e.g. `plugin:weather/berlin` calls `fetch("berlin")` in `plugins/weather.rhai`.
+pub const PLUGIN_SCHEME: &str = "plugin:";
+
+static HOST: OnceLock<PluginHost> = OnceLock::new();
+
+// Limits on a single script call, so a runaway or hostile plugin fails instead of hanging or
+// exhausting memory. Strings may hold a whole feed's content.
+const MAX_OPERATIONS: u64 = 10_000_000;
+const MAX_CALL_LEVELS: usize = 64;
+const MAX_STRING_SIZE: usize = DEFAULT_MAX_FEED_SIZE_MB as usize * 1024 * 1024;
+const MAX_ARRAY_SIZE: usize = 100_000;
+const MAX_MAP_SIZE: usize = 10_000;
+
+struct Plugin {
+    name: String,
+    ast: AST,
+}
+
+/// Rhai scripts loaded from the configured plugins directory.
+///
+/// Each script may define any of these functions:
+/// - `transform_item(item)`: receives an item map and returns it modified, or `()` to drop it.
+///   The guid's `isPermaLink` flag is the map's `guid_is_permalink`.
+/// - `fetch(arg)`: returns `#{ title, link, description, items: [...] }` for `plugin:` feeds.
+/// - `on_new_items(feed_name, items)`: called with items that were stored for the first time.
+pub struct PluginHost {
+    engine: Engine,
+    plugins: Vec<Plugin>,
+}
+
+impl PluginHost {
+    pub fn load(dir: &Path) -> Result<Self> {
+        let mut engine = Engine::new();
+        engine
+            .set_max_operations(MAX_OPERATIONS)
+            .set_max_call_levels(MAX_CALL_LEVELS)
+            .set_max_string_size(MAX_STRING_SIZE)
+            .set_max_array_size(MAX_ARRAY_SIZE)
+            .set_max_map_size(MAX_MAP_SIZE);
+        let mut plugins = Vec::new();
+        let entries =
+            fs::read_dir(dir).context(format!("Failed to read plugins dir: {:?}", dir))?;
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("rhai"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let name = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or_default()
+                .to_string();
+            let ast = engine
+                .compile_file(path.clone())
+                .map_err(|err| anyhow::anyhow!("Failed to compile plugin {:?}: {}", path, err))?;
+            plugins.push(Plugin { name, ast });
+        }
+
+        Ok(Self { engine, plugins })
+    }
+
+    fn has_fn(plugin: &Plugin, name: &str, arity: usize) -> bool {
+        plugin
+            .ast
+            .iter_functions()
+            .any(|f| f.name == name && f.params.len() == arity)
+    }
+
+    fn transform_channel(&self, channel: &mut Channel) {
+        let plugins: Vec<_> = self
+            .plugins
+            .iter()
+            .filter(|plugin| Self::has_fn(plugin, "transform_item", 1))
+            .collect();
+        if plugins.is_empty() {
+            return;
+        }
+
+        let items = channel
+            .items()
+            .iter()
+            .filter_map(|item| {
+                let mut value = Dynamic::from_map(item_to_map(item));
+                for plugin in &plugins {
+                    let result = self.engine.call_fn::<Dynamic>(
+                        &mut Scope::new(),
+                        &plugin.ast,
+                        "transform_item",
+                        (value.clone(),),
+                    );
+                    match result {
+                        Ok(result) if result.is_unit() => return None,
+                        Ok(result) => value = result,
+                        Err(err) => {
+                            tracing::warn!("Plugin {} transform_item failed: {}", plugin.name, err)
+                        }
+                    }
+                }
+                value.try_cast::<Map>().map(|map| {
+                    let mut item = item.clone();
+                    apply_map(&mut item, &map);
+                    item
+                })
+            })
+            .collect::<Vec<_>>();
+        channel.set_items(items);
+    }
+
+    fn fetch(&self, source: &str) -> Result<Channel> {
+        let (name, arg) = source.split_once('/').unwrap_or((source, ""));
+        let plugin = self
+            .plugins
+            .iter()
+            .find(|plugin| plugin.name == name)
+            .context(format!("Plugin not found: {}", name))?;
+        let result = self
+            .engine
+            .call_fn::<Map>(&mut Scope::new(), &plugin.ast, "fetch", (arg.to_string(),))
+            .map_err(|err| anyhow::anyhow!("Plugin {} fetch failed: {}", name, err))?;
+
+        let items = result
+            .get("items")
+            .and_then(|items| items.clone().try_cast::<Array>())
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|item| item.try_cast::<Map>())
+            .map(|map| {
+                let mut item = Item::default();
+                apply_map(&mut item, &map);
+                item
+            })
+            .collect::<Vec<_>>();
+
+        Ok(ChannelBuilder::default()
+            .title(map_str(&result, "title").unwrap_or_else(|| name.to_string()))
+            .link(map_str(&result, "link").unwrap_or_default())
+            .description(map_str(&result, "description").unwrap_or_default())
+            .items(items)
+            .build())
+    }
+
+    fn notify_new_items(&self, feed_name: &str, items: &[Item]) {
+        let items: Array = items
+            .iter()
+            .map(|item| Dynamic::from_map(item_to_map(item)))
+            .collect();
+        for plugin in &self.plugins {
+            if !Self::has_fn(plugin, "on_new_items", 2) {
+                continue;
+            }
+            let result = self.engine.call_fn::<Dynamic>(
+                &mut Scope::new(),
+                &plugin.ast,
+                "on_new_items",
+                (feed_name.to_string(), items.clone()),
+            );
+            if let Err(err) = result {
+                tracing::warn!("Plugin {} on_new_items failed: {}", plugin.name, err);
+            }
+        }
+    }
+}
+
+/// Loads plugins from `dir` and makes them available to the fetch and store pipeline.
+pub fn install(dir: &Path) -> Result<()> {
+    let host = PluginHost::load(dir)?;
+    let _ = HOST.set(host);
+    Ok(())
+}
+
+pub fn is_plugin_source(url: &str) -> bool {
+    url.starts_with(PLUGIN_SCHEME)
+}
+
+pub fn fetch_source(url: &str) -> Result<Channel> {
+    let source = url.trim_start_matches(PLUGIN_SCHEME);
+    HOST.get()
+        .context("No plugins are loaded; set [plugins] dir in the config")?
+        .fetch(source)
+}
+
+pub fn transform_channel(channel: &mut Channel) {
+    if let Some(host) = HOST.get() {
+        host.transform_channel(channel);
+    }
+}
+
+pub fn notify_new_items(feed_name: &str, items: &[Item]) {
+    if items.is_empty() {
+        return;
+    }
+    if let Some(host) = HOST.get() {
+        host.notify_new_items(feed_name, items);
+    }
+}
+
+fn item_to_map(item: &Item) -> Map {
+    let mut map = Map::new();
+    let fields = [
+        ("title", item.title()),
+        ("link", item.link()),
+        ("description", item.description()),
+        ("content", item.content()),
+        ("pub_date", item.pub_date()),
+        ("author", item.author()),
+        ("guid", item.guid().map(|guid| guid.value())),
+    ];
+    for (key, value) in fields {
+        let value = value
+            .map(|v| Dynamic::from(v.to_string()))
+            .unwrap_or(Dynamic::UNIT);
+        map.insert(key.into(), value);
+    }
+    let permalink = item
+        .guid()
+        .map(|guid| Dynamic::from(guid.is_permalink()))
+        .unwrap_or(Dynamic::UNIT);
+    map.insert("guid_is_permalink".into(), permalink);
+    map
+}
+
+fn apply_map(item: &mut Item, map: &Map) {
+    item.set_title(map_str(map, "title"));
+    item.set_link(map_str(map, "link"));
+    item.set_description(map_str(map, "description"));
+    item.set_content(map_str(map, "content"));
+    item.set_pub_date(map_str(map, "pub_date"));
+    item.set_author(map_str(map, "author"));
+    // Items a plugin builds from scratch get guids that aren't permalinks unless it says so.
+    let permalink = map
+        .get("guid_is_permalink")
+        .and_then(|value| value.as_bool().ok())
+        .unwrap_or(false);
+    item.set_guid(map_str(map, "guid").map(|value| rss::Guid { value, permalink }));
+}
+
+fn map_str(map: &Map, key: &str) -> Option<String> {
+    map.get(key)
+        .filter(|value| !value.is_unit())
+        .map(|value| value.to_string())
+}
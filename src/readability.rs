@@ -0,0 +1,157 @@
+use std::sync::LazyLock;
+
+use anyhow::{anyhow, Context, Result};
+use rss::ItemBuilder;
+use scraper::{ElementRef, Html, Node, Selector};
+use url::Url;
+
+use crate::db::{self, escape_html};
+use crate::feed::FetchLimits;
+use crate::http_cache;
+
+/// Containers tried in order for the article body; the first holding enough text wins.
+const CANDIDATES: &[&str] = &[
+    "[itemprop=articleBody]",
+    ".entry-content",
+    ".post-content",
+    ".article-content",
+    ".article-body",
+    ".post-body",
+    "article",
+    "main",
+    "[role=main]",
+    "#content",
+];
+
+/// Characters of text a candidate needs to be taken as the article rather than a teaser.
+const MIN_TEXT_CHARS: usize = 300;
+
+/// Page furniture dropped from the extracted content.
+static NOISE: LazyLock<Selector> = LazyLock::new(|| {
+    Selector::parse(
+        "script, style, noscript, template, nav, header, footer, aside, form, button, iframe, \
+         svg, .share, .sharing, .social, .related, .comments, #comments, .newsletter",
+    )
+    .unwrap()
+});
+
+/// Elements written without a closing tag.
+const VOID: &[&str] = &["img", "br", "hr", "source", "wbr"];
+
+/// Attributes kept on extracted elements; everything else (classes, handlers, styles) goes.
+/// `data-src` and `data-original` hold the real image of lazy-loaded `<img>`s.
+const KEPT_ATTRIBUTES: &[&str] = &[
+    "href",
+    "src",
+    "data-src",
+    "data-original",
+    "alt",
+    "title",
+    "colspan",
+    "rowspan",
+];
+
+/// Fetches an article's web page and converts its main content to markdown, the same way feed
+/// content is, for reading articles whose feed only carries a summary.
+pub async fn fetch_markdown(url: &str) -> Result<String> {
+    let response = http_cache::get_untrusted(url, FetchLimits::default().max_bytes)
+        .await
+        .context(format!("Failed to fetch {}", url))?;
+    if !response.status.is_success() {
+        return Err(http_cache::error_status(
+            format!("Failed to fetch {}", url),
+            response.status,
+        ));
+    }
+    let base = Url::parse(url).context("Invalid article URL")?;
+    let html = String::from_utf8_lossy(&response.body);
+    let content =
+        extract(&html, &base).ok_or_else(|| anyhow!("No article content found on {}", url))?;
+    Ok(db::extract_markdown(
+        &ItemBuilder::default().content(content).build(),
+    ))
+}
+
+/// The HTML of the page's main content, with links and images made absolute against `base`.
+pub fn extract(html: &str, base: &Url) -> Option<String> {
+    let document = Html::parse_document(html);
+    let content = CANDIDATES
+        .iter()
+        .filter_map(|selector| Selector::parse(selector).ok())
+        .find_map(|selector| {
+            document
+                .select(&selector)
+                .max_by_key(|element| text_len(*element))
+                .filter(|element| text_len(*element) >= MIN_TEXT_CHARS)
+        })
+        .or_else(|| densest_block(&document))?;
+    let mut extracted = String::new();
+    write_children(content, base, &mut extracted);
+    Some(extracted)
+}
+
+fn text_len(element: ElementRef) -> usize {
+    element.text().map(|text| text.trim().len()).sum()
+}
+
+/// Fallback for pages without a recognizable container: the element whose own paragraphs hold
+/// the most text.
+fn densest_block(document: &Html) -> Option<ElementRef<'_>> {
+    let blocks = Selector::parse("div, section, td").ok()?;
+    document
+        .select(&blocks)
+        .map(|block| {
+            let text: usize = block
+                .children()
+                .filter_map(ElementRef::wrap)
+                .filter(|child| child.value().name() == "p")
+                .map(text_len)
+                .sum();
+            (block, text)
+        })
+        .filter(|(_, text)| *text >= MIN_TEXT_CHARS)
+        .max_by_key(|(_, text)| *text)
+        .map(|(block, _)| block)
+}
+
+fn write_children(element: ElementRef, base: &Url, out: &mut String) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => out.push_str(&escape_html(text)),
+            Node::Element(_) => {
+                let Some(child) = ElementRef::wrap(child) else {
+                    continue;
+                };
+                if NOISE.matches(&child) {
+                    continue;
+                }
+                write_element(child, base, out);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn write_element(element: ElementRef, base: &Url, out: &mut String) {
+    let name = element.value().name();
+    out.push('<');
+    out.push_str(name);
+    for (attribute, value) in element.value().attrs() {
+        if !KEPT_ATTRIBUTES.contains(&attribute) {
+            continue;
+        }
+        let value = match attribute {
+            "href" | "src" | "data-src" | "data-original" => base
+                .join(value)
+                .map_or_else(|_| value.to_string(), String::from),
+            _ => value.to_string(),
+        };
+        out.push_str(&format!(" {}=\"{}\"", attribute, escape_html(&value)));
+    }
+    out.push('>');
+    if VOID.contains(&name) {
+        return;
+    }
+    write_children(element, base, out);
+    out.push_str(&format!("</{}>", name));
+}
@@ -0,0 +1,203 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+use anyhow::Result;
+use regex::Regex;
+
+use crate::db::{ArticleRecord, Database};
+
+/// Related articles listed under an article.
+pub const RELATED_LIMIT: usize = 5;
+/// Cosine similarity below which two articles aren't considered related.
+const MIN_SIMILARITY: f64 = 0.1;
+/// Times a title word counts over a word of the body, since titles say what an article is about.
+const TITLE_WEIGHT: usize = 3;
+
+/// Link and image targets and bare URLs, whose words say nothing about the article.
+static LINK_TARGET: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\]\([^)]*\)|https?://\S+").unwrap());
+
+/// Term indexes by store directory.
+static INDEXES: LazyLock<Mutex<HashMap<PathBuf, TermIndex>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Term counts of the stored articles, so finding related articles doesn't read and tokenize
+/// every article again. Articles stored by this process are added as they are written; ones
+/// another process stored or removed are picked up by the next lookup.
+#[derive(Default)]
+struct TermIndex {
+    /// Term counts by article ID, with the article's last modified time when it was counted.
+    documents: HashMap<String, (String, HashMap<String, usize>)>,
+    /// Articles each term appears in.
+    document_frequency: HashMap<String, usize>,
+    /// Related articles by article ID, cleared whenever an article is added or removed.
+    results: HashMap<String, Vec<(f64, ArticleRecord)>>,
+}
+
+impl TermIndex {
+    fn insert(&mut self, id: &str, modified: &str, counts: HashMap<String, usize>) {
+        self.remove(id);
+        for term in counts.keys() {
+            *self.document_frequency.entry(term.clone()).or_default() += 1;
+        }
+        self.documents
+            .insert(id.to_string(), (modified.to_string(), counts));
+        self.results.clear();
+    }
+
+    fn remove(&mut self, id: &str) {
+        let Some((_, counts)) = self.documents.remove(id) else {
+            return;
+        };
+        for term in counts.keys() {
+            if let Some(frequency) = self.document_frequency.get_mut(term) {
+                *frequency -= 1;
+                if *frequency == 0 {
+                    self.document_frequency.remove(term);
+                }
+            }
+        }
+        self.results.clear();
+    }
+
+    fn weights(&self, counts: &HashMap<String, usize>) -> HashMap<String, f64> {
+        let total = self.documents.len() as f64;
+        counts
+            .iter()
+            .map(|(term, &count)| {
+                let idf = (1.0 + total / self.document_frequency[term] as f64).ln();
+                (term.clone(), (1.0 + (count as f64).ln()) * idf)
+            })
+            .collect()
+    }
+}
+
+const STOPWORDS: &[&str] = &[
+    "about", "after", "all", "also", "and", "any", "are", "because", "been", "before", "but",
+    "can", "could", "did", "does", "for", "from", "had", "has", "have", "her", "his", "how",
+    "into", "its", "just", "more", "most", "not", "now", "one", "only", "other", "our", "out",
+    "over", "she", "some", "such", "than", "that", "the", "their", "them", "then", "there",
+    "these", "they", "this", "those", "through", "too", "very", "was", "were", "what", "when",
+    "where", "which", "while", "who", "why", "will", "with", "would", "you", "your",
+];
+
+/// Adds an article that was just written to the store at `store_dir` to its term index.
+pub(crate) fn index_article(
+    store_dir: &Path,
+    id: &str,
+    modified: &str,
+    title: &str,
+    markdown: &str,
+) {
+    let counts = term_counts(title, markdown);
+    let mut indexes = INDEXES.lock().unwrap();
+    if let Some(index) = indexes.get_mut(store_dir) {
+        index.insert(id, modified, counts);
+    }
+}
+
+/// Stored articles most like the one with ID `id`, most similar first, with their cosine
+/// similarity. Articles are compared by the TF-IDF weights of the words of their title and
+/// stored markdown, so ones sharing rarer words rank above ones sharing only common ones.
+pub fn related_articles(
+    db: &Database,
+    id: &str,
+    limit: usize,
+) -> Result<Vec<(f64, ArticleRecord)>> {
+    let records = db.list_articles()?;
+    let unindexed: Vec<&ArticleRecord> = {
+        let mut indexes = INDEXES.lock().unwrap();
+        let index = indexes.entry(db.store_dir().to_path_buf()).or_default();
+        let stored: HashSet<&str> = records.iter().map(|record| record.id.as_str()).collect();
+        let removed: Vec<String> = index
+            .documents
+            .keys()
+            .filter(|id| !stored.contains(id.as_str()))
+            .cloned()
+            .collect();
+        for id in removed {
+            index.remove(&id);
+        }
+        records
+            .iter()
+            .filter(|record| {
+                index
+                    .documents
+                    .get(&record.id)
+                    .is_none_or(|(modified, _)| modified != record.last_modified())
+            })
+            .collect()
+    };
+    // Articles are read without holding the lock, since the first lookup reads all of them.
+    let counted: Vec<(&ArticleRecord, HashMap<String, usize>)> = unindexed
+        .into_iter()
+        .map(|record| {
+            let markdown = db.read_article_markdown(record).unwrap_or_default();
+            (record, term_counts(&record.title, &markdown))
+        })
+        .collect();
+
+    let mut indexes = INDEXES.lock().unwrap();
+    let index = indexes.entry(db.store_dir().to_path_buf()).or_default();
+    for (record, counts) in counted {
+        index.insert(&record.id, record.last_modified(), counts);
+    }
+    if let Some(related) = index.results.get(id) {
+        return Ok(related.iter().take(limit).cloned().collect());
+    }
+    let Some((_, target)) = index.documents.get(id) else {
+        return Ok(Vec::new());
+    };
+    let target_weights = index.weights(target);
+    let target_norm = norm(&target_weights);
+    if target_norm == 0.0 {
+        return Ok(Vec::new());
+    }
+
+    let mut related: Vec<(f64, ArticleRecord)> = records
+        .into_iter()
+        .filter(|record| record.id != id)
+        .filter_map(|record| {
+            let (_, counts) = index.documents.get(&record.id)?;
+            let weights = index.weights(counts);
+            let dot: f64 = weights
+                .iter()
+                .filter_map(|(term, weight)| Some(weight * target_weights.get(term)?))
+                .sum();
+            let similarity = dot / (norm(&weights) * target_norm);
+            (similarity >= MIN_SIMILARITY).then_some((similarity, record))
+        })
+        .collect();
+    related.sort_by(|a, b| b.0.total_cmp(&a.0));
+    index.results.insert(id.to_string(), related.clone());
+    related.truncate(limit);
+    Ok(related)
+}
+
+fn norm(weights: &HashMap<String, f64>) -> f64 {
+    weights
+        .values()
+        .map(|weight| weight * weight)
+        .sum::<f64>()
+        .sqrt()
+}
+
+fn term_counts(title: &str, markdown: &str) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for term in terms(title) {
+        *counts.entry(term).or_default() += TITLE_WEIGHT;
+    }
+    for term in terms(&LINK_TARGET.replace_all(markdown, "]")) {
+        *counts.entry(term).or_default() += 1;
+    }
+    counts
+}
+
+/// Lowercased words of at least three letters, without numbers and common English words.
+fn terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 3 && !word.chars().all(|c| c.is_numeric()))
+        .map(str::to_lowercase)
+        .filter(|word| !STOPWORDS.contains(&word.as_str()))
+}
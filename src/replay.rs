@@ -0,0 +1,205 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::db::{Database, StoreOptions};
+use crate::feed;
+use crate::fetcher::FeedFetcher;
+use crate::http_cache::{self, Response};
+
+/// The config a recording was made with, next to the responses.
+const RECORDING_CONFIG: &str = "feeds.toml";
+/// Store replayed articles are written to, inside the recording. It is emptied before each
+/// replay so every replay starts from the same state.
+const REPLAY_STORE: &str = "replay-store";
+
+/// Status and headers of a recorded response; its body is in a `.body` file next to it.
+#[derive(Serialize, Deserialize)]
+struct RecordedResponse {
+    url: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+}
+
+fn response_path(dir: &Path, url: &str, extension: &str) -> PathBuf {
+    let key = hex::encode(Sha256::digest(url.as_bytes()));
+    dir.join(format!("{}.{}", key, extension))
+}
+
+/// Fetches through another fetcher and saves every GET response it returns, error statuses
+/// included, to a directory `ReplayFetcher` can answer from.
+pub struct RecordingFetcher<'a> {
+    inner: &'a dyn FeedFetcher,
+    dir: PathBuf,
+}
+
+impl<'a> RecordingFetcher<'a> {
+    pub fn new(inner: &'a dyn FeedFetcher, dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir).context(format!("Failed to create {:?}", dir))?;
+        Ok(Self {
+            inner,
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    async fn save(&self, url: &str, response: &Response) -> Result<()> {
+        let recorded = RecordedResponse {
+            url: url.to_string(),
+            status: response.status.as_u16(),
+            headers: response
+                .headers
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.to_string(), value.to_str().ok()?.to_string()))
+                })
+                .collect(),
+        };
+        tokio::fs::write(response_path(&self.dir, url, "body"), &response.body).await?;
+        let meta = serde_json::to_vec_pretty(&recorded)?;
+        tokio::fs::write(response_path(&self.dir, url, "json"), meta).await?;
+        Ok(())
+    }
+}
+
+impl FeedFetcher for RecordingFetcher<'_> {
+    fn get<'a>(&'a self, url: &'a str, max_bytes: u64) -> BoxFuture<'a, Result<Response>> {
+        Box::pin(async move {
+            let response = self.inner.get(url, max_bytes).await?;
+            if let Err(err) = self.save(url, &response).await {
+                tracing::warn!("Failed to record the response of {}: {:#}", url, err);
+            }
+            Ok(response)
+        })
+    }
+
+    fn head<'a>(&'a self, url: &'a str, headers: HeaderMap) -> BoxFuture<'a, Result<Response>> {
+        self.inner.head(url, headers)
+    }
+}
+
+/// Answers GET requests from the responses a `RecordingFetcher` saved, without the network.
+/// URLs that weren't recorded fail, as do HEAD requests, so feeds are always fetched in full.
+pub struct ReplayFetcher {
+    dir: PathBuf,
+}
+
+impl ReplayFetcher {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    async fn load(&self, url: &str) -> Result<Response> {
+        let meta = tokio::fs::read(response_path(&self.dir, url, "json"))
+            .await
+            .context(format!("No recorded response for {}", url))?;
+        let recorded: RecordedResponse =
+            serde_json::from_slice(&meta).context(format!("Invalid recording of {}", url))?;
+        let body = tokio::fs::read(response_path(&self.dir, url, "body"))
+            .await
+            .context(format!("Missing recorded body of {}", url))?;
+        let headers = recorded
+            .headers
+            .iter()
+            .filter_map(|(name, value)| {
+                Some((
+                    HeaderName::from_bytes(name.as_bytes()).ok()?,
+                    HeaderValue::from_str(value).ok()?,
+                ))
+            })
+            .collect();
+        Ok(Response {
+            status: StatusCode::from_u16(recorded.status)
+                .context(format!("Invalid recorded status of {}", url))?,
+            headers,
+            body,
+            from_cache: false,
+        })
+    }
+}
+
+impl FeedFetcher for ReplayFetcher {
+    fn get<'a>(&'a self, url: &'a str, max_bytes: u64) -> BoxFuture<'a, Result<Response>> {
+        Box::pin(async move {
+            let response = self.load(url).await?;
+            if response.body.len() as u64 > max_bytes {
+                return Err(http_cache::too_large(max_bytes));
+            }
+            Ok(response)
+        })
+    }
+
+    fn head<'a>(&'a self, url: &'a str, _headers: HeaderMap) -> BoxFuture<'a, Result<Response>> {
+        Box::pin(async move { anyhow::bail!("HEAD requests are not replayed: {}", url) })
+    }
+}
+
+/// Saves the config a recording was made with into it. Newsletters are left out, since they
+/// aren't fetched over HTTP and carry credentials, and so are settings naming local files.
+pub fn save_recording_config(dir: &Path, cfg: &Config) -> Result<()> {
+    let cfg = Config {
+        newsletters: Vec::new(),
+        plugins: None,
+        blocklist: None,
+        backup: None,
+        ..cfg.clone()
+    };
+    cfg.save(&dir.join(RECORDING_CONFIG))
+}
+
+/// Config of the recording in `dir`, used by `replay` unless another is given.
+pub fn recording_config_path(dir: &Path) -> PathBuf {
+    dir.join(RECORDING_CONFIG)
+}
+
+/// Runs each configured feed's recorded responses through fetching, parsing and storing into an
+/// empty store inside the recording, printing what each feed yielded or failed with. Images
+/// aren't recorded, so they're kept as remote links instead of being downloaded.
+pub async fn replay(dir: &Path, cfg: &Config) -> Result<()> {
+    let store_dir = dir.join(REPLAY_STORE);
+    if store_dir.exists() {
+        std::fs::remove_dir_all(&store_dir)
+            .context(format!("Failed to empty the replay store {:?}", store_dir))?;
+    }
+    let database = Database::initialize(&store_dir, None).await?;
+    let fetcher = ReplayFetcher::new(dir);
+
+    let feeds = cfg.get_all_feeds();
+    let mut failed = 0;
+    for feed in &feeds {
+        if feed.newsletter.is_some() {
+            say!("{}: newsletters aren't recorded", feed.name);
+            continue;
+        }
+        let stored = async {
+            let channel = feed::fetch_configured_feed(&fetcher, feed).await?;
+            let options = StoreOptions {
+                monthly_budget_bytes: Some(0),
+                ..feed.store_options()
+            };
+            let added = database
+                .store_channel(&feed.name, &feed.url, &channel, options)
+                .await?;
+            anyhow::Ok((channel.items().len(), added.len()))
+        };
+        match stored.await {
+            Ok((items, added)) => say!("{}: {} items, {} stored", feed.name, items, added),
+            Err(err) => {
+                failed += 1;
+                eprintln!("{}: {:#}", feed.name, err);
+            }
+        }
+    }
+    say!("Replayed into {}", store_dir.display());
+    if failed > 0 {
+        anyhow::bail!("{} of {} feeds failed to replay", failed, feeds.len());
+    }
+    Ok(())
+}
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::config::{Config, KeywordRule};
+use crate::db::{ArticleRecord, Database};
+
+/// How an article's interest score adds up, shown so the rules can be tuned.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, utoipa::ToSchema)]
+pub struct Score {
+    pub total: f64,
+    /// The feed's `weight`.
+    pub feed: f64,
+    /// Sum of the keyword rules matching the title.
+    pub keywords: f64,
+    /// `read_weight` times the share of the feed's stored articles that were opened.
+    pub history: f64,
+}
+
+/// Scores stored articles from the config's `[scoring]` rules, feed weights and reading history.
+pub struct Scorer {
+    keywords: Vec<KeywordRule>,
+    feed_weights: HashMap<String, f64>,
+    /// Share of each feed's stored articles that were opened, from 0 to 1.
+    read_shares: HashMap<String, f64>,
+    read_weight: f64,
+}
+
+impl Scorer {
+    pub fn new(config: &Config, db: &Database) -> Result<Self> {
+        let scoring = config.scoring.clone();
+        let keywords = scoring
+            .as_ref()
+            .map(|scoring| {
+                scoring
+                    .keywords
+                    .iter()
+                    .map(|rule| KeywordRule {
+                        pattern: rule.pattern.to_lowercase(),
+                        weight: rule.weight,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let feed_weights = config
+            .get_all_feeds()
+            .into_iter()
+            .filter_map(|feed| Some((feed.name, feed.weight?)))
+            .collect();
+
+        let mut stored: HashMap<String, usize> = HashMap::new();
+        for record in db.list_articles()? {
+            *stored.entry(record.feed_name).or_default() += 1;
+        }
+        let mut opened: HashMap<String, usize> = HashMap::new();
+        for (_, record) in db.reading_history()? {
+            *opened.entry(record.feed_name).or_default() += 1;
+        }
+        let read_shares = opened
+            .into_iter()
+            .filter_map(|(feed_name, opened)| {
+                let stored = *stored.get(&feed_name)?;
+                Some((feed_name, opened as f64 / stored as f64))
+            })
+            .collect();
+
+        Ok(Self {
+            keywords,
+            feed_weights,
+            read_shares,
+            read_weight: scoring.map_or(1.0, |scoring| scoring.read_weight),
+        })
+    }
+
+    pub fn score(&self, record: &ArticleRecord) -> Score {
+        let feed = self
+            .feed_weights
+            .get(&record.feed_name)
+            .copied()
+            .unwrap_or_default();
+        let title = record.title.to_lowercase();
+        let keywords = self
+            .keywords
+            .iter()
+            .filter(|rule| title.contains(&rule.pattern))
+            .fold(0.0, |sum, rule| sum + rule.weight);
+        let history = self.read_weight
+            * self
+                .read_shares
+                .get(&record.feed_name)
+                .copied()
+                .unwrap_or_default();
+        Score {
+            total: feed + keywords + history,
+            feed,
+            keywords,
+            history,
+        }
+    }
+
+    /// Highest score first, and the newest first among articles scoring the same.
+    pub fn rank(&self, records: Vec<ArticleRecord>) -> Vec<(Score, ArticleRecord)> {
+        let mut scored: Vec<_> = records
+            .into_iter()
+            .map(|record| (self.score(&record), record))
+            .collect();
+        scored.sort_by(|(a_score, a), (b_score, b)| {
+            b_score
+                .total
+                .total_cmp(&a_score.total)
+                .then_with(|| b.last_modified().cmp(a.last_modified()))
+        });
+        scored
+    }
+}
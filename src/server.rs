@@ -1,167 +1,1428 @@
 use anyhow::{Context, Result};
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::{Html, IntoResponse},
-    routing::get,
-    Json, Router,
+    extract::{ConnectInfo, DefaultBodyLimit, Path, Query, Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{Html, IntoResponse, Redirect, Response},
+    routing::{get, post, put},
+    Form, Json, Router,
 };
+use base64::prelude::*;
+use regex::Regex;
 use rss::Channel;
-use serde::Serialize;
-use std::{net::SocketAddr, sync::Arc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tokio::sync::Mutex;
-use tower_http::services::ServeDir;
+use tower_http::{
+    compression::CompressionLayer,
+    cors::{AllowOrigin, Any, CorsLayer},
+    limit::RequestBodyLimitLayer,
+    timeout::TimeoutLayer,
+    trace::TraceLayer,
+};
+use url::Url;
+use utoipa::{IntoParams, OpenApi, ToSchema};
 
 use crate::{
-    config::{Config, Feed},
-    db, feed,
+    backup,
+    cache::RenderCache,
+    cloud,
+    config::{Config, Feed, FeedItem},
+    dates,
+    db::{self, escape_html},
+    enrich::{self, Discussion},
+    feed,
+    fetcher::{FeedFetcher, HttpFetcher},
+    hygiene, i18n, newsletter, readability, related,
+    scoring::{Score, Scorer},
+    sync::SyncArticle,
+    writer::StoreWriter,
 };
 
 #[derive(Clone)]
 struct AppState {
-    feeds: Vec<Feed>,
-    cache: Arc<Mutex<Vec<Option<Channel>>>>,
+    config: Arc<Mutex<Config>>,
+    config_path: PathBuf,
+    feeds: Arc<Mutex<Vec<Feed>>>,
+    cache: Arc<Mutex<Vec<Option<CachedChannel>>>>,
+    render_cache: Arc<Mutex<RenderCache<String>>>,
     db: db::Database,
+    store: StoreWriter,
+    /// Prefix of every route, like `/rss`, or empty when served at the root.
+    base_path: String,
+    /// Where the server is reachable from the internet, including `base_path`, from
+    /// `--public-url`.
+    public_url: Option<Url>,
+    /// Public URL of the rssCloud notification route, when the server has a `--public-url`.
+    cloud_callback: Option<Url>,
+    cloud: Arc<Mutex<cloud::Subscriptions>>,
+    fetcher: Arc<dyn FeedFetcher>,
+}
+
+/// Articles returned by the archive listing unless the request sets `limit`.
+const ARCHIVE_PAGE_LIMIT: usize = 100;
+
+/// Number of newest articles republished in the aggregate feeds.
+const AGGREGATE_FEED_LIMIT: usize = 100;
+
+/// Stored images are named after their source URL, so a name always refers to the same picture.
+const IMAGE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// Days a feed is muted for when the request doesn't say.
+const MUTE_DAYS: i64 = 7;
+
+/// Largest request body the API accepts; no endpoint takes more than a small JSON payload.
+const MAX_REQUEST_BODY_BYTES: usize = 64 * 1024;
+
+/// Largest sync upload (one article or image).
+const MAX_SYNC_BODY_BYTES: usize = 32 * 1024 * 1024;
+
+/// How long a feed served offline from the archive is kept before the fetch is retried, unless
+/// its own cache max age is shorter.
+const OFFLINE_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Fixed-window request counter per client IP.
+#[derive(Clone)]
+struct RateLimiter {
+    limit: u32,
+    window: Duration,
+    hits: Arc<std::sync::Mutex<HashMap<IpAddr, (Instant, u32)>>>,
 }
 
-#[derive(Serialize, Clone)]
+impl RateLimiter {
+    fn new(limit: u32, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            hits: Arc::new(std::sync::Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn check(&self, ip: IpAddr) -> bool {
+        if self.limit == 0 {
+            return true;
+        }
+
+        let now = Instant::now();
+        let mut hits = self.hits.lock().unwrap_or_else(|err| err.into_inner());
+        if hits.len() > 4096 {
+            hits.retain(|_, (start, _)| now.duration_since(*start) < self.window);
+        }
+
+        let entry = hits.entry(ip).or_insert((now, 0));
+        if now.duration_since(entry.0) >= self.window {
+            *entry = (now, 0);
+        }
+        entry.1 += 1;
+        entry.1 <= self.limit
+    }
+}
+
+#[derive(Clone)]
+struct CachedChannel {
+    channel: Channel,
+    /// Number of leading items that came from the feed; the rest were merged from the archive.
+    latest_items: usize,
+    fetched_at: Instant,
+    /// Set when the feed couldn't be fetched and `channel` was rebuilt from the archive.
+    offline: Option<OfflineNotice>,
+}
+
+#[derive(Serialize, Clone, ToSchema)]
+struct OfflineNotice {
+    /// Why the live fetch failed.
+    error: String,
+    /// Last successful fetch (RFC 3339), if the feed was ever fetched.
+    last_fetched: Option<String>,
+}
+
+#[derive(Serialize, Clone, ToSchema)]
 struct FeedInfo {
     name: String,
     url: String,
     is_rsshub: bool,
+    /// Category path, with nested levels separated by `/`.
+    category: Option<String>,
+    /// When the feed is muted, the time it's muted until in the configured `date_format`.
+    muted_until: Option<String>,
+    /// BCP 47 language of the feed's articles: its `language` setting, else the channel's
+    /// `<language>` when it was last stored.
+    language: Option<String>,
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, ToSchema)]
 struct FeedResponse {
     title: String,
     description: Option<String>,
+    /// BCP 47 language of the feed's articles: its `language` setting, else the channel's
+    /// `<language>`.
+    language: Option<String>,
     items: Vec<ItemMeta>,
+    /// Number of items in the list, including archived history unless `latest` was requested.
+    total_items: usize,
+    /// Number of leading items from the latest fetch; the rest come from the archive.
+    latest_items: usize,
+    /// Offset of the next page of items, when there are more.
+    next_offset: Option<usize>,
+    /// Present when the items come from the local archive because the feed is unreachable.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    offline: Option<OfflineNotice>,
 }
 
-#[derive(Serialize, Clone)]
+/// Channel-level details, for evaluating a feed.
+#[derive(Serialize, ToSchema)]
+struct ChannelMeta {
+    title: String,
+    description: Option<String>,
+    /// The channel's `<link>`, usually the site's home page.
+    site_link: Option<String>,
+    language: Option<String>,
+    last_build_date: Option<String>,
+    pub_date: Option<String>,
+    image: Option<String>,
+    /// Minutes the feed may be cached, as advertised by the publisher.
+    ttl: Option<String>,
+    generator: Option<String>,
+    /// Items in the feed itself, not counting archived history.
+    item_count: usize,
+}
+
+#[derive(Serialize, Clone, ToSchema)]
 struct ItemMeta {
     id: usize,
     title: String,
     link: Option<String>,
     pub_date: Option<String>,
+    /// `pub_date` in the server's timezone and the configured `date_format`.
+    pub_date_local: Option<String>,
+    /// Last update reported by the feed (RFC 3339), when it differs from `pub_date`.
+    updated: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct ArchivedArticle {
+    /// Stable article ID, usable with `/article/{id}`.
+    id: String,
+    title: String,
+    feed_name: String,
+    link: Option<String>,
+    published: String,
+    updated: Option<String>,
+    /// How the interest score adds up, when the listing is sorted by score.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    score: Option<Score>,
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, ToSchema)]
+struct RelatedArticle {
+    /// Stable article ID, usable with `/article/{id}`.
+    id: String,
+    title: String,
+    feed_name: String,
+    link: Option<String>,
+    published: String,
+    /// Cosine similarity of the two articles' words, from 0 to 1.
+    similarity: f64,
+}
+
+#[derive(Serialize, Clone, ToSchema)]
 struct ItemContent {
+    /// Stable article ID, usable with `/article/{id}`.
+    id: String,
     title: String,
     link: Option<String>,
+    /// The item's `<author>`, or else its `dc:creator`.
+    author: Option<String>,
     pub_date: Option<String>,
+    /// `pub_date` in the server's timezone and the configured `date_format`.
+    pub_date_local: Option<String>,
+    /// Last update reported by the feed (RFC 3339), when it differs from `pub_date`.
+    updated: Option<String>,
+    /// `updated` in the server's timezone and the configured `date_format`.
+    updated_local: Option<String>,
     content_html: String,
+    /// Tracking pixels, analytics scripts and ad iframes stripped from the content.
+    trackers_removed: usize,
+    starred: bool,
+    /// The configured `article_css` for this feed, applied to the article container.
+    css: Option<String>,
+    /// BCP 47 language of the article, from its feed; sets the article container's `lang`.
+    language: Option<String>,
+    /// Whether `?content=full` can extract the article from its web page: the feed has
+    /// `full_content` set and the item a link.
+    full_content: bool,
+    /// Headings of `content_html` to jump to, for articles long enough to have some.
+    toc: Vec<TocEntry>,
+    /// How far into the article reading was left off (0 to 1), to scroll back to.
+    position: Option<f64>,
+    /// Another stored article with the same link, from another feed or an earlier fetch.
+    previous_copy: Option<PreviousCopy>,
+}
+
+#[derive(Serialize, Clone, ToSchema)]
+struct PreviousCopy {
+    /// Stable article ID, usable with `/article/{id}`.
+    id: String,
+    feed_name: String,
+    published: String,
+    /// When it was last opened (RFC 3339), if it was read.
+    read_at: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+struct ReadingPosition {
+    /// Fraction of the article scrolled past: 0 is the top, 1 the end. The top and the very end
+    /// forget the position.
+    position: f64,
+}
+
+#[derive(Serialize, Clone, ToSchema)]
+struct TocEntry {
+    /// 1 for `<h1>` through 6 for `<h6>`.
+    level: u8,
+    /// `id` of the heading element in `content_html`.
+    id: String,
+    text: String,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "RSS Reader API", version = "1"),
+    paths(
+        list_feeds,
+        get_feed,
+        get_feed_meta,
+        refresh_feed,
+        mute_feed,
+        unmute_feed,
+        get_item,
+        get_item_discussion,
+        list_archive,
+        list_updates,
+        bandwidth_stats,
+        list_runs,
+        star_article,
+        unstar_article,
+        set_reading_position,
+        related_articles,
+        export_article
+    ),
+    components(schemas(
+        FeedInfo,
+        FeedResponse,
+        ChannelMeta,
+        OfflineNotice,
+        ItemMeta,
+        ItemContent,
+        TocEntry,
+        ReadingPosition,
+        RelatedArticle,
+        PreviousCopy,
+        ContentSource,
+        ArchivedArticle,
+        Score,
+        TimelineSort,
+        ExportFormat,
+        FeedBandwidth,
+        db::Run,
+        db::RunFeed,
+        db::RunOutcome,
+        Updates,
+        FeedUpdates,
+        Discussion
+    ))
+)]
+struct ApiDoc;
+
+pub struct ServerOptions {
+    /// Config file that feeds subscribed through the web UI are saved to.
+    pub config_path: PathBuf,
+    pub host: String,
+    pub port: u16,
+    /// Overrides `host` and `port`.
+    pub bind: Option<Bind>,
+    /// Path prefix of every route, like `/rss`, when a reverse proxy serves the app under it.
+    pub base_path: Option<String>,
+    /// Where the server is reachable from the internet, including any `base_path`.
+    pub public_url: Option<Url>,
+    pub open_browser: bool,
+    /// Origins allowed to call the API cross-origin; `*` allows any.
+    pub cors_origins: Vec<String>,
+    /// API requests allowed per client IP per minute; 0 disables the limit.
+    pub rate_limit_per_minute: u32,
+    pub request_timeout: Duration,
+}
+
+/// Where the server listens, as given to `--bind`.
+#[derive(Debug, Clone)]
+pub enum Bind {
+    Tcp(SocketAddr),
+    /// Unix domain socket (`unix:PATH`), for running behind a reverse proxy.
+    Unix(PathBuf),
+}
+
+impl std::str::FromStr for Bind {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.strip_prefix("unix:") {
+            Some("") => Err(String::from("missing socket path after unix:")),
+            Some(path) => Ok(Bind::Unix(PathBuf::from(path))),
+            None => value
+                .parse()
+                .map(Bind::Tcp)
+                .map_err(|_| format!("expected HOST:PORT or unix:PATH, got {}", value)),
+        }
+    }
+}
+
+/// `/rss` from `rss`, `/rss/` or `/rss`; empty for no prefix. Only URL-safe path characters are
+/// accepted since the prefix is written into the pages' HTML and scripts.
+fn normalize_base_path(raw: &str) -> Result<String> {
+    let trimmed = raw.trim().trim_matches('/');
+    let valid = trimmed
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "-._~/".contains(c));
+    if !valid || trimmed.contains("//") {
+        anyhow::bail!("Invalid base path: {}", raw);
+    }
+    Ok(if trimmed.is_empty() {
+        String::new()
+    } else {
+        format!("/{}", trimmed)
+    })
+}
+
+/// Points links to locally stored images at the images route under `base_path`.
+fn rebase_images(html: &str, base_path: &str) -> String {
+    if base_path.is_empty() {
+        return html.to_string();
+    }
+    html.replace("\"/images/", &format!("\"{}/images/", base_path))
 }
 
 pub async fn run_server(
     config: Config,
-    host: String,
-    port: u16,
-    open_browser: bool,
+    options: ServerOptions,
     database: db::Database,
 ) -> Result<()> {
+    init_tracing();
+    let base_path = normalize_base_path(options.base_path.as_deref().unwrap_or_default())?;
+
     let feeds = config.get_all_feeds();
     let cache = vec![None; feeds.len()];
+    let store = StoreWriter::spawn(database.clone());
+    for feed in feeds.iter().filter(|feed| feed.newsletter.is_some()) {
+        tokio::spawn(newsletter::poll(feed.clone(), store.clone()));
+    }
+    if let Some(settings) = config.backup.clone() {
+        tokio::spawn(backup::schedule(
+            settings,
+            options.config_path.clone(),
+            store.clone(),
+        ));
+    }
     let state = AppState {
-        feeds,
+        config: Arc::new(Mutex::new(config)),
+        config_path: options.config_path.clone(),
+        feeds: Arc::new(Mutex::new(feeds)),
         cache: Arc::new(Mutex::new(cache)),
+        render_cache: Arc::new(Mutex::new(RenderCache::default())),
         db: database,
+        store: store.clone(),
+        base_path: base_path.clone(),
+        public_url: options.public_url.clone(),
+        cloud_callback: options
+            .public_url
+            .as_ref()
+            .map(|url| {
+                format!(
+                    "{}{}",
+                    url.as_str().trim_end_matches('/'),
+                    cloud::NOTIFY_PATH
+                )
+                .parse()
+            })
+            .transpose()
+            .context("Invalid --public-url")?,
+        cloud: Arc::default(),
+        fetcher: Arc::new(HttpFetcher),
     };
 
+    let limiter = RateLimiter::new(options.rate_limit_per_minute, Duration::from_secs(60));
+    let api = Router::new()
+        .route("/feeds", get(list_feeds))
+        .route("/feeds/:index", get(get_feed))
+        .route("/feeds/:index/refresh", post(refresh_feed))
+        .route("/feeds/:index/items/:item_index", get(get_item))
+        .route(
+            "/feeds/:index/items/:item_index/discussion",
+            get(get_item_discussion),
+        )
+        .route("/feeds/:index/meta", get(get_feed_meta))
+        .route("/feeds/:index/mute", post(mute_feed).delete(unmute_feed))
+        .route("/articles", get(list_archive))
+        .route("/updates", get(list_updates))
+        .route("/stats", get(bandwidth_stats))
+        .route("/runs", get(list_runs))
+        .route(
+            "/articles/:id/star",
+            post(star_article).delete(unstar_article),
+        )
+        .route("/articles/:id/position", put(set_reading_position))
+        .route("/articles/:id/related", get(related_articles))
+        .route("/articles/:id/export", get(export_article))
+        .layer(middleware::from_fn_with_state(limiter.clone(), rate_limit))
+        .layer(RequestBodyLimitLayer::new(MAX_REQUEST_BODY_BYTES));
+
+    // Sync transfers whole articles and images, so it gets a larger body limit and no rate limit.
+    let sync = Router::new()
+        .route("/sync/state", get(sync_state).post(merge_sync_state))
+        .route(
+            "/sync/articles",
+            get(list_sync_articles).post(import_sync_article),
+        )
+        .route("/sync/articles/:id", get(get_sync_article))
+        .route("/sync/images/:name", put(import_sync_image))
+        .layer(DefaultBodyLimit::max(MAX_SYNC_BODY_BYTES))
+        .layer(RequestBodyLimitLayer::new(MAX_SYNC_BODY_BYTES));
+    let api = api.merge(sync);
+
+    // Subscribing fetches the page it is given, so it shares the API's rate limit.
+    let subscribe_routes = Router::new()
+        .route("/subscribe", get(subscribe_page).post(subscribe))
+        .layer(middleware::from_fn_with_state(limiter, rate_limit));
+
     let app = Router::new()
         .route("/", get(index))
-        .route("/api/feeds", get(list_feeds))
-        .route("/api/feeds/:index", get(get_feed))
-        .route("/api/feeds/:index/items/:item_index", get(get_item))
-        .nest_service(
-            "/images",
-            ServeDir::new(db::default_store_dir().join("images")),
-        )
+        .route("/article/:id", get(article_page))
+        .merge(subscribe_routes)
+        .route(cloud::NOTIFY_PATH, get(cloud_challenge).post(cloud_notify))
+        .route("/feeds/all.xml", get(all_feed_xml))
+        .route("/feeds/starred.xml", get(starred_feed_xml))
+        .route("/feeds/history.xml", get(history_feed_xml))
+        .route("/api/openapi.json", get(openapi_json))
+        .route("/api/docs", get(swagger_ui))
+        .route("/images/:name", get(serve_image))
+        .layer(RequestBodyLimitLayer::new(MAX_REQUEST_BODY_BYTES))
+        .nest("/api/v1", api);
+    let app = if base_path.is_empty() {
+        app
+    } else {
+        // `nest` only matches the prefix itself, so the index also needs its trailing-slash form.
+        Router::new()
+            .route(&format!("{}/", base_path), get(index))
+            .nest(&base_path, app)
+    };
+    let app = app
+        .layer(TimeoutLayer::new(options.request_timeout))
+        .layer(cors_layer(&options.cors_origins)?)
+        .layer(CompressionLayer::new())
+        .layer(TraceLayer::new_for_http())
         .with_state(state);
 
-    let addr: SocketAddr = format!("{}:{}", host, port)
-        .parse()
-        .context("Invalid host/port")?;
-    let url = format!("http://{}", addr);
+    let bind = match options.bind {
+        Some(bind) => bind,
+        None => Bind::Tcp(
+            format!("{}:{}", options.host, options.port)
+                .parse()
+                .context("Invalid host/port")?,
+        ),
+    };
+    match bind {
+        Bind::Tcp(addr) => {
+            let url = format!("http://{}{}/", addr, base_path);
+            let listener = tokio::net::TcpListener::bind(addr).await?;
+            say!("Server running at {}", url);
+            if options.open_browser {
+                let _ = open::that(&url);
+            }
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .with_graceful_shutdown(shutdown_signal())
+            .await?;
+        }
+        Bind::Unix(path) => serve_unix(app, &path).await?,
+    }
 
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    println!("Server running at {}", url);
-    if open_browser {
-        let _ = open::that(&url);
+    if store.pending() > 0 {
+        say!("Waiting for {} pending store task(s)...", store.pending());
     }
-    axum::serve(listener, app).await?;
+    store.flush().await;
     Ok(())
 }
 
-async fn index() -> Html<&'static str> {
-    Html(INDEX_HTML)
+/// Removes the server's Unix socket file when it stops, however it stops.
+#[cfg(unix)]
+struct SocketFile(PathBuf);
+
+#[cfg(unix)]
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Serves `app` over HTTP/1.1 on a Unix socket at `path` until a shutdown signal, then lets
+/// open connections finish and removes the socket. A socket file left behind by a server that
+/// is no longer running is replaced; anything else at `path` is an error.
+#[cfg(unix)]
+async fn serve_unix(app: Router, path: &std::path::Path) -> Result<()> {
+    use hyper_util::{
+        rt::TokioIo, server::graceful::GracefulShutdown, service::TowerToHyperService,
+    };
+    use std::os::unix::fs::FileTypeExt;
+
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            anyhow::bail!("{} exists and is not a socket", path.display());
+        }
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            anyhow::bail!("Another server is listening on {}", path.display());
+        }
+        std::fs::remove_file(path)
+            .context(format!("Failed to remove stale socket {}", path.display()))?;
+    }
+    let listener = tokio::net::UnixListener::bind(path)
+        .context(format!("Failed to bind {}", path.display()))?;
+    let _socket = SocketFile(path.to_path_buf());
+    say!("Server running at unix:{}", path.display());
+
+    let graceful = GracefulShutdown::new();
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    tracing::warn!("Failed to accept a connection: {}", err);
+                    continue;
+                }
+            },
+            _ = &mut shutdown => break,
+        };
+        let connection = hyper::server::conn::http1::Builder::new()
+            .serve_connection(TokioIo::new(stream), TowerToHyperService::new(app.clone()));
+        let connection = graceful.watch(connection);
+        tokio::spawn(async move {
+            if let Err(err) = connection.await {
+                tracing::debug!("Connection failed: {}", err);
+            }
+        });
+    }
+    graceful.shutdown().await;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+async fn serve_unix(_app: Router, _path: &std::path::Path) -> Result<()> {
+    anyhow::bail!("Unix sockets are not supported on this platform")
+}
+
+/// The client's IP: the peer address over TCP, or over a Unix socket the address the reverse
+/// proxy forwards in `X-Forwarded-For` or `X-Real-IP`. Requests without one share a limit.
+fn client_ip(connect_info: Option<ConnectInfo<SocketAddr>>, headers: &HeaderMap) -> IpAddr {
+    if let Some(ConnectInfo(addr)) = connect_info {
+        return addr.ip();
+    }
+    let forwarded = headers
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .or_else(|| {
+            headers
+                .get("x-real-ip")
+                .and_then(|value| value.to_str().ok())
+        });
+    forwarded
+        .and_then(|ip| ip.trim().parse().ok())
+        .unwrap_or(IpAddr::V4(std::net::Ipv4Addr::UNSPECIFIED))
+}
+
+async fn rate_limit(
+    State(limiter): State<RateLimiter>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !limiter.check(client_ip(connect_info, request.headers())) {
+        return (StatusCode::TOO_MANY_REQUESTS, "Rate limit exceeded").into_response();
+    }
+    next.run(request).await
+}
+
+fn init_tracing() {
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| "rss_reader=info,tower_http=debug".into());
+    let _ = tracing_subscriber::fmt().with_env_filter(filter).try_init();
+}
+
+fn cors_layer(origins: &[String]) -> Result<CorsLayer> {
+    let layer = CorsLayer::new().allow_methods(Any).allow_headers(Any);
+    if origins.iter().any(|origin| origin == "*") {
+        return Ok(layer.allow_origin(Any));
+    }
+
+    let origins = origins
+        .iter()
+        .map(|origin| {
+            HeaderValue::from_str(origin).context(format!("Invalid CORS origin: {}", origin))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(layer.allow_origin(AllowOrigin::list(origins)))
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    say!("Shutting down...");
+}
+
+/// Serves a stored image with a long-lived cache lifetime and an ETag from its size and
+/// modification time, answering a matching `If-None-Match` with 304.
+async fn serve_image(
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Response {
+    let Some(path) = state.db.image_path(&name) else {
+        return (StatusCode::NOT_FOUND, "Image not found").into_response();
+    };
+    let metadata = match tokio::fs::metadata(&path).await {
+        Ok(metadata) if metadata.is_file() => metadata,
+        _ => return (StatusCode::NOT_FOUND, "Image not found").into_response(),
+    };
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let etag = format!("\"{:x}-{:x}\"", metadata.len(), modified);
+    let fresh = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value
+                .split(',')
+                .map(|tag| tag.trim().trim_start_matches("W/"))
+                .any(|tag| tag == "*" || tag == etag)
+        });
+    let cache_headers = [
+        (header::ETAG, etag),
+        (header::CACHE_CONTROL, IMAGE_CACHE_CONTROL.to_string()),
+    ];
+    if fresh {
+        return (StatusCode::NOT_MODIFIED, cache_headers).into_response();
+    }
+
+    let bytes = match tokio::fs::read(&path).await {
+        Ok(bytes) => bytes,
+        Err(err) => return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    };
+    let content_type = db::image_content_type(&name, &bytes);
+    (
+        cache_headers,
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff"),
+        ],
+        bytes,
+    )
+        .into_response()
+}
+
+async fn index(State(state): State<AppState>) -> Html<String> {
+    Html(
+        INDEX_HTML
+            .replace("__BASE__", &state.base_path)
+            .replace("__LANG__", i18n::locale().tag())
+            .replace("__STRINGS__", &i18n::web_strings())
+            .replace("__MATH__", MATH_SCRIPT),
+    )
+}
+
+async fn article_page(Path(id): Path<String>, State(state): State<AppState>) -> Response {
+    let record = match state.db.find_article(&id) {
+        Ok(Some(record)) => record,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Article not found").into_response(),
+        Err(err) => return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    };
+    let page = render_article_page(&state, &record).await;
+    Html(rebase_images(&page, &state.base_path)).into_response()
+}
+
+/// The standalone page of a stored article, as served at `/article/{id}`.
+async fn render_article_page(state: &AppState, record: &db::ArticleRecord) -> String {
+    let markdown = state.db.read_article_markdown(record).unwrap_or_default();
+    let content_html = state.render_cache.lock().await.get_or_render(
+        &record.id,
+        &markdown,
+        db::render_markdown_html,
+    );
+    let feed = state
+        .feeds
+        .lock()
+        .await
+        .iter()
+        .find(|feed| feed.name == record.feed_name)
+        .cloned();
+    let css = state.config.lock().await.article_css(feed.as_ref());
+    let language = article_language(&state.db, feed.as_ref(), &record.feed_name);
+    standalone_article_html(record, &content_html, &language, css.as_deref(), "")
+}
+
+/// The language an article page is marked up in: the feed's configured `language`, else the one
+/// its channel declared, else the UI's.
+pub fn article_language(database: &db::Database, feed: Option<&Feed>, feed_name: &str) -> String {
+    match feed {
+        Some(feed) => feed
+            .language_override()
+            .or_else(|| database.feed_language(&feed.name)),
+        None => database.feed_language(feed_name),
+    }
+    .unwrap_or_else(|| i18n::locale().tag().to_string())
+}
+
+/// A standalone HTML page for an article whose markdown was rendered to `content_html`, with
+/// `nav` (HTML) above it.
+pub fn standalone_article_html(
+    record: &db::ArticleRecord,
+    content_html: &str,
+    language: &str,
+    css: Option<&str>,
+    nav: &str,
+) -> String {
+    let title = escape_html(&record.title);
+    let canonical = record
+        .link
+        .as_deref()
+        .map(|link| format!(r#"<link rel="canonical" href="{}" />"#, escape_html(link)))
+        .unwrap_or_default();
+    let original = record
+        .link
+        .as_deref()
+        .map(|link| format!(r#" · <a href="{}">Original</a>"#, escape_html(link)))
+        .unwrap_or_default();
+    let meta = format!(
+        "{} · {}{}",
+        escape_html(&record.feed_name),
+        escape_html(&record.time),
+        original
+    );
+    let css = css
+        .map(|css| format!("<style>article {{ {} }}</style>", css.replace("</", "<\\/")))
+        .unwrap_or_default();
+
+    ARTICLE_HTML
+        .replace("{{lang}}", language)
+        .replace("{{title}}", &title)
+        .replace("{{canonical}}", &canonical)
+        .replace("{{meta}}", &meta)
+        .replace("{{css}}", &css)
+        .replace("{{nav}}", nav)
+        .replace("{{math}}", MATH_SCRIPT)
+        .replace("{{content}}", content_html)
+}
+
+/// Embeds the locally stored images an article page refers to as data URIs, so the page reads
+/// the same once saved outside the server. Images that can't be read keep their link.
+fn inline_images(html: &str, db: &db::Database) -> String {
+    let re = Regex::new(r#"(src=["'])/images/([A-Za-z0-9.]+)"#).unwrap();
+    re.replace_all(html, |caps: &regex::Captures<'_>| {
+        let image = db
+            .image_path(&caps[2])
+            .and_then(|path| std::fs::read(path).ok());
+        match image {
+            Some(bytes) => format!(
+                "{}data:{};base64,{}",
+                &caps[1],
+                db::image_content_type(&caps[2], &bytes),
+                BASE64_STANDARD.encode(&bytes)
+            ),
+            None => caps[0].to_string(),
+        }
+    })
+    .into_owned()
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ArchiveQuery {
+    /// Only articles from this feed.
+    feed: Option<String>,
+    /// Published or updated at or after this time (RFC 3339 or `YYYY-MM-DD`).
+    since: Option<String>,
+    /// Published or updated before this time (RFC 3339 or `YYYY-MM-DD`).
+    until: Option<String>,
+    /// Case-insensitive title search.
+    q: Option<String>,
+    /// Maximum number of articles returned (default 100).
+    limit: Option<usize>,
+    /// Order of the articles (default `time`, or `score` when the config sets
+    /// `scoring.sort_by_score`).
+    sort: Option<TimelineSort>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct TimelineQuery {
+    /// Order of the articles (default `time`, or `score` when the config sets
+    /// `scoring.sort_by_score`).
+    sort: Option<TimelineSort>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+enum TimelineSort {
+    /// Newest first.
+    Time,
+    /// Highest interest score first (see `[scoring]` in the config).
+    Score,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct PageQuery {
+    /// Index of the first item returned (default 0).
+    offset: Option<usize>,
+    /// Maximum number of items returned (default: the config's `item_limit`, or all).
+    limit: Option<usize>,
+    /// Only the items of the latest fetch, without the archived history (default false).
+    latest: Option<bool>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ItemQuery {
+    /// Which content to render (default `feed`).
+    content: Option<ContentSource>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+enum ContentSource {
+    /// The item's content as carried by the feed and stored.
+    Feed,
+    /// The article extracted from the item's web page, for feeds with `full_content`.
+    Full,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct MuteQuery {
+    /// Days to mute the feed for (default 7).
+    days: Option<i64>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct UpdatesQuery {
+    /// Count articles published or updated after this time (RFC 3339, `YYYY-MM-DD` or Unix
+    /// seconds).
+    since: String,
+}
+
+/// Stored articles newer than `since`, per subscribed feed.
+#[derive(Serialize, ToSchema)]
+struct Updates {
+    /// `since` as RFC 3339.
+    since: String,
+    /// The server's current time; pass it as the next `since` to count only what arrived since.
+    now: String,
+    total: usize,
+    /// Only feeds with new articles, in config order.
+    feeds: Vec<FeedUpdates>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct FeedUpdates {
+    /// Feed index, as used by `/api/v1/feeds/{index}`.
+    index: usize,
+    name: String,
+    new_items: usize,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct StatsQuery {
+    /// Month to report as `YYYY-MM` (default: the current month).
+    month: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct RunsQuery {
+    /// Number of runs to return, newest first (default: all that are kept).
+    limit: Option<usize>,
+}
+
+/// Bytes a feed downloaded in a month, with its configured budget.
+#[derive(Serialize, ToSchema)]
+struct FeedBandwidth {
+    feed_name: String,
+    month: String,
+    feed_bytes: u64,
+    image_bytes: u64,
+    total_bytes: u64,
+    /// Monthly budget in bytes; once reached, images are no longer localized.
+    budget_bytes: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct SubscribeQuery {
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct SubscribeForm {
+    name: String,
+    url: String,
+    #[serde(default)]
+    force: bool,
+}
+
+async fn subscribe_page(Query(query): Query<SubscribeQuery>) -> Response {
+    let feeds = match feed::discover_feeds(&query.url).await {
+        Ok(feeds) => feeds,
+        Err(err) => {
+            return (
+                StatusCode::BAD_GATEWAY,
+                Html(render_subscribe_page(&format!(
+                    "<p>Could not load {}: {}</p>",
+                    escape_html(&query.url),
+                    escape_html(&err.to_string())
+                ))),
+            )
+                .into_response()
+        }
+    };
+
+    let body = if feeds.is_empty() {
+        format!("<p>No RSS feeds found on {}.</p>", escape_html(&query.url))
+    } else {
+        feeds
+            .iter()
+            .map(|found| {
+                format!(
+                    r#"<form method="post" action="subscribe">
+  <input type="hidden" name="url" value="{url}" />
+  <label>Name <input name="name" value="{title}" /></label>
+  <small>{url}</small>
+  <button type="submit">Subscribe</button>
+</form>"#,
+                    url = escape_html(&found.url),
+                    title = escape_html(&found.title),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    Html(render_subscribe_page(&body)).into_response()
+}
+
+/// Whether a request's `Origin`, or failing that its `Referer`, names the host it was sent to,
+/// so another site can't post the subscribe form on a visitor's behalf.
+fn is_same_origin(headers: &HeaderMap) -> bool {
+    let Some(host) = headers
+        .get(header::HOST)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return false;
+    };
+    let source = headers
+        .get(header::ORIGIN)
+        .or_else(|| headers.get(header::REFERER))
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| Url::parse(value).ok());
+    source.is_some_and(|url| {
+        let authority = match (url.host_str(), url.port()) {
+            (Some(name), Some(port)) => format!("{}:{}", name, port),
+            (Some(name), None) => name.to_string(),
+            (None, _) => return false,
+        };
+        authority.eq_ignore_ascii_case(host)
+    })
 }
 
+async fn subscribe(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(form): Form<SubscribeForm>,
+) -> Response {
+    if !is_same_origin(&headers) {
+        return (
+            StatusCode::FORBIDDEN,
+            "Cross-origin subscribe requests are refused",
+        )
+            .into_response();
+    }
+    // Only URLs are fetched, so a form can't have the server read a file or its stdin.
+    if feed::check_remote_url(&form.url).is_err() {
+        let body = format!(
+            "<p>{} is not an http or https URL.</p>",
+            escape_html(&form.url)
+        );
+        return (StatusCode::BAD_REQUEST, Html(render_subscribe_page(&body))).into_response();
+    }
+    let duplicate = state.config.lock().await.find_duplicate(&form.url);
+    if let Some(existing) = duplicate.filter(|_| !form.force) {
+        let body = format!(
+            r#"<p>Already subscribed to this feed as <strong>{name}</strong> ({existing}).</p>
+<form method="post" action="subscribe">
+  <input type="hidden" name="url" value="{url}" />
+  <input type="hidden" name="name" value="{new_name}" />
+  <input type="hidden" name="force" value="true" />
+  <button type="submit">Subscribe anyway</button>
+</form>"#,
+            name = escape_html(&existing.name),
+            existing = escape_html(&existing.display_url()),
+            url = escape_html(&form.url),
+            new_name = escape_html(&form.name),
+        );
+        return (StatusCode::CONFLICT, Html(render_subscribe_page(&body))).into_response();
+    }
+
+    let item = if form.name.trim().is_empty() {
+        match feed::fetch_channel(state.fetcher.as_ref(), &form.url).await {
+            Ok(channel) => FeedItem::from_channel(&form.url, &channel),
+            Err(_) => FeedItem::new(&form.url, &form.url),
+        }
+    } else {
+        FeedItem::new(form.name.trim(), &form.url)
+    };
+
+    let mut config = state.config.lock().await;
+    config.add_feed_item(item);
+    if let Err(err) = config.save(&state.config_path) {
+        return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response();
+    }
+
+    let feeds = config.get_all_feeds();
+    *state.cache.lock().await = vec![None; feeds.len()];
+    *state.feeds.lock().await = feeds;
+    Redirect::to(&format!("{}/", state.base_path)).into_response()
+}
+
+fn render_subscribe_page(body: &str) -> String {
+    SUBSCRIBE_HTML.replace("{{body}}", body)
+}
+
+async fn openapi_json(State(state): State<AppState>) -> Json<utoipa::openapi::OpenApi> {
+    let mut doc = ApiDoc::openapi();
+    if !state.base_path.is_empty() {
+        doc.servers = Some(vec![utoipa::openapi::Server::new(&state.base_path)]);
+    }
+    Json(doc)
+}
+
+async fn swagger_ui() -> Html<&'static str> {
+    Html(SWAGGER_HTML)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/feeds",
+    responses((status = 200, description = "Configured feeds", body = [FeedInfo]))
+)]
 async fn list_feeds(State(state): State<AppState>) -> Json<Vec<FeedInfo>> {
+    let snoozes = state.db.snoozes().unwrap_or_default();
     let feeds = state
         .feeds
+        .lock()
+        .await
         .iter()
         .map(|feed| FeedInfo {
             name: feed.name.clone(),
-            url: feed.url.clone(),
+            url: feed.display_url(),
             is_rsshub: feed.is_rsshub,
+            category: feed.category.clone(),
+            muted_until: snoozes
+                .iter()
+                .filter(|snooze| snooze.mutes(&feed.name))
+                .map(|snooze| snooze.until.as_str())
+                .max()
+                .and_then(|until| local_date(Some(until))),
+            language: feed
+                .language_override()
+                .or_else(|| state.db.feed_language(&feed.name)),
         })
         .collect();
     Json(feeds)
 }
 
-async fn get_feed(Path(index): Path<usize>, State(state): State<AppState>) -> impl IntoResponse {
-    let feed = match state.feeds.get(index) {
-        Some(feed) => feed.clone(),
+#[utoipa::path(
+    post,
+    path = "/api/v1/feeds/{index}/mute",
+    params(("index" = usize, Path, description = "Feed index"), MuteQuery),
+    responses(
+        (status = 204, description = "Feed muted"),
+        (status = 404, description = "Feed not found")
+    )
+)]
+async fn mute_feed(
+    Path(index): Path<usize>,
+    Query(query): Query<MuteQuery>,
+    State(state): State<AppState>,
+) -> Response {
+    let Some(feed) = state.feeds.lock().await.get(index).cloned() else {
+        return (StatusCode::NOT_FOUND, "Feed not found").into_response();
+    };
+    let until = chrono::Utc::now() + chrono::Duration::days(query.days.unwrap_or(MUTE_DAYS));
+    let snooze = db::Snooze {
+        feed_name: Some(feed.name),
+        pattern: None,
+        until: until.to_rfc3339(),
+    };
+    match state.db.snooze(snooze) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/feeds/{index}/mute",
+    params(("index" = usize, Path, description = "Feed index")),
+    responses(
+        (status = 204, description = "Feed unmuted"),
+        (status = 404, description = "Feed not found")
+    )
+)]
+async fn unmute_feed(Path(index): Path<usize>, State(state): State<AppState>) -> Response {
+    let Some(feed) = state.feeds.lock().await.get(index).cloned() else {
+        return (StatusCode::NOT_FOUND, "Feed not found").into_response();
+    };
+    match state.db.unsnooze(Some(&feed.name), None) {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/feeds/{index}",
+    params(("index" = usize, Path, description = "Feed index"), PageQuery),
+    responses(
+        (status = 200, description = "Feed items", body = FeedResponse),
+        (status = 404, description = "Feed not found"),
+        (status = 502, description = "Upstream fetch failed")
+    )
+)]
+async fn get_feed(
+    Path(index): Path<usize>,
+    Query(page): Query<PageQuery>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let feed = match state.feeds.lock().await.get(index).cloned() {
+        Some(feed) => feed,
         None => return (StatusCode::NOT_FOUND, "Feed not found").into_response(),
     };
 
-    let channel = match get_or_fetch_channel(index, &feed, &state).await {
-        Ok(channel) => channel,
+    let cached = match get_or_fetch_channel(index, &feed, &state).await {
+        Ok(cached) => cached,
         Err(response) => return response,
     };
 
-    let db = state.db.clone();
-    let feed_name = feed.name.clone();
-    let feed_url = feed.url.clone();
-    let channel_clone = channel.clone();
-    tokio::spawn(async move {
-        let _ = db
-            .store_channel(&feed_name, &feed_url, &channel_clone)
-            .await;
-    });
+    if cached.offline.is_none() {
+        spawn_store(&state, &feed, &cached.channel);
+    }
+    let limit = page_limit(&page, &state).await;
+    let snoozes = state.db.snoozes().unwrap_or_default();
+    Json(channel_to_response(&cached, &feed, &snoozes, &page, limit)).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/feeds/{index}/meta",
+    params(("index" = usize, Path, description = "Feed index")),
+    responses(
+        (status = 200, description = "Channel details", body = ChannelMeta),
+        (status = 404, description = "Feed not found"),
+        (status = 502, description = "Upstream fetch failed")
+    )
+)]
+async fn get_feed_meta(
+    Path(index): Path<usize>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let feed = match state.feeds.lock().await.get(index).cloned() {
+        Some(feed) => feed,
+        None => return (StatusCode::NOT_FOUND, "Feed not found").into_response(),
+    };
+
+    let (channel, item_count) = match get_or_fetch_channel(index, &feed, &state).await {
+        Ok(cached) => (cached.channel, cached.latest_items),
+        Err(response) => return response,
+    };
 
-    Json(channel_to_response(&channel)).into_response()
+    let text = |value: &str| Some(value.trim().to_string()).filter(|value| !value.is_empty());
+    Json(ChannelMeta {
+        title: channel.title().to_string(),
+        description: text(channel.description()),
+        site_link: text(channel.link()),
+        language: channel.language().and_then(text),
+        last_build_date: channel.last_build_date().and_then(text),
+        pub_date: channel.pub_date().and_then(text),
+        image: channel.image().and_then(|image| text(image.url())),
+        ttl: channel.ttl().and_then(text),
+        generator: channel.generator().and_then(text),
+        item_count,
+    })
+    .into_response()
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/feeds/{index}/refresh",
+    params(("index" = usize, Path, description = "Feed index"), PageQuery),
+    responses(
+        (status = 200, description = "Freshly fetched feed items", body = FeedResponse),
+        (status = 404, description = "Feed not found"),
+        (status = 502, description = "Upstream fetch failed")
+    )
+)]
+async fn refresh_feed(
+    Path(index): Path<usize>,
+    Query(page): Query<PageQuery>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let feed = match state.feeds.lock().await.get(index).cloned() {
+        Some(feed) => feed,
+        None => return (StatusCode::NOT_FOUND, "Feed not found").into_response(),
+    };
+
+    let cached = match fetch_and_cache_channel(index, &feed, &state).await {
+        Ok(cached) => cached,
+        Err(response) => return response,
+    };
+
+    if cached.offline.is_none() {
+        spawn_store(&state, &feed, &cached.channel);
+    }
+    let limit = page_limit(&page, &state).await;
+    let snoozes = state.db.snoozes().unwrap_or_default();
+    Json(channel_to_response(&cached, &feed, &snoozes, &page, limit)).into_response()
+}
+
+/// The requested page size, falling back to the config's `item_limit`.
+async fn page_limit(page: &PageQuery, state: &AppState) -> Option<usize> {
+    match page.limit {
+        Some(limit) => Some(limit),
+        None => state.config.lock().await.item_limit,
+    }
+}
+
+fn spawn_store(state: &AppState, feed: &Feed, channel: &Channel) {
+    state
+        .store
+        .store_channel(&feed.name, &feed.url, channel.clone(), feed.store_options());
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/feeds/{index}/items/{item_index}",
+    params(
+        ("index" = usize, Path, description = "Feed index"),
+        ("item_index" = usize, Path, description = "Item index within the feed"),
+        ItemQuery
+    ),
+    responses(
+        (status = 200, description = "Rendered article", body = ItemContent),
+        (status = 400, description = "Full content requested for a feed without `full_content`"),
+        (status = 404, description = "Feed or item not found"),
+        (status = 502, description = "Upstream fetch or full content extraction failed")
+    )
+)]
 async fn get_item(
     Path((index, item_index)): Path<(usize, usize)>,
+    Query(query): Query<ItemQuery>,
     State(state): State<AppState>,
 ) -> impl IntoResponse {
-    let feed = match state.feeds.get(index) {
-        Some(feed) => feed.clone(),
+    let feed = match state.feeds.lock().await.get(index).cloned() {
+        Some(feed) => feed,
         None => return (StatusCode::NOT_FOUND, "Feed not found").into_response(),
     };
 
     let channel = match get_or_fetch_channel(index, &feed, &state).await {
-        Ok(channel) => channel,
+        Ok(cached) => cached.channel,
         Err(response) => return response,
     };
+    let language = channel_language(&feed, &channel);
 
     let item = match channel.items().get(item_index) {
         Some(item) => item,
         None => return (StatusCode::NOT_FOUND, "Item not found").into_response(),
     };
 
-    let markdown = match state.db.read_item_markdown(&feed.name, &feed.url, item) {
+    let item_id = db::item_id(&feed.name, &feed.url, item);
+    let css = state.config.lock().await.article_css(Some(&feed));
+    let full_content = feed.full_content && item.link().is_some();
+    let source = query.content.unwrap_or(ContentSource::Feed);
+    if source == ContentSource::Full && !full_content {
+        return (
+            StatusCode::BAD_REQUEST,
+            "Full content is not enabled for this feed",
+        )
+            .into_response();
+    }
+    let stored = match source {
+        ContentSource::Feed => state.db.read_item_markdown(&feed.name, &feed.url, item),
+        ContentSource::Full => {
+            match readability::fetch_markdown(item.link().unwrap_or_default()).await {
+                Ok(markdown) => Some(markdown),
+                Err(err) => return (StatusCode::BAD_GATEWAY, format!("{:#}", err)).into_response(),
+            }
+        }
+    };
+    let markdown = match stored {
         Some(markdown) => markdown,
         None => {
             return Json(ItemContent {
+                id: item_id,
                 title: item.title().unwrap_or("No Title").to_string(),
                 link: item.link().map(|s| s.to_string()),
+                author: item.author().map(str::to_string),
                 pub_date: item.pub_date().map(|s| s.to_string()),
+                pub_date_local: local_date(item.pub_date()),
+                updated: db::item_updated(item),
+                updated_local: local_date(db::item_updated(item).as_deref()),
                 content_html: "<em>Content is still processing.</em>".to_string(),
+                trackers_removed: 0,
+                starred: false,
+                css,
+                language,
+                full_content,
+                toc: Vec::new(),
+                position: None,
+                previous_copy: None,
             })
             .into_response();
         }
@@ -170,49 +1431,859 @@ async fn get_item(
     let content_html = if markdown.trim().is_empty() {
         "<em>No content.</em>".to_string()
     } else {
-        db::render_markdown_html(&markdown)
+        let key = match source {
+            ContentSource::Feed => item_id.clone(),
+            ContentSource::Full => format!("{}#full", item_id),
+        };
+        let html = state.render_cache.lock().await.get_or_render(
+            &key,
+            &markdown,
+            db::render_markdown_html,
+        );
+        rebase_images(&html, &state.base_path)
     };
 
+    let toc = db::table_of_contents(&content_html)
+        .into_iter()
+        .map(|heading| TocEntry {
+            level: heading.level,
+            id: heading.id,
+            text: heading.text,
+        })
+        .collect();
+    let _ = state.db.mark_read(&item_id);
+    let _ = state.db.record_open(&item_id);
+    let starred = is_starred(&state.db, &item_id);
+    let position = state.db.reading_position(&item_id).ok().flatten();
+    let previous_copy = item
+        .link()
+        .and_then(|link| state.db.previous_copy(&item_id, link).ok().flatten())
+        .map(|copy| PreviousCopy {
+            id: copy.record.id,
+            feed_name: copy.record.feed_name,
+            published: copy.record.time,
+            read_at: copy.read_at,
+        });
     Json(ItemContent {
+        id: item_id,
         title: item.title().unwrap_or("No Title").to_string(),
         link: item.link().map(|s| s.to_string()),
+        author: item.author().map(str::to_string),
         pub_date: item.pub_date().map(|s| s.to_string()),
+        pub_date_local: local_date(item.pub_date()),
+        updated: db::item_updated(item),
+        updated_local: local_date(db::item_updated(item).as_deref()),
+        trackers_removed: hygiene::removed_count(&markdown),
         content_html,
+        starred,
+        css,
+        language,
+        full_content,
+        toc,
+        position,
+        previous_copy,
+    })
+    .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/feeds/{index}/items/{item_index}/discussion",
+    params(
+        ("index" = usize, Path, description = "Feed index"),
+        ("item_index" = usize, Path, description = "Item index within the feed")
+    ),
+    responses(
+        (status = 200, description = "Comment count and thread link", body = Discussion),
+        (status = 404, description = "Feed or item not found, or no known discussion site"),
+        (status = 502, description = "Upstream fetch failed")
+    )
+)]
+async fn get_item_discussion(
+    Path((index, item_index)): Path<(usize, usize)>,
+    State(state): State<AppState>,
+) -> Response {
+    let feed = match state.feeds.lock().await.get(index).cloned() {
+        Some(feed) => feed,
+        None => return (StatusCode::NOT_FOUND, "Feed not found").into_response(),
+    };
+
+    let channel = match get_or_fetch_channel(index, &feed, &state).await {
+        Ok(cached) => cached.channel,
+        Err(response) => return response,
+    };
+
+    let item = match channel.items().get(item_index) {
+        Some(item) => item,
+        None => return (StatusCode::NOT_FOUND, "Item not found").into_response(),
+    };
+
+    match enrich::fetch_discussion(item).await {
+        Ok(Some(discussion)) => Json(discussion).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "No discussion found").into_response(),
+        Err(err) => (StatusCode::BAD_GATEWAY, err.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/articles",
+    params(ArchiveQuery),
+    responses((status = 200, description = "Stored articles, newest or highest scoring first", body = [ArchivedArticle]))
+)]
+async fn list_archive(
+    Query(query): Query<ArchiveQuery>,
+    State(state): State<AppState>,
+) -> Response {
+    let limit = query.limit.unwrap_or(ARCHIVE_PAGE_LIMIT);
+    let by_score = sorts_by_score(&state, query.sort).await;
+    let query = db::ArticleQuery {
+        feed_name: query.feed,
+        since: query.since,
+        until: query.until,
+        text: query.q,
+        limit: (!by_score).then_some(limit),
+    };
+    let records = match state.db.query_articles(&query) {
+        Ok(records) => records,
+        Err(err) => return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    };
+    let scored: Vec<(Option<Score>, db::ArticleRecord)> = if by_score {
+        match scorer(&state).await {
+            Ok(scorer) => scorer
+                .rank(records)
+                .into_iter()
+                .take(limit)
+                .map(|(score, record)| (Some(score), record))
+                .collect(),
+            Err(err) => {
+                return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+            }
+        }
+    } else {
+        records.into_iter().map(|record| (None, record)).collect()
+    };
+    Json(
+        scored
+            .into_iter()
+            .map(|(score, record)| ArchivedArticle {
+                id: record.id,
+                title: record.title,
+                feed_name: record.feed_name,
+                link: record.link,
+                published: record.time,
+                updated: record.updated,
+                score,
+            })
+            .collect::<Vec<_>>(),
+    )
+    .into_response()
+}
+
+/// Whether a timeline is ordered by score: as requested, else as the config's `[scoring]` says.
+async fn sorts_by_score(state: &AppState, requested: Option<TimelineSort>) -> bool {
+    match requested {
+        Some(sort) => sort == TimelineSort::Score,
+        None => state
+            .config
+            .lock()
+            .await
+            .scoring
+            .as_ref()
+            .is_some_and(|scoring| scoring.sort_by_score),
+    }
+}
+
+async fn scorer(state: &AppState) -> Result<Scorer> {
+    let config = state.config.lock().await.clone();
+    Scorer::new(&config, &state.db)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/runs",
+    params(RunsQuery),
+    responses((
+        status = 200,
+        description = "Journaled `refresh` runs, newest first, with what each did per feed",
+        body = [db::Run]
+    ))
+)]
+async fn list_runs(Query(query): Query<RunsQuery>, State(state): State<AppState>) -> Response {
+    match state.db.runs() {
+        Ok(mut runs) => {
+            runs.truncate(query.limit.unwrap_or(runs.len()));
+            Json(runs).into_response()
+        }
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/stats",
+    params(StatsQuery),
+    responses((status = 200, description = "Bandwidth used per feed", body = [FeedBandwidth]))
+)]
+async fn bandwidth_stats(
+    Query(query): Query<StatsQuery>,
+    State(state): State<AppState>,
+) -> Response {
+    let month = query.month.unwrap_or_else(db::current_month);
+    let records = match state.db.bandwidth(&month) {
+        Ok(records) => records,
+        Err(err) => return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    };
+    let feeds = state.feeds.lock().await;
+    Json(
+        records
+            .into_iter()
+            .map(|record| FeedBandwidth {
+                total_bytes: record.total(),
+                budget_bytes: feeds
+                    .iter()
+                    .find(|feed| feed.name == record.feed_name)
+                    .and_then(Feed::monthly_budget_bytes),
+                feed_name: record.feed_name,
+                month: record.month,
+                feed_bytes: record.feed_bytes,
+                image_bytes: record.image_bytes,
+            })
+            .collect::<Vec<_>>(),
+    )
+    .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/updates",
+    params(UpdatesQuery),
+    responses(
+        (status = 200, description = "New article counts per feed", body = Updates),
+        (status = 400, description = "Unrecognized `since`")
+    )
+)]
+async fn list_updates(
+    Query(query): Query<UpdatesQuery>,
+    State(state): State<AppState>,
+) -> Response {
+    let since = query
+        .since
+        .parse::<i64>()
+        .ok()
+        .and_then(|seconds| chrono::DateTime::from_timestamp(seconds, 0))
+        .or_else(|| db::parse_query_time(&query.since));
+    let Some(since) = since else {
+        return (StatusCode::BAD_REQUEST, "Unrecognized since").into_response();
+    };
+    let now = chrono::Utc::now();
+    let counts = match state.db.new_item_counts(since) {
+        Ok(counts) => counts,
+        Err(err) => return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    };
+    let feeds: Vec<FeedUpdates> = state
+        .feeds
+        .lock()
+        .await
+        .iter()
+        .enumerate()
+        .filter_map(|(index, feed)| {
+            let new_items = *counts.get(&feed.name)?;
+            Some(FeedUpdates {
+                index,
+                name: feed.name.clone(),
+                new_items,
+            })
+        })
+        .collect();
+    Json(Updates {
+        since: since.to_rfc3339(),
+        now: now.to_rfc3339(),
+        total: feeds.iter().map(|feed| feed.new_items).sum(),
+        feeds,
     })
     .into_response()
 }
 
+fn is_starred(db: &db::Database, id: &str) -> bool {
+    db.starred_ids()
+        .map(|ids| ids.contains(id))
+        .unwrap_or(false)
+}
+
+fn local_date(raw: Option<&str>) -> Option<String> {
+    dates::parse(raw?).map(|date| dates::absolute(&date))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/articles/{id}/star",
+    params(("id" = String, Path, description = "Article ID")),
+    responses((status = 204, description = "Article starred"))
+)]
+async fn star_article(Path(id): Path<String>, State(state): State<AppState>) -> Response {
+    set_starred(&state, &id, true)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/articles/{id}/star",
+    params(("id" = String, Path, description = "Article ID")),
+    responses((status = 204, description = "Article unstarred"))
+)]
+async fn unstar_article(Path(id): Path<String>, State(state): State<AppState>) -> Response {
+    set_starred(&state, &id, false)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/articles/{id}/position",
+    params(("id" = String, Path, description = "Article ID")),
+    request_body = ReadingPosition,
+    responses((status = 204, description = "Reading position saved"))
+)]
+async fn set_reading_position(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Json(body): Json<ReadingPosition>,
+) -> Response {
+    match state.db.set_reading_position(&id, body.position) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/articles/{id}/related",
+    params(("id" = String, Path, description = "Article ID")),
+    responses((
+        status = 200,
+        description = "Stored articles sharing the most words with this one, most similar first",
+        body = [RelatedArticle]
+    ))
+)]
+async fn related_articles(Path(id): Path<String>, State(state): State<AppState>) -> Response {
+    let db = state.db.clone();
+    let related = tokio::task::spawn_blocking(move || {
+        related::related_articles(&db, &id, related::RELATED_LIMIT)
+    })
+    .await;
+    match related {
+        Ok(Ok(related)) => Json(
+            related
+                .into_iter()
+                .map(|(similarity, record)| RelatedArticle {
+                    id: record.id,
+                    title: record.title,
+                    feed_name: record.feed_name,
+                    link: record.link,
+                    published: record.time,
+                    similarity,
+                })
+                .collect::<Vec<_>>(),
+        )
+        .into_response(),
+        Ok(Err(err)) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ExportQuery {
+    format: ExportFormat,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+enum ExportFormat {
+    /// The stored markdown file.
+    Markdown,
+    /// The article page with its stored images embedded, viewable offline.
+    Html,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/articles/{id}/export",
+    params(("id" = String, Path, description = "Article ID"), ExportQuery),
+    responses(
+        (
+            status = 200,
+            description = "Article as a file download",
+            content((String = "text/markdown"), (String = "text/html"))
+        ),
+        (status = 404, description = "Article not found")
+    )
+)]
+async fn export_article(
+    Path(id): Path<String>,
+    Query(query): Query<ExportQuery>,
+    State(state): State<AppState>,
+) -> Response {
+    let record = match state.db.find_article(&id) {
+        Ok(Some(record)) => record,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Article not found").into_response(),
+        Err(err) => return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    };
+    let (body, content_type, extension) = match query.format {
+        ExportFormat::Markdown => match state.db.read_article_markdown(&record) {
+            Some(markdown) => (markdown, "text/markdown; charset=utf-8", "md"),
+            None => return (StatusCode::NOT_FOUND, "Article file not found").into_response(),
+        },
+        ExportFormat::Html => (
+            inline_images(&render_article_page(&state, &record).await, &state.db),
+            "text/html; charset=utf-8",
+            "html",
+        ),
+    };
+    let stem = record
+        .path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(&record.id);
+    let disposition = format!(r#"attachment; filename="{}.{}""#, stem, extension);
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+fn set_starred(state: &AppState, id: &str, starred: bool) -> Response {
+    match state.db.set_starred(id, starred) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
+
+async fn sync_state(State(state): State<AppState>) -> Response {
+    match state.db.item_states() {
+        Ok(states) => Json(states.into_values().collect::<Vec<_>>()).into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
+
+async fn merge_sync_state(
+    State(state): State<AppState>,
+    Json(incoming): Json<Vec<db::ItemState>>,
+) -> Response {
+    match state.db.merge_item_states(incoming) {
+        Ok((merged, _)) => Json(merged).into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
+
+async fn list_sync_articles(State(state): State<AppState>) -> Response {
+    match state.db.list_articles() {
+        Ok(records) => Json(
+            records
+                .iter()
+                .map(|record| SyncArticle::from_record(record, None))
+                .collect::<Vec<_>>(),
+        )
+        .into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
+
+async fn get_sync_article(Path(id): Path<String>, State(state): State<AppState>) -> Response {
+    match state.db.find_article(&id) {
+        Ok(Some(record)) => {
+            let markdown = state.db.read_article_markdown(&record);
+            Json(SyncArticle::from_record(&record, markdown)).into_response()
+        }
+        Ok(None) => (StatusCode::NOT_FOUND, "Article not found").into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
+
+async fn import_sync_article(
+    State(state): State<AppState>,
+    Json(article): Json<SyncArticle>,
+) -> Response {
+    let markdown = article.markdown.clone().unwrap_or_default();
+    match state
+        .store
+        .import_article(article.to_record(), markdown)
+        .await
+    {
+        Ok(true) => StatusCode::CREATED.into_response(),
+        Ok(false) => StatusCode::OK.into_response(),
+        Err(err) => (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    }
+}
+
+async fn import_sync_image(
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+    body: axum::body::Bytes,
+) -> Response {
+    match state.db.write_image(&name, &body) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(err) => (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    }
+}
+
+/// Every stored article except snoozed ones, newest or highest scoring first.
+async fn all_feed_xml(
+    headers: HeaderMap,
+    Query(query): Query<TimelineQuery>,
+    State(state): State<AppState>,
+) -> Response {
+    let mut records: Vec<_> = match (state.db.list_articles(), state.db.snoozes()) {
+        (Ok(records), Ok(snoozes)) => records
+            .into_iter()
+            .filter(|record| !db::is_snoozed(&snoozes, &record.feed_name, &record.title))
+            .collect(),
+        (Err(err), _) | (_, Err(err)) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+        }
+    };
+    if sorts_by_score(&state, query.sort).await {
+        records = match scorer(&state).await {
+            Ok(scorer) => scorer
+                .rank(records)
+                .into_iter()
+                .map(|(_, record)| record)
+                .collect(),
+            Err(err) => {
+                return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+            }
+        };
+    } else {
+        records.sort_by(|a, b| b.last_modified().cmp(a.last_modified()));
+    }
+    render_aggregate_feed(&state, &headers, "RSS Reader: All articles", records)
+}
+
+async fn starred_feed_xml(headers: HeaderMap, State(state): State<AppState>) -> Response {
+    let mut records: Vec<_> = match (state.db.list_articles(), state.db.starred_ids()) {
+        (Ok(records), Ok(starred)) => records
+            .into_iter()
+            .filter(|record| starred.contains(&record.id))
+            .collect(),
+        (Err(err), _) | (_, Err(err)) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+        }
+    };
+    records.sort_by(|a, b| b.last_modified().cmp(a.last_modified()));
+    render_aggregate_feed(&state, &headers, "RSS Reader: Starred", records)
+}
+
+/// Recently read articles, most recently opened first.
+async fn history_feed_xml(headers: HeaderMap, State(state): State<AppState>) -> Response {
+    let records = match state.db.reading_history() {
+        Ok(history) => history.into_iter().map(|(_, record)| record).collect(),
+        Err(err) => return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    };
+    render_aggregate_feed(&state, &headers, "RSS Reader: Recently read", records)
+}
+
+/// Republishes `records` as RSS, keeping their order. Links point at the `--public-url`, or
+/// at the request's Host when the server has none.
+fn render_aggregate_feed(
+    state: &AppState,
+    headers: &HeaderMap,
+    title: &str,
+    records: Vec<db::ArticleRecord>,
+) -> Response {
+    let base_url = match &state.public_url {
+        Some(url) => url.as_str().trim_end_matches('/').to_string(),
+        None => headers
+            .get(header::HOST)
+            .and_then(|host| host.to_str().ok())
+            .map(|host| format!("http://{}{}", host, state.base_path))
+            .unwrap_or_else(|| state.base_path.clone()),
+    };
+
+    let items: Vec<rss::Item> = records
+        .into_iter()
+        .take(AGGREGATE_FEED_LIMIT)
+        .map(|record| {
+            let permalink = format!("{}/article/{}", base_url, record.id);
+            let markdown = state.db.read_article_markdown(&record).unwrap_or_default();
+            rss::ItemBuilder::default()
+                .title(Some(record.title.clone()))
+                .link(Some(
+                    record.link.clone().unwrap_or_else(|| permalink.clone()),
+                ))
+                .guid(Some(rss::Guid {
+                    value: permalink,
+                    permalink: true,
+                }))
+                .pub_date(
+                    chrono::DateTime::parse_from_rfc3339(&record.time)
+                        .ok()
+                        .map(|time| time.to_rfc2822()),
+                )
+                .dublin_core_ext(record.updated.clone().map(|updated| {
+                    rss::extension::dublincore::DublinCoreExtension {
+                        dates: vec![updated],
+                        ..Default::default()
+                    }
+                }))
+                .categories(vec![rss::Category {
+                    name: record.feed_name.clone(),
+                    domain: None,
+                }])
+                .description(Some(rebase_images(
+                    &db::render_markdown_html(&markdown),
+                    &base_url,
+                )))
+                .build()
+        })
+        .collect();
+
+    let channel = rss::ChannelBuilder::default()
+        .title(title)
+        .link(base_url)
+        .description("Articles republished from the local archive")
+        .items(items)
+        .build();
+
+    (
+        [(header::CONTENT_TYPE, "application/rss+xml; charset=utf-8")],
+        channel.to_string(),
+    )
+        .into_response()
+}
+
 async fn get_or_fetch_channel(
     index: usize,
     feed: &Feed,
     state: &AppState,
-) -> Result<Channel, axum::response::Response> {
-    if let Some(cached) = state.cache.lock().await.get(index).cloned().flatten() {
-        return Ok(cached);
+) -> Result<CachedChannel, axum::response::Response> {
+    let cached = state.cache.lock().await.get(index).cloned().flatten();
+    if let Some(cached) = cached {
+        let max_age = match cached.offline {
+            Some(_) => OFFLINE_RETRY_INTERVAL.min(feed.cache_max_age()),
+            None => feed.cache_max_age(),
+        };
+        if cached.fetched_at.elapsed() < max_age {
+            return Ok(cached);
+        }
+        // Feeds pushing their changes through rssCloud are refetched when notified.
+        let pushed = match feed::build_feed_url(feed) {
+            Ok(url) => state.cloud.lock().await.is_active(&url),
+            Err(_) => false,
+        };
+        if cached.offline.is_none() && pushed {
+            return Ok(cached);
+        }
+        // Until the channel's `<ttl>` passes, and during its `<skipHours>`/`<skipDays>`, keep
+        // serving the cached copy; the refresh endpoint still fetches on request.
+        let fetched_at = chrono::Utc::now()
+            - chrono::TimeDelta::from_std(cached.fetched_at.elapsed()).unwrap_or_default();
+        if cached.offline.is_none()
+            && feed::UpdateHints::from_channel(&cached.channel)
+                .next_fetch(fetched_at, chrono::Utc::now())
+                .is_some()
+        {
+            return Ok(cached);
+        }
+        if cached.offline.is_none() && feed::is_unchanged(state.fetcher.as_ref(), feed).await {
+            let cached = CachedChannel {
+                fetched_at: Instant::now(),
+                ..cached
+            };
+            if let Some(slot) = state.cache.lock().await.get_mut(index) {
+                *slot = Some(cached.clone());
+            }
+            return Ok(cached);
+        }
+    }
+
+    fetch_and_cache_channel(index, feed, state).await
+}
+
+/// Asks the channel's rssCloud, if it has one the server can use, to notify the server of
+/// changes, unless a subscription is already current.
+fn subscribe_to_cloud(state: &AppState, feed: &Feed, channel: &Channel) {
+    let (Some(callback), Some(cloud)) = (state.cloud_callback.clone(), channel.cloud().cloned())
+    else {
+        return;
+    };
+    let Ok(feed_url) = feed::build_feed_url(feed) else {
+        return;
+    };
+    if !cloud::is_supported(&cloud) {
+        return;
+    }
+    let subscriptions = state.cloud.clone();
+    tokio::spawn(async move {
+        if !subscriptions.lock().await.start(&feed_url) {
+            return;
+        }
+        match cloud::subscribe(&cloud, &feed_url, &callback).await {
+            Ok(()) => tracing::info!("Subscribed to rssCloud notifications for {}", feed_url),
+            Err(err) => {
+                tracing::warn!("rssCloud subscription for {} failed: {:#}", feed_url, err);
+                subscriptions.lock().await.cancel(&feed_url);
+            }
+        }
+    });
+}
+
+#[derive(Deserialize)]
+struct CloudChallenge {
+    url: String,
+    challenge: String,
+}
+
+/// Answers a cloud checking that the server asked to be notified about `url`.
+async fn cloud_challenge(
+    Query(query): Query<CloudChallenge>,
+    State(state): State<AppState>,
+) -> Response {
+    if state.cloud.lock().await.contains(&query.url) {
+        query.challenge.into_response()
+    } else {
+        (StatusCode::NOT_FOUND, "Not subscribed to this feed").into_response()
     }
+}
+
+#[derive(Deserialize)]
+struct CloudNotification {
+    url: String,
+}
 
-    let channel = match feed::fetch_configured_feed(feed).await {
-        Ok(channel) => channel,
-        Err(err) => return Err((StatusCode::BAD_GATEWAY, err.to_string()).into_response()),
+/// A cloud reporting that the feed at `url` changed: the feeds with that URL are fetched and
+/// stored again.
+async fn cloud_notify(
+    State(state): State<AppState>,
+    Form(notification): Form<CloudNotification>,
+) -> Response {
+    if !state.cloud.lock().await.contains(&notification.url) {
+        return (StatusCode::NOT_FOUND, "Not subscribed to this feed").into_response();
+    }
+    let feeds: Vec<(usize, Feed)> = state
+        .feeds
+        .lock()
+        .await
+        .iter()
+        .cloned()
+        .enumerate()
+        .filter(|(_, feed)| feed::build_feed_url(feed).is_ok_and(|url| url == notification.url))
+        .collect();
+    tokio::spawn(async move {
+        for (index, feed) in feeds {
+            if let Ok(cached) = fetch_and_cache_channel(index, &feed, &state).await {
+                if cached.offline.is_none() {
+                    spawn_store(&state, &feed, &cached.channel);
+                }
+            }
+        }
+    });
+    "OK".into_response()
+}
+
+/// Fetches the feed and caches the result. If the fetch fails, the last live copy in the cache
+/// or else the feed's archived articles are served instead, marked as offline.
+async fn fetch_and_cache_channel(
+    index: usize,
+    feed: &Feed,
+    state: &AppState,
+) -> Result<CachedChannel, axum::response::Response> {
+    let cached = match feed::fetch_configured_feed(state.fetcher.as_ref(), feed).await {
+        Ok(mut channel) => {
+            subscribe_to_cloud(state, feed, &channel);
+            let latest_items = channel.items().len();
+            if let Err(err) = state.db.merge_archived(&feed.name, &feed.url, &mut channel) {
+                tracing::warn!("Failed to merge archived {}: {:#}", feed.name, err);
+            }
+            CachedChannel {
+                channel,
+                latest_items,
+                fetched_at: Instant::now(),
+                offline: None,
+            }
+        }
+        Err(err) => {
+            let previous = state
+                .cache
+                .lock()
+                .await
+                .get(index)
+                .cloned()
+                .flatten()
+                .map(|cached| (cached.channel, cached.latest_items));
+            let (channel, latest_items) = match previous {
+                Some(previous) => previous,
+                None => match state.db.archived_channel(&feed.name) {
+                    Ok(channel) if !channel.items().is_empty() => {
+                        let items = channel.items().len();
+                        (channel, items)
+                    }
+                    _ => return Err((StatusCode::BAD_GATEWAY, err.to_string()).into_response()),
+                },
+            };
+            tracing::warn!("Serving {} from the archive: {:#}", feed.name, err);
+            CachedChannel {
+                channel,
+                latest_items,
+                fetched_at: Instant::now(),
+                offline: Some(OfflineNotice {
+                    error: err.to_string(),
+                    last_fetched: state.db.last_fetch(&feed.name),
+                }),
+            }
+        }
     };
 
     if let Some(slot) = state.cache.lock().await.get_mut(index) {
-        *slot = Some(channel.clone());
+        *slot = Some(cached.clone());
     }
 
-    Ok(channel)
+    Ok(cached)
 }
 
-fn channel_to_response(channel: &Channel) -> FeedResponse {
-    let items = channel
+/// The page's items, at most `limit` of them. Item IDs stay indexes into the whole channel.
+/// One page of the channel's items. Items hidden by a snooze are left out but keep their index
+/// as ID.
+fn channel_to_response(
+    cached: &CachedChannel,
+    feed: &Feed,
+    snoozes: &[db::Snooze],
+    page: &PageQuery,
+    limit: Option<usize>,
+) -> FeedResponse {
+    let channel = &cached.channel;
+    let offset = page.offset.unwrap_or(0);
+    let listed = if page.latest.unwrap_or(false) {
+        cached.latest_items
+    } else {
+        channel.items().len()
+    };
+    let visible: Vec<_> = channel
         .items()
         .iter()
         .enumerate()
+        .take(listed)
+        .filter(|(_, item)| {
+            let title = item.title().unwrap_or_default();
+            !snoozes.iter().any(|snooze| snooze.hides(&feed.name, title))
+        })
+        .collect();
+    let total_items = visible.len();
+    let end = limit.map_or(total_items, |limit| {
+        offset.saturating_add(limit).min(total_items)
+    });
+    let items = visible
+        .into_iter()
+        .take(end)
+        .skip(offset)
         .map(|(idx, item)| ItemMeta {
             id: idx,
             title: item.title().unwrap_or("No Title").to_string(),
             link: item.link().map(|s| s.to_string()),
             pub_date: item.pub_date().map(|s| s.to_string()),
+            pub_date_local: local_date(item.pub_date()),
+            updated: db::item_updated(item),
         })
         .collect();
 
@@ -223,12 +2294,24 @@ fn channel_to_response(channel: &Channel) -> FeedResponse {
         } else {
             Some(channel.description().to_string())
         },
+        language: channel_language(feed, channel),
         items,
+        total_items,
+        latest_items: cached.latest_items,
+        next_offset: (end < total_items).then_some(end),
+        offline: cached.offline.clone(),
     }
 }
 
-const INDEX_HTML: &str = r#"<!doctype html>
-<html lang="en">
+/// Language of a feed's articles: the feed's `language` setting, else the channel's `<language>`
+/// (which an archived channel has from the last stored fetch).
+fn channel_language(feed: &Feed, channel: &Channel) -> Option<String> {
+    feed.language_override()
+        .or_else(|| channel.language().and_then(feed::language_tag))
+}
+
+const INDEX_HTML: &str = r##"<!doctype html>
+<html lang="__LANG__">
   <head>
     <meta charset="utf-8" />
     <meta name="viewport" content="width=device-width, initial-scale=1" />
@@ -327,14 +2410,49 @@ const INDEX_HTML: &str = r#"<!doctype html>
         color: #fffaf3;
         border-color: var(--accent);
       }
-      .list li small {
-        display: block;
+      .list li small {
+        display: block;
+        font-size: 12px;
+        color: var(--muted);
+        margin-top: 4px;
+      }
+      .list li.active small {
+        color: #ffe9cf;
+      }
+      .date {
+        cursor: pointer;
+      }
+      .list li.muted {
+        opacity: 0.55;
+      }
+      .badge {
+        font-size: 11px;
+        padding: 1px 7px;
+        border-radius: 999px;
+        background: var(--accent);
+        color: #fffaf3;
+      }
+      .detail .meta .badge {
+        color: #fffaf3;
+        text-decoration: none;
+      }
+      .list li.active .badge {
+        background: #fffaf3;
+        color: var(--accent);
+      }
+      .list li.category {
+        cursor: default;
+        margin: 12px 0 0;
+        padding-top: 4px;
+        padding-bottom: 4px;
         font-size: 12px;
+        letter-spacing: 1px;
+        text-transform: uppercase;
         color: var(--muted);
-        margin-top: 4px;
       }
-      .list li.active small {
-        color: #ffe9cf;
+      .list li.category:hover {
+        border-color: transparent;
+        background: none;
       }
       .detail {
         padding: 18px 22px 28px;
@@ -358,10 +2476,84 @@ const INDEX_HTML: &str = r#"<!doctype html>
       }
       .detail .content {
         line-height: 1.6;
+        overflow-wrap: break-word;
+        hyphens: auto;
+      }
+      .detail .content:lang(zh),
+      .detail .content:lang(ja),
+      .detail .content:lang(ko) {
+        line-height: 1.8;
+        hyphens: manual;
+        line-break: strict;
+      }
+      .detail .content:lang(zh) {
+        font-family: "Georgia", "Songti SC", "Noto Serif CJK SC", "Source Han Serif SC", "SimSun", serif;
+      }
+      .detail .content:lang(zh-TW),
+      .detail .content:lang(zh-HK),
+      .detail .content:lang(zh-Hant) {
+        font-family: "Georgia", "Songti TC", "Noto Serif CJK TC", "Source Han Serif TC", "PMingLiU", serif;
+      }
+      .detail .content:lang(ja) {
+        font-family: "Georgia", "Hiragino Mincho ProN", "Noto Serif CJK JP", "Yu Mincho", serif;
+      }
+      .detail .content:lang(ko) {
+        font-family: "Georgia", "AppleMyungjo", "Noto Serif CJK KR", "Batang", serif;
+        word-break: keep-all;
       }
       .detail .content p {
         margin: 0 0 12px;
       }
+      .detail .article-body.with-toc {
+        display: grid;
+        grid-template-columns: minmax(0, 1fr) 200px;
+        gap: 24px;
+        align-items: start;
+      }
+      .detail .toc {
+        position: sticky;
+        top: 0;
+        order: 2;
+        font-size: 13px;
+        border-left: 2px solid var(--border);
+        padding-left: 12px;
+      }
+      .detail .toc strong {
+        display: block;
+        margin-bottom: 6px;
+        color: var(--muted);
+        text-transform: uppercase;
+        letter-spacing: 1px;
+        font-size: 11px;
+      }
+      .detail .toc a {
+        display: block;
+        padding: 2px 0;
+        color: var(--ink);
+      }
+      .detail .toc a:hover {
+        color: var(--accent);
+      }
+      .detail .related {
+        margin-top: 32px;
+        border-top: 1px solid var(--border);
+        padding-top: 12px;
+        font-size: 14px;
+      }
+      .detail .related h4 {
+        margin: 0 0 8px;
+        color: var(--muted);
+        text-transform: uppercase;
+        letter-spacing: 1px;
+        font-size: 11px;
+      }
+      .detail .related ul {
+        margin: 0;
+        padding-left: 18px;
+      }
+      .detail .related small {
+        color: var(--muted);
+      }
       .detail .content code {
         background: var(--accent-soft);
         padding: 2px 4px;
@@ -395,6 +2587,13 @@ const INDEX_HTML: &str = r#"<!doctype html>
         background: var(--accent);
         color: #fffaf3;
       }
+      .detail a.back-button {
+        color: var(--ink);
+        text-decoration: none;
+      }
+      .detail a.back-button:hover {
+        color: #fffaf3;
+      }
       .hidden {
         display: none;
       }
@@ -409,138 +2608,1025 @@ const INDEX_HTML: &str = r#"<!doctype html>
         section.content {
           min-height: auto;
         }
+        .detail .article-body.with-toc {
+          display: block;
+        }
+        .detail .toc {
+          position: static;
+          margin-bottom: 16px;
+        }
+      }
+      @media print {
+        body {
+          background: none;
+        }
+        header,
+        .sidebar,
+        section.content > h2,
+        .detail .meta button,
+        .detail .meta .back-button,
+        .detail .toc,
+        .detail .related,
+        #commentsLink {
+          display: none;
+        }
+        main {
+          display: block;
+          padding: 0;
+        }
+        section.content {
+          border: 0;
+          box-shadow: none;
+          background: none;
+        }
+        .detail {
+          overflow: visible;
+          padding: 0;
+        }
+        .detail a {
+          color: inherit;
+          text-decoration: underline;
+        }
       }
     </style>
   </head>
   <body>
     <header>
       <h1>RSS Reader</h1>
-      <p>Sidebar navigation for feeds and items with a focused article view.</p>
+      <p>
+        <span data-i18n>Sidebar navigation for feeds and items with a focused article view.</span>
+        <a id="bookmarklet" href="#" data-i18n>Subscribe</a>
+        <span data-i18n>(drag to your bookmarks bar).</span>
+      </p>
     </header>
     <main>
       <aside class="sidebar">
         <div id="feedsView" class="panel">
-          <h2>Feeds</h2>
+          <h2 data-i18n>Feeds</h2>
           <ul id="feedList" class="list"></ul>
         </div>
         <div id="itemsView" class="panel hidden">
           <div class="panel-header">
-            <button id="backToFeeds" class="back-button">Back</button>
-            <h2>Items</h2>
+            <button id="backToFeeds" class="back-button" data-i18n>Back</button>
+            <h2 data-i18n>Items</h2>
+            <select id="historyMode" class="back-button" title="Item history">
+              <option value="archive" data-i18n>Full archive</option>
+              <option value="latest" data-i18n>Latest from feed</option>
+            </select>
+            <button id="refreshFeed" class="back-button" data-i18n>Refresh</button>
+            <button id="muteFeed" class="back-button" title="Hide from unread counts and All articles for a week">Mute</button>
           </div>
           <ul id="itemList" class="list"></ul>
         </div>
       </aside>
       <section class="content">
-        <h2>Article</h2>
-        <div id="article" class="detail placeholder">Select a feed and item to read.</div>
+        <h2 data-i18n>Article</h2>
+        <div id="article" class="detail placeholder" data-i18n>Select a feed and item to read.</div>
       </section>
     </main>
+    __MATH__
     <script>
+      const STRINGS = __STRINGS__;
+      const BASE = "__BASE__";
       const feedList = document.getElementById("feedList");
       const itemList = document.getElementById("itemList");
       const article = document.getElementById("article");
       const feedsView = document.getElementById("feedsView");
       const itemsView = document.getElementById("itemsView");
       const backToFeeds = document.getElementById("backToFeeds");
+      const refreshFeed = document.getElementById("refreshFeed");
+      const historyMode = document.getElementById("historyMode");
+      const muteFeed = document.getElementById("muteFeed");
       let feeds = [];
       let currentFeedIndex = null;
+      let absoluteDates = false;
+      // New articles per feed index since the page was loaded, and how many were there when the
+      // feed was last opened.
+      const UPDATES_POLL_MS = 60000;
+      const loadedAt = new Date().toISOString();
+      let newItems = {};
+      const seenItems = {};
+
+      function t(text) {
+        return STRINGS[text] || text;
+      }
+
+      document.querySelectorAll("[data-i18n]").forEach((element) => {
+        element.textContent = t(element.textContent.trim());
+      });
+
+      // Dates are shown in the browser's timezone, relative to now unless toggled by a click.
+      // Absolute dates come preformatted with the configured `date_format`.
+      function formatDate(value, local) {
+        const date = new Date(value);
+        if (!value || isNaN(date)) {
+          return value || "";
+        }
+        const lang = document.documentElement.lang;
+        if (absoluteDates) {
+          return local || date.toLocaleString(lang, { dateStyle: "medium", timeStyle: "short" });
+        }
+        const relative = new Intl.RelativeTimeFormat(lang, { numeric: "auto" });
+        const minutes = Math.round((date - Date.now()) / 60000);
+        const startOfDay = (d) => new Date(d.getFullYear(), d.getMonth(), d.getDate());
+        const days = Math.round((startOfDay(date) - startOfDay(new Date())) / 86400000);
+        if (minutes > 1 || days < -6) {
+          return date.toLocaleDateString(lang);
+        }
+        if (minutes > -60) {
+          return relative.format(Math.min(minutes, 0), "minute");
+        }
+        if (days === 0) {
+          return relative.format(Math.round(minutes / 60), "hour");
+        }
+        return relative.format(days, "day");
+      }
+
+      function dateElement(tag, value, local) {
+        const element = document.createElement(tag);
+        element.className = "date";
+        element.dataset.date = value;
+        element.dataset.local = local || "";
+        element.title = local || new Date(value).toLocaleString();
+        element.textContent = formatDate(value, local);
+        return element;
+      }
+
+      function refreshDates() {
+        document.querySelectorAll(".date").forEach((element) => {
+          element.textContent = formatDate(element.dataset.date, element.dataset.local);
+        });
+      }
+
+      document.addEventListener("click", (event) => {
+        if (event.target.classList.contains("date")) {
+          event.stopPropagation();
+          absoluteDates = !absoluteDates;
+          refreshDates();
+        }
+      }, true);
+
+      // How far into the open article the reader has scrolled is saved to the server, so it
+      // reopens at the same spot here or in the TUI.
+      const POSITION_SAVE_MS = 1000;
+      let positionArticle = null;
+      let positionTimer = null;
+
+      // The article scrolls in its own pane on wide screens and with the page on narrow ones.
+      function articleScroller() {
+        return article.scrollHeight > article.clientHeight ? article : document.scrollingElement;
+      }
+
+      function savePosition() {
+        clearTimeout(positionTimer);
+        positionTimer = null;
+        if (!positionArticle) {
+          return;
+        }
+        const scroller = articleScroller();
+        const range = scroller.scrollHeight - scroller.clientHeight;
+        fetch(`${BASE}/api/v1/articles/${positionArticle}/position`, {
+          method: "PUT",
+          headers: { "Content-Type": "application/json" },
+          body: JSON.stringify({ position: range > 0 ? scroller.scrollTop / range : 0 }),
+          keepalive: true,
+        }).catch(() => {});
+      }
+
+      function trackPosition() {
+        if (positionArticle) {
+          clearTimeout(positionTimer);
+          positionTimer = setTimeout(savePosition, POSITION_SAVE_MS);
+        }
+      }
+
+      article.addEventListener("scroll", trackPosition);
+      window.addEventListener("scroll", trackPosition);
+      window.addEventListener("pagehide", () => {
+        if (positionTimer) {
+          savePosition();
+        }
+      });
 
       function clearActive(list) {
         list.querySelectorAll("li").forEach((li) => li.classList.remove("active"));
       }
 
-      function renderFeeds() {
-        feedList.innerHTML = "";
+      function feedItem(index) {
+        return feedList.querySelector(`li[data-index="${index}"]`);
+      }
+
+      // Uncategorized feeds first, then each category followed by the ones nested in it.
+      function categoryTree() {
+        const root = { feeds: [], children: new Map() };
         feeds.forEach((feed, index) => {
+          let node = root;
+          (feed.category ? feed.category.split("/") : []).forEach((level) => {
+            if (!node.children.has(level)) {
+              node.children.set(level, { feeds: [], children: new Map() });
+            }
+            node = node.children.get(level);
+          });
+          node.feeds.push(index);
+        });
+        return root;
+      }
+
+      function renderFeeds(node = categoryTree(), depth = 0) {
+        if (depth === 0) {
+          feedList.innerHTML = "";
+        }
+        const indent = `${10 + depth * 16}px`;
+        node.feeds.forEach((index) => {
+          const feed = feeds[index];
           const li = document.createElement("li");
-          li.innerHTML = `${feed.name}<small>${feed.url}</small>`;
+          li.dataset.index = index;
+          li.style.paddingLeft = indent;
+          const unseen = (newItems[index] || 0) - (seenItems[index] || 0);
+          const badge = unseen > 0 ? ` <span class="badge">${t("{} new").replace("{}", unseen)}</span>` : "";
+          li.innerHTML = `${feed.name}${badge}<small>${feed.url}</small>`;
+          if (feed.muted_until) {
+            li.classList.add("muted");
+            li.querySelector("small").textContent +=
+              ` · ${t("Muted until {}").replace("{}", feed.muted_until)}`;
+          }
           li.addEventListener("click", () => loadFeed(index, li));
           feedList.appendChild(li);
         });
+        node.children.forEach((child, name) => {
+          const heading = document.createElement("li");
+          heading.className = "category";
+          heading.style.paddingLeft = indent;
+          heading.textContent = name;
+          feedList.appendChild(heading);
+          renderFeeds(child, depth + 1);
+        });
       }
 
       function renderItems(items) {
         itemList.innerHTML = "";
         if (!items || items.length === 0) {
-          itemList.innerHTML = "<li class='placeholder'>No items.</li>";
-          article.innerHTML = "No items.";
+          itemList.innerHTML = `<li class='placeholder'>${t("No items.")}</li>`;
+          article.innerHTML = t("No items.");
           return;
         }
-        items.forEach((item, index) => {
+        appendItems(items);
+      }
+
+      function appendItems(items) {
+        items.forEach((item) => {
           const li = document.createElement("li");
-          li.textContent = item.title || "Untitled";
+          li.textContent = item.title || t("Untitled");
+          if (item.pub_date) {
+            li.appendChild(dateElement("small", item.pub_date, item.pub_date_local));
+          }
           li.addEventListener("click", () => loadItem(item, li));
           itemList.appendChild(li);
         });
       }
 
+      function historyQuery(separator) {
+        return historyMode.value === "latest" ? `${separator}latest=true` : "";
+      }
+
+      function appendLoadMore(index, nextOffset) {
+        const more = document.createElement("li");
+        more.className = "placeholder";
+        more.style.cursor = "pointer";
+        more.textContent = t("Load more");
+        more.addEventListener("click", async () => {
+          more.textContent = t("Loading...");
+          const res = await fetch(
+            `${BASE}/api/v1/feeds/${index}?offset=${nextOffset}${historyQuery("&")}`
+          );
+          if (!res.ok) {
+            more.textContent = t("Failed to load more. Click to retry.");
+            return;
+          }
+          const page = await res.json();
+          more.remove();
+          appendItems(page.items);
+          if (page.next_offset != null) {
+            appendLoadMore(index, page.next_offset);
+          }
+        });
+        itemList.appendChild(more);
+      }
+
+      // Switches the open article between the feed's content and the full text extracted from
+      // its page, remembering the choice per feed.
+      function setupReaderToggle(item, feedIndex) {
+        const key = `readerMode:${feeds[feedIndex].url}`;
+        const toggle = document.getElementById("readerToggle");
+        const status = document.getElementById("readerStatus");
+        const body = article.querySelector(".content");
+        let source = "feed";
+        const show = async (next) => {
+          toggle.disabled = true;
+          status.textContent = t("Loading...");
+          try {
+            const res = await fetch(`${BASE}/api/v1/feeds/${feedIndex}/items/${item.id}?content=${next}`);
+            if (!res.ok) {
+              throw new Error(await res.text());
+            }
+            const loaded = await res.json();
+            body.innerHTML = loaded.content_html;
+            renderToc(loaded.toc);
+            renderMath(body);
+            source = next;
+            localStorage.setItem(key, next);
+            status.textContent = "";
+          } catch (err) {
+            status.textContent = t("Failed to load full content");
+            status.title = err.message;
+          }
+          toggle.disabled = false;
+          toggle.textContent = t(source === "full" ? "Feed content" : "Full content");
+        };
+        toggle.addEventListener("click", () => show(source === "full" ? "feed" : "full"));
+        if (localStorage.getItem(key) === "full") {
+          show("full");
+        }
+      }
+
+      // Lists the article's headings beside it, each scrolling the article to its heading.
+      function renderToc(toc) {
+        const container = article.querySelector(".article-body");
+        container.querySelector(".toc")?.remove();
+        container.classList.toggle("with-toc", toc.length > 0);
+        if (!toc.length) {
+          return;
+        }
+        const nav = document.createElement("nav");
+        nav.className = "toc";
+        const title = document.createElement("strong");
+        title.textContent = t("Contents");
+        nav.appendChild(title);
+        const top = Math.min(...toc.map((entry) => entry.level));
+        for (const entry of toc) {
+          const link = document.createElement("a");
+          link.href = `#${entry.id}`;
+          link.textContent = entry.text;
+          link.style.paddingLeft = `${(entry.level - top) * 12}px`;
+          link.addEventListener("click", (event) => {
+            event.preventDefault();
+            document.getElementById(entry.id)?.scrollIntoView({ behavior: "smooth" });
+          });
+          nav.appendChild(link);
+        }
+        container.prepend(nav);
+      }
+
+      // Links to the copy of the article already in the archive, saying when it was read or
+      // published.
+      function previousCopyBadge(copy) {
+        const text = t(copy.read_at ? "Previously read on {} in {}" : "Previously stored on {} in {}");
+        const date = new Date(copy.read_at || copy.published).toLocaleDateString(document.documentElement.lang);
+        const badge = document.createElement("a");
+        badge.className = "badge";
+        badge.href = `${BASE}/article/${copy.id}`;
+        badge.target = "_blank";
+        badge.textContent = text.replace("{}", date).replace("{}", copy.feed_name);
+        return badge;
+      }
+
+      // Lists stored articles like the open one under it, once they've been found.
+      async function renderRelated(id) {
+        const res = await fetch(`${BASE}/api/v1/articles/${id}/related`);
+        const related = res.ok ? await res.json() : [];
+        const body = article.querySelector(".article-body");
+        // Left out when another article was opened meanwhile.
+        if (!related.length || !body || positionArticle !== id) {
+          return;
+        }
+        const section = document.createElement("section");
+        section.className = "related";
+        const title = document.createElement("h4");
+        title.textContent = t("Related from your archive");
+        const list = document.createElement("ul");
+        for (const entry of related) {
+          const li = document.createElement("li");
+          const link = document.createElement("a");
+          link.href = `${BASE}/article/${entry.id}`;
+          link.target = "_blank";
+          link.textContent = entry.title;
+          const meta = document.createElement("small");
+          meta.textContent = `${entry.feed_name} · `;
+          meta.appendChild(dateElement("span", entry.published));
+          li.append(link, " ", meta);
+          list.appendChild(li);
+        }
+        section.append(title, list);
+        body.after(section);
+      }
+
       async function loadItem(item, li) {
         clearActive(itemList);
         li.classList.add("active");
-        article.innerHTML = "Loading article...";
+        if (positionTimer) {
+          savePosition();
+        }
+        positionArticle = null;
+        article.innerHTML = t("Loading article...");
+        const feedIndex = currentFeedIndex;
         try {
-          const res = await fetch(`/api/feeds/${currentFeedIndex}/items/${item.id}`);
+          const res = await fetch(`${BASE}/api/v1/feeds/${feedIndex}/items/${item.id}`);
           if (!res.ok) {
             throw new Error(await res.text());
           }
           const content = await res.json();
           const link = content.link
-            ? `<a href="${content.link}" target="_blank">Open link</a>`
+            ? `<a href="${content.link}" target="_blank">${t("Open link")}</a>`
+            : "";
+          const published = content.pub_date ? dateElement("span", content.pub_date, content.pub_date_local).outerHTML : "";
+          const date = content.updated
+            ? `${published} (${t("updated")} ${dateElement("span", content.updated, content.updated_local).outerHTML})`
+            : published;
+          const authorElement = document.createElement("span");
+          authorElement.textContent = content.author ? t("by {}").replace("{}", content.author) : "";
+          const author = content.author ? authorElement.outerHTML : "";
+          const trackers = content.trackers_removed
+            ? `<span>${content.trackers_removed} ${t("tracker(s) removed")}</span>`
+            : "";
+          const permalink = `<a href="${BASE}/article/${content.id}" target="_blank">${t("Permalink")}</a>`;
+          const copy = content.previous_copy;
+          const previous = copy ? previousCopyBadge(copy).outerHTML : "";
+          const star = `<button class="back-button" id="starToggle">${t(content.starred ? "Unstar" : "Star")}</button>`;
+          const comments = `<button class="back-button" id="commentsButton">${t("Comments")}</button> <span id="commentsLink"></span>`;
+          const reader = content.full_content
+            ? `<button class="back-button" id="readerToggle">${t("Full content")}</button> <span id="readerStatus"></span>`
             : "";
-          const date = content.pub_date ? content.pub_date : "";
+          const exportUrl = `${BASE}/api/v1/articles/${content.id}/export?format=`;
+          const exports = `<a class="back-button" href="${exportUrl}markdown" download>${t("Export as Markdown")}</a>
+            <a class="back-button" href="${exportUrl}html" download>${t("Export as HTML")}</a>
+            <button class="back-button" id="printButton">${t("Print")}</button>`;
           article.innerHTML = `
-            <h3>${content.title || "Untitled"}</h3>
-            <div class="meta">${date} ${link}</div>
-            <div class="content">${content.content_html}</div>
+            <h3>${content.title || t("Untitled")}</h3>
+            <div class="meta">${date} ${author} ${previous} ${link} ${permalink} ${star} ${reader} ${comments} ${exports} ${trackers}</div>
+            <div class="article-body"><div class="content">${content.content_html}</div></div>
           `;
+          renderToc(content.toc);
+          if (content.language) {
+            article.querySelector(".content").lang = content.language;
+          }
+          renderMath(article);
+          if (content.css) {
+            const style = document.createElement("style");
+            style.textContent = `.detail .content { ${content.css} }`;
+            article.prepend(style);
+          }
+          document.getElementById("printButton").addEventListener("click", () => window.print());
+          document.getElementById("starToggle").addEventListener("click", async (event) => {
+            const method = content.starred ? "DELETE" : "POST";
+            const res = await fetch(`${BASE}/api/v1/articles/${content.id}/star`, { method });
+            if (res.ok) {
+              content.starred = !content.starred;
+              event.target.textContent = t(content.starred ? "Unstar" : "Star");
+            }
+          });
+          const itemIndex = item.id;
+          document.getElementById("commentsButton").addEventListener("click", async () => {
+            const target = document.getElementById("commentsLink");
+            target.textContent = t("Loading...");
+            const res = await fetch(`${BASE}/api/v1/feeds/${currentFeedIndex}/items/${itemIndex}/discussion`);
+            if (!res.ok) {
+              target.textContent = t(res.status === 404 ? "No discussion found" : "Failed to load comments");
+              return;
+            }
+            const discussion = await res.json();
+            const count = discussion.comments === null ? "" : `${discussion.comments} `;
+            target.innerHTML = `<a href="${discussion.url}" target="_blank">${count}${t("comments on")} ${discussion.site}</a>`;
+          });
+          if (content.full_content) {
+            setupReaderToggle(item, feedIndex);
+          }
+          renderRelated(content.id);
+          if (content.position) {
+            const scroller = articleScroller();
+            scroller.scrollTop = content.position * (scroller.scrollHeight - scroller.clientHeight);
+          }
+          positionArticle = content.id;
         } catch (err) {
-          article.innerHTML = `<span style="color: var(--accent);">Failed to load article.</span>`;
+          article.innerHTML = `<span style="color: var(--accent);">${t("Failed to load article.")}</span>`;
         }
       }
 
-      async function loadFeed(index, li) {
+      async function loadFeed(index, li, refresh = false) {
         clearActive(feedList);
         li.classList.add("active");
         currentFeedIndex = index;
-        article.innerHTML = "Loading...";
+        seenItems[index] = newItems[index] || 0;
+        li.querySelector(".badge")?.remove();
+        muteFeed.textContent = t(feeds[index].muted_until ? "Unmute" : "Mute");
+        article.innerHTML = t("Loading...");
         itemList.innerHTML = "";
         feedsView.classList.add("hidden");
         itemsView.classList.remove("hidden");
         try {
-          const res = await fetch(`/api/feeds/${index}`);
+          const res = refresh
+            ? await fetch(`${BASE}/api/v1/feeds/${index}/refresh${historyQuery("?")}`, { method: "POST" })
+            : await fetch(`${BASE}/api/v1/feeds/${index}${historyQuery("?")}`);
           if (!res.ok) {
             throw new Error(await res.text());
           }
           const feed = await res.json();
           renderItems(feed.items);
+          if (feed.next_offset != null) {
+            appendLoadMore(index, feed.next_offset);
+          }
+          if (feed.offline) {
+            const notice = document.createElement("li");
+            notice.className = "placeholder";
+            const when = feed.offline.last_fetched
+              ? new Date(feed.offline.last_fetched).toLocaleString()
+              : t("never");
+            notice.textContent = t(
+              "Feed unreachable, showing archived items (last fetched: {})"
+            ).replace("{}", when);
+            notice.title = feed.offline.error;
+            itemList.prepend(notice);
+          }
           if (feed.items && feed.items.length) {
             const firstItem = feed.items[0];
-            const firstLi = itemList.querySelector("li");
+            const firstLi = itemList.querySelector("li:not(.placeholder)");
             if (firstLi) {
               loadItem(firstItem, firstLi);
             }
           }
         } catch (err) {
-          article.innerHTML = `<span style="color: var(--accent);">Failed to load feed.</span>`;
+          article.innerHTML = `<span style="color: var(--accent);">${t("Failed to load feed.")}</span>`;
         }
       }
 
       async function init() {
-        const res = await fetch("/api/feeds");
+        const res = await fetch(`${BASE}/api/v1/feeds`);
         feeds = await res.json();
         renderFeeds();
+        setInterval(pollUpdates, UPDATES_POLL_MS);
+      }
+
+      async function pollUpdates() {
+        const res = await fetch(`${BASE}/api/v1/updates?since=${encodeURIComponent(loadedAt)}`);
+        if (!res.ok) {
+          return;
+        }
+        const updates = await res.json();
+        newItems = {};
+        updates.feeds.forEach((feed) => {
+          newItems[feed.index] = feed.new_items;
+        });
+        renderFeeds();
+        if (currentFeedIndex !== null) {
+          feedItem(currentFeedIndex)?.classList.add("active");
+        }
       }
 
       backToFeeds.addEventListener("click", () => {
         itemsView.classList.add("hidden");
         feedsView.classList.remove("hidden");
         itemList.innerHTML = "";
-        article.innerHTML = "Select a feed and item to read.";
+        article.innerHTML = t("Select a feed and item to read.");
+      });
+
+      document.getElementById("bookmarklet").href =
+        `javascript:location.href='${location.origin}${BASE}/subscribe?url='+encodeURIComponent(location.href)`;
+
+      historyMode.addEventListener("change", () => {
+        if (currentFeedIndex === null) {
+          return;
+        }
+        const li = feedItem(currentFeedIndex);
+        if (li) {
+          loadFeed(currentFeedIndex, li);
+        }
+      });
+
+      refreshFeed.addEventListener("click", () => {
+        if (currentFeedIndex === null) {
+          return;
+        }
+        const li = feedItem(currentFeedIndex);
+        if (li) {
+          loadFeed(currentFeedIndex, li, true);
+        }
+      });
+
+      muteFeed.addEventListener("click", async () => {
+        if (currentFeedIndex === null) {
+          return;
+        }
+        const method = feeds[currentFeedIndex].muted_until ? "DELETE" : "POST";
+        const res = await fetch(`${BASE}/api/v1/feeds/${currentFeedIndex}/mute`, { method });
+        if (!res.ok) {
+          return;
+        }
+        feeds = await (await fetch(`${BASE}/api/v1/feeds`)).json();
+        renderFeeds();
+        feedItem(currentFeedIndex)?.classList.add("active");
+        muteFeed.textContent = t(feeds[currentFeedIndex].muted_until ? "Unmute" : "Mute");
       });
 
       init();
     </script>
   </body>
 </html>
+"##;
+
+const SWAGGER_HTML: &str = r##"<!doctype html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8" />
+    <title>RSS Reader API</title>
+    <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css" />
+  </head>
+  <body>
+    <div id="swagger-ui"></div>
+    <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+    <script>
+      window.ui = SwaggerUIBundle({ url: "openapi.json", dom_id: "#swagger-ui" });
+    </script>
+  </body>
+</html>
+"##;
+
+/// Typesets the equations of rendered articles (`data-math-style` code from comrak's math
+/// extension) with KaTeX, loaded only once an article has any. Without it, or offline, the TeX
+/// stays readable as code.
+const MATH_SCRIPT: &str = r#"<script>
+      const KATEX = "https://unpkg.com/katex@0.16/dist/";
+      let katexLoading = null;
+      function loadKatex() {
+        katexLoading ??= new Promise((resolve, reject) => {
+          const style = document.createElement("link");
+          style.rel = "stylesheet";
+          style.href = `${KATEX}katex.min.css`;
+          document.head.append(style);
+          const script = document.createElement("script");
+          script.src = `${KATEX}katex.min.js`;
+          script.onload = resolve;
+          script.onerror = () => {
+            katexLoading = null;
+            reject();
+          };
+          document.head.append(script);
+        });
+        return katexLoading;
+      }
+      function renderMath(root) {
+        const equations = root.querySelectorAll("code[data-math-style]");
+        if (!equations.length) {
+          return;
+        }
+        loadKatex()
+          .then(() => {
+            for (const code of equations) {
+              const display = code.dataset.mathStyle === "display";
+              const target = display && code.parentElement.tagName === "PRE" ? code.parentElement : code;
+              const rendered = document.createElement(display ? "div" : "span");
+              katex.render(code.textContent, rendered, { displayMode: display, throwOnError: false });
+              target.replaceWith(rendered);
+            }
+          })
+          .catch(() => {});
+      }
+    </script>"#;
+
+const ARTICLE_HTML: &str = r#"<!doctype html>
+<html lang="{{lang}}">
+  <head>
+    <meta charset="utf-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+    <title>{{title}}</title>
+    {{canonical}}
+    <style>
+      body {
+        margin: 0;
+        background: #fbf7f0;
+        color: #1f1b16;
+        font-family: "Georgia", "Times New Roman", serif;
+      }
+      article {
+        max-width: 42rem;
+        margin: 0 auto;
+        padding: 48px 24px 80px;
+        font-size: 19px;
+        line-height: 1.7;
+        overflow-wrap: break-word;
+        hyphens: auto;
+      }
+      article:lang(zh),
+      article:lang(ja),
+      article:lang(ko) {
+        line-height: 1.9;
+        hyphens: manual;
+        line-break: strict;
+      }
+      article:lang(zh) {
+        font-family: "Georgia", "Songti SC", "Noto Serif CJK SC", "Source Han Serif SC", "SimSun", serif;
+      }
+      article:lang(zh-TW),
+      article:lang(zh-HK),
+      article:lang(zh-Hant) {
+        font-family: "Georgia", "Songti TC", "Noto Serif CJK TC", "Source Han Serif TC", "PMingLiU", serif;
+      }
+      article:lang(ja) {
+        font-family: "Georgia", "Hiragino Mincho ProN", "Noto Serif CJK JP", "Yu Mincho", serif;
+      }
+      article:lang(ko) {
+        font-family: "Georgia", "AppleMyungjo", "Noto Serif CJK KR", "Batang", serif;
+        word-break: keep-all;
+      }
+      h1 {
+        font-size: 2.1em;
+        line-height: 1.2;
+        margin: 0 0 8px;
+      }
+      .meta {
+        color: #7a6756;
+        font-size: 15px;
+        margin-bottom: 32px;
+      }
+      a {
+        color: #c05621;
+      }
+      img {
+        max-width: 100%;
+        height: auto;
+      }
+      pre,
+      code {
+        background: #f7d9b5;
+        border-radius: 4px;
+        font-size: 0.85em;
+      }
+      pre {
+        padding: 12px;
+        overflow-x: auto;
+      }
+      blockquote {
+        margin: 0;
+        padding-left: 16px;
+        border-left: 3px solid #e4c9a6;
+        color: #5a4a3c;
+      }
+      body > nav {
+        max-width: 42rem;
+        margin: 0 auto;
+        padding: 24px 24px 0;
+        font-size: 15px;
+      }
+      @media print {
+        body {
+          background: none;
+        }
+        article {
+          max-width: none;
+          padding: 0;
+        }
+        body > nav {
+          display: none;
+        }
+        a {
+          color: inherit;
+        }
+      }
+    </style>
+    {{css}}
+  </head>
+  <body>
+    {{nav}}
+    <article>
+      <h1>{{title}}</h1>
+      <div class="meta">{{meta}}</div>
+      {{content}}
+    </article>
+    {{math}}
+    <script>
+      renderMath(document.querySelector("article"));
+    </script>
+  </body>
+</html>
+"#;
+
+const SUBSCRIBE_HTML: &str = r#"<!doctype html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+    <title>Subscribe - RSS Reader</title>
+    <style>
+      body {
+        margin: 0 auto;
+        max-width: 40rem;
+        padding: 32px 24px;
+        background: #f6f1e5;
+        color: #1f1b16;
+        font-family: "Georgia", "Times New Roman", serif;
+      }
+      form {
+        display: flex;
+        flex-direction: column;
+        gap: 8px;
+        padding: 16px;
+        margin-bottom: 16px;
+        background: #fff8ef;
+        border: 1px solid #e4c9a6;
+        border-radius: 12px;
+      }
+      small {
+        color: #7a6756;
+        word-break: break-all;
+      }
+      input {
+        width: 100%;
+        font: inherit;
+      }
+      button {
+        align-self: flex-start;
+        border: 1px solid #c05621;
+        background: #c05621;
+        color: #fffaf3;
+        border-radius: 999px;
+        padding: 6px 14px;
+        cursor: pointer;
+      }
+    </style>
+  </head>
+  <body>
+    <h1>Subscribe</h1>
+    {{body}}
+    <p><a href="./">Back to reader</a></p>
+  </body>
+</html>
 "#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fetcher::MockFetcher;
+    use crate::test_util::TempDir;
+
+    async fn test_state(name: &str) -> (AppState, TempDir) {
+        let dir = TempDir::new(name);
+        let database = db::Database::initialize(&dir.join("articles"), None)
+            .await
+            .unwrap();
+        let state = AppState {
+            config: Arc::default(),
+            config_path: dir.join("feeds.toml"),
+            feeds: Arc::default(),
+            cache: Arc::default(),
+            render_cache: Arc::default(),
+            store: StoreWriter::spawn(database.clone()),
+            db: database,
+            base_path: String::new(),
+            public_url: None,
+            cloud_callback: None,
+            cloud: Arc::default(),
+            fetcher: Arc::new(MockFetcher::default()),
+        };
+        (state, dir)
+    }
+
+    #[tokio::test]
+    async fn subscribe_rejects_anything_but_http_urls() {
+        let (state, _dir) = test_state("subscribe_urls").await;
+        for url in [
+            "/etc/passwd",
+            "-",
+            "file:///etc/passwd",
+            "ftp://example.com/feed.xml",
+        ] {
+            let form = SubscribeForm {
+                name: String::new(),
+                url: url.to_string(),
+                force: false,
+            };
+            let response = subscribe(State(state.clone()), same_origin(), Form(form)).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", url);
+        }
+        assert!(state.config.lock().await.get_all_feeds().is_empty());
+        assert!(!state.config_path.exists());
+    }
+
+    fn same_origin() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::HOST, HeaderValue::from_static("localhost:8080"));
+        headers.insert(
+            header::ORIGIN,
+            HeaderValue::from_static("http://localhost:8080"),
+        );
+        headers
+    }
+
+    #[tokio::test]
+    async fn subscribe_refuses_cross_origin_posts() {
+        let (state, _dir) = test_state("subscribe_origin").await;
+        let mut cross_origin = same_origin();
+        cross_origin.insert(
+            header::ORIGIN,
+            HeaderValue::from_static("https://attacker.example"),
+        );
+        let mut no_origin = same_origin();
+        no_origin.remove(header::ORIGIN);
+        let mut cross_referer = no_origin.clone();
+        cross_referer.insert(
+            header::REFERER,
+            HeaderValue::from_static("https://attacker.example/page"),
+        );
+        for headers in [cross_origin, no_origin, cross_referer] {
+            let form = SubscribeForm {
+                name: "Feed".to_string(),
+                url: "https://example.com/feed.xml".to_string(),
+                force: false,
+            };
+            let response = subscribe(State(state.clone()), headers, Form(form)).await;
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        }
+        assert!(!state.config_path.exists());
+
+        let mut referer = same_origin();
+        referer.remove(header::ORIGIN);
+        referer.insert(
+            header::REFERER,
+            HeaderValue::from_static("http://localhost:8080/subscribe?url=x"),
+        );
+        let form = SubscribeForm {
+            name: "Feed".to_string(),
+            url: "https://example.com/feed.xml".to_string(),
+            force: false,
+        };
+        let response = subscribe(State(state.clone()), referer, Form(form)).await;
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(state.config.lock().await.get_all_feeds().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn offline_fallback_is_retried_before_the_cache_max_age() {
+        let (mut state, _dir) = test_state("offline_retry").await;
+        let url = "https://example.com/feed.xml";
+        let fetcher =
+            Arc::new(MockFetcher::default().serve_fixture(url, "fixtures/fetch/blog.xml"));
+        state.fetcher = fetcher.clone();
+        let mut config = Config::default();
+        config.add_feed_item(FeedItem::new("Blog", url));
+        let feed = config.get_all_feeds().remove(0);
+        let offline = CachedChannel {
+            channel: Channel::default(),
+            latest_items: 0,
+            fetched_at: Instant::now() - OFFLINE_RETRY_INTERVAL * 2,
+            offline: Some(OfflineNotice {
+                error: "Failed to fetch RSS feed".to_string(),
+                last_fetched: None,
+            }),
+        };
+        assert!(offline.fetched_at.elapsed() < feed.cache_max_age());
+        *state.cache.lock().await = vec![Some(offline)];
+
+        let Ok(cached) = get_or_fetch_channel(0, &feed, &state).await else {
+            panic!("The feed wasn't fetched again");
+        };
+        assert!(cached.offline.is_none());
+        assert!(!cached.channel.items().is_empty());
+        assert_eq!(fetcher.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn aggregate_feeds_link_to_the_public_url() {
+        let (mut state, _dir) = test_state("aggregate_public_url").await;
+        state.base_path = "/rss".to_string();
+        state.public_url = Some("https://example.com/rss/".parse().unwrap());
+        let record = db::ArticleRecord {
+            id: "a".repeat(64),
+            time: "2026-10-01T08:00:00+00:00".to_string(),
+            title: "Pictures".to_string(),
+            feed_name: "Blog".to_string(),
+            path: PathBuf::new(),
+            link: None,
+            updated: None,
+        };
+        state
+            .db
+            .import_article(&record, "![cat](/images/cat.png)")
+            .unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(header::HOST, "internal:7878".parse().unwrap());
+
+        let records = state.db.list_articles().unwrap();
+        let response = render_aggregate_feed(&state, &headers, "All", records);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let channel = Channel::read_from(&body[..]).unwrap();
+        let item = &channel.items()[0];
+        assert_eq!(channel.link(), "https://example.com/rss");
+        assert_eq!(
+            item.link(),
+            Some(format!("https://example.com/rss/article/{}", record.id).as_str())
+        );
+        assert!(item
+            .description()
+            .unwrap()
+            .contains("\"https://example.com/rss/images/cat.png\""));
+    }
+}
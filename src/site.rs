@@ -0,0 +1,274 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use anyhow::{Context, Result};
+use regex::{Captures, Regex};
+
+use crate::config::{Config, Feed};
+use crate::db::{self, escape_html, ArticleQuery, ArticleRecord, Database};
+use crate::{i18n, server};
+
+/// Locally stored images an article page refers to.
+static IMAGE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(src=["'])/images/([A-Za-z0-9.]+)"#).unwrap());
+
+/// What `export` wrote.
+pub struct SiteExport {
+    pub articles: usize,
+    pub images: usize,
+}
+
+/// An index page of the site and the groups it lists the articles in.
+#[derive(PartialEq)]
+enum Index {
+    Date,
+    Feed,
+    Category,
+}
+
+impl Index {
+    fn file_name(&self) -> &'static str {
+        match self {
+            Index::Date => "index.html",
+            Index::Feed => "feeds.html",
+            Index::Category => "categories.html",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Index::Date => tr!("By date"),
+            Index::Feed => tr!("By feed"),
+            Index::Category => tr!("By category"),
+        }
+    }
+}
+
+/// Writes the starred articles to `output` as a static site that needs no server to be read: a
+/// page per article under `articles/`, the stored images they show under `images/`, and pages
+/// listing them by month (`index.html`), feed (`feeds.html`) and category (`categories.html`,
+/// when feeds have one). Files from an earlier export are overwritten but never removed.
+pub fn export(config: &Config, database: &Database, output: &Path) -> Result<SiteExport> {
+    let starred = database.starred_ids()?;
+    let records: Vec<ArticleRecord> = database
+        .query_articles(&ArticleQuery::default())?
+        .into_iter()
+        .filter(|record| starred.contains(&record.id))
+        .collect();
+    let feeds: HashMap<String, Feed> = config
+        .get_all_feeds()
+        .into_iter()
+        .map(|feed| (feed.name.clone(), feed))
+        .collect();
+
+    let articles_dir = output.join("articles");
+    let images_dir = output.join("images");
+    for dir in [&articles_dir, &images_dir] {
+        fs::create_dir_all(dir).context(format!("Failed to create {}", dir.display()))?;
+    }
+
+    let nav = format!(
+        r#"<nav><a href="../index.html">{}</a></nav>"#,
+        escape_html(tr!("Starred articles"))
+    );
+    let mut images = HashSet::new();
+    for record in &records {
+        let markdown = database.read_article_markdown(record).unwrap_or_default();
+        let content = copy_images(
+            &db::render_markdown_html(&markdown),
+            database,
+            &images_dir,
+            &mut images,
+        )?;
+        let feed = feeds.get(&record.feed_name);
+        let language = server::article_language(database, feed, &record.feed_name);
+        let css = config.article_css(feed);
+        let page =
+            server::standalone_article_html(record, &content, &language, css.as_deref(), &nav);
+        write(&articles_dir.join(format!("{}.html", record.id)), &page)?;
+    }
+
+    let category = |record: &ArticleRecord| {
+        feeds
+            .get(&record.feed_name)
+            .and_then(|feed| feed.category.clone())
+    };
+    let mut indexes = vec![Index::Date, Index::Feed];
+    if records.iter().any(|record| category(record).is_some()) {
+        indexes.push(Index::Category);
+    }
+    for index in &indexes {
+        let mut groups: BTreeMap<String, Vec<&ArticleRecord>> = BTreeMap::new();
+        for record in &records {
+            let group = match index {
+                Index::Date => record.time.get(..7).map(String::from),
+                Index::Feed => Some(record.feed_name.clone()),
+                Index::Category => category(record),
+            };
+            if let Some(group) = group {
+                groups.entry(group).or_default().push(record);
+            }
+        }
+        let mut groups: Vec<_> = groups.into_iter().collect();
+        if matches!(index, Index::Date) {
+            groups.reverse();
+        }
+        write(
+            &output.join(index.file_name()),
+            &index_page(index, &indexes, &groups),
+        )?;
+    }
+
+    Ok(SiteExport {
+        articles: records.len(),
+        images: images.len(),
+    })
+}
+
+/// Copies the stored images `html` shows into `images_dir` and points it at the copies, relative
+/// to the article pages. Images no longer stored keep their link.
+fn copy_images(
+    html: &str,
+    database: &Database,
+    images_dir: &Path,
+    copied: &mut HashSet<String>,
+) -> Result<String> {
+    let mut failed = None;
+    let html = IMAGE.replace_all(html, |caps: &Captures| {
+        let name = &caps[2];
+        let Some(source) = database.image_path(name).filter(|path| path.is_file()) else {
+            return caps[0].to_string();
+        };
+        if copied.insert(name.to_string()) {
+            if let Err(err) = fs::copy(&source, images_dir.join(name)) {
+                failed.get_or_insert(
+                    anyhow::Error::from(err)
+                        .context(format!("Failed to copy {}", source.display())),
+                );
+            }
+        }
+        format!("{}../images/{}", &caps[1], name)
+    });
+    match failed {
+        Some(err) => Err(err),
+        None => Ok(html.into_owned()),
+    }
+}
+
+fn index_page(
+    current: &Index,
+    indexes: &[Index],
+    groups: &[(String, Vec<&ArticleRecord>)],
+) -> String {
+    let nav = indexes
+        .iter()
+        .map(|index| {
+            if index == current {
+                format!("<strong>{}</strong>", escape_html(index.label()))
+            } else {
+                format!(
+                    r#"<a href="{}">{}</a>"#,
+                    index.file_name(),
+                    escape_html(index.label())
+                )
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" · ");
+
+    let mut content = String::new();
+    if groups.is_empty() {
+        content.push_str(&format!(
+            "<p>{}</p>",
+            escape_html(tr!("No starred articles."))
+        ));
+    }
+    for (group, records) in groups {
+        content.push_str(&format!("<h2>{}</h2>\n<ul>\n", escape_html(group)));
+        for record in records {
+            let date = record.time.get(..10).unwrap_or(&record.time);
+            content.push_str(&format!(
+                r#"<li><a href="articles/{}.html">{}</a> <span class="meta">{} · {}</span></li>"#,
+                escape_html(&record.id),
+                escape_html(&record.title),
+                escape_html(&record.feed_name),
+                escape_html(date)
+            ));
+            content.push('\n');
+        }
+        content.push_str("</ul>\n");
+    }
+
+    INDEX_HTML
+        .replace("{{lang}}", i18n::locale().tag())
+        .replace("{{title}}", &escape_html(tr!("Starred articles")))
+        .replace("{{nav}}", &nav)
+        .replace("{{content}}", &content)
+}
+
+fn write(path: &Path, content: &str) -> Result<()> {
+    fs::write(path, content).context(format!("Failed to write {}", path.display()))
+}
+
+const INDEX_HTML: &str = r#"<!doctype html>
+<html lang="{{lang}}">
+  <head>
+    <meta charset="utf-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+    <title>{{title}}</title>
+    <style>
+      body {
+        margin: 0;
+        background: #fbf7f0;
+        color: #1f1b16;
+        font-family: "Georgia", "Times New Roman", serif;
+      }
+      main {
+        max-width: 42rem;
+        margin: 0 auto;
+        padding: 48px 24px 80px;
+        font-size: 18px;
+        line-height: 1.6;
+      }
+      h1 {
+        font-size: 2.1em;
+        line-height: 1.2;
+        margin: 0 0 8px;
+      }
+      nav {
+        color: #7a6756;
+        font-size: 15px;
+        margin-bottom: 32px;
+      }
+      h2 {
+        font-size: 1.2em;
+        margin: 32px 0 8px;
+        border-bottom: 1px solid #e4c9a6;
+      }
+      ul {
+        padding-left: 0;
+        list-style: none;
+      }
+      li {
+        margin: 6px 0;
+      }
+      a {
+        color: #c05621;
+      }
+      .meta {
+        color: #7a6756;
+        font-size: 14px;
+      }
+    </style>
+  </head>
+  <body>
+    <main>
+      <h1>{{title}}</h1>
+      <nav>{{nav}}</nav>
+      {{content}}
+    </main>
+  </body>
+</html>
+"#;
@@ -0,0 +1,196 @@
+use std::collections::HashSet;
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::db::{ArticleRecord, Database, ItemState};
+
+/// An article as exchanged over the sync API; `markdown` is omitted in listings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncArticle {
+    pub id: String,
+    pub time: String,
+    pub title: String,
+    pub feed_name: String,
+    pub link: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub markdown: Option<String>,
+}
+
+impl SyncArticle {
+    pub fn from_record(record: &ArticleRecord, markdown: Option<String>) -> Self {
+        Self {
+            id: record.id.clone(),
+            time: record.time.clone(),
+            title: record.title.clone(),
+            feed_name: record.feed_name.clone(),
+            link: record.link.clone(),
+            updated: record.updated.clone(),
+            markdown,
+        }
+    }
+
+    pub fn to_record(&self) -> ArticleRecord {
+        ArticleRecord {
+            id: self.id.clone(),
+            time: self.time.clone(),
+            title: self.title.clone(),
+            feed_name: self.feed_name.clone(),
+            path: Default::default(),
+            link: self.link.clone(),
+            updated: self.updated.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    pub states_merged: usize,
+    pub articles_pulled: usize,
+    pub articles_pushed: usize,
+    pub images_transferred: usize,
+}
+
+pub struct SyncOptions {
+    pub pull: bool,
+    pub push: bool,
+}
+
+/// Synchronizes read/starred state and stored articles with a remote server instance.
+pub async fn sync_with_remote(
+    db: &Database,
+    remote: &str,
+    options: &SyncOptions,
+) -> Result<SyncReport> {
+    let remote = remote.trim_end_matches('/');
+    let client = reqwest::Client::new();
+    let mut report = SyncReport::default();
+
+    let local_states: Vec<ItemState> = db.item_states()?.into_values().collect();
+    let remote_states: Vec<ItemState> = if options.push {
+        client
+            .post(format!("{}/api/v1/sync/state", remote))
+            .json(&local_states)
+            .send()
+            .await
+            .context("Failed to push item state")?
+            .error_for_status()?
+            .json()
+            .await
+            .context("Failed to parse remote item state")?
+    } else {
+        client
+            .get(format!("{}/api/v1/sync/state", remote))
+            .send()
+            .await
+            .context("Failed to fetch remote item state")?
+            .error_for_status()?
+            .json()
+            .await
+            .context("Failed to parse remote item state")?
+    };
+    if options.pull {
+        let (_, changed) = db.merge_item_states(remote_states)?;
+        report.states_merged = changed;
+    }
+
+    let remote_articles: Vec<SyncArticle> = client
+        .get(format!("{}/api/v1/sync/articles", remote))
+        .send()
+        .await
+        .context("Failed to list remote articles")?
+        .error_for_status()?
+        .json()
+        .await
+        .context("Failed to parse remote article list")?;
+    let remote_ids: HashSet<String> = remote_articles.iter().map(|a| a.id.clone()).collect();
+    let local_articles = db.list_articles()?;
+    let local_ids: HashSet<String> = local_articles.iter().map(|a| a.id.clone()).collect();
+
+    if options.pull {
+        for meta in remote_articles
+            .iter()
+            .filter(|a| !local_ids.contains(&a.id))
+        {
+            let article: SyncArticle = client
+                .get(format!("{}/api/v1/sync/articles/{}", remote, meta.id))
+                .send()
+                .await
+                .context("Failed to fetch remote article")?
+                .error_for_status()?
+                .json()
+                .await
+                .context("Failed to parse remote article")?;
+            let markdown = article.markdown.clone().unwrap_or_default();
+            if db.import_article(&article.to_record(), &markdown)? {
+                report.articles_pulled += 1;
+            }
+            for name in image_names(&markdown) {
+                let Some(path) = db.image_path(&name) else {
+                    continue;
+                };
+                if path.exists() {
+                    continue;
+                }
+                let response = client
+                    .get(format!("{}/images/{}", remote, name))
+                    .send()
+                    .await?;
+                if response.status().is_success() {
+                    db.write_image(&name, &response.bytes().await?)?;
+                    report.images_transferred += 1;
+                }
+            }
+        }
+    }
+
+    if options.push {
+        for record in local_articles
+            .iter()
+            .filter(|a| !remote_ids.contains(&a.id))
+        {
+            let markdown = db.read_article_markdown(record).unwrap_or_default();
+            for name in image_names(&markdown) {
+                let Some(path) = db.image_path(&name) else {
+                    continue;
+                };
+                let Ok(bytes) = std::fs::read(&path) else {
+                    continue;
+                };
+                client
+                    .put(format!("{}/api/v1/sync/images/{}", remote, name))
+                    .body(bytes)
+                    .send()
+                    .await
+                    .context("Failed to push image")?
+                    .error_for_status()?;
+                report.images_transferred += 1;
+            }
+            client
+                .post(format!("{}/api/v1/sync/articles", remote))
+                .json(&SyncArticle::from_record(record, Some(markdown)))
+                .send()
+                .await
+                .context("Failed to push article")?
+                .error_for_status()?;
+            report.articles_pushed += 1;
+        }
+    }
+
+    Ok(report)
+}
+
+/// Names of localized images (`/images/<name>`) referenced by stored markdown.
+pub fn image_names(markdown: &str) -> Vec<String> {
+    let re = Regex::new(r"/images/([A-Za-z0-9]+\.[A-Za-z0-9]+)").unwrap();
+    let mut names: Vec<String> = re
+        .captures_iter(markdown)
+        .filter_map(|caps| caps.get(1).map(|m| m.as_str().to_string()))
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
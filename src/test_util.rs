@@ -0,0 +1,46 @@
+//! Helpers shared by the unit tests.
+
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+use crate::db::Database;
+
+/// A directory for one test under the system temp dir, removed with everything in it when
+/// dropped, so a test that fails doesn't leave it behind.
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    /// `rss_reader_<name>_<pid>`, emptied first in case an earlier run was killed. It is created
+    /// by whatever first writes into it.
+    pub(crate) fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("rss_reader_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        Self(path)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// A database stored in a fresh [`TempDir`].
+pub(crate) async fn temp_database(name: &str) -> (Database, TempDir) {
+    let dir = TempDir::new(name);
+    (Database::initialize(&dir, None).await.unwrap(), dir)
+}
@@ -0,0 +1,79 @@
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, Context, Result};
+use base64::prelude::*;
+
+use crate::config::ClipboardMode;
+
+/// Clipboard commands tried in order, with their arguments.
+const SYSTEM_COMMANDS: &[(&str, &[&str])] = &[
+    ("pbcopy", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("clip.exe", &[]),
+];
+
+/// The configured mode, or OSC 52 when the TUI runs over SSH, where a clipboard command would
+/// only reach the remote host's clipboard.
+pub fn effective_mode(configured: Option<ClipboardMode>) -> ClipboardMode {
+    configured.unwrap_or_else(|| {
+        if std::env::var_os("SSH_TTY").is_some() || std::env::var_os("SSH_CONNECTION").is_some() {
+            ClipboardMode::Osc52
+        } else {
+            ClipboardMode::System
+        }
+    })
+}
+
+pub fn copy(text: &str, mode: ClipboardMode) -> Result<()> {
+    match mode {
+        ClipboardMode::System => copy_with_command(text),
+        ClipboardMode::Osc52 => copy_with_osc52(text),
+    }
+}
+
+fn copy_with_command(text: &str) -> Result<()> {
+    for (program, args) in SYSTEM_COMMANDS {
+        let mut child = match Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err).context(format!("Failed to run {}", program)),
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(text.as_bytes())
+                .context(format!("Failed to write to {}", program))?;
+        }
+        let status = child.wait()?;
+        if status.success() {
+            return Ok(());
+        }
+        return Err(anyhow!("{} exited with {}", program, status));
+    }
+    Err(anyhow!(
+        "No clipboard command found; install wl-copy, xclip or xsel, or set clipboard = \"osc52\""
+    ))
+}
+
+/// Asks the terminal to put `text` on the clipboard. Inside tmux the sequence is wrapped so tmux
+/// passes it on to the outer terminal (which needs `set -g allow-passthrough on`).
+fn copy_with_osc52(text: &str) -> Result<()> {
+    let sequence = format!("\x1b]52;c;{}\x07", BASE64_STANDARD.encode(text));
+    let sequence = if std::env::var_os("TMUX").is_some() {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
+    };
+    let mut stdout = io::stdout();
+    stdout.write_all(sequence.as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
@@ -0,0 +1,249 @@
+use std::io::{self, Write};
+use std::sync::LazyLock;
+
+use crossterm::cursor::{MoveTo, RestorePosition, SavePosition};
+use crossterm::queue;
+use crossterm::style::{Attribute, Print, SetAttribute, SetStyle};
+use ratatui::backend::IntoCrossterm;
+use ratatui::buffer::Buffer;
+use ratatui::style::Style;
+use regex::Regex;
+
+/// Box-drawing characters that border the panes, where wrapped text continues on the next row.
+const BORDERS: &[&str] = &["│", "┃", "║"];
+
+static URL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"https?://[^\s│┃║<>"'\[\]{}]+"#).unwrap());
+
+/// Characters of a URL that wrapped onto the start of the next row.
+static CONTINUATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"^[^\s│┃║<>"'\[\]{}]+"#).unwrap());
+
+/// Cells on one row that show (part of) a URL.
+#[derive(Debug, Clone, PartialEq)]
+struct Segment {
+    x: u16,
+    y: u16,
+    cells: Vec<(String, Style)>,
+}
+
+/// A URL shown on screen, possibly wrapped over several rows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    url: String,
+    segments: Vec<Segment>,
+}
+
+/// Makes the URLs visible in the TUI clickable with OSC 8 escape sequences. ratatui has no notion
+/// of hyperlinks, so after each frame the URLs found in the drawn buffer are printed over
+/// themselves wrapped in OSC 8, whenever they changed since the last frame.
+pub struct Hyperlinks {
+    enabled: bool,
+    drawn: Vec<Link>,
+}
+
+impl Hyperlinks {
+    /// `configured` is the config's `hyperlinks`; by default they are only used in terminals known
+    /// to support them, since others may print the escape sequences.
+    pub fn new(configured: Option<bool>) -> Self {
+        Self {
+            enabled: configured.unwrap_or_else(is_supported),
+            drawn: Vec::new(),
+        }
+    }
+
+    /// Forgets what was drawn, for after the screen was cleared.
+    pub fn reset(&mut self) {
+        self.drawn.clear();
+    }
+
+    /// Finds the URLs in a drawn frame; nothing when hyperlinks are off.
+    pub fn scan(&self, buffer: &Buffer) -> Vec<Link> {
+        if !self.enabled {
+            return Vec::new();
+        }
+        find_links(buffer)
+    }
+
+    pub fn draw(&mut self, out: &mut impl Write, links: Vec<Link>) -> io::Result<()> {
+        if links == self.drawn {
+            return Ok(());
+        }
+        queue!(out, SavePosition)?;
+        for link in &links {
+            for segment in &link.segments {
+                queue!(
+                    out,
+                    MoveTo(segment.x, segment.y),
+                    Print(format!("\x1b]8;;{}\x1b\\", link.url))
+                )?;
+                for (symbol, style) in &segment.cells {
+                    queue!(
+                        out,
+                        SetAttribute(Attribute::Reset),
+                        SetStyle((*style).into_crossterm()),
+                        Print(symbol)
+                    )?;
+                }
+                queue!(out, Print("\x1b]8;;\x1b\\"))?;
+            }
+        }
+        queue!(out, SetAttribute(Attribute::Reset), RestorePosition)?;
+        out.flush()?;
+        self.drawn = links;
+        Ok(())
+    }
+}
+
+/// Whether the terminal is one known to support OSC 8.
+fn is_supported() -> bool {
+    let var = |name: &str| std::env::var(name).unwrap_or_default();
+    let term = var("TERM");
+    if term == "dumb" || term == "linux" {
+        return false;
+    }
+    matches!(
+        var("TERM_PROGRAM").as_str(),
+        "iTerm.app" | "WezTerm" | "vscode" | "ghostty" | "Hyper" | "Tabby" | "rio"
+    ) || [
+        "KITTY_WINDOW_ID",
+        "WT_SESSION",
+        "KONSOLE_VERSION",
+        "ALACRITTY_WINDOW_ID",
+    ]
+    .iter()
+    .any(|name| std::env::var_os(name).is_some())
+        || var("VTE_VERSION")
+            .parse::<u32>()
+            .is_ok_and(|version| version >= 5000)
+        || ["kitty", "foot", "wezterm", "ghostty", "alacritty"]
+            .iter()
+            .any(|name| term.contains(name))
+}
+
+/// One row of the buffer as text, with the byte offset of each cell's symbol.
+struct Row {
+    text: String,
+    offsets: Vec<usize>,
+}
+
+impl Row {
+    fn new(buffer: &Buffer, y: u16) -> Self {
+        let mut text = String::new();
+        let mut offsets = Vec::new();
+        for x in buffer.area.left()..buffer.area.right() {
+            offsets.push(text.len());
+            text.push_str(buffer[(x, y)].symbol());
+        }
+        Self { text, offsets }
+    }
+
+    /// Index of the cell whose symbol starts at byte `offset`, or the row's width past its end.
+    fn cell_at(&self, offset: usize) -> usize {
+        self.offsets
+            .iter()
+            .position(|&start| start >= offset)
+            .unwrap_or(self.offsets.len())
+    }
+
+    /// Whether a URL ending before cell `end` may continue on the next row: it reaches the edge
+    /// of the screen or of a bordered pane.
+    fn at_edge(&self, end: usize) -> bool {
+        match self.offsets.get(end) {
+            None => true,
+            Some(&start) => BORDERS
+                .iter()
+                .any(|border| self.text[start..].starts_with(border)),
+        }
+    }
+
+    /// Cell where the row's content starts, after any pane border.
+    fn content_start(&self) -> usize {
+        self.offsets
+            .iter()
+            .position(|&start| {
+                !BORDERS
+                    .iter()
+                    .any(|border| self.text[start..].starts_with(border))
+            })
+            .unwrap_or(self.offsets.len())
+    }
+}
+
+fn segment(buffer: &Buffer, y: u16, cells: std::ops::Range<usize>) -> Segment {
+    let x = buffer.area.left() + cells.start as u16;
+    Segment {
+        x,
+        y,
+        cells: cells
+            .map(|index| {
+                let cell = &buffer[(buffer.area.left() + index as u16, y)];
+                (cell.symbol().to_string(), cell.style())
+            })
+            .collect(),
+    }
+}
+
+/// Drops punctuation that more likely ends the sentence than the URL, like a trailing period or
+/// the closing parenthesis of `(https://example.com)`.
+fn trim_punctuation(url: &str) -> &str {
+    let trimmed = url.trim_end_matches(['.', ',', ';', ':', '!', '?']);
+    match trimmed.strip_suffix(')') {
+        Some(rest) if !rest.contains('(') => rest,
+        _ => trimmed,
+    }
+}
+
+fn find_links(buffer: &Buffer) -> Vec<Link> {
+    let rows: Vec<Row> = (buffer.area.top()..buffer.area.bottom())
+        .map(|y| Row::new(buffer, y))
+        .collect();
+    let mut links = Vec::new();
+    // Cells already taken by a URL continuing from the row above, per row.
+    let mut continued = vec![0; rows.len()];
+    for (index, row) in rows.iter().enumerate() {
+        for found in URL.find_iter(&row.text) {
+            let start = row.cell_at(found.start());
+            if start < continued[index] {
+                continue;
+            }
+            let url = found.as_str();
+            let trimmed = trim_punctuation(url);
+            let end = row.cell_at(found.start() + trimmed.len());
+            let y = buffer.area.top() + index as u16;
+            let mut link = Link {
+                url: trimmed.to_string(),
+                segments: vec![segment(buffer, y, start..end)],
+            };
+            let mut wraps = trimmed.len() == url.len() && row.at_edge(end);
+            let mut next = index + 1;
+            while wraps && next < rows.len() {
+                let row = &rows[next];
+                let content = row.content_start();
+                let Some(rest) = row
+                    .offsets
+                    .get(content)
+                    .and_then(|&offset| CONTINUATION.find(&row.text[offset..]))
+                else {
+                    break;
+                };
+                let joined = format!("{}{}", link.url, rest.as_str());
+                let kept = trim_punctuation(&joined)
+                    .len()
+                    .saturating_sub(link.url.len());
+                let end = row.cell_at(row.offsets[content] + kept);
+                link.url.push_str(&rest.as_str()[..kept]);
+                link.segments.push(segment(
+                    buffer,
+                    buffer.area.top() + next as u16,
+                    content..end,
+                ));
+                continued[next] = end;
+                wraps = kept == rest.len() && row.at_edge(end);
+                next += 1;
+            }
+            links.push(link);
+        }
+    }
+    links
+}
@@ -1,24 +1,74 @@
+mod clipboard;
+mod hyperlink;
+mod text_input;
+
 use crate::{
-    config::{Config, Feed},
-    db, feed,
+    cache::RenderCache,
+    config::{self, ClipboardMode, Config, Feed, FeedItem, CATEGORY_SEPARATOR},
+    crypto, dates, db,
+    enrich::{self, Discussion},
+    feed,
+    fetcher::{FeedFetcher, HttpFetcher},
+    footnotes, hygiene, media, related,
+    writer::StoreWriter,
 };
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use chrono::{Duration, Local, Utc};
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
+    event::{
+        self, DisableBracketedPaste, EnableBracketedPaste, Event, KeyCode, KeyEvent, KeyEventKind,
+        KeyModifiers,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use futures::{future, stream, StreamExt};
 use minimad::{parse_text, Composite, CompositeStyle, Line as MdLine, Options};
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
     Frame, Terminal,
 };
 use rss::Channel;
 use rss::Item;
+use std::collections::{HashMap, HashSet};
 use std::io::{self, Stdout};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use hyperlink::Hyperlinks;
+use text_input::{InputAction, TextInput};
+
+/// Feeds fetched at once by the startup prefetch.
+const PREFETCH_CONCURRENCY: usize = 4;
+
+/// Days `z` mutes a feed for.
+const MUTE_DAYS: i64 = 7;
+
+/// Viewer `v` opens articles with when the config doesn't set `viewer`.
+const DEFAULT_VIEWER: &str = "${PAGER:-less}";
+
+/// Editor `E` opens the config file in.
+const EDITOR: &str = "${VISUAL:-${EDITOR:-vi}}";
+
+/// Smallest terminal the layout fits in; anything smaller shows a placeholder instead.
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 10;
+
+/// An article waiting to be shown by an external viewer once the TUI steps aside.
+struct ViewerRequest {
+    command: String,
+    id: String,
+    markdown: String,
+    /// The stored markdown file, when it can be passed as is.
+    path: Option<PathBuf>,
+}
 
 #[derive(PartialEq)]
 pub enum Screen {
@@ -27,50 +77,245 @@ pub enum Screen {
     Article,
 }
 
+/// Input shown in place of the status bar.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Prompt {
+    /// URL or RSSHub route to open, after `O`.
+    Open,
+    /// Text to find in the listed item titles, after `/`.
+    Search,
+    /// Text to find in the open article, after `/` in the article view.
+    Find,
+}
+
+/// Text searched for in the open article; its matches are highlighted and `n`/`N` move between
+/// them.
+pub struct ArticleSearch {
+    query: String,
+    /// Index of the match last jumped to.
+    current: usize,
+    /// Matches found on the last draw.
+    count: usize,
+    /// Set when the view should scroll to the current match on the next draw.
+    jump: bool,
+}
+
+/// A row of the feed list: a category heading or an index into `App::feeds`.
+#[derive(Clone, PartialEq)]
+enum FeedRow {
+    Category(String),
+    Feed(usize),
+}
+
+/// A feed that failed to fetch, with the full error and the archived copy to fall back to.
+pub struct FetchError {
+    pub feed: Feed,
+    pub message: String,
+    pub cached: Option<(Channel, String)>,
+}
+
+/// A feed being fetched in the background while the TUI keeps drawing; `Esc` cancels it.
+pub struct PendingFetch {
+    feed: Feed,
+    origin: FetchOrigin,
+    cancel: CancellationToken,
+    task: JoinHandle<Result<Channel>>,
+}
+
+/// Outcome of a job other than a feed fetch that ran in the background while the TUI kept
+/// drawing, picked up by `poll_background`.
+enum BackgroundResult {
+    Download {
+        name: String,
+        result: Result<PathBuf>,
+    },
+    Discussion {
+        index: usize,
+        link: Option<String>,
+        result: Result<Option<Discussion>>,
+    },
+    Related {
+        id: String,
+        related: Vec<db::ArticleRecord>,
+    },
+}
+
+/// Where a fetch was started, deciding what happens once it succeeds.
+enum FetchOrigin {
+    FeedList,
+    /// Opened from the `O` prompt; `existing` names the subscription with the same URL, if any.
+    Prompt {
+        existing: Option<String>,
+    },
+}
+
 pub struct App {
     pub config: Option<Config>,
+    /// Where `config` is saved when the feed list is rearranged.
+    pub config_path: Option<PathBuf>,
     pub feeds: Vec<Feed>,
     pub current_feed: Option<Channel>,
     pub current_feed_name: Option<String>,
     pub current_feed_url: Option<String>,
     pub item_markdown: Vec<Option<String>>,
+    pub discussions: Vec<Option<Discussion>>,
+    pub render_cache: RenderCache<Vec<Line<'static>>>,
     pub db: Option<db::Database>,
+    pub store: Option<StoreWriter>,
+    /// Downloads feeds opened, refreshed and prefetched.
+    pub fetcher: Arc<dyn FeedFetcher>,
     pub current_items: Vec<Item>,
+    /// Items listed when a feed is opened; `m` lists this many more.
+    pub item_limit: Option<usize>,
+    /// Number of `current_items` listed so far.
+    pub shown_items: usize,
     pub current_screen: Screen,
     pub feed_state: ListState,
+    /// Categories whose feeds are hidden in the feed list.
+    pub collapsed: HashSet<String>,
+    /// Unread stored articles per feed name.
+    pub unread: HashMap<String, usize>,
+    /// Active snoozes, reloaded with the unread counts.
+    pub snoozes: Vec<db::Snooze>,
     pub item_state: ListState,
     pub should_quit: bool,
     pub status_message: String,
     pub scroll_offset: u16,
+    /// Furthest the open article can scroll at the current terminal size, updated on each draw.
+    pub max_scroll: u16,
+    /// Attachment of the open article that `o` and `s` act on.
+    pub selected_attachment: usize,
+    pub prompt: Option<Prompt>,
+    pub open_input: TextInput,
+    pub search_input: TextInput,
+    pub find_input: TextInput,
+    /// Search within the open article, after `/` in the article view.
+    pub article_search: Option<ArticleSearch>,
+    /// Feed opened from the prompt that isn't in the config; `S` subscribes to it.
+    pub temporary_feed: Option<Feed>,
+    /// Whether the channel details popup is shown over the item list.
+    pub show_channel_meta: bool,
+    /// Table of contents of the open article shown over it by `T`, with the selected heading.
+    pub toc: Option<ListState>,
+    /// Heading picked from the table of contents, scrolled to on the next draw.
+    toc_jump: Option<usize>,
+    /// Where reading the opened article was left off, scrolled to on the next draw once its
+    /// length is known.
+    resume_position: Option<f64>,
+    /// Stored articles most like the open one, listed under it.
+    pub related: Vec<db::ArticleRecord>,
+    /// Another stored article with the open one's link, from another feed or an earlier fetch.
+    pub previous_copy: Option<db::PreviousCopy>,
+    /// Item dates are shown as local date and time instead of relative to now.
+    pub absolute_dates: bool,
+    /// Command articles are opened with instead of the article view (`viewer` in the config).
+    pub viewer: Option<String>,
+    /// Where `y` copies links to (`clipboard` in the config).
+    pub clipboard: Option<ClipboardMode>,
+    viewer_request: Option<ViewerRequest>,
+    /// Set by `E`; the config file is opened in the editor once the TUI can step aside.
+    edit_config_requested: bool,
+    /// Why the edited config file couldn't be loaded, shown in a popup.
+    pub config_error: Option<String>,
+    /// The last failed fetch, shown in a popup until an action is chosen.
+    pub fetch_error: Option<FetchError>,
+    pub pending_fetch: Option<PendingFetch>,
+    /// Results of the startup prefetch, arriving as each feed completes.
+    prefetch: Option<mpsc::UnboundedReceiver<(Feed, Result<Channel>)>>,
+    /// Feeds the startup prefetch hasn't finished yet.
+    pub prefetch_pending: usize,
+    background_sender: mpsc::UnboundedSender<BackgroundResult>,
+    background: mpsc::UnboundedReceiver<BackgroundResult>,
+    /// Cancels the attachment download in progress; `Esc` fires it.
+    download: Option<CancellationToken>,
+    /// Prefetched channels not opened yet, by feed URL.
+    pub prefetched: HashMap<String, Channel>,
+    /// Items in each feed's latest fetch by URL, `None` when that fetch failed.
+    pub item_counts: HashMap<String, Option<usize>>,
+    /// Set when fetched channels were queued for storage and unread counts need reloading.
+    unread_stale: bool,
     pub is_loading: bool,
 }
 
 impl App {
-    pub fn new() -> Self {
+    fn new() -> Self {
+        let (background_sender, background) = mpsc::unbounded_channel();
         Self {
             config: None,
+            config_path: None,
             feeds: Vec::new(),
             current_feed: None,
             current_feed_name: None,
             current_feed_url: None,
             item_markdown: Vec::new(),
+            discussions: Vec::new(),
+            render_cache: RenderCache::default(),
             db: None,
+            store: None,
+            fetcher: Arc::new(HttpFetcher),
             current_items: Vec::new(),
+            item_limit: None,
+            shown_items: 0,
             current_screen: Screen::Feeds,
             feed_state: ListState::default(),
+            collapsed: HashSet::new(),
+            unread: HashMap::new(),
+            snoozes: Vec::new(),
             item_state: ListState::default(),
             should_quit: false,
-            status_message: String::from("Press 'q' to quit, 'Enter' to select, 'Esc' to go back"),
+            status_message: String::from(tr!(
+                "Press 'q' to quit, 'Enter' to select, 'Esc' to go back"
+            )),
             scroll_offset: 0,
+            max_scroll: 0,
+            selected_attachment: 0,
+            prompt: None,
+            open_input: TextInput::default(),
+            search_input: TextInput::default(),
+            find_input: TextInput::default(),
+            article_search: None,
+            temporary_feed: None,
+            show_channel_meta: false,
+            toc: None,
+            toc_jump: None,
+            resume_position: None,
+            related: Vec::new(),
+            previous_copy: None,
+            absolute_dates: false,
+            viewer: None,
+            clipboard: None,
+            viewer_request: None,
+            edit_config_requested: false,
+            config_error: None,
+            fetch_error: None,
+            pending_fetch: None,
+            prefetch: None,
+            prefetch_pending: 0,
+            background_sender,
+            background,
+            download: None,
+            prefetched: HashMap::new(),
+            item_counts: HashMap::new(),
+            unread_stale: false,
             is_loading: false,
         }
     }
 
-    pub fn with_config_and_db(config: Config, db: Option<db::Database>) -> Self {
+    pub fn with_config_and_db(
+        config: Config,
+        config_path: PathBuf,
+        db: Option<db::Database>,
+    ) -> Self {
         let mut app = Self::new();
         app.feeds = config.get_all_feeds();
+        app.item_limit = config.item_limit;
+        app.viewer = config.viewer.clone();
+        app.clipboard = config.clipboard;
         app.config = Some(config);
+        app.config_path = Some(config_path);
+        app.store = db.clone().map(StoreWriter::spawn);
         app.db = db;
+        app.refresh_unread();
         if !app.feeds.is_empty() {
             app.feed_state.select(Some(0));
         }
@@ -88,83 +333,564 @@ impl App {
         app.current_feed = Some(channel);
         app.current_items = items;
         app.item_markdown = vec![None; app.current_items.len()];
+        app.discussions = vec![None; app.current_items.len()];
+        app.store = db.clone().map(StoreWriter::spawn);
         app.db = db;
         app.current_feed_name = feed_name;
         app.current_feed_url = feed_url;
         app.current_screen = Screen::Items;
+        app.shown_items = app.current_items.len();
         if !app.current_items.is_empty() {
             app.item_state.select(Some(0));
         }
         app
     }
 
-    pub async fn fetch_feed(
-        &mut self,
-        url_or_route: String,
-        is_rsshub: bool,
-        rsshub_host: Option<String>,
-        feed_name: Option<String>,
-    ) -> Result<()> {
+    /// Lists only the first `limit` items of each feed until more are requested with `m`.
+    pub fn with_item_limit(mut self, limit: Option<usize>) -> Self {
+        self.item_limit = limit;
+        self.shown_items = limit.map_or(self.current_items.len(), |limit| {
+            limit.min(self.current_items.len())
+        });
+        self
+    }
+
+    /// Starts fetching the feed in the background, cancelling any fetch still running. Its items
+    /// are shown by `poll_fetch` once it completes.
+    fn fetch_feed(&mut self, feed: Feed, origin: FetchOrigin) {
+        self.cancel_fetch();
+        let cancel = CancellationToken::new();
+        let task = tokio::spawn({
+            let feed = feed.clone();
+            let cancel = cancel.clone();
+            let fetcher = self.fetcher.clone();
+            async move { feed::fetch_cancellable(fetcher.as_ref(), &feed, &cancel).await }
+        });
         self.is_loading = true;
-        self.status_message = format!("Fetching {}...", url_or_route);
-
-        let url_source = url_or_route.clone();
-        let url_result = if is_rsshub {
-            let host = rsshub_host
-                .as_deref()
-                .ok_or_else(|| anyhow::anyhow!("RSSHub host missing for feed"))?;
-            feed::build_rsshub_url(host, &url_or_route)
-        } else {
-            Ok(url_or_route)
+        self.status_message = tr!("Fetching {}... Press 'Esc' to cancel.", feed.display_url());
+        self.pending_fetch = Some(PendingFetch {
+            feed,
+            origin,
+            cancel,
+            task,
+        });
+    }
+
+    /// Fetches every feed in the background, a few at a time, so that opening one later shows
+    /// it without waiting. Results are picked up by `poll_prefetch`.
+    pub fn start_prefetch(&mut self) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let feeds = self.feeds.clone();
+        let fetcher = self.fetcher.clone();
+        self.prefetch_pending = feeds.len();
+        self.prefetch = Some(receiver);
+        tokio::spawn(async move {
+            let fetcher = fetcher.as_ref();
+            stream::iter(feeds)
+                .map(|feed| async move {
+                    let result = feed::fetch_configured_feed(fetcher, &feed).await;
+                    (feed, result)
+                })
+                .buffer_unordered(PREFETCH_CONCURRENCY)
+                .for_each(|result| {
+                    let _ = sender.send(result);
+                    future::ready(())
+                })
+                .await;
+        });
+    }
+
+    /// Records prefetched feeds as they complete, storing them and refreshing the unread counts
+    /// once the writer has caught up.
+    pub fn poll_prefetch(&mut self) {
+        let mut received = Vec::new();
+        if let Some(receiver) = self.prefetch.as_mut() {
+            while let Ok(result) = receiver.try_recv() {
+                received.push(result);
+            }
+        }
+        for (feed, result) in received {
+            self.prefetch_pending = self.prefetch_pending.saturating_sub(1);
+            match result {
+                Ok(channel) => {
+                    self.item_counts
+                        .insert(feed.url.clone(), Some(channel.items().len()));
+                    if let Some(store) = &self.store {
+                        store.store_channel(
+                            &feed.name,
+                            &feed.url,
+                            channel.clone(),
+                            feed.store_options(),
+                        );
+                        self.unread_stale = true;
+                    }
+                    self.prefetched.insert(feed.url, channel);
+                }
+                Err(_) => {
+                    self.item_counts.insert(feed.url, None);
+                }
+            }
+        }
+        if self.prefetch_pending == 0 {
+            self.prefetch = None;
+        }
+        if self.unread_stale && self.store.as_ref().is_none_or(|store| store.pending() == 0) {
+            self.unread_stale = false;
+            self.refresh_unread();
+        }
+    }
+
+    /// Shows the results of background jobs that finished since the last call.
+    pub fn poll_background(&mut self) {
+        while let Ok(result) = self.background.try_recv() {
+            match result {
+                BackgroundResult::Download { name, result } => {
+                    self.download = None;
+                    self.status_message = match result {
+                        Ok(path) => tr!("Saved {} to {}", name, path.display()),
+                        Err(err) => tr!("Error: {}", format!("{:#}", err)),
+                    };
+                }
+                BackgroundResult::Discussion {
+                    index,
+                    link,
+                    result,
+                } => self.show_discussion(index, link, result),
+                BackgroundResult::Related { id, related } => {
+                    if self.current_screen == Screen::Article
+                        && self.selected_item_id().as_deref() == Some(id.as_str())
+                    {
+                        self.related = related;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Cancels the running fetch, if any. Returns whether one was cancelled.
+    pub fn cancel_fetch(&mut self) -> bool {
+        let Some(pending) = self.pending_fetch.take() else {
+            return false;
         };
+        pending.cancel.cancel();
+        self.is_loading = false;
+        self.status_message = tr!("Cancelled fetching {}", pending.feed.display_url());
+        true
+    }
 
-        let channel_result = match url_result {
-            Ok(url) => feed::fetch_channel(&url).await,
-            Err(err) => Err(err),
+    /// Shows the result of the background fetch once it has finished.
+    pub async fn poll_fetch(&mut self) {
+        if !self
+            .pending_fetch
+            .as_ref()
+            .is_some_and(|pending| pending.task.is_finished())
+        {
+            return;
+        }
+        let Some(pending) = self.pending_fetch.take() else {
+            return;
         };
+        let result = pending
+            .task
+            .await
+            .unwrap_or_else(|err| Err(anyhow!("Fetch task failed: {}", err)));
+        if self.finish_fetch(pending.feed.clone(), result).is_ok() {
+            if let FetchOrigin::Prompt { existing } = pending.origin {
+                self.offer_subscription(pending.feed, existing);
+            }
+        }
+    }
 
-        match channel_result {
+    /// Shows a fetched channel's items. A feed without a name takes the channel's title.
+    fn finish_fetch(&mut self, mut feed: Feed, result: Result<Channel>) -> Result<()> {
+        match result {
             Ok(channel) => {
-                self.current_items = channel.items().to_vec();
-                self.current_feed = Some(channel);
-                self.current_feed_name = feed_name;
-                self.current_feed_url = Some(url_source);
-                self.item_markdown = vec![None; self.current_items.len()];
-                self.is_loading = false;
-                self.status_message =
-                    String::from("Loaded feed. Press 'Enter' to view article, 'Esc' to back.");
-                self.current_screen = Screen::Items;
-                self.item_state.select(Some(0));
-
-                if let (Some(db), Some(feed_name), Some(feed_url), Some(channel)) = (
-                    self.db.clone(),
-                    self.current_feed_name.clone(),
-                    self.current_feed_url.clone(),
-                    self.current_feed.clone(),
-                ) {
-                    tokio::spawn(async move {
-                        let _ = db.store_channel(&feed_name, &feed_url, &channel).await;
-                    });
+                if feed.name.is_empty() {
+                    feed.name = Some(channel.title().trim())
+                        .filter(|title| !title.is_empty())
+                        .unwrap_or(&feed.url)
+                        .to_string();
+                }
+                if let Some(store) = &self.store {
+                    store.store_channel(
+                        &feed.name,
+                        &feed.url,
+                        channel.clone(),
+                        feed.store_options(),
+                    );
+                    self.unread_stale = true;
                 }
+                self.item_counts
+                    .insert(feed.url.clone(), Some(channel.items().len()));
+                self.show_fetched_channel(channel, &feed);
                 Ok(())
             }
             Err(e) => {
                 self.is_loading = false;
-                self.status_message = format!("Error: {}", e);
+                self.status_message = tr!("Error: {}", e);
+                self.item_counts.insert(feed.url.clone(), None);
+                self.fetch_error = Some(FetchError {
+                    message: e
+                        .chain()
+                        .map(|cause| cause.to_string())
+                        .collect::<Vec<_>>()
+                        .join("\ncaused by: "),
+                    cached: self.archived_channel(&feed.name),
+                    feed,
+                });
                 Err(e)
             }
         }
     }
 
+    /// Shows a freshly fetched channel followed by the feed's archived articles it no longer
+    /// lists.
+    fn show_fetched_channel(&mut self, mut channel: Channel, feed: &Feed) {
+        let archived = self
+            .db
+            .as_ref()
+            .and_then(|db| db.merge_archived(&feed.name, &feed.url, &mut channel).ok())
+            .unwrap_or_default();
+        let status = if archived > 0 {
+            tr!(
+                "Loaded feed and {} older articles from the archive. Press 'Enter' to view article, 'Esc' to back.",
+                archived
+            )
+        } else {
+            String::from(tr!(
+                "Loaded feed. Press 'Enter' to view article, 'Esc' to back."
+            ))
+        };
+        self.show_channel(channel, feed, status);
+    }
+
+    fn show_channel(&mut self, channel: Channel, feed: &Feed, status: String) {
+        self.current_items = channel
+            .items()
+            .iter()
+            .filter(|item| {
+                let title = item.title().unwrap_or_default();
+                !self
+                    .snoozes
+                    .iter()
+                    .any(|snooze| snooze.hides(&feed.name, title))
+            })
+            .cloned()
+            .collect();
+        self.current_feed = Some(channel);
+        self.current_feed_name = Some(feed.name.clone());
+        self.current_feed_url = Some(feed.url.clone());
+        self.item_markdown = vec![None; self.current_items.len()];
+        self.discussions = vec![None; self.current_items.len()];
+        self.shown_items = self.item_limit.map_or(self.current_items.len(), |limit| {
+            limit.min(self.current_items.len())
+        });
+        self.is_loading = false;
+        self.status_message = status;
+        self.current_screen = Screen::Items;
+        self.item_state.select(Some(0));
+    }
+
+    /// Lists the next `item_limit` items of the open feed and selects the first of them.
+    pub fn load_more(&mut self) {
+        if self.current_screen != Screen::Items {
+            return;
+        }
+        let total = self.current_items.len();
+        if self.shown_items >= total {
+            self.status_message = tr!("All {} items are listed", total);
+            return;
+        }
+        let first_new = self.shown_items;
+        self.shown_items = self
+            .item_limit
+            .map_or(total, |limit| first_new.saturating_add(limit).min(total));
+        self.item_state.select(Some(first_new));
+        self.status_message = tr!("Listing {} of {} items", self.shown_items, total);
+    }
+
+    /// Handles a key pressed while the fetch error popup is open.
+    pub async fn handle_fetch_error_key(&mut self, code: KeyCode) {
+        let Some(error) = self.fetch_error.take() else {
+            return;
+        };
+        match code {
+            KeyCode::Char('r') => self.fetch_feed(error.feed, FetchOrigin::FeedList),
+            KeyCode::Char('o') => {
+                let url = match &error.feed.newsletter {
+                    Some(_) => error.feed.display_url(),
+                    None => feed::build_feed_url(&error.feed).unwrap_or(error.feed.url.clone()),
+                };
+                self.status_message = match open::that(&url) {
+                    Ok(()) => tr!("Opened {}", url),
+                    Err(err) => tr!("Error: failed to open {}: {}", url, err),
+                };
+                self.fetch_error = Some(error);
+            }
+            KeyCode::Char('v') => match error.cached {
+                Some((channel, notice)) => {
+                    let status = format!("{} ({})", notice, error.message.replace('\n', " "));
+                    self.show_channel(channel, &error.feed, status);
+                }
+                None => {
+                    self.status_message = String::from(tr!("No cached copy of this feed."));
+                    self.fetch_error = Some(error);
+                }
+            },
+            KeyCode::Char('p') => self.pause_feed(&error.feed),
+            KeyCode::Esc | KeyCode::Char('q') => {}
+            _ => self.fetch_error = Some(error),
+        }
+    }
+
+    pub fn start_prompt(&mut self, prompt: Prompt) {
+        let allowed = match prompt {
+            Prompt::Open => self.current_screen != Screen::Article,
+            Prompt::Search => self.current_screen == Screen::Items,
+            Prompt::Find => self.current_screen == Screen::Article,
+        };
+        if allowed {
+            self.prompt = Some(prompt);
+            self.prompt_input_mut().reset();
+        }
+    }
+
+    fn prompt_input_mut(&mut self) -> &mut TextInput {
+        match self.prompt {
+            Some(Prompt::Search) => &mut self.search_input,
+            Some(Prompt::Find) => &mut self.find_input,
+            _ => &mut self.open_input,
+        }
+    }
+
+    pub fn handle_prompt_key(&mut self, key: KeyEvent) {
+        let Some(prompt) = self.prompt else {
+            return;
+        };
+        match self.prompt_input_mut().handle_key(key) {
+            InputAction::Submit(value) => {
+                self.prompt = None;
+                if value.is_empty() {
+                    return;
+                }
+                match prompt {
+                    Prompt::Open => self.open_source(&value),
+                    Prompt::Search => self.search_items(&value),
+                    Prompt::Find => {
+                        self.article_search = Some(ArticleSearch {
+                            query: value,
+                            current: 0,
+                            count: 0,
+                            jump: true,
+                        })
+                    }
+                }
+            }
+            InputAction::Cancel => self.prompt = None,
+            InputAction::Edited => {}
+        }
+    }
+
+    pub fn handle_paste(&mut self, text: &str) {
+        if self.prompt.is_some() {
+            self.prompt_input_mut().paste(text);
+        }
+    }
+
+    /// Selects the next listed item whose title contains `query`, wrapping around to the top.
+    fn search_items(&mut self, query: &str) {
+        let needle = query.to_lowercase();
+        let start = self.item_state.selected().map_or(0, |i| i + 1);
+        let found = (0..self.shown_items)
+            .map(|offset| (start + offset) % self.shown_items)
+            .find(|&i| {
+                self.current_items[i]
+                    .title()
+                    .unwrap_or_default()
+                    .to_lowercase()
+                    .contains(&needle)
+            });
+        match found {
+            Some(i) => self.item_state.select(Some(i)),
+            None => self.status_message = tr!("No listed item matches \"{}\"", query),
+        }
+    }
+
+    /// Jumps to the next match of the article search, wrapping around to the first, or to the
+    /// previous one when `forward` is false.
+    pub fn next_match(&mut self, forward: bool) {
+        let Some(search) = self.article_search.as_mut() else {
+            return;
+        };
+        if search.count == 0 || self.current_screen != Screen::Article {
+            return;
+        }
+        search.current = if forward {
+            (search.current + 1) % search.count
+        } else {
+            (search.current + search.count - 1) % search.count
+        };
+        search.jump = true;
+    }
+
+    /// Opens a URL, file or RSSHub route (starting with `/`) without adding it to the config.
+    fn open_source(&mut self, source: &str) {
+        let existing = self
+            .config
+            .as_ref()
+            .and_then(|config| config.find_duplicate(source));
+        let feed = existing.clone().unwrap_or_else(|| {
+            let rsshub_host = source.starts_with('/').then(|| {
+                self.config
+                    .as_ref()
+                    .map(|config| config.rsshub.host.clone())
+                    .unwrap_or_default()
+            });
+            FeedItem::new("", source).to_feed(rsshub_host.as_deref())
+        });
+        self.temporary_feed = None;
+        self.fetch_feed(
+            feed,
+            FetchOrigin::Prompt {
+                existing: existing.map(|existing| existing.name),
+            },
+        );
+    }
+
+    /// Offers to subscribe to a feed opened from the prompt, unless it's already subscribed.
+    fn offer_subscription(&mut self, feed: Feed, existing: Option<String>) {
+        self.status_message = match existing {
+            Some(existing) => tr!("Already subscribed as {}.", existing),
+            None if self.config_path.is_some() => {
+                let mut feed = feed;
+                feed.name = self.current_feed_name.clone().unwrap_or_default();
+                let message = tr!("Opened {}. Press 'S' to subscribe.", feed.name);
+                self.temporary_feed = Some(feed);
+                message
+            }
+            None => self.status_message.clone(),
+        };
+    }
+
+    /// Adds the feed opened from the prompt to the config file.
+    pub fn subscribe_temporary_feed(&mut self) {
+        if self.current_screen != Screen::Items {
+            return;
+        }
+        let (Some(feed), Some(config)) = (self.temporary_feed.take(), self.config.as_mut()) else {
+            return;
+        };
+        let item = match &self.current_feed {
+            Some(channel) if !feed.is_rsshub => FeedItem::from_channel(&feed.url, channel),
+            _ => FeedItem::new(&feed.name, &feed.url),
+        };
+        if feed.is_rsshub {
+            config.rsshub_feeds.push(item);
+        } else {
+            config.add_feed_item(item);
+        }
+        let saved = self.config_path.as_deref().map(|path| config.save(path));
+        self.feeds = config.get_all_feeds();
+        self.status_message = match saved {
+            Some(Err(err)) => tr!("Error: {}", format!("{:#}", err)),
+            _ => tr!("Subscribed to {}", feed.name),
+        };
+    }
+
+    /// Pauses the feed in the config file, which drops it from the feed list.
+    fn pause_feed(&mut self, feed: &Feed) {
+        let Some(config) = self.config.as_mut() else {
+            return;
+        };
+        config.set_paused(&feed.url, true);
+        let saved = self.config_path.as_deref().map(|path| config.save(path));
+        self.feeds = config.get_all_feeds();
+        self.clamp_feed_selection();
+        self.status_message = match saved {
+            Some(Err(err)) => tr!("Error: {}", format!("{:#}", err)),
+            _ => tr!("Paused {}", feed.name),
+        };
+    }
+
+    fn clamp_feed_selection(&mut self) {
+        let rows = self.feed_rows().len();
+        self.feed_state.select(
+            self.feed_state
+                .selected()
+                .filter(|_| rows > 0)
+                .map(|i| i.min(rows - 1)),
+        );
+    }
+
+    /// Opens the config file in `$VISUAL` or `$EDITOR` and reloads it afterwards.
+    pub fn edit_config(&mut self) {
+        if self.config_path.is_some() {
+            self.edit_config_requested = true;
+        } else {
+            self.status_message = String::from(tr!("No config file to edit"));
+        }
+    }
+
+    /// Reloads the config file after it was edited. An invalid file is reported in a popup and
+    /// the previous config stays in use.
+    fn reload_config(&mut self) {
+        let Some(path) = self.config_path.clone() else {
+            return;
+        };
+        match config::load_or_create_config(&path) {
+            Ok(config) => {
+                self.feeds = config.get_all_feeds();
+                self.item_limit = config.item_limit;
+                self.viewer = config.viewer.clone();
+                self.clipboard = config.clipboard;
+                self.config = Some(config);
+                self.clamp_feed_selection();
+                self.refresh_unread();
+                self.status_message =
+                    tr!("Reloaded {}: {} feeds", path.display(), self.feeds.len());
+            }
+            Err(err) => self.config_error = Some(format!("{:#}", err)),
+        }
+    }
+
+    /// Handles a key pressed while the config error popup is open: `e` edits the file again,
+    /// anything else keeps the previous config.
+    pub fn handle_config_error_key(&mut self, code: KeyCode) {
+        self.config_error = None;
+        if code == KeyCode::Char('e') {
+            self.edit_config();
+        } else {
+            self.status_message = String::from(tr!("Kept the previous config"));
+        }
+    }
+
+    /// The feed's stored articles and a status line saying they're offline copies.
+    fn archived_channel(&self, feed_name: &str) -> Option<(Channel, String)> {
+        let db = self.db.as_ref()?;
+        let channel = db
+            .archived_channel(feed_name)
+            .ok()
+            .filter(|channel| !channel.items().is_empty())?;
+        let last_fetched = db
+            .last_fetch(feed_name)
+            .unwrap_or_else(|| String::from(tr!("never")));
+        let notice = tr!(
+            "Offline: showing archived items, last fetched {}",
+            last_fetched
+        );
+        Some((channel, notice))
+    }
+
     pub fn next(&mut self) {
         match self.current_screen {
             Screen::Feeds => {
-                if self.feeds.is_empty() {
+                let rows = self.feed_rows().len();
+                if rows == 0 {
                     return;
                 }
                 let i = match self.feed_state.selected() {
                     Some(i) => {
-                        if i >= self.feeds.len() - 1 {
+                        if i >= rows - 1 {
                             0
                         } else {
                             i + 1
@@ -175,12 +901,12 @@ impl App {
                 self.feed_state.select(Some(i));
             }
             Screen::Items => {
-                if self.current_items.is_empty() {
+                if self.shown_items == 0 {
                     return;
                 }
                 let i = match self.item_state.selected() {
                     Some(i) => {
-                        if i >= self.current_items.len() - 1 {
+                        if i >= self.shown_items - 1 {
                             0
                         } else {
                             i + 1
@@ -199,13 +925,14 @@ impl App {
     pub fn previous(&mut self) {
         match self.current_screen {
             Screen::Feeds => {
-                if self.feeds.is_empty() {
+                let rows = self.feed_rows().len();
+                if rows == 0 {
                     return;
                 }
                 let i = match self.feed_state.selected() {
                     Some(i) => {
                         if i == 0 {
-                            self.feeds.len() - 1
+                            rows - 1
                         } else {
                             i - 1
                         }
@@ -215,13 +942,13 @@ impl App {
                 self.feed_state.select(Some(i));
             }
             Screen::Items => {
-                if self.current_items.is_empty() {
+                if self.shown_items == 0 {
                     return;
                 }
                 let i = match self.item_state.selected() {
                     Some(i) => {
                         if i == 0 {
-                            self.current_items.len() - 1
+                            self.shown_items - 1
                         } else {
                             i - 1
                         }
@@ -238,49 +965,154 @@ impl App {
 
     pub async fn select(&mut self) {
         match self.current_screen {
-            Screen::Feeds => {
-                if let Some(i) = self.feed_state.selected() {
-                    if let Some(feed) = self.feeds.get(i) {
-                        let is_rsshub = feed.is_rsshub;
-                        let host = feed.rsshub_host.clone();
-                        let feed_name = Some(feed.name.clone());
-
-                        if let Err(e) = self
-                            .fetch_feed(feed.url.clone(), is_rsshub, host, feed_name)
-                            .await
-                        {
-                            // Status message is set in fetch_feed on error for more specific details
-                            if self.status_message.starts_with("Fetching") {
-                                self.status_message = format!("Error: {}", e);
-                            }
-                            self.is_loading = false;
+            Screen::Feeds => match self.selected_row() {
+                Some(FeedRow::Feed(i)) => {
+                    if let Some(feed) = self.feeds.get(i).cloned() {
+                        match self.prefetched.remove(&feed.url) {
+                            Some(channel) => self.show_fetched_channel(channel, &feed),
+                            None => self.fetch_feed(feed, FetchOrigin::FeedList),
                         }
                     }
                 }
-            }
+                Some(FeedRow::Category(name)) => self.toggle_category(name),
+                None => {}
+            },
             Screen::Items => {
                 if self.item_state.selected().is_some() {
-                    self.status_message = String::from("Loading article...");
+                    self.status_message = String::from(tr!("Loading article..."));
                     if let Err(e) = self.load_markdown_for_selected().await {
-                        self.status_message = format!("Error: {}", e);
+                        self.status_message = tr!("Error: {}", e);
+                        return;
+                    }
+                    if let Some(command) = self.viewer.clone() {
+                        self.request_viewer(command);
                         return;
                     }
                     self.current_screen = Screen::Article;
                     self.scroll_offset = 0;
+                    self.selected_attachment = 0;
+                    self.article_search = None;
+                    let id = self.selected_item_id();
+                    self.resume_position = id
+                        .as_ref()
+                        .and_then(|id| self.db.as_ref()?.reading_position(id).ok().flatten());
+                    self.previous_copy = id.as_ref().and_then(|id| {
+                        let link = self
+                            .current_items
+                            .get(self.item_state.selected()?)?
+                            .link()?;
+                        self.db.as_ref()?.previous_copy(id, link).ok().flatten()
+                    });
+                    self.related = Vec::new();
+                    if let Some((id, db)) = id.zip(self.db.clone()) {
+                        self.find_related(id, db);
+                    }
                     self.status_message =
-                        String::from("Reading article. Press 'Esc' or 'q' to back.");
+                        String::from(tr!("Reading article. Press 'Esc' or 'q' to back."));
+                }
+            }
+            Screen::Article => {}
+        }
+    }
+
+    /// Opens the selected article with the configured `viewer`, or `$PAGER` when there's none.
+    pub async fn open_in_viewer(&mut self) {
+        match self.current_screen {
+            Screen::Feeds => return,
+            Screen::Items => {
+                if let Err(err) = self.load_markdown_for_selected().await {
+                    self.status_message = tr!("Error: {}", err);
+                    return;
                 }
             }
             Screen::Article => {}
         }
+        let command = self
+            .viewer
+            .clone()
+            .unwrap_or_else(|| DEFAULT_VIEWER.to_string());
+        self.request_viewer(command);
+    }
+
+    /// Stored ID of the selected item.
+    fn selected_item_id(&self) -> Option<String> {
+        let item = self.current_items.get(self.item_state.selected()?)?;
+        let feed_name = self
+            .current_feed_name
+            .as_deref()
+            .unwrap_or(tr!("Unknown Feed"));
+        let feed_url = self.current_feed_url.as_deref().unwrap_or("unknown");
+        Some(db::item_id(feed_name, feed_url, item))
+    }
+
+    /// Remembers how far into the open article the reader scrolled, so it reopens there in the
+    /// TUI or the web UI.
+    fn save_reading_position(&self) {
+        let (Some(db), Some(id)) = (&self.db, self.selected_item_id()) else {
+            return;
+        };
+        let position = if self.max_scroll == 0 {
+            0.0
+        } else {
+            f64::from(self.scroll_offset) / f64::from(self.max_scroll)
+        };
+        let _ = db.set_reading_position(&id, position);
+    }
+
+    fn request_viewer(&mut self, command: String) {
+        let Some(index) = self.item_state.selected() else {
+            return;
+        };
+        let Some(item) = self.current_items.get(index) else {
+            return;
+        };
+        let feed_name = self
+            .current_feed_name
+            .as_deref()
+            .unwrap_or(tr!("Unknown Feed"));
+        let feed_url = self.current_feed_url.as_deref().unwrap_or("unknown");
+        let id = db::item_id(feed_name, feed_url, item);
+        let markdown = self
+            .item_markdown
+            .get(index)
+            .cloned()
+            .flatten()
+            .unwrap_or_else(|| db::extract_markdown(item));
+        let path = self.db.as_ref().and_then(|db| db.plain_article_path(&id));
+        self.viewer_request = Some(ViewerRequest {
+            command,
+            id,
+            markdown,
+            path,
+        });
     }
 
     pub fn back(&mut self) {
+        if self.cancel_fetch() {
+            return;
+        }
+        if let Some(download) = self.download.take() {
+            download.cancel();
+            self.status_message = String::from(tr!("Download cancelled."));
+            return;
+        }
+        if self.show_channel_meta {
+            self.show_channel_meta = false;
+            return;
+        }
+        if self.toc.take().is_some() {
+            return;
+        }
+        if self.current_screen == Screen::Article && self.article_search.take().is_some() {
+            self.status_message = String::from(tr!("Reading article. Press 'Esc' or 'q' to back."));
+            return;
+        }
         match self.current_screen {
             Screen::Article => {
+                self.save_reading_position();
                 self.current_screen = Screen::Items;
                 self.status_message =
-                    String::from("Feed items. Press 'Enter' to read, 'Esc' to feeds.");
+                    String::from(tr!("Feed items. Press 'Enter' to read, 'Esc' to feeds."));
             }
             Screen::Items => {
                 // Only go back to feeds if we have a config (navigating via config)
@@ -293,7 +1125,10 @@ impl App {
                     self.current_feed_url = None;
                     self.current_items.clear();
                     self.item_markdown.clear();
-                    self.status_message = String::from("Select a feed. Press 'Enter' to open.");
+                    self.discussions.clear();
+                    self.refresh_unread();
+                    self.status_message =
+                        String::from(tr!("Select a feed. Press 'Enter' to open."));
                 } else {
                     // Direct mode, just quit? or do nothing?
                     // Let's do nothing or maybe just quit
@@ -306,68 +1141,587 @@ impl App {
         }
     }
 
-    pub fn scroll_down(&mut self) {
-        self.scroll_offset = self.scroll_offset.saturating_add(1);
+    /// Feed groups in display order: uncategorized feeds first, then categories in the order
+    /// they first appear in the config, each followed by the categories nested in it. Parents
+    /// of nested categories are listed even when no feed is filed directly under them.
+    fn categories(&self) -> Vec<Option<String>> {
+        let mut paths: Vec<String> = Vec::new();
+        for category in self
+            .feeds
+            .iter()
+            .filter_map(|feed| feed.category.as_deref())
+        {
+            let levels: Vec<&str> = category.split(CATEGORY_SEPARATOR).collect();
+            for depth in 1..=levels.len() {
+                let path = levels[..depth].join(&CATEGORY_SEPARATOR.to_string());
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+        // Sort by the first appearance of each level, which keeps children under their parent.
+        let appearance = |path: &String| -> Vec<usize> {
+            paths
+                .iter()
+                .enumerate()
+                .filter(|(_, ancestor)| config::in_category(path, ancestor))
+                .map(|(i, _)| i)
+                .collect()
+        };
+        let mut sorted = paths.clone();
+        sorted.sort_by_key(appearance);
+        std::iter::once(None)
+            .chain(sorted.into_iter().map(Some))
+            .collect()
     }
 
-    pub fn scroll_up(&mut self) {
-        self.scroll_offset = self.scroll_offset.saturating_sub(1);
+    /// Whether `category` sits inside a collapsed category, not counting itself.
+    fn is_hidden(&self, category: &str) -> bool {
+        self.collapsed
+            .iter()
+            .any(|name| name != category && config::in_category(category, name))
     }
 
-    async fn load_markdown_for_selected(&mut self) -> Result<()> {
-        let Some(index) = self.item_state.selected() else {
-            return Ok(());
-        };
-        if self
-            .item_markdown
-            .get(index)
-            .map(|value| value.is_some())
-            .unwrap_or(false)
-        {
-            return Ok(());
+    /// Rows of the feed list, leaving out the feeds and subcategories of collapsed categories.
+    fn feed_rows(&self) -> Vec<FeedRow> {
+        let mut rows = Vec::new();
+        for category in self.categories() {
+            if let Some(name) = &category {
+                if self.is_hidden(name) {
+                    continue;
+                }
+                rows.push(FeedRow::Category(name.clone()));
+                if self.collapsed.contains(name) {
+                    continue;
+                }
+            }
+            rows.extend(
+                self.feeds
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, feed)| feed.category == category)
+                    .map(|(i, _)| FeedRow::Feed(i)),
+            );
         }
+        rows
+    }
 
-        let item = match self.current_items.get(index) {
-            Some(item) => item,
-            None => return Ok(()),
-        };
-        let feed_name = self.current_feed_name.as_deref().unwrap_or("Unknown Feed");
-        let feed_url = self.current_feed_url.as_deref().unwrap_or("unknown");
+    fn selected_row(&self) -> Option<FeedRow> {
+        let index = self.feed_state.selected()?;
+        self.feed_rows().into_iter().nth(index)
+    }
 
-        let markdown = if let Some(db) = &self.db {
-            db.read_item_markdown(feed_name, feed_url, item)
-        } else {
-            Some(db::extract_markdown(item))
-        };
+    fn select_row(&mut self, row: &FeedRow) {
+        let index = self
+            .feed_rows()
+            .iter()
+            .position(|candidate| candidate == row);
+        self.feed_state.select(index);
+    }
 
-        if let Some(slot) = self.item_markdown.get_mut(index) {
-            *slot = markdown;
+    /// The selected category heading, or the category of the selected feed.
+    fn selected_category(&self) -> Option<String> {
+        match self.selected_row()? {
+            FeedRow::Category(name) => Some(name),
+            FeedRow::Feed(i) => self.feeds.get(i)?.category.clone(),
         }
+    }
 
-        Ok(())
+    /// Hides the feeds of the selected category and selects its heading. On a heading that is
+    /// already collapsed, collapses the category it is nested in.
+    pub fn collapse_category(&mut self) {
+        if self.current_screen != Screen::Feeds {
+            return;
+        }
+        let Some(mut name) = self.selected_category() else {
+            return;
+        };
+        if matches!(self.selected_row(), Some(FeedRow::Category(_)))
+            && self.collapsed.contains(&name)
+        {
+            match name.rsplit_once(CATEGORY_SEPARATOR) {
+                Some((parent, _)) => name = parent.to_string(),
+                None => return,
+            }
+        }
+        self.collapsed.insert(name.clone());
+        self.select_row(&FeedRow::Category(name));
     }
-}
 
-pub async fn run_tui(mut app: App) -> Result<()> {
-    if let (Some(db), Some(feed_name), Some(feed_url), Some(channel)) = (
-        app.db.clone(),
-        app.current_feed_name.clone(),
-        app.current_feed_url.clone(),
-        app.current_feed.clone(),
-    ) {
-        tokio::spawn(async move {
-            let _ = db.store_channel(&feed_name, &feed_url, &channel).await;
-        });
+    fn toggle_category(&mut self, name: String) {
+        if !self.collapsed.remove(&name) {
+            self.collapsed.insert(name);
+        }
     }
 
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    if let Err(err) = execute!(stdout, EnterAlternateScreen) {
-        let _ = disable_raw_mode();
-        return Err(err.into());
+    pub fn expand_category(&mut self) {
+        if self.current_screen != Screen::Feeds {
+            return;
+        }
+        if let Some(FeedRow::Category(name)) = self.selected_row() {
+            self.collapsed.remove(&name);
+        }
     }
-    let backend = ratatui::backend::CrosstermBackend::new(stdout);
-    let mut terminal = match Terminal::new(backend) {
+
+    fn refresh_unread(&mut self) {
+        if let Some(db) = &self.db {
+            self.unread = db.unread_counts().unwrap_or_default();
+            self.snoozes = db.snoozes().unwrap_or_default();
+        }
+    }
+
+    /// When the feed is muted, the time it's muted until.
+    fn muted_until(&self, feed: &Feed) -> Option<&str> {
+        self.snoozes
+            .iter()
+            .filter(|snooze| snooze.mutes(&feed.name))
+            .map(|snooze| snooze.until.as_str())
+            .max()
+    }
+
+    /// Mutes the selected feed for a week, or unmutes it when it's muted.
+    pub fn toggle_mute(&mut self) {
+        if self.current_screen != Screen::Feeds {
+            return;
+        }
+        let Some(FeedRow::Feed(i)) = self.selected_row() else {
+            return;
+        };
+        let (Some(feed), Some(db)) = (self.feeds.get(i), &self.db) else {
+            return;
+        };
+        let result = if self.muted_until(feed).is_some() {
+            db.unsnooze(Some(&feed.name), None)
+                .map(|_| tr!("Unmuted {}", feed.name))
+        } else {
+            let until = Utc::now() + Duration::days(MUTE_DAYS);
+            db.snooze(db::Snooze {
+                feed_name: Some(feed.name.clone()),
+                pattern: None,
+                until: until.to_rfc3339(),
+            })
+            .map(|_| {
+                tr!(
+                    "Muted {} until {}",
+                    feed.name,
+                    dates::absolute(&until.with_timezone(&Local))
+                )
+            })
+        };
+        self.status_message = result.unwrap_or_else(|err| tr!("Error: {}", format!("{:#}", err)));
+        self.refresh_unread();
+    }
+
+    fn feed_unread(&self, feed: &Feed) -> usize {
+        self.unread.get(&feed.name).copied().unwrap_or_default()
+    }
+
+    /// Unread articles of the category, counting its subcategories.
+    fn category_unread(&self, category: &str) -> usize {
+        self.feeds
+            .iter()
+            .filter(|feed| {
+                feed.category
+                    .as_deref()
+                    .is_some_and(|name| config::in_category(name, category))
+            })
+            .map(|feed| self.feed_unread(feed))
+            .sum()
+    }
+
+    /// Marks every stored article of the selected category and its subcategories as read, or of
+    /// the selected feed when it has no category.
+    pub fn mark_category_read(&mut self) {
+        if self.current_screen != Screen::Feeds {
+            return;
+        }
+        let (label, feed_names) = match (self.selected_category(), self.selected_row()) {
+            (Some(category), _) => {
+                let names = self
+                    .feeds
+                    .iter()
+                    .filter(|feed| {
+                        feed.category
+                            .as_deref()
+                            .is_some_and(|name| config::in_category(name, &category))
+                    })
+                    .map(|feed| feed.name.clone())
+                    .collect();
+                (category, names)
+            }
+            (None, Some(FeedRow::Feed(i))) => match self.feeds.get(i) {
+                Some(feed) => (feed.name.clone(), vec![feed.name.clone()]),
+                None => return,
+            },
+            _ => return,
+        };
+        let Some(db) = &self.db else {
+            return;
+        };
+        self.status_message = match db.mark_feeds_read(&feed_names) {
+            Ok(marked) => tr!("Marked {} article(s) in {} as read", marked, label),
+            Err(err) => tr!("Error: {}", format!("{:#}", err)),
+        };
+        self.refresh_unread();
+    }
+
+    /// Moves the selected feed one place up or down, into the neighbouring category once it
+    /// reaches either end of its own, and saves the new order to the config file.
+    pub fn move_selected_feed(&mut self, up: bool) {
+        if self.current_screen != Screen::Feeds {
+            return;
+        }
+        let Some(FeedRow::Feed(index)) = self.selected_row() else {
+            return;
+        };
+        let Some(feed) = self.feeds.get(index) else {
+            return;
+        };
+        let (name, url, category) = (feed.name.clone(), feed.url.clone(), feed.category.clone());
+        let categories = self.categories();
+        let Some(config) = self.config.as_mut() else {
+            return;
+        };
+        let message = if config.move_feed(&url, up) {
+            if up {
+                tr!("Moved {} up", name)
+            } else {
+                tr!("Moved {} down", name)
+            }
+        } else {
+            let group = categories
+                .iter()
+                .position(|candidate| *candidate == category)
+                .unwrap_or_default();
+            let target = if up {
+                group.checked_sub(1).and_then(|i| categories.get(i))
+            } else {
+                categories.get(group + 1)
+            };
+            let Some(target) = target.cloned() else {
+                self.status_message = if up {
+                    tr!("{} is already at the top of the list", name)
+                } else {
+                    tr!("{} is already at the bottom of the list", name)
+                };
+                return;
+            };
+            config.set_category(&url, target.clone());
+            if let Some(target) = &target {
+                self.collapsed.remove(target);
+            }
+            tr!(
+                "Moved {} to {}",
+                name,
+                target.as_deref().unwrap_or(tr!("uncategorized"))
+            )
+        };
+        let saved = self.config_path.as_deref().map(|path| config.save(path));
+        self.feeds = config.get_all_feeds();
+        if let Some(index) = self.feeds.iter().position(|feed| feed.url == url) {
+            self.select_row(&FeedRow::Feed(index));
+        }
+        self.status_message = match saved {
+            Some(Err(err)) => tr!("Error: {}", format!("{:#}", err)),
+            _ => message,
+        };
+    }
+
+    /// Shows or hides the details of the open channel.
+    pub fn toggle_channel_meta(&mut self) {
+        if self.current_screen == Screen::Items && self.current_feed.is_some() {
+            self.show_channel_meta = !self.show_channel_meta;
+        }
+    }
+
+    /// Opens the table of contents of the open article.
+    pub fn open_toc(&mut self) {
+        if self.current_screen != Screen::Article {
+            return;
+        }
+        let headings = self.article_headings();
+        if headings.is_empty() {
+            self.status_message = tr!(
+                "No table of contents: the article has fewer than {} headings",
+                db::MIN_TOC_HEADINGS
+            );
+            return;
+        }
+        let mut state = ListState::default();
+        state.select(Some(0));
+        self.toc = Some(state);
+    }
+
+    pub fn handle_toc_key(&mut self, code: KeyCode) {
+        let count = self.article_headings().len();
+        let Some(state) = self.toc.as_mut() else {
+            return;
+        };
+        let selected = state.selected().unwrap_or(0);
+        match code {
+            KeyCode::Char('j') | KeyCode::Down => {
+                state.select(Some((selected + 1).min(count.saturating_sub(1))));
+            }
+            KeyCode::Char('k') | KeyCode::Up => state.select(Some(selected.saturating_sub(1))),
+            KeyCode::Enter => {
+                self.toc_jump = Some(selected);
+                self.toc = None;
+            }
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('T') => self.toc = None,
+            _ => {}
+        }
+    }
+
+    fn article_headings(&self) -> Vec<TocHeading> {
+        self.item_state
+            .selected()
+            .and_then(|index| self.item_markdown.get(index))
+            .and_then(|markdown| markdown.as_deref())
+            .map(toc_headings)
+            .unwrap_or_default()
+    }
+
+    /// Switches item dates between relative ("3h ago") and absolute local time.
+    pub fn toggle_date_style(&mut self) {
+        self.absolute_dates = !self.absolute_dates;
+    }
+
+    fn current_attachments(&self) -> Vec<media::Attachment> {
+        self.item_state
+            .selected()
+            .and_then(|i| self.current_items.get(i))
+            .map(media::item_attachments)
+            .unwrap_or_default()
+    }
+
+    fn selected_attachment(&self) -> Option<media::Attachment> {
+        if self.current_screen != Screen::Article {
+            return None;
+        }
+        self.current_attachments()
+            .into_iter()
+            .nth(self.selected_attachment)
+    }
+
+    pub fn select_attachment(&mut self, index: usize) {
+        if self.current_screen != Screen::Article {
+            return;
+        }
+        match self.current_attachments().get(index) {
+            Some(attachment) => {
+                self.selected_attachment = index;
+                self.status_message = tr!(
+                    "Attachment {}: {}. Press 'o' to open, 's' to save.",
+                    index + 1,
+                    attachment.describe()
+                );
+            }
+            None => self.status_message = tr!("No attachment {}", index + 1),
+        }
+    }
+
+    /// Opens the selected attachment with the system's default application.
+    pub fn open_attachment(&mut self) {
+        let Some(attachment) = self.selected_attachment() else {
+            return;
+        };
+        self.status_message = match open::that(&attachment.url) {
+            Ok(()) => tr!("Opened {}", attachment.url),
+            Err(err) => tr!("Error: failed to open {}: {}", attachment.url, err),
+        };
+    }
+
+    /// Copies the selected item's link to the clipboard.
+    pub fn copy_link(&mut self) {
+        if self.current_screen == Screen::Feeds {
+            return;
+        }
+        let Some(link) = self
+            .item_state
+            .selected()
+            .and_then(|index| self.current_items.get(index))
+            .and_then(|item| item.link())
+            .map(str::to_string)
+        else {
+            self.status_message = String::from(tr!("This item has no link"));
+            return;
+        };
+        let mode = clipboard::effective_mode(self.clipboard);
+        self.status_message = match clipboard::copy(&link, mode) {
+            Ok(()) if mode == ClipboardMode::Osc52 => tr!("Copied {} (OSC 52)", link),
+            Ok(()) => tr!("Copied {}", link),
+            Err(err) => tr!("Error: {}", format!("{:#}", err)),
+        };
+    }
+
+    /// Downloads the selected attachment into the downloads directory.
+    /// Starts saving the selected attachment in the background; `poll_background` reports where
+    /// it was saved.
+    pub fn save_attachment(&mut self) {
+        let Some(attachment) = self.selected_attachment() else {
+            return;
+        };
+        if self.download.is_some() {
+            self.status_message = String::from(tr!("A download is already in progress."));
+            return;
+        }
+        let cancel = CancellationToken::new();
+        self.download = Some(cancel.clone());
+        let name = attachment.file_name();
+        self.status_message = tr!("Downloading {}... Press 'Esc' to cancel.", name);
+        let sender = self.background_sender.clone();
+        tokio::spawn(async move {
+            let result = media::download(&attachment, &media::download_dir(), &cancel).await;
+            let _ = sender.send(BackgroundResult::Download { name, result });
+        });
+    }
+
+    pub fn scroll_down(&mut self) {
+        self.scroll_offset = self.scroll_offset.saturating_add(1).min(self.max_scroll);
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.scroll_offset = self.scroll_offset.saturating_sub(1);
+    }
+
+    /// Looks up the articles related to article `id` on a blocking thread, since the first
+    /// lookup reads every stored article.
+    fn find_related(&self, id: String, db: db::Database) {
+        let sender = self.background_sender.clone();
+        tokio::task::spawn_blocking(move || {
+            let related = related::related_articles(&db, &id, related::RELATED_LIMIT)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(_, record)| record)
+                .collect();
+            let _ = sender.send(BackgroundResult::Related { id, related });
+        });
+    }
+
+    /// Fetches the comment count of the selected item's discussion thread in
+    /// the background.
+    pub fn refresh_discussion(&mut self) {
+        if self.current_screen == Screen::Feeds {
+            return;
+        }
+        let Some(index) = self.item_state.selected() else {
+            return;
+        };
+        let Some(item) = self.current_items.get(index).cloned() else {
+            return;
+        };
+
+        self.status_message = String::from(tr!("Fetching comments..."));
+        let sender = self.background_sender.clone();
+        tokio::spawn(async move {
+            let result = enrich::fetch_discussion(&item).await;
+            let link = item.link().map(str::to_string);
+            let _ = sender.send(BackgroundResult::Discussion {
+                index,
+                link,
+                result,
+            });
+        });
+    }
+
+    /// Shows a finished discussion lookup, unless the item list has changed
+    /// since it was started.
+    fn show_discussion(
+        &mut self,
+        index: usize,
+        link: Option<String>,
+        result: Result<Option<Discussion>>,
+    ) {
+        let Some(item) = self.current_items.get(index) else {
+            return;
+        };
+        if item.link() != link.as_deref() {
+            return;
+        }
+        match result {
+            Ok(Some(discussion)) => {
+                self.status_message = format!(
+                    "{}: {} - {}",
+                    discussion.site,
+                    comment_label(&discussion),
+                    discussion.url
+                );
+                if let Some(slot) = self.discussions.get_mut(index) {
+                    *slot = Some(discussion);
+                }
+            }
+            Ok(None) => {
+                self.status_message = String::from(tr!("No discussion found for this item."));
+            }
+            Err(e) => {
+                self.status_message = tr!("Error: {}", e);
+            }
+        }
+    }
+
+    async fn load_markdown_for_selected(&mut self) -> Result<()> {
+        let Some(index) = self.item_state.selected() else {
+            return Ok(());
+        };
+        if self
+            .item_markdown
+            .get(index)
+            .map(|value| value.is_some())
+            .unwrap_or(false)
+        {
+            return Ok(());
+        }
+
+        let item = match self.current_items.get(index) {
+            Some(item) => item,
+            None => return Ok(()),
+        };
+        let feed_name = self
+            .current_feed_name
+            .as_deref()
+            .unwrap_or(tr!("Unknown Feed"));
+        let feed_url = self.current_feed_url.as_deref().unwrap_or("unknown");
+
+        let markdown = if let Some(db) = &self.db {
+            let id = db::item_id(feed_name, feed_url, item);
+            let _ = db.mark_read(&id);
+            let _ = db.record_open(&id);
+            db.read_item_markdown(feed_name, feed_url, item)
+        } else {
+            Some(db::extract_markdown(item))
+        };
+
+        if let Some(slot) = self.item_markdown.get_mut(index) {
+            *slot = markdown;
+        }
+
+        Ok(())
+    }
+}
+
+pub async fn run_tui(mut app: App) -> Result<()> {
+    if let (Some(store), Some(feed_name), Some(feed_url), Some(channel)) = (
+        &app.store,
+        &app.current_feed_name,
+        &app.current_feed_url,
+        &app.current_feed,
+    ) {
+        store.store_channel(
+            feed_name,
+            feed_url,
+            channel.clone(),
+            db::StoreOptions::default(),
+        );
+    }
+
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    if let Err(err) = execute!(stdout, EnterAlternateScreen, EnableBracketedPaste) {
+        let _ = disable_raw_mode();
+        return Err(err.into());
+    }
+    let backend = ratatui::backend::CrosstermBackend::new(stdout);
+    let mut terminal = match Terminal::new(backend) {
         Ok(terminal) => terminal,
         Err(err) => {
             let _ = disable_raw_mode();
@@ -378,6 +1732,9 @@ pub async fn run_tui(mut app: App) -> Result<()> {
 
     let res = run_app(&mut terminal, &mut app).await;
     let cleanup_res = restore_terminal(&mut terminal);
+    if let Some(store) = &app.store {
+        store.flush().await;
+    }
 
     if let Err(err) = res {
         let _ = cleanup_res;
@@ -392,7 +1749,11 @@ fn restore_terminal(
     terminal: &mut Terminal<ratatui::backend::CrosstermBackend<Stdout>>,
 ) -> Result<()> {
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    execute!(
+        terminal.backend_mut(),
+        DisableBracketedPaste,
+        LeaveAlternateScreen
+    )?;
     terminal.show_cursor()?;
     Ok(())
 }
@@ -401,13 +1762,45 @@ async fn run_app(
     terminal: &mut Terminal<ratatui::backend::CrosstermBackend<Stdout>>,
     app: &mut App,
 ) -> Result<()> {
+    let job_signals = JobSignals::register();
+    let configured_hyperlinks =
+        |app: &App| app.config.as_ref().and_then(|config| config.hyperlinks);
+    let mut hyperlinks = Hyperlinks::new(configured_hyperlinks(app));
     loop {
-        terminal.draw(|f| ui(f, app))?;
+        app.poll_prefetch();
+        app.poll_fetch().await;
+        app.poll_background();
+        let links = hyperlinks.scan(terminal.draw(|f| ui(f, app))?.buffer);
+        hyperlinks.draw(terminal.backend_mut(), links)?;
 
         // Very basic polling. For true async, we need a better event loop.
         if event::poll(std::time::Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
+            let event = event::read()?;
+            if let Event::Resize(..) = event {
+                // Start the next frame from a blank screen; `ui` re-wraps the article for the new
+                // width and clamps the scroll offset to it.
+                terminal.autoresize()?;
+                terminal.clear()?;
+                hyperlinks.reset();
+            }
+            if let Event::Paste(text) = &event {
+                app.handle_paste(text);
+            }
+            if let Event::Key(key) = event {
+                if key.kind == KeyEventKind::Press
+                    && key.code == KeyCode::Char('z')
+                    && key.modifiers.contains(KeyModifiers::CONTROL)
+                {
+                    job_signals.stop.store(true, Ordering::Relaxed);
+                } else if key.kind == KeyEventKind::Press && app.prompt.is_some() {
+                    app.handle_prompt_key(key);
+                } else if key.kind == KeyEventKind::Press && app.fetch_error.is_some() {
+                    app.handle_fetch_error_key(key.code).await;
+                } else if key.kind == KeyEventKind::Press && app.config_error.is_some() {
+                    app.handle_config_error_key(key.code);
+                } else if key.kind == KeyEventKind::Press && app.toc.is_some() {
+                    app.handle_toc_key(key.code);
+                } else if key.kind == KeyEventKind::Press {
                     match key.code {
                         KeyCode::Char('q') => {
                             if app.current_screen == Screen::Article {
@@ -434,23 +1827,243 @@ async fn run_app(
                         KeyCode::Char('u') | KeyCode::PageUp => {
                             app.scroll_up();
                         }
+                        KeyCode::Char('c') => {
+                            app.refresh_discussion();
+                        }
+                        KeyCode::Char('K') | KeyCode::Char('<') => {
+                            app.move_selected_feed(true);
+                        }
+                        KeyCode::Char('J') | KeyCode::Char('>') => {
+                            app.move_selected_feed(false);
+                        }
+                        KeyCode::Char('h') | KeyCode::Left => {
+                            app.collapse_category();
+                        }
+                        KeyCode::Char('l') | KeyCode::Right => {
+                            app.expand_category();
+                        }
+                        KeyCode::Char('A') => {
+                            app.mark_category_read();
+                        }
+                        KeyCode::Char('z') => {
+                            app.toggle_mute();
+                        }
+                        KeyCode::Char('i') => {
+                            app.toggle_channel_meta();
+                        }
+                        KeyCode::Char('m') => {
+                            app.load_more();
+                        }
+                        KeyCode::Char('t') => {
+                            app.toggle_date_style();
+                        }
+                        KeyCode::Char('T') => {
+                            app.open_toc();
+                        }
+                        KeyCode::Char('O') => {
+                            app.start_prompt(Prompt::Open);
+                        }
+                        KeyCode::Char('/') => {
+                            app.start_prompt(if app.current_screen == Screen::Article {
+                                Prompt::Find
+                            } else {
+                                Prompt::Search
+                            });
+                        }
+                        KeyCode::Char('n') => {
+                            app.next_match(true);
+                        }
+                        KeyCode::Char('N') => {
+                            app.next_match(false);
+                        }
+                        KeyCode::Char('S') => {
+                            app.subscribe_temporary_feed();
+                        }
+                        KeyCode::Char('o') => {
+                            app.open_attachment();
+                        }
+                        KeyCode::Char('v') => {
+                            app.open_in_viewer().await;
+                        }
+                        KeyCode::Char('E') => {
+                            app.edit_config();
+                        }
+                        KeyCode::Char('s') => {
+                            app.save_attachment();
+                        }
+                        KeyCode::Char('y') => {
+                            app.copy_link();
+                        }
+                        KeyCode::Char(digit @ '1'..='9') => {
+                            app.select_attachment(usize::from(digit as u8 - b'1'));
+                        }
                         _ => {}
                     }
                 }
             }
         }
 
+        if let Some(request) = app.viewer_request.take() {
+            app.status_message = match run_viewer(terminal, &request) {
+                Ok(status) if status.success() => String::from(tr!(
+                    "Closed the viewer. Press 'Enter' to view article, 'Esc' to back."
+                )),
+                Ok(status) => tr!("{} exited with {}", request.command, status),
+                Err(err) => tr!("Error: {}", format!("{:#}", err)),
+            };
+            hyperlinks.reset();
+        }
+
+        if job_signals.stop.swap(false, Ordering::Relaxed) {
+            suspend(terminal)?;
+            job_signals.resumed.store(false, Ordering::Relaxed);
+        }
+        if job_signals.resumed.swap(false, Ordering::Relaxed) {
+            resume(terminal)?;
+            hyperlinks.reset();
+        }
+
+        if std::mem::take(&mut app.edit_config_requested) {
+            if let Some(path) = app.config_path.clone() {
+                match run_external(terminal, EDITOR, &path) {
+                    Ok(_) => app.reload_config(),
+                    Err(err) => app.status_message = tr!("Error: {}", format!("{:#}", err)),
+                }
+                hyperlinks = Hyperlinks::new(configured_hyperlinks(app));
+            }
+        }
+
         if app.should_quit {
             return Ok(());
         }
     }
 }
 
+/// SIGTSTP and SIGCONT flags for the TUI's lifetime. Catching SIGTSTP (e.g. `kill -TSTP`) lets the
+/// TUI leave the alternate screen before stopping, and SIGCONT tells it to set the terminal up
+/// again, since the shell resets it while the process is stopped.
+#[derive(Default)]
+struct JobSignals {
+    stop: Arc<AtomicBool>,
+    resumed: Arc<AtomicBool>,
+    #[cfg(unix)]
+    ids: Vec<signal_hook::SigId>,
+}
+
+impl JobSignals {
+    fn register() -> Self {
+        #[allow(unused_mut)]
+        let mut signals = Self::default();
+        #[cfg(unix)]
+        for (signal, flag) in [
+            (signal_hook::consts::SIGTSTP, signals.stop.clone()),
+            (signal_hook::consts::SIGCONT, signals.resumed.clone()),
+        ] {
+            match signal_hook::flag::register(signal, flag) {
+                Ok(id) => signals.ids.push(id),
+                Err(err) => tracing::warn!("Failed to handle signal {}: {}", signal, err),
+            }
+        }
+        signals
+    }
+}
+
+#[cfg(unix)]
+impl Drop for JobSignals {
+    fn drop(&mut self) {
+        for id in self.ids.drain(..) {
+            signal_hook::low_level::unregister(id);
+        }
+    }
+}
+
+/// Leaves the TUI and stops the process as Ctrl-Z does in a shell, coming back once it's resumed
+/// with `fg`.
+#[cfg(unix)]
+fn suspend(terminal: &mut Terminal<ratatui::backend::CrosstermBackend<Stdout>>) -> Result<()> {
+    restore_terminal(terminal)?;
+    signal_hook::low_level::raise(signal_hook::consts::SIGSTOP)?;
+    resume(terminal)
+}
+
+#[cfg(not(unix))]
+fn suspend(_terminal: &mut Terminal<ratatui::backend::CrosstermBackend<Stdout>>) -> Result<()> {
+    Ok(())
+}
+
+/// Re-enters raw mode and the alternate screen and redraws everything on the next frame.
+fn resume(terminal: &mut Terminal<ratatui::backend::CrosstermBackend<Stdout>>) -> Result<()> {
+    enable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        EnterAlternateScreen,
+        EnableBracketedPaste
+    )?;
+    terminal.clear()?;
+    Ok(())
+}
+
+/// Runs the viewer on the article's markdown file. Articles that aren't stored as plain files
+/// are written to a private temporary file, removed once the viewer exits.
+fn run_viewer(
+    terminal: &mut Terminal<ratatui::backend::CrosstermBackend<Stdout>>,
+    request: &ViewerRequest,
+) -> Result<std::process::ExitStatus> {
+    let (path, temporary) = match &request.path {
+        Some(path) => (path.clone(), false),
+        None => {
+            let short_id = request.id.get(..12).unwrap_or(&request.id);
+            let path = std::env::temp_dir().join(format!("rss_reader-{}.md", short_id));
+            crypto::write_private(&path, request.markdown.as_bytes())?;
+            (path, true)
+        }
+    };
+    let status = run_external(terminal, &request.command, &path);
+    if temporary {
+        let _ = std::fs::remove_file(&path);
+    }
+    status
+}
+
+/// Leaves the TUI, runs `command` with `path` as its last argument and returns to the TUI once
+/// it exits. The command goes through `sh`, so it can use shell syntax like `$PAGER`.
+fn run_external(
+    terminal: &mut Terminal<ratatui::backend::CrosstermBackend<Stdout>>,
+    command: &str,
+    path: &std::path::Path,
+) -> Result<std::process::ExitStatus> {
+    restore_terminal(terminal)?;
+    let status = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", command))
+        .arg("sh")
+        .arg(path)
+        .status();
+    resume(terminal)?;
+    status.map_err(|err| anyhow!("Failed to run {}: {}", command, err))
+}
+
 fn ui(f: &mut Frame, app: &mut App) {
+    let area = f.area();
+    if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+        let message = tr!(
+            "Terminal too small ({}x{}), need at least {}x{}",
+            area.width,
+            area.height,
+            MIN_WIDTH,
+            MIN_HEIGHT
+        );
+        let placeholder = Paragraph::new(message)
+            .style(Style::default().fg(Color::Yellow))
+            .wrap(Wrap { trim: true });
+        f.render_widget(placeholder, area);
+        return;
+    }
+
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([Constraint::Min(0), Constraint::Length(3)].as_ref())
-        .split(f.area());
+        .split(area);
 
     let main_area = chunks[0];
     let status_area = chunks[1];
@@ -458,47 +2071,134 @@ fn ui(f: &mut Frame, app: &mut App) {
     match app.current_screen {
         Screen::Feeds => {
             let items: Vec<ListItem> = app
-                .feeds
-                .iter()
-                .map(|feed| {
-                    ListItem::new(Line::from(vec![
-                        Span::styled(
-                            format!("{} ", feed.name),
-                            Style::default().add_modifier(Modifier::BOLD),
-                        ),
-                        Span::styled(format!("({})", feed.url), Style::default().fg(Color::Gray)),
-                    ]))
+                .feed_rows()
+                .into_iter()
+                .filter_map(|row| match row {
+                    FeedRow::Category(name) => {
+                        let marker = if app.collapsed.contains(&name) {
+                            "▸"
+                        } else {
+                            "▾"
+                        };
+                        let unread = app.category_unread(&name);
+                        let (indent, label) = category_label(&name);
+                        Some(ListItem::new(Line::from(vec![
+                            Span::styled(
+                                format!("{}{} {} ", indent, marker, label),
+                                Style::default()
+                                    .add_modifier(Modifier::BOLD)
+                                    .fg(Color::Magenta),
+                            ),
+                            Span::styled(
+                                tr!("({} unread)", unread),
+                                Style::default().fg(Color::Gray),
+                            ),
+                        ])))
+                    }
+                    FeedRow::Feed(i) => {
+                        let feed = app.feeds.get(i)?;
+                        let indent = match &feed.category {
+                            Some(category) => format!("{}  ", category_label(category).0),
+                            None => String::new(),
+                        };
+                        let unread = app.feed_unread(feed);
+                        let muted_until = app.muted_until(feed);
+                        let name_style = match muted_until {
+                            Some(_) => Style::default().fg(Color::DarkGray),
+                            None => Style::default().add_modifier(Modifier::BOLD),
+                        };
+                        let mut spans = vec![Span::styled(
+                            format!("{}{} ", indent, feed.name),
+                            name_style,
+                        )];
+                        if let Some(until) = muted_until {
+                            spans.push(Span::styled(
+                                format!("{} ", tr!("muted until {}", dates::display(until, false))),
+                                Style::default().fg(Color::DarkGray),
+                            ));
+                        } else if unread > 0 {
+                            spans.push(Span::styled(
+                                format!("[{}] ", unread),
+                                Style::default().fg(Color::Cyan),
+                            ));
+                        }
+                        match app.item_counts.get(&feed.url) {
+                            Some(Some(count)) => spans.push(Span::styled(
+                                format!("{} ", tr!("{} items", count)),
+                                Style::default().fg(Color::Green),
+                            )),
+                            Some(None) => spans.push(Span::styled(
+                                format!("{} ", tr!("failed")),
+                                Style::default().fg(Color::Red),
+                            )),
+                            None => {}
+                        }
+                        spans.push(Span::styled(
+                            format!("({})", feed.display_url()),
+                            Style::default().fg(Color::Gray),
+                        ));
+                        Some(ListItem::new(Line::from(spans)))
+                    }
                 })
                 .collect();
 
-            let list = List::new(items)
-                .block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .title("Feeds Configuration"),
-                )
-                .highlight_style(
-                    Style::default()
-                        .add_modifier(Modifier::BOLD)
-                        .fg(Color::Yellow),
-                )
-                .highlight_symbol(">> ");
+            let list =
+                List::new(items)
+                    .block(Block::default().borders(Borders::ALL).title(
+                        if app.prefetch_pending > 0 {
+                            tr!(
+                                "Feeds Configuration (prefetching, {} left)",
+                                app.prefetch_pending
+                            )
+                        } else {
+                            tr!("Feeds Configuration").to_string()
+                        },
+                    ))
+                    .highlight_style(
+                        Style::default()
+                            .add_modifier(Modifier::BOLD)
+                            .fg(Color::Yellow),
+                    )
+                    .highlight_symbol(">> ");
 
             f.render_stateful_widget(list, main_area, &mut app.feed_state);
         }
         Screen::Items => {
-            let title = if let Some(channel) = &app.current_feed {
+            let mut title = if let Some(channel) = &app.current_feed {
                 channel.title().to_string()
             } else {
-                "Feed Items".to_string()
+                tr!("Feed Items").to_string()
             };
+            if app.shown_items < app.current_items.len() {
+                title = tr!(
+                    "{} ({} of {}, 'm' for more)",
+                    title,
+                    app.shown_items,
+                    app.current_items.len()
+                );
+            }
 
             let items: Vec<ListItem> = app
                 .current_items
                 .iter()
-                .map(|i| {
-                    let title = i.title().unwrap_or("No Title");
-                    ListItem::new(Line::from(Span::raw(title)))
+                .take(app.shown_items)
+                .enumerate()
+                .map(|(index, i)| {
+                    let title = i.title().unwrap_or(tr!("No Title"));
+                    let mut spans = vec![Span::raw(title)];
+                    if let Some(pub_date) = i.pub_date() {
+                        spans.push(Span::styled(
+                            format!(" {}", dates::display(pub_date, !app.absolute_dates)),
+                            Style::default().fg(Color::DarkGray),
+                        ));
+                    }
+                    if let Some(Some(discussion)) = app.discussions.get(index) {
+                        spans.push(Span::styled(
+                            format!(" [{}]", comment_label(discussion)),
+                            Style::default().fg(Color::Gray),
+                        ));
+                    }
+                    ListItem::new(Line::from(spans))
                 })
                 .collect();
 
@@ -519,24 +2219,85 @@ fn ui(f: &mut Frame, app: &mut App) {
                 .selected()
                 .and_then(|i| app.current_items.get(i));
 
-            let details_text = if let Some(item) = selected_item {
+            let mut jump_line = None;
+            let mut details_text = if let Some(item) = selected_item {
                 let mut lines = Vec::new();
                 lines.push(Line::from(vec![
-                    Span::styled("Title: ", Style::default().add_modifier(Modifier::BOLD)),
-                    Span::raw(item.title().unwrap_or("No Title")),
+                    Span::styled(
+                        tr!("Title: "),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(item.title().unwrap_or(tr!("No Title"))),
                 ]));
 
                 if let Some(link) = item.link() {
                     lines.push(Line::from(vec![
-                        Span::styled("Link: ", Style::default().add_modifier(Modifier::BOLD)),
+                        Span::styled(tr!("Link: "), Style::default().add_modifier(Modifier::BOLD)),
                         Span::raw(link),
                     ]));
                 }
 
+                if let Some(author) = item.author() {
+                    lines.push(Line::from(vec![
+                        Span::styled(
+                            tr!("Author: "),
+                            Style::default().add_modifier(Modifier::BOLD),
+                        ),
+                        Span::raw(author),
+                    ]));
+                }
+
                 if let Some(pub_date) = item.pub_date() {
                     lines.push(Line::from(vec![
-                        Span::styled("Date: ", Style::default().add_modifier(Modifier::BOLD)),
-                        Span::raw(pub_date),
+                        Span::styled(tr!("Date: "), Style::default().add_modifier(Modifier::BOLD)),
+                        Span::raw(dates::display(pub_date, !app.absolute_dates)),
+                    ]));
+                }
+
+                if let Some(updated) = db::item_updated(item) {
+                    lines.push(Line::from(vec![
+                        Span::styled(
+                            tr!("Updated: "),
+                            Style::default().add_modifier(Modifier::BOLD),
+                        ),
+                        Span::raw(dates::display(&updated, !app.absolute_dates)),
+                    ]));
+                }
+
+                if let Some(copy) = &app.previous_copy {
+                    let text = match &copy.read_at {
+                        Some(read_at) => tr!(
+                            "Previously read on {} in {}",
+                            dates::display(read_at, false),
+                            copy.record.feed_name
+                        ),
+                        None => tr!(
+                            "Previously stored on {} in {}",
+                            dates::display(&copy.record.time, false),
+                            copy.record.feed_name
+                        ),
+                    };
+                    lines.push(Line::from(Span::styled(
+                        text,
+                        Style::default().fg(Color::Yellow),
+                    )));
+                }
+
+                if let Some(Some(discussion)) = app
+                    .item_state
+                    .selected()
+                    .and_then(|i| app.discussions.get(i))
+                {
+                    lines.push(Line::from(vec![
+                        Span::styled(
+                            tr!("Comments: "),
+                            Style::default().add_modifier(Modifier::BOLD),
+                        ),
+                        Span::raw(format!(
+                            "{} ({})",
+                            comment_label(discussion),
+                            discussion.url
+                        )),
                     ]));
                 }
 
@@ -548,40 +2309,566 @@ fn ui(f: &mut Frame, app: &mut App) {
                     .and_then(|value| value.as_ref());
                 match markdown {
                     Some(markdown) => {
+                        let removed = hygiene::removed_count(markdown);
+                        if removed > 0 {
+                            lines.push(Line::from(Span::styled(
+                                format!("{} tracker(s) removed", removed),
+                                Style::default().fg(Color::DarkGray),
+                            )));
+                        }
                         if !markdown.trim().is_empty() {
+                            let feed_name = app
+                                .current_feed_name
+                                .as_deref()
+                                .unwrap_or(tr!("Unknown Feed"));
+                            let feed_url = app.current_feed_url.as_deref().unwrap_or("unknown");
+                            let width = main_area.width.saturating_sub(2);
+                            let cache_key =
+                                format!("{}:{}", db::item_id(feed_name, feed_url, item), width);
                             lines.push(Line::from(""));
-                            lines.extend(markdown_to_lines(markdown, main_area.width));
+                            if let Some(heading) = app.toc_jump.take() {
+                                jump_line = toc_headings(markdown)
+                                    .get(heading)
+                                    .map(|heading| lines.len() + heading.line);
+                            }
+                            lines.extend(app.render_cache.get_or_render(
+                                &cache_key,
+                                markdown,
+                                |markdown| markdown_to_lines(markdown, width),
+                            ));
                         } else {
-                            lines.push(Line::from("No content."));
+                            lines.push(Line::from(tr!("No content.")));
                         }
                     }
                     None => {
-                        lines.push(Line::from("Content is still processing..."));
+                        lines.push(Line::from(tr!("Content is still processing...")));
+                    }
+                }
+
+                let attachments = media::item_attachments(item);
+                if !attachments.is_empty() {
+                    lines.push(Line::from(""));
+                    lines.push(Line::from(Span::styled(
+                        tr!("Attachments"),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    )));
+                    for (i, attachment) in attachments.iter().enumerate() {
+                        let style = if i == app.selected_attachment {
+                            Style::default().fg(Color::Yellow)
+                        } else {
+                            Style::default()
+                        };
+                        lines.push(Line::from(vec![
+                            Span::styled(format!("[{}] {} ", i + 1, attachment.describe()), style),
+                            Span::styled(attachment.url.clone(), Style::default().fg(Color::Gray)),
+                        ]));
+                    }
+                    lines.push(Line::from(Span::styled(
+                        tr!("1-9 select, o open, s save to data/downloads"),
+                        Style::default().fg(Color::DarkGray),
+                    )));
+                }
+
+                if !app.related.is_empty() {
+                    lines.push(Line::from(""));
+                    lines.push(Line::from(Span::styled(
+                        tr!("Related from your archive"),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    )));
+                    for record in &app.related {
+                        lines.push(Line::from(vec![
+                            Span::raw(format!("• {} ", record.title)),
+                            Span::styled(
+                                format!(
+                                    "({}, {})",
+                                    record.feed_name,
+                                    dates::display(&record.time, !app.absolute_dates)
+                                ),
+                                Style::default().fg(Color::Gray),
+                            ),
+                        ]));
                     }
                 }
 
                 lines
             } else {
-                vec![Line::from("No item selected")]
+                vec![Line::from(tr!("No item selected"))]
             };
 
+            if let Some(search) = app.article_search.as_mut() {
+                let matches = highlight_matches(&mut details_text, &search.query, search.current);
+                // The status bar is only rewritten when the search moved or its matches changed,
+                // so other messages stay until then.
+                let announce = search.jump || search.count != matches.len();
+                search.count = matches.len();
+                search.current = search.current.min(matches.len().saturating_sub(1));
+                if std::mem::take(&mut search.jump) {
+                    jump_line = matches.get(search.current).copied();
+                }
+                if announce && matches.is_empty() {
+                    app.status_message = tr!("No match for \"{}\" in the article", search.query);
+                } else if announce {
+                    app.status_message = tr!(
+                        "Match {} of {} for \"{}\" (n next, N previous, Esc to clear)",
+                        search.current + 1,
+                        matches.len(),
+                        search.query
+                    );
+                }
+            }
+            // Rows the lines above the chosen heading or match wrap to, which is how far to
+            // scroll.
+            let jump_row = jump_line.map(|line| {
+                Paragraph::new(details_text[..line.min(details_text.len())].to_vec())
+                    .wrap(Wrap { trim: true })
+                    .line_count(main_area.width.saturating_sub(2))
+            });
             let paragraph = Paragraph::new(details_text)
-                .block(Block::default().borders(Borders::ALL).title("Article View"))
-                .wrap(Wrap { trim: true })
-                .scroll((app.scroll_offset, 0));
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(tr!("Article View")),
+                )
+                .wrap(Wrap { trim: true });
+            let line_count = paragraph.line_count(main_area.width.saturating_sub(2));
+            app.max_scroll =
+                u16::try_from(line_count.saturating_sub(usize::from(main_area.height)))
+                    .unwrap_or(u16::MAX);
+            if let Some(row) = jump_row {
+                app.scroll_offset = u16::try_from(row).unwrap_or(u16::MAX);
+            } else if let Some(position) = app.resume_position.take() {
+                app.scroll_offset = (position * f64::from(app.max_scroll)).round() as u16;
+            }
+            app.scroll_offset = app.scroll_offset.min(app.max_scroll);
+            let paragraph = paragraph.scroll((app.scroll_offset, 0));
 
             f.render_widget(paragraph, main_area);
         }
     }
 
+    if app.show_channel_meta && app.current_screen == Screen::Items {
+        if let Some(channel) = &app.current_feed {
+            let area = centered_rect(70, 60, main_area);
+            let popup = Paragraph::new(channel_meta_lines(channel))
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(tr!("Channel Details (Esc or 'i' to close)")),
+                )
+                .wrap(Wrap { trim: true });
+            f.render_widget(Clear, area);
+            f.render_widget(popup, area);
+        }
+    }
+
+    if app.toc.is_some() && app.current_screen == Screen::Article {
+        let headings = app.article_headings();
+        let top = headings
+            .iter()
+            .map(|heading| heading.level)
+            .min()
+            .unwrap_or(1);
+        let items: Vec<ListItem> = headings
+            .iter()
+            .map(|heading| {
+                let indent = "  ".repeat(usize::from(heading.level - top));
+                ListItem::new(format!("{}{}", indent, heading.text))
+            })
+            .collect();
+        let area = centered_rect(60, 70, main_area);
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(tr!("Contents (Enter to jump, Esc to close)")),
+            )
+            .highlight_style(
+                Style::default()
+                    .add_modifier(Modifier::BOLD)
+                    .fg(Color::Yellow),
+            )
+            .highlight_symbol(">> ");
+        f.render_widget(Clear, area);
+        if let Some(state) = app.toc.as_mut() {
+            f.render_stateful_widget(list, area, state);
+        }
+    }
+
+    if let Some(error) = &app.fetch_error {
+        let area = centered_rect(80, 60, main_area);
+        let mut lines = vec![Line::from(Span::styled(
+            error.feed.url.clone(),
+            Style::default().fg(Color::Gray),
+        ))];
+        lines.push(Line::from(""));
+        lines.extend(
+            error
+                .message
+                .lines()
+                .map(|line| Line::from(line.to_string())),
+        );
+        lines.push(Line::from(""));
+        let cached = if error.cached.is_some() {
+            tr!("[v] View cached copy  ")
+        } else {
+            ""
+        };
+        lines.push(Line::from(Span::styled(
+            tr!(
+                "[r] Retry  [o] Open in browser  {}[p] Pause feed  [Esc] Dismiss",
+                cached
+            ),
+            Style::default().add_modifier(Modifier::BOLD),
+        )));
+        let popup = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Red))
+                    .title(tr!(
+                        "Failed to fetch {}",
+                        if error.feed.name.is_empty() {
+                            &error.feed.url
+                        } else {
+                            &error.feed.name
+                        }
+                    )),
+            )
+            .wrap(Wrap { trim: true });
+        f.render_widget(Clear, area);
+        f.render_widget(popup, area);
+    }
+
+    if let Some(error) = &app.config_error {
+        let area = centered_rect(80, 60, main_area);
+        let mut lines: Vec<Line> = error
+            .lines()
+            .map(|line| Line::from(line.to_string()))
+            .collect();
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            tr!("[e] Edit again  [Esc] Keep the previous config"),
+            Style::default().add_modifier(Modifier::BOLD),
+        )));
+        // Parse errors point at the offending column, so leading spaces are kept.
+        let popup = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Red))
+                    .title(tr!("Invalid config")),
+            )
+            .wrap(Wrap { trim: false });
+        f.render_widget(Clear, area);
+        f.render_widget(popup, area);
+    }
+
     // Status Bar
-    let status_paragraph = Paragraph::new(app.status_message.clone())
-        .block(Block::default().borders(Borders::ALL).title("Status"));
+    let status_paragraph = match app.prompt {
+        Some(prompt) => {
+            let (input, title) = match prompt {
+                Prompt::Open => (
+                    &app.open_input,
+                    tr!("Open URL or RSSHub route (Enter to open, Esc to cancel)"),
+                ),
+                Prompt::Search => (
+                    &app.search_input,
+                    tr!("Find in item titles (Enter to find next, Esc to cancel)"),
+                ),
+                Prompt::Find => (
+                    &app.find_input,
+                    tr!("Find in the article (Enter to search, Esc to cancel)"),
+                ),
+            };
+            let (line, cursor_x) = input.view(status_area.width.saturating_sub(2));
+            f.set_cursor_position((status_area.x + 1 + cursor_x, status_area.y + 1));
+            Paragraph::new(line).block(Block::default().borders(Borders::ALL).title(title))
+        }
+        None => Paragraph::new(app.status_message.clone())
+            .block(Block::default().borders(Borders::ALL).title(tr!("Status"))),
+    };
     f.render_widget(status_paragraph, status_area);
 }
 
-fn markdown_to_lines(markdown: &str, width: u16) -> Vec<Line<'static>> {
-    let text = parse_text(markdown, Options::default());
+fn channel_meta_lines(channel: &Channel) -> Vec<Line<'static>> {
+    let ttl = channel.ttl().map(|ttl| tr!("{} minutes", ttl));
+    let last_build = channel
+        .last_build_date()
+        .map(|date| dates::display(date, false));
+    let published = channel.pub_date().map(|date| dates::display(date, false));
+    let fields = [
+        (tr!("Title"), Some(channel.title())),
+        (tr!("Description"), Some(channel.description())),
+        (tr!("Site"), Some(channel.link())),
+        (tr!("Language"), channel.language()),
+        (tr!("Last build"), last_build.as_deref()),
+        (tr!("Published"), published.as_deref()),
+        (tr!("Image"), channel.image().map(|image| image.url())),
+        (tr!("TTL"), ttl.as_deref()),
+        (tr!("Generator"), channel.generator()),
+    ];
+    let mut lines: Vec<Line<'static>> = fields
+        .into_iter()
+        .filter_map(|(label, value)| {
+            let value = value.map(str::trim).filter(|value| !value.is_empty())?;
+            Some(Line::from(vec![
+                Span::styled(
+                    format!("{}: ", label),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(value.to_string()),
+            ]))
+        })
+        .collect();
+    lines.push(Line::from(vec![
+        Span::styled(
+            tr!("Items: "),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(channel.items().len().to_string()),
+    ]));
+    lines
+}
+
+/// A rectangle of the given percentage of `area`, centered in it.
+fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(area);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(vertical[1])[1]
+}
+
+/// Indentation for the category's nesting depth, and its last level as the heading text.
+fn category_label(category: &str) -> (String, &str) {
+    match category.rsplit_once(CATEGORY_SEPARATOR) {
+        Some((parent, label)) => ("  ".repeat(parent.split(CATEGORY_SEPARATOR).count()), label),
+        None => (String::new(), category),
+    }
+}
+
+fn comment_label(discussion: &Discussion) -> String {
+    match discussion.comments {
+        Some(count) => tr!("{} comments", count),
+        None => String::from(tr!("comments")),
+    }
+}
+
+/// The terminal can't collapse sections, so `<details>` markers become a bold summary line
+/// followed by the section's content.
+fn expand_details(markdown: &str) -> String {
+    markdown
+        .lines()
+        .filter(|line| line.trim() != "</details>" && !hygiene::is_marker(line))
+        .map(|line| match db::details_summary(line) {
+            Some(summary) => format!("**▸ {}**", summary),
+            None => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Footnote references become superscript numbers, with the notes listed under a rule at the
+/// end of the article.
+fn append_footnotes(markdown: &str) -> String {
+    let (mut markdown, notes) = footnotes::number_markdown(markdown);
+    if notes.is_empty() {
+        return markdown;
+    }
+    markdown.push_str(&format!("\n\n---\n\n**{}**\n", tr!("Notes")));
+    for (index, note) in notes.iter().enumerate() {
+        markdown.push_str(&format!(
+            "\n{} {}\n",
+            footnotes::superscript(index + 1),
+            note
+        ));
+    }
+    markdown
+}
+
+/// Math blocks stay raw TeX in a code block, under a marker line telling them from code.
+fn mark_math(markdown: &str) -> String {
+    markdown
+        .lines()
+        .map(|line| match line.trim() {
+            "```math" => "**∑ TeX**\n```".to_string(),
+            _ => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Highlights the case-insensitive matches of `query` in `lines`, the `current`th one apart from
+/// the rest, and returns the index of the line each match is on.
+fn highlight_matches(lines: &mut [Line<'_>], query: &str, current: usize) -> Vec<usize> {
+    let fold = |c: char| c.to_lowercase().next().unwrap_or(c);
+    let needle: Vec<char> = query.chars().map(fold).collect();
+    let mut found = Vec::new();
+    if needle.is_empty() {
+        return found;
+    }
+    let match_style = Style::default().bg(Color::Yellow).fg(Color::Black);
+    let current_style = Style::default().bg(Color::LightRed).fg(Color::Black);
+    for (index, line) in lines.iter_mut().enumerate() {
+        let text: Vec<char> = line
+            .spans
+            .iter()
+            .flat_map(|span| span.content.chars())
+            .map(fold)
+            .collect();
+        // Character ranges of the matches on this line, with the style each is shown in.
+        let mut ranges = Vec::new();
+        let mut start = 0;
+        while start + needle.len() <= text.len() {
+            if text[start..start + needle.len()] == needle[..] {
+                let style = if found.len() == current {
+                    current_style
+                } else {
+                    match_style
+                };
+                ranges.push((start, start + needle.len(), style));
+                found.push(index);
+                start += needle.len();
+            } else {
+                start += 1;
+            }
+        }
+        if ranges.is_empty() {
+            continue;
+        }
+        let mut spans = Vec::new();
+        let mut offset = 0;
+        for span in line.spans.drain(..) {
+            let chars: Vec<char> = span.content.chars().collect();
+            let end = offset + chars.len();
+            let mut position = offset;
+            while position < end {
+                let (next, style) = match ranges
+                    .iter()
+                    .find(|(start, end, _)| *start <= position && position < *end)
+                {
+                    Some((_, match_end, style)) => (*match_end, span.style.patch(*style)),
+                    None => (
+                        ranges
+                            .iter()
+                            .map(|(start, _, _)| *start)
+                            .find(|start| *start > position)
+                            .unwrap_or(end),
+                        span.style,
+                    ),
+                };
+                let next = next.min(end);
+                spans.push(Span::styled(
+                    chars[position - offset..next - offset]
+                        .iter()
+                        .collect::<String>(),
+                    style,
+                ));
+                position = next;
+            }
+            offset = end;
+        }
+        line.spans = spans;
+    }
+    found
+}
+
+/// A heading of the open article, for its table of contents.
+struct TocHeading {
+    /// Index of the heading among the lines `markdown_to_lines` renders.
+    line: usize,
+    level: u8,
+    text: String,
+}
+
+/// The headings of an article when it has at least `db::MIN_TOC_HEADINGS`, found the way
+/// `markdown_to_lines` renders it so each one knows its line.
+fn toc_headings(markdown: &str) -> Vec<TocHeading> {
+    let markdown = article_text(markdown);
+    let headings: Vec<TocHeading> = parse_text(&markdown, Options::default())
+        .lines
+        .iter()
+        .enumerate()
+        .filter_map(|(line, md_line)| match md_line {
+            MdLine::Normal(composite) => match composite.style {
+                CompositeStyle::Header(level) => Some(TocHeading {
+                    line,
+                    level,
+                    text: composite_plain(composite).trim().to_string(),
+                }),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    if headings.len() < db::MIN_TOC_HEADINGS {
+        return Vec::new();
+    }
+    headings
+}
+
+/// Stored markdown as the article view shows it.
+fn article_text(markdown: &str) -> String {
+    append_footnotes(&mark_math(&expand_details(&atx_headings(markdown))))
+}
+
+/// minimad only knows `#` headings, so the underlined ones html2md writes for `<h1>` and `<h2>`
+/// are rewritten as `#` and `##`, and the closing `#`s it adds to the others dropped.
+fn atx_headings(markdown: &str) -> String {
+    let lines: Vec<&str> = markdown.lines().collect();
+    let mut converted = Vec::with_capacity(lines.len());
+    let mut in_code = false;
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index];
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+        let underline = lines.get(index + 1).map(|next| next.trim()).filter(|next| {
+            next.len() >= 2 && (next.chars().all(|c| c == '=') || next.chars().all(|c| c == '-'))
+        });
+        match underline {
+            Some(underline)
+                if !in_code
+                    && !line.trim().is_empty()
+                    && !line.trim_start().starts_with(['#', '>', '-', '*', '|']) =>
+            {
+                let level = if underline.starts_with('=') {
+                    "#"
+                } else {
+                    "##"
+                };
+                converted.push(format!("{} {}", level, line.trim()));
+                index += 2;
+                continue;
+            }
+            _ => {}
+        }
+        // A closing sequence is set off by a space, so `# C#` keeps its `#`.
+        let closed = Some(line.trim_end_matches('#'))
+            .filter(|rest| !in_code && line.starts_with('#') && rest.len() < line.len())
+            .filter(|rest| rest.ends_with(' '))
+            .map(str::trim_end);
+        converted.push(closed.unwrap_or(line).to_string());
+        index += 1;
+    }
+    converted.join("\n")
+}
+
+pub fn markdown_to_lines(markdown: &str, width: u16) -> Vec<Line<'static>> {
+    let markdown = article_text(markdown);
+    let text = parse_text(&markdown, Options::default());
     let max_width = usize::from(width.max(1));
     let mut lines = Vec::new();
 
@@ -605,7 +2892,7 @@ fn markdown_to_lines(markdown: &str, width: u16) -> Vec<Line<'static>> {
     }
 
     if lines.is_empty() {
-        lines.push(Line::from("No content."));
+        lines.push(Line::from(tr!("No content.")));
     }
 
     lines
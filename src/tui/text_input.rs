@@ -0,0 +1,153 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::text::{Line, Span};
+
+/// What a key pressed in a [`TextInput`] asks the prompt to do.
+pub enum InputAction {
+    /// Enter was pressed with this text, which is now in the history.
+    Submit(String),
+    Cancel,
+    Edited,
+}
+
+/// Single-line input with a movable cursor, pasting and a history of submitted values.
+///
+/// The cursor counts characters rather than bytes, so multi-byte and IME-composed input edits
+/// correctly, and display widths come from ratatui so wide characters line up with the cursor.
+#[derive(Default)]
+pub struct TextInput {
+    value: String,
+    cursor: usize,
+    history: Vec<String>,
+    /// History entry being shown while browsing with Up and Down.
+    history_index: Option<usize>,
+    /// What was typed before browsing the history, restored by going past its newest entry.
+    draft: String,
+}
+
+impl TextInput {
+    /// Clears the text for a new prompt, keeping the history.
+    pub fn reset(&mut self) {
+        self.set_value(String::new());
+        self.history_index = None;
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> InputAction {
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Enter => return InputAction::Submit(self.submit()),
+            KeyCode::Esc => return InputAction::Cancel,
+            KeyCode::Char('a') if control => self.cursor = 0,
+            KeyCode::Char('e') if control => self.cursor = self.len(),
+            KeyCode::Char('u') if control => {
+                let start = self.byte_index(self.cursor);
+                self.value.replace_range(..start, "");
+                self.cursor = 0;
+            }
+            KeyCode::Char(c) if !control => self.insert(c),
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.value.remove(self.byte_index(self.cursor));
+            }
+            KeyCode::Delete if self.cursor < self.len() => {
+                self.value.remove(self.byte_index(self.cursor));
+            }
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.len(),
+            KeyCode::Up => self.browse_history(true),
+            KeyCode::Down => self.browse_history(false),
+            _ => {}
+        }
+        InputAction::Edited
+    }
+
+    /// Inserts pasted text at the cursor. Line breaks become spaces since the input is a single
+    /// line, and other control characters are dropped.
+    pub fn paste(&mut self, text: &str) {
+        for c in text.trim_end_matches(['\r', '\n']).chars() {
+            match c {
+                '\n' | '\r' | '\t' => self.insert(' '),
+                c if c.is_control() => {}
+                c => self.insert(c),
+            }
+        }
+    }
+
+    /// The visible part of the text for an area `width` columns wide, scrolled so the cursor
+    /// stays in view, and the cursor's column within it.
+    pub fn view(&self, width: u16) -> (Line<'static>, u16) {
+        let width = usize::from(width.max(1));
+        let chars: Vec<char> = self.value.chars().collect();
+        let mut start = 0;
+        while start < self.cursor && text_width(&chars[start..self.cursor]) >= width {
+            start += 1;
+        }
+        let cursor_x = text_width(&chars[start..self.cursor]);
+        let mut end = self.cursor;
+        while end < chars.len() && text_width(&chars[start..=end]) <= width {
+            end += 1;
+        }
+        let visible: String = chars[start..end].iter().collect();
+        (
+            Line::from(visible),
+            u16::try_from(cursor_x).unwrap_or(u16::MAX),
+        )
+    }
+
+    fn submit(&mut self) -> String {
+        let value = self.value.trim().to_string();
+        if !value.is_empty() && self.history.last() != Some(&value) {
+            self.history.push(value.clone());
+        }
+        self.history_index = None;
+        value
+    }
+
+    fn browse_history(&mut self, older: bool) {
+        if self.history.is_empty() {
+            return;
+        }
+        let index = match (self.history_index, older) {
+            (None, true) => {
+                self.draft = self.value.clone();
+                Some(self.history.len() - 1)
+            }
+            (None, false) => return,
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) if index + 1 < self.history.len() => Some(index + 1),
+            (Some(_), false) => None,
+        };
+        self.history_index = index;
+        let value = match index {
+            Some(index) => self.history[index].clone(),
+            None => std::mem::take(&mut self.draft),
+        };
+        self.set_value(value);
+    }
+
+    fn set_value(&mut self, value: String) {
+        self.value = value;
+        self.cursor = self.len();
+    }
+
+    fn insert(&mut self, c: char) {
+        self.value.insert(self.byte_index(self.cursor), c);
+        self.cursor += 1;
+    }
+
+    fn len(&self) -> usize {
+        self.value.chars().count()
+    }
+
+    fn byte_index(&self, cursor: usize) -> usize {
+        self.value
+            .char_indices()
+            .nth(cursor)
+            .map_or(self.value.len(), |(index, _)| index)
+    }
+}
+
+fn text_width(chars: &[char]) -> usize {
+    Span::raw(chars.iter().collect::<String>()).width()
+}
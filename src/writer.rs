@@ -0,0 +1,149 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use rss::Channel;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::backup;
+use crate::db::{ArticleRecord, Database, StoreOptions};
+
+enum Job {
+    StoreChannel {
+        feed_name: String,
+        feed_url: String,
+        channel: Box<Channel>,
+        options: StoreOptions,
+    },
+    ImportArticle {
+        record: ArticleRecord,
+        markdown: String,
+        reply: oneshot::Sender<Result<bool>>,
+    },
+    Backup {
+        config_path: PathBuf,
+        output: PathBuf,
+        reply: oneshot::Sender<Result<u64>>,
+    },
+    Flush(oneshot::Sender<()>),
+}
+
+/// Handle to the single task that performs all archive writes, one at a time and in the order
+/// they were queued, so concurrent fetches never interleave their index.csv updates.
+#[derive(Clone)]
+pub struct StoreWriter {
+    jobs: mpsc::UnboundedSender<Job>,
+    pending: Arc<AtomicUsize>,
+}
+
+impl StoreWriter {
+    /// Starts the writer task. It runs until every handle has been dropped.
+    pub fn spawn(db: Database) -> Self {
+        let (jobs, mut queue) = mpsc::unbounded_channel();
+        let pending = Arc::new(AtomicUsize::new(0));
+        let counter = pending.clone();
+        tokio::spawn(async move {
+            while let Some(job) = queue.recv().await {
+                match job {
+                    Job::StoreChannel {
+                        feed_name,
+                        feed_url,
+                        channel,
+                        options,
+                    } => {
+                        if let Err(err) = db
+                            .store_channel(&feed_name, &feed_url, &channel, options)
+                            .await
+                        {
+                            tracing::warn!("Failed to store {}: {:#}", feed_name, err);
+                        }
+                        counter.fetch_sub(1, Ordering::SeqCst);
+                    }
+                    Job::ImportArticle {
+                        record,
+                        markdown,
+                        reply,
+                    } => {
+                        let _ = reply.send(db.import_article(&record, &markdown));
+                    }
+                    Job::Backup {
+                        config_path,
+                        output,
+                        reply,
+                    } => {
+                        let _ = reply.send(backup::create(&db, &config_path, &output));
+                    }
+                    Job::Flush(done) => {
+                        let _ = done.send(());
+                    }
+                }
+            }
+        });
+        Self { jobs, pending }
+    }
+
+    /// Queues a fetched channel to be stored in the background.
+    pub fn store_channel(
+        &self,
+        feed_name: &str,
+        feed_url: &str,
+        channel: Channel,
+        options: StoreOptions,
+    ) {
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        let job = Job::StoreChannel {
+            feed_name: feed_name.to_string(),
+            feed_url: feed_url.to_string(),
+            channel: Box::new(channel),
+            options,
+        };
+        if self.jobs.send(job).is_err() {
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Stores an article received from another instance once earlier writes are done.
+    pub async fn import_article(&self, record: ArticleRecord, markdown: String) -> Result<bool> {
+        let (reply, result) = oneshot::channel();
+        self.jobs
+            .send(Job::ImportArticle {
+                record,
+                markdown,
+                reply,
+            })
+            .map_err(|_| anyhow!("Store writer has stopped"))?;
+        result
+            .await
+            .map_err(|_| anyhow!("Store writer has stopped"))?
+    }
+
+    /// Backs up the config and the store once earlier writes are done, holding later ones until
+    /// the backup is written. Returns its size in bytes.
+    pub async fn backup(&self, config_path: &Path, output: &Path) -> Result<u64> {
+        let (reply, result) = oneshot::channel();
+        self.jobs
+            .send(Job::Backup {
+                config_path: config_path.to_path_buf(),
+                output: output.to_path_buf(),
+                reply,
+            })
+            .map_err(|_| anyhow!("Store writer has stopped"))?;
+        result
+            .await
+            .map_err(|_| anyhow!("Store writer has stopped"))?
+    }
+
+    /// Number of queued channels not yet stored.
+    pub fn pending(&self) -> usize {
+        self.pending.load(Ordering::SeqCst)
+    }
+
+    /// Waits until every write queued so far has finished.
+    pub async fn flush(&self) {
+        let (done, finished) = oneshot::channel();
+        if self.jobs.send(Job::Flush(done)).is_ok() {
+            let _ = finished.await;
+        }
+    }
+}